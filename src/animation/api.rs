@@ -0,0 +1,1060 @@
+use bytes::Bytes;
+use reqwest::header::{CONTENT_TYPE, COOKIE, USER_AGENT};
+use roboat::assetdelivery::{AssetBatchPayload, AssetBatchResponse};
+use roboat::ide::ide_types::NewAnimation;
+use roboat::{ClientBuilder, RoboatError};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::config::UploaderConfig;
+use super::model::{ANIMATION_ASSET_TYPE_ID, AssetCreator};
+use super::moderation::ModerationState;
+use super::place::{PlaceVersion, PlaceVersionsPage};
+use crate::modules::upload::MODEL_ASSET_TYPE_ID;
+use crate::pipeline::BoxFuture;
+
+/// The Roblox API calls AnimationUploader makes, so they can be swapped for a fake.
+/// Calls made as an account take its cookie, since uploads are spread over a pool of them.
+///
+/// # Examples
+///
+/// ```rust
+/// let api = MockRobloxApi::new()
+///     .with_asset(1234, AssetCreator::User(1), Some("https://example.com/1234".to_string()));
+/// let uploader = AnimationUploader::builder()
+///     .roblosecurity("unused")
+///     .api(Arc::new(api))
+///     .build()?;
+/// ```
+pub trait RobloxApi: Send + Sync {
+    /// Download locations and asset types of up to 250 assets. `place_id` is sent as the
+    /// Roblox-Place-Id header, which assets only shared with a place need.
+    fn asset_metadata_batch<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        payloads: Vec<AssetBatchPayload>,
+        place_id: u64,
+    ) -> BoxFuture<'a, Result<Vec<AssetBatchResponse>, RoboatError>>;
+
+    /// An asset's display name and owner.
+    fn asset_details<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, anyhow::Result<(Option<String>, AssetCreator)>>;
+
+    /// Root place IDs of a user's or group's games.
+    fn root_place_ids(&self, creator: AssetCreator)
+    -> BoxFuture<'_, Result<Vec<u64>, RoboatError>>;
+
+    /// Download location of one asset from the v1 assetdelivery endpoint, for assets the
+    /// batch API returned no location for. None when it has none either.
+    fn asset_location<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<Option<String>, RoboatError>>;
+
+    /// Sends one GET for a download location and returns the response whatever its status,
+    /// before its body is read. Retries and timeouts are up to the caller.
+    fn download<'a>(
+        &'a self,
+        url: &'a str,
+    ) -> BoxFuture<'a, Result<reqwest::Response, RoboatError>>;
+
+    /// Whether a cookie's user may create items in a group. A group that doesn't exist is a
+    /// 404 UnidentifiedStatusCode.
+    fn can_create_group_items<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        group_id: u64,
+    ) -> BoxFuture<'a, Result<bool, RoboatError>>;
+
+    /// Where moderation is with an asset the cookie's user can manage.
+    fn moderation_state<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<ModerationState, RoboatError>>;
+
+    /// Publishes a model file (.rbxm) owned by the cookie's user, or `group_id`, and returns
+    /// its new asset ID.
+    fn upload_model<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        data: Bytes,
+        name: &'a str,
+        group_id: Option<u64>,
+    ) -> BoxFuture<'a, Result<u64, RoboatError>>;
+
+    /// Publishes an animation and returns its new asset ID.
+    fn upload_animation<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        animation: NewAnimation,
+    ) -> BoxFuture<'a, Result<String, RoboatError>>;
+
+    /// ID of the user a cookie belongs to.
+    fn user_id<'a>(&'a self, roblosecurity: &'a str) -> BoxFuture<'a, Result<u64, RoboatError>>;
+
+    /// Name of the user a cookie belongs to.
+    fn username<'a>(&'a self, roblosecurity: &'a str)
+    -> BoxFuture<'a, Result<String, RoboatError>>;
+
+    /// Name of a user or group. One that doesn't exist is a 404 UnidentifiedStatusCode.
+    fn creator_name(&self, creator: AssetCreator) -> BoxFuture<'_, Result<String, RoboatError>>;
+
+    /// Universe (experience) a place belongs to, None when the place doesn't exist.
+    fn place_universe_id(&self, place_id: u64) -> BoxFuture<'_, Result<Option<u64>, RoboatError>>;
+
+    /// One page of a place's saved versions, newest first.
+    fn place_versions_page<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        cursor: Option<String>,
+    ) -> BoxFuture<'a, Result<PlaceVersionsPage, RoboatError>>;
+
+    /// Downloads a place file: the given saved version, or the latest one if `version` is
+    /// None. A version the place doesn't have is a 404 UnidentifiedStatusCode.
+    fn download_place<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> BoxFuture<'a, Result<Bytes, RoboatError>>;
+}
+
+/// Answers with `{"location": "https://..."}` for an asset ID appended to it.
+const ASSET_LOCATION_URL: &str = "https://assetdelivery.roblox.com/v1/assetId/";
+const ASSET_BATCH_URL: &str = "https://assetdelivery.roblox.com/v2/assets/batch";
+/// Legacy upload endpoint that accepts a model file with the cookie.
+const MODEL_UPLOAD_URL: &str = "https://data.roblox.com/Data/Upload.ashx";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupMembership {
+    permissions: Option<GroupPermissions>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupPermissions {
+    group_economy_permissions: Option<GroupEconomyPermissions>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupEconomyPermissions {
+    #[serde(default)]
+    create_items: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatorDetails {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaceUniverse {
+    universe_id: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetModeration {
+    moderation_result: Option<ModerationResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModerationResult {
+    moderation_state: Option<String>,
+}
+
+/// The real Roblox API, through roboat and the uploader's HTTP client.
+pub struct RoboatApi {
+    config: UploaderConfig,
+    http_client: reqwest::Client,
+}
+
+impl RoboatApi {
+    pub fn new(config: UploaderConfig, http_client: reqwest::Client) -> Self {
+        Self {
+            config,
+            http_client,
+        }
+    }
+
+    fn client(&self, roblosecurity: &str) -> roboat::Client {
+        ClientBuilder::new()
+            .roblosecurity(roblosecurity.to_string())
+            .reqwest_client(self.http_client.clone())
+            .build()
+    }
+
+    /// Sends a GET with the cookie, mapping the statuses every endpoint shares to errors.
+    async fn get_with_cookie(
+        &self,
+        roblosecurity: &str,
+        url: &str,
+    ) -> Result<reqwest::Response, RoboatError> {
+        self.send_with_cookie(roblosecurity, self.http_client.get(url))
+            .await
+    }
+
+    /// Sends a request with the cookie, mapping the statuses every endpoint shares to errors.
+    async fn send_with_cookie(
+        &self,
+        roblosecurity: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, RoboatError> {
+        self.send(
+            request.header(COOKIE, format!(".ROBLOSECURITY={}", roblosecurity)),
+            self.config.timeout,
+        )
+        .await
+    }
+
+    /// Sends a request, mapping the statuses every endpoint shares to errors.
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        timeout: Duration,
+    ) -> Result<reqwest::Response, RoboatError> {
+        let response = request
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(RoboatError::ReqwestError)?;
+        check_status(response)
+    }
+}
+
+/// Maps the error statuses every endpoint shares to errors.
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response, RoboatError> {
+    match response.status().as_u16() {
+        status if (200..300).contains(&status) => Ok(response),
+        429 => Err(RoboatError::TooManyRequests),
+        401 => Err(RoboatError::InvalidRoblosecurity),
+        status => Err(RoboatError::UnidentifiedStatusCode(status)),
+    }
+}
+
+impl RobloxApi for RoboatApi {
+    fn asset_metadata_batch<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        payloads: Vec<AssetBatchPayload>,
+        place_id: u64,
+    ) -> BoxFuture<'a, Result<Vec<AssetBatchResponse>, RoboatError>> {
+        Box::pin(async move {
+            // The place ID goes on this request only, so the shared client (with its proxy and
+            // timeout) is used instead of one built with it as a default header
+            let body = serde_json::to_vec(&payloads).map_err(|_| RoboatError::MalformedResponse)?;
+            let request = self
+                .http_client
+                .post(ASSET_BATCH_URL)
+                .header("Roblox-Place-Id", place_id)
+                .header(USER_AGENT, "Roblox/WinInet")
+                .header(CONTENT_TYPE, "application/json")
+                .body(body);
+            let response = self.send_with_cookie(roblosecurity, request).await?;
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)
+        })
+    }
+
+    fn asset_details<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, anyhow::Result<(Option<String>, AssetCreator)>> {
+        Box::pin(async move {
+            let asset_info = self.client(roblosecurity).get_asset_info(asset_id).await?;
+            let name = asset_info.display_name;
+
+            // Check if owned by user
+            if let Some(user_id) = asset_info.creation_context.creator.user_id {
+                let user_id_parsed = user_id
+                    .parse::<u64>()
+                    .map_err(|e| anyhow::anyhow!("Failed to parse user_id '{}': {}", user_id, e))?;
+                return Ok((name, AssetCreator::User(user_id_parsed)));
+            }
+
+            // Check if owned by group
+            if let Some(group_id) = asset_info.creation_context.creator.group_id {
+                let group_id_parsed = group_id.parse::<u64>().map_err(|e| {
+                    anyhow::anyhow!("Failed to parse group_id '{}': {}", group_id, e)
+                })?;
+                return Ok((name, AssetCreator::Group(group_id_parsed)));
+            }
+
+            Err(anyhow::anyhow!(
+                "No user_id or group_id found for asset {}",
+                asset_id
+            ))
+        })
+    }
+
+    fn root_place_ids(
+        &self,
+        creator: AssetCreator,
+    ) -> BoxFuture<'_, Result<Vec<u64>, RoboatError>> {
+        Box::pin(async move {
+            // Games are public, no cookie needed
+            let client = ClientBuilder::new()
+                .reqwest_client(self.http_client.clone())
+                .build();
+            let games_response = match creator {
+                AssetCreator::User(user_id) => client.user_games(user_id).await?,
+                AssetCreator::Group(group_id) => client.group_games(group_id).await?,
+            };
+
+            Ok(games_response
+                .data
+                .iter()
+                .map(|game| game.root_place.id)
+                .collect())
+        })
+    }
+
+    fn asset_location<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<Option<String>, RoboatError>> {
+        Box::pin(async move {
+            let url = format!("{}{}", ASSET_LOCATION_URL, asset_id);
+            let response = match self.get_with_cookie(roblosecurity, &url).await {
+                Ok(response) => response,
+                // Deleted or never existed, so there is nothing to download
+                Err(RoboatError::UnidentifiedStatusCode(404)) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            let body: serde_json::Value =
+                serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)?;
+            Ok(body["location"].as_str().map(str::to_string))
+        })
+    }
+
+    fn download<'a>(
+        &'a self,
+        url: &'a str,
+    ) -> BoxFuture<'a, Result<reqwest::Response, RoboatError>> {
+        Box::pin(async move {
+            self.http_client
+                .get(url)
+                .send()
+                .await
+                .map_err(RoboatError::ReqwestError)
+        })
+    }
+
+    fn can_create_group_items<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        group_id: u64,
+    ) -> BoxFuture<'a, Result<bool, RoboatError>> {
+        Box::pin(async move {
+            let url = format!(
+                "https://groups.roblox.com/v1/groups/{}/membership",
+                group_id
+            );
+            let response = match self.get_with_cookie(roblosecurity, &url).await {
+                // Roblox answers 400 for group IDs that were never used
+                Err(RoboatError::UnidentifiedStatusCode(400)) => {
+                    return Err(RoboatError::UnidentifiedStatusCode(404));
+                }
+                result => result?,
+            };
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            let membership: GroupMembership =
+                serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)?;
+            Ok(membership
+                .permissions
+                .and_then(|permissions| permissions.group_economy_permissions)
+                .is_some_and(|economy| economy.create_items))
+        })
+    }
+
+    fn moderation_state<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<ModerationState, RoboatError>> {
+        Box::pin(async move {
+            let url = format!(
+                "https://apis.roblox.com/assets/user-auth/v1/assets/{}?readMask=moderationResult",
+                asset_id
+            );
+            let response = self.get_with_cookie(roblosecurity, &url).await?;
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            let asset: AssetModeration =
+                serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)?;
+            let state = asset
+                .moderation_result
+                .and_then(|result| result.moderation_state)
+                .unwrap_or_default()
+                .to_lowercase();
+
+            Ok(if state.contains("approved") {
+                ModerationState::Approved
+            } else if state.contains("rejected") {
+                ModerationState::Rejected
+            } else {
+                ModerationState::Reviewing
+            })
+        })
+    }
+
+    fn upload_model<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        data: Bytes,
+        name: &'a str,
+        group_id: Option<u64>,
+    ) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move {
+            let mut url =
+                url::Url::parse(MODEL_UPLOAD_URL).map_err(|_| RoboatError::MalformedResponse)?;
+            url.query_pairs_mut()
+                .append_pair("assetid", "0")
+                .append_pair("type", "Model")
+                .append_pair("name", name)
+                .append_pair("description", "")
+                .append_pair("genreTypeId", "1")
+                .append_pair("ispublic", "False")
+                .append_pair("allowComments", "False");
+            if let Some(group_id) = group_id {
+                url.query_pairs_mut()
+                    .append_pair("groupId", &group_id.to_string());
+            }
+
+            // The first request is rejected with the CSRF token to send with the second
+            let mut csrf_token = None;
+            for _ in 0..2 {
+                let mut request = self
+                    .http_client
+                    .post(url.clone())
+                    .timeout(self.config.timeout)
+                    .header(COOKIE, format!(".ROBLOSECURITY={}", roblosecurity))
+                    .header(CONTENT_TYPE, "application/octet-stream")
+                    .header(USER_AGENT, "RobloxStudio/WinInet")
+                    .body(data.clone());
+                if let Some(token) = &csrf_token {
+                    request = request.header("X-CSRF-TOKEN", token);
+                }
+
+                let response = request.send().await.map_err(RoboatError::ReqwestError)?;
+                if response.status().as_u16() == 403
+                    && csrf_token.is_none()
+                    && let Some(token) = response.headers().get("x-csrf-token")
+                {
+                    csrf_token = Some(token.clone());
+                    continue;
+                }
+
+                let response = check_status(response)?;
+                let body = response.text().await.map_err(RoboatError::ReqwestError)?;
+                return body
+                    .trim()
+                    .parse()
+                    .map_err(|_| RoboatError::MalformedResponse);
+            }
+
+            // Rejected again with a fresh token
+            Err(RoboatError::UnidentifiedStatusCode(403))
+        })
+    }
+
+    fn upload_animation<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        animation: NewAnimation,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move {
+            self.client(roblosecurity)
+                .upload_new_animation(animation)
+                .await
+        })
+    }
+
+    fn user_id<'a>(&'a self, roblosecurity: &'a str) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move { self.client(roblosecurity).user_id().await })
+    }
+
+    fn username<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move { self.client(roblosecurity).username().await })
+    }
+
+    fn creator_name(&self, creator: AssetCreator) -> BoxFuture<'_, Result<String, RoboatError>> {
+        Box::pin(async move {
+            let url = match creator {
+                AssetCreator::User(user_id) => {
+                    format!("https://users.roblox.com/v1/users/{}", user_id)
+                }
+                AssetCreator::Group(group_id) => {
+                    format!("https://groups.roblox.com/v1/groups/{}", group_id)
+                }
+            };
+            let response = match self
+                .send(self.http_client.get(&url), self.config.timeout)
+                .await
+            {
+                // Both endpoints answer 400 for IDs that were never used
+                Err(RoboatError::UnidentifiedStatusCode(400)) => {
+                    return Err(RoboatError::UnidentifiedStatusCode(404));
+                }
+                result => result?,
+            };
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            let details: CreatorDetails =
+                serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)?;
+            Ok(details.name)
+        })
+    }
+
+    fn place_universe_id(&self, place_id: u64) -> BoxFuture<'_, Result<Option<u64>, RoboatError>> {
+        Box::pin(async move {
+            let url = format!(
+                "https://apis.roblox.com/universes/v1/places/{}/universe",
+                place_id
+            );
+            let response = self
+                .send(self.http_client.get(&url), self.config.timeout)
+                .await?;
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            let universe: PlaceUniverse =
+                serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)?;
+            Ok(universe.universe_id)
+        })
+    }
+
+    fn place_versions_page<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        cursor: Option<String>,
+    ) -> BoxFuture<'a, Result<PlaceVersionsPage, RoboatError>> {
+        Box::pin(async move {
+            let mut url = format!(
+                "https://develop.roblox.com/v1/assets/{}/saved-versions?limit=100&sortOrder=Desc",
+                place_id
+            );
+            if let Some(cursor) = &cursor {
+                url.push_str(&format!("&cursor={}", cursor));
+            }
+            let response = self.get_with_cookie(roblosecurity, &url).await?;
+            let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+            serde_json::from_slice(&data).map_err(|_| RoboatError::MalformedResponse)
+        })
+    }
+
+    fn download_place<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> BoxFuture<'a, Result<Bytes, RoboatError>> {
+        Box::pin(async move {
+            let mut url = format!("https://assetdelivery.roblox.com/v1/asset/?id={}", place_id);
+            if let Some(version) = version {
+                url.push_str(&format!("&version={}", version));
+            }
+            // Places are much bigger than the other responses, so they get the download timeout
+            let request = self
+                .http_client
+                .get(&url)
+                .header(COOKIE, format!(".ROBLOSECURITY={}", roblosecurity));
+            let response = self.send(request, self.config.download_timeout).await?;
+            response.bytes().await.map_err(RoboatError::ReqwestError)
+        })
+    }
+}
+
+/// An asset the mock API knows about.
+#[derive(Debug, Clone)]
+pub struct MockApiAsset {
+    pub creator: AssetCreator,
+    pub name: Option<String>,
+    /// Where the batch API says the asset can be downloaded from.
+    pub location: Option<String>,
+    pub asset_type_id: u64,
+    /// What a download from the location returns. None answers downloads with a 404.
+    pub data: Option<Bytes>,
+}
+
+/// A place the mock API knows about.
+#[derive(Debug, Clone)]
+pub struct MockPlace {
+    pub universe_id: u64,
+    /// Saved versions newest first, each with the place file it downloads as. The first one
+    /// is what a download without a version returns.
+    pub versions: Vec<(PlaceVersion, Bytes)>,
+}
+
+/// A MockRobloxApi call that can be rate limited with MockRobloxApi::with_rate_limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockCall {
+    AssetMetadataBatch,
+    AssetDetails,
+    RootPlaceIds,
+    AssetLocation,
+    ModerationState,
+    UploadAnimation,
+    UploadModel,
+}
+
+/// An animation or model uploaded to the mock API.
+#[derive(Debug, Clone)]
+pub struct MockUpload {
+    pub asset_id: u64,
+    pub group_id: Option<u64>,
+    pub name: String,
+    pub data: Bytes,
+}
+
+/// An in-memory RobloxApi for tests: any cookie is the same user, every user and group has
+/// one game, and uploads are kept so they can be checked.
+///
+/// # Examples
+///
+/// ```rust
+/// let api = Arc::new(MockRobloxApi::new().with_asset(1234, AssetCreator::Group(5), None));
+/// let uploader = AnimationUploader::builder()
+///     .roblosecurity("unused")
+///     .api(api.clone())
+///     .build()?;
+/// uploader.upload_animation(data, UploadTarget::Group(5)).await?;
+/// assert_eq!(api.uploads().len(), 1);
+/// ```
+///
+/// # Notes:
+/// Uploaded animations are added as approved assets of their owner, with a location serving
+/// the uploaded data, so they resolve and download on later lookups. Nothing goes to the
+/// network. Calls answer at once unless with_rate_limit makes some of them a 429 first.
+pub struct MockRobloxApi {
+    user_id: u64,
+    username: String,
+    root_place_id: u64,
+    assets: Mutex<HashMap<u64, MockApiAsset>>,
+    uploads: Mutex<Vec<MockUpload>>,
+    model_uploads: Mutex<Vec<MockUpload>>,
+    next_upload_id: AtomicU64,
+    /// Groups the user may not create items in.
+    groups_without_permission: Vec<u64>,
+    /// Names of users and groups besides the cookie's user.
+    creator_names: HashMap<AssetCreator, String>,
+    places: HashMap<u64, MockPlace>,
+    /// How many more times each call answers TooManyRequests before it goes through.
+    rate_limits: Mutex<HashMap<MockCall, u32>>,
+}
+
+impl Default for MockRobloxApi {
+    fn default() -> Self {
+        Self {
+            user_id: 1,
+            username: "MockUser".to_string(),
+            root_place_id: 1,
+            assets: Mutex::new(HashMap::new()),
+            uploads: Mutex::new(Vec::new()),
+            model_uploads: Mutex::new(Vec::new()),
+            next_upload_id: AtomicU64::new(900_000_000),
+            groups_without_permission: Vec::new(),
+            creator_names: HashMap::new(),
+            places: HashMap::new(),
+            rate_limits: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl MockRobloxApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The user every cookie belongs to.
+    pub fn with_user<S: Into<String>>(mut self, user_id: u64, username: S) -> Self {
+        self.user_id = user_id;
+        self.username = username.into();
+        self
+    }
+
+    /// Adds an animation owned by the given creator.
+    pub fn with_asset(
+        self,
+        asset_id: u64,
+        creator: AssetCreator,
+        location: Option<String>,
+    ) -> Self {
+        self.assets.lock().unwrap().insert(
+            asset_id,
+            MockApiAsset {
+                creator,
+                name: None,
+                location,
+                asset_type_id: ANIMATION_ASSET_TYPE_ID,
+                data: None,
+            },
+        );
+        self
+    }
+
+    /// Adds an animation owned by the given creator that downloads as `data`.
+    pub fn with_animation<D: Into<Bytes>>(
+        self,
+        asset_id: u64,
+        creator: AssetCreator,
+        data: D,
+    ) -> Self {
+        self.assets.lock().unwrap().insert(
+            asset_id,
+            MockApiAsset {
+                creator,
+                name: None,
+                location: Some(mock_location(asset_id)),
+                asset_type_id: ANIMATION_ASSET_TYPE_ID,
+                data: Some(data.into()),
+            },
+        );
+        self
+    }
+
+    /// Takes the "Create and edit group items" permission away from the user in a group.
+    pub fn without_group_permission(mut self, group_id: u64) -> Self {
+        self.groups_without_permission.push(group_id);
+        self
+    }
+
+    /// Adds an asset of any type, e.g. a sound next to the animations.
+    pub fn with_asset_details(self, asset_id: u64, asset: MockApiAsset) -> Self {
+        self.assets.lock().unwrap().insert(asset_id, asset);
+        self
+    }
+
+    /// Names a user or group, for owner reports.
+    pub fn with_creator_name<S: Into<String>>(mut self, creator: AssetCreator, name: S) -> Self {
+        self.creator_names.insert(creator, name.into());
+        self
+    }
+
+    /// Adds a place that can be looked up, listed and downloaded.
+    pub fn with_place(mut self, place_id: u64, place: MockPlace) -> Self {
+        self.places.insert(place_id, place);
+        self
+    }
+
+    /// Answers the next `times` calls of `call` with TooManyRequests, like Roblox does when
+    /// its rate limit is hit, before letting the call through.
+    pub fn with_rate_limit(self, call: MockCall, times: u32) -> Self {
+        *self.rate_limits.lock().unwrap().entry(call).or_default() += times;
+        self
+    }
+
+    /// How many more times `call` will answer TooManyRequests.
+    pub fn rate_limits_left(&self, call: MockCall) -> u32 {
+        self.rate_limits
+            .lock()
+            .unwrap()
+            .get(&call)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Fails the call with TooManyRequests while its rate limit lasts.
+    fn check_rate_limit(&self, call: MockCall) -> Result<(), RoboatError> {
+        match self.rate_limits.lock().unwrap().get_mut(&call) {
+            Some(left) if *left > 0 => {
+                *left -= 1;
+                Err(RoboatError::TooManyRequests)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Animations uploaded so far, in order.
+    pub fn uploads(&self) -> Vec<MockUpload> {
+        self.uploads.lock().unwrap().clone()
+    }
+
+    /// Models (modules) uploaded so far, in order.
+    pub fn model_uploads(&self) -> Vec<MockUpload> {
+        self.model_uploads.lock().unwrap().clone()
+    }
+}
+
+/// Where the mock says an asset with data can be downloaded from.
+pub(crate) fn mock_location(asset_id: u64) -> String {
+    format!("https://mock.invalid/asset/{}", asset_id)
+}
+
+impl RobloxApi for MockRobloxApi {
+    fn asset_metadata_batch<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        payloads: Vec<AssetBatchPayload>,
+        _place_id: u64,
+    ) -> BoxFuture<'a, Result<Vec<AssetBatchResponse>, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::AssetMetadataBatch)?;
+            let assets = self.assets.lock().unwrap();
+            payloads
+                .into_iter()
+                .map(|payload| {
+                    let asset = payload
+                        .asset_id
+                        .as_deref()
+                        .and_then(|asset_id| asset_id.parse::<u64>().ok())
+                        .and_then(|asset_id| assets.get(&asset_id));
+                    // Built from JSON like the real responses, roboat's fields aren't all public
+                    let response = match asset {
+                        Some(asset) => json!({
+                            "requestId": payload.request_id,
+                            "assetTypeId": asset.asset_type_id,
+                            "locations": asset.location.iter().map(|location| json!({
+                                "assetFormat": "source",
+                                "location": location,
+                            })).collect::<Vec<_>>(),
+                        }),
+                        None => json!({
+                            "requestId": payload.request_id,
+                            "errors": [{ "code": 404, "message": "Asset not found" }],
+                        }),
+                    };
+                    serde_json::from_value(response).map_err(|_| RoboatError::MalformedResponse)
+                })
+                .collect()
+        })
+    }
+
+    fn asset_details<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, anyhow::Result<(Option<String>, AssetCreator)>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::AssetDetails)?;
+            let assets = self.assets.lock().unwrap();
+            let asset = assets
+                .get(&asset_id)
+                .ok_or(RoboatError::UnidentifiedStatusCode(404))?;
+            Ok((asset.name.clone(), asset.creator))
+        })
+    }
+
+    fn root_place_ids(
+        &self,
+        _creator: AssetCreator,
+    ) -> BoxFuture<'_, Result<Vec<u64>, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::RootPlaceIds)?;
+            Ok(vec![self.root_place_id])
+        })
+    }
+
+    fn asset_location<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<Option<String>, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::AssetLocation)?;
+            let assets = self.assets.lock().unwrap();
+            Ok(assets
+                .get(&asset_id)
+                .and_then(|asset| asset.location.clone()))
+        })
+    }
+
+    fn download<'a>(
+        &'a self,
+        url: &'a str,
+    ) -> BoxFuture<'a, Result<reqwest::Response, RoboatError>> {
+        Box::pin(async move {
+            let data = self
+                .assets
+                .lock()
+                .unwrap()
+                .values()
+                .find(|asset| asset.location.as_deref() == Some(url))
+                .and_then(|asset| asset.data.clone());
+            let response = match data {
+                Some(data) => http::Response::new(data),
+                None => http::Response::builder()
+                    .status(404)
+                    .body(Bytes::new())
+                    .map_err(|_| RoboatError::MalformedResponse)?,
+            };
+            Ok(reqwest::Response::from(response))
+        })
+    }
+
+    fn can_create_group_items<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        group_id: u64,
+    ) -> BoxFuture<'a, Result<bool, RoboatError>> {
+        Box::pin(async move { Ok(!self.groups_without_permission.contains(&group_id)) })
+    }
+
+    fn moderation_state<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<ModerationState, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::ModerationState)?;
+            if self.assets.lock().unwrap().contains_key(&asset_id) {
+                Ok(ModerationState::Approved)
+            } else {
+                Err(RoboatError::UnidentifiedStatusCode(404))
+            }
+        })
+    }
+
+    fn upload_model<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        data: Bytes,
+        name: &'a str,
+        group_id: Option<u64>,
+    ) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::UploadModel)?;
+            let asset_id = self.next_upload_id.fetch_add(1, Ordering::SeqCst);
+            let creator = match group_id {
+                Some(group_id) => AssetCreator::Group(group_id),
+                None => AssetCreator::User(self.user_id),
+            };
+
+            self.assets.lock().unwrap().insert(
+                asset_id,
+                MockApiAsset {
+                    creator,
+                    name: Some(name.to_string()),
+                    location: Some(mock_location(asset_id)),
+                    asset_type_id: MODEL_ASSET_TYPE_ID,
+                    data: Some(data.clone()),
+                },
+            );
+            self.model_uploads.lock().unwrap().push(MockUpload {
+                asset_id,
+                group_id,
+                name: name.to_string(),
+                data,
+            });
+            Ok(asset_id)
+        })
+    }
+
+    fn upload_animation<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        animation: NewAnimation,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move {
+            self.check_rate_limit(MockCall::UploadAnimation)?;
+            let asset_id = self.next_upload_id.fetch_add(1, Ordering::SeqCst);
+            let creator = match animation.group_id {
+                Some(group_id) => AssetCreator::Group(group_id),
+                None => AssetCreator::User(self.user_id),
+            };
+
+            self.assets.lock().unwrap().insert(
+                asset_id,
+                MockApiAsset {
+                    creator,
+                    name: Some(animation.name.clone()),
+                    location: Some(mock_location(asset_id)),
+                    asset_type_id: ANIMATION_ASSET_TYPE_ID,
+                    data: Some(animation.animation_data.clone()),
+                },
+            );
+            self.uploads.lock().unwrap().push(MockUpload {
+                asset_id,
+                group_id: animation.group_id,
+                name: animation.name,
+                data: animation.animation_data,
+            });
+            Ok(asset_id.to_string())
+        })
+    }
+
+    fn user_id<'a>(&'a self, _roblosecurity: &'a str) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move { Ok(self.user_id) })
+    }
+
+    fn username<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move { Ok(self.username.clone()) })
+    }
+
+    fn creator_name(&self, creator: AssetCreator) -> BoxFuture<'_, Result<String, RoboatError>> {
+        Box::pin(async move {
+            match self.creator_names.get(&creator) {
+                Some(name) => Ok(name.clone()),
+                None if creator == AssetCreator::User(self.user_id) => Ok(self.username.clone()),
+                None => Err(RoboatError::UnidentifiedStatusCode(404)),
+            }
+        })
+    }
+
+    fn place_universe_id(&self, place_id: u64) -> BoxFuture<'_, Result<Option<u64>, RoboatError>> {
+        Box::pin(async move { Ok(self.places.get(&place_id).map(|place| place.universe_id)) })
+    }
+
+    fn place_versions_page<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        place_id: u64,
+        _cursor: Option<String>,
+    ) -> BoxFuture<'a, Result<PlaceVersionsPage, RoboatError>> {
+        Box::pin(async move {
+            let place = self
+                .places
+                .get(&place_id)
+                .ok_or(RoboatError::UnidentifiedStatusCode(404))?;
+            Ok(PlaceVersionsPage {
+                data: place
+                    .versions
+                    .iter()
+                    .map(|(version, _)| version.clone())
+                    .collect(),
+                next_page_cursor: None,
+            })
+        })
+    }
+
+    fn download_place<'a>(
+        &'a self,
+        _roblosecurity: &'a str,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> BoxFuture<'a, Result<Bytes, RoboatError>> {
+        Box::pin(async move {
+            let place = self
+                .places
+                .get(&place_id)
+                .ok_or(RoboatError::UnidentifiedStatusCode(404))?;
+            place
+                .versions
+                .iter()
+                .find(|(saved, _)| version.is_none_or(|version| saved.version_number == version))
+                .map(|(_, data)| data.clone())
+                .ok_or(RoboatError::UnidentifiedStatusCode(404))
+        })
+    }
+}
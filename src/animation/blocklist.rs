@@ -0,0 +1,78 @@
+use roboat::RoboatError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the CLI keeps the blocklist unless told otherwise.
+pub const DEFAULT_BLOCKLIST_FILE: &str = "moderation-blocklist.json";
+
+/// Why and when a source animation was blocklisted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedAsset {
+    pub reason: String,
+    /// Unix timestamp (seconds) of the rejected upload.
+    pub rejected_at: u64,
+}
+
+/// Source animation IDs whose uploads were rejected by moderation.
+/// Persisted between runs so they aren't downloaded, reuploaded and rejected every time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModerationBlocklist {
+    pub assets: BTreeMap<u64, BlockedAsset>,
+}
+
+impl ModerationBlocklist {
+    /// Loads the blocklist from a JSON file. A missing file is an empty blocklist.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read blocklist '{}': {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse blocklist '{}': {}", path.display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write blocklist '{}': {}", path.display(), e))
+    }
+
+    pub fn get(&self, asset_id: u64) -> Option<&BlockedAsset> {
+        self.assets.get(&asset_id)
+    }
+
+    /// Blocklists an asset, keeping the first rejection if it was already listed.
+    pub fn insert(&mut self, asset_id: u64, reason: String) {
+        let rejected_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        self.assets.entry(asset_id).or_insert(BlockedAsset {
+            reason,
+            rejected_at,
+        });
+    }
+}
+
+/// Returns true if an upload error means moderation rejected the animation.
+///
+/// # Notes:
+/// Roblox reports these as an error code with a message mentioning moderation or
+/// inappropriate content, so the message is what gets checked.
+pub fn is_moderation_rejection(error: &RoboatError) -> bool {
+    match error {
+        RoboatError::UnknownRobloxErrorCode { message, .. } => {
+            let message = message.to_lowercase();
+            message.contains("moderat") || message.contains("inappropriate")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often and how patiently requests are retried.
+///
+/// # Notes:
+/// Upload attempts are set per run with UploadOptions::max_retries; this covers everything
+/// around them (metadata fetches, downloads, verification lookups) and the rate limit waits.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_fetch_retries: u32,
+    pub(crate) max_download_retries: u32,
+    pub(crate) max_verify_retries: u32,
+    pub(crate) rate_limit_backoff: Duration,
+    pub(crate) verify_backoff: Duration,
+    pub(crate) retry_delay: Duration,
+    pub(crate) max_retry_delay: Duration,
+    pub(crate) fetch_retry_delay: Duration,
+    pub(crate) retry_budget: Option<u32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_fetch_retries: 9,
+            max_download_retries: 2,
+            max_verify_retries: 4,
+            rate_limit_backoff: Duration::from_secs(30),
+            verify_backoff: Duration::from_secs(5),
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(60),
+            fetch_retry_delay: Duration::from_secs(2),
+            retry_budget: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets every retry count (fetch, download, verify) at once.
+    pub fn max_retries(self, max_retries: u32) -> Self {
+        self.max_fetch_retries(max_retries)
+            .max_download_retries(max_retries)
+            .max_verify_retries(max_retries)
+    }
+
+    /// Retries of a batch metadata fetch. Defaults to 9.
+    pub fn max_fetch_retries(mut self, max_fetch_retries: u32) -> Self {
+        self.max_fetch_retries = max_fetch_retries;
+        self
+    }
+
+    /// Retries of a failed animation download, after the first attempt. Defaults to 2.
+    pub fn max_download_retries(mut self, max_download_retries: u32) -> Self {
+        self.max_download_retries = max_download_retries;
+        self
+    }
+
+    /// Retries of a rate limited owner lookup of an uploaded animation. Defaults to 4.
+    pub fn max_verify_retries(mut self, max_verify_retries: u32) -> Self {
+        self.max_verify_retries = max_verify_retries;
+        self
+    }
+
+    /// After a rate limit on a fetch or upload, every task waits this long times the attempt
+    /// number. Defaults to 30 seconds.
+    pub fn rate_limit_backoff(mut self, rate_limit_backoff: Duration) -> Self {
+        self.rate_limit_backoff = rate_limit_backoff;
+        self
+    }
+
+    /// Like rate_limit_backoff, for owner lookups during verification. Defaults to 5 seconds.
+    pub fn verify_backoff(mut self, verify_backoff: Duration) -> Self {
+        self.verify_backoff = verify_backoff;
+        self
+    }
+
+    /// Wait after the first failed upload attempt. It doubles with each further attempt, up to
+    /// max_retry_delay, and is jittered so tasks don't retry in lockstep. Defaults to 1 second.
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Longest wait between failed upload attempts. Defaults to 60 seconds.
+    pub fn max_retry_delay(mut self, max_retry_delay: Duration) -> Self {
+        self.max_retry_delay = max_retry_delay;
+        self
+    }
+
+    /// Wait between failed metadata fetches. Defaults to 2 seconds.
+    pub fn fetch_retry_delay(mut self, fetch_retry_delay: Duration) -> Self {
+        self.fetch_retry_delay = fetch_retry_delay;
+        self
+    }
+
+    /// Upload retries shared by every task of a batch. Each retry spends one and every
+    /// successful upload earns back a tenth of one, so when most uploads keep failing the
+    /// tasks give up instead of retrying each one max_retries times. Retries after a rate
+    /// limit are free, since those already wait it out. None (the default) retries without
+    /// a shared limit.
+    pub fn retry_budget(mut self, retry_budget: Option<u32>) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+
+    /// The wait after the given failed upload attempt: retry_delay doubled per attempt, capped
+    /// at max_retry_delay, and then a random amount between half of that and all of it.
+    pub(crate) fn retry_wait(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .retry_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_retry_delay);
+        backoff / 2 + jitter(backoff / 2)
+    }
+
+    /// The rate limit wait for the given attempt.
+    pub(crate) fn rate_limit_wait(&self, attempt: u32) -> Duration {
+        self.rate_limit_backoff.saturating_mul(attempt)
+    }
+
+    /// The verification rate limit wait for the given attempt.
+    pub(crate) fn verify_wait(&self, attempt: u32) -> Duration {
+        self.verify_backoff.saturating_mul(attempt)
+    }
+}
+
+/// A random duration between zero and `max`, to spread out tasks that would otherwise wake
+/// at the same moment.
+pub(crate) fn jitter(max: Duration) -> Duration {
+    // RandomState is seeded differently every time, which is random enough for this
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+/// Makes the reqwest::ClientBuilder the uploader's HTTP clients start from, set with
+/// UploaderConfig::http_client.
+#[derive(Clone)]
+pub struct ClientFactory(Arc<dyn Fn() -> reqwest::ClientBuilder + Send + Sync>);
+
+impl ClientFactory {
+    pub fn new<F: Fn() -> reqwest::ClientBuilder + Send + Sync + 'static>(factory: F) -> Self {
+        Self(Arc::new(factory))
+    }
+}
+
+impl fmt::Debug for ClientFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClientFactory")
+    }
+}
+
+/// Downloaded animations larger than this are streamed to a temp file by default (1 MiB).
+pub const DEFAULT_SPOOL_THRESHOLD: u64 = 1024 * 1024;
+
+/// Settings of an AnimationUploader, set with AnimationUploader::builder().
+#[derive(Debug, Clone)]
+pub struct UploaderConfig {
+    pub(crate) retry: RetryPolicy,
+    pub(crate) timeout: Duration,
+    pub(crate) download_timeout: Duration,
+    pub(crate) spool_threshold: Option<u64>,
+    pub(crate) http_client: Option<ClientFactory>,
+}
+
+impl Default for UploaderConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            timeout: Duration::from_secs(10),
+            download_timeout: Duration::from_secs(15),
+            spool_threshold: Some(DEFAULT_SPOOL_THRESHOLD),
+            http_client: None,
+        }
+    }
+}
+
+impl UploaderConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Timeout of Roblox API requests. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout of each animation file download. Defaults to 15 seconds.
+    pub fn download_timeout(mut self, download_timeout: Duration) -> Self {
+        self.download_timeout = download_timeout;
+        self
+    }
+
+    /// Animation downloads larger than this many bytes are streamed to a temp file during a
+    /// batch upload and read back only while uploading, so thousands of queued animations
+    /// don't sit in memory. None keeps every download in memory. Defaults to 1 MiB.
+    pub fn spool_threshold(mut self, spool_threshold: Option<u64>) -> Self {
+        self.spool_threshold = spool_threshold;
+        self
+    }
+
+    /// Customises the HTTP clients (proxy, root certificates, user agent...). The uploader
+    /// builds one client from it and reuses it for every request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let proxy = reqwest::Proxy::all("http://proxy.corp:8080")?;
+    /// let config = UploaderConfig::new()
+    ///     .http_client(move || reqwest::Client::builder().proxy(proxy.clone()));
+    /// ```
+    pub fn http_client<F: Fn() -> reqwest::ClientBuilder + Send + Sync + 'static>(
+        mut self,
+        factory: F,
+    ) -> Self {
+        self.http_client = Some(ClientFactory::new(factory));
+        self
+    }
+
+    /// A ClientBuilder with the settings of http_client, or a plain one.
+    pub(crate) fn client_builder(&self) -> reqwest::ClientBuilder {
+        match &self.http_client {
+            Some(ClientFactory(factory)) => factory(),
+            None => reqwest::ClientBuilder::new(),
+        }
+    }
+}
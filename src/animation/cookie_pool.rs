@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::tasks::RateLimiter;
+
+/// One account uploads can be made with. Roblox rate limits uploads per account, so each has
+/// its own rate limiter.
+pub(crate) struct PoolAccount {
+    pub(crate) roblosecurity: String,
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+}
+
+/// The accounts an AnimationUploader spreads batch uploads over, round-robin. The first is
+/// the uploader's own cookie and shares its rate limiter with every other request.
+pub(crate) struct CookiePool {
+    accounts: Vec<PoolAccount>,
+    next: AtomicUsize,
+}
+
+impl CookiePool {
+    pub(crate) fn new(primary: PoolAccount) -> Self {
+        Self {
+            accounts: vec![primary],
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds an account with a rate limiter of its own.
+    pub(crate) fn push(&mut self, roblosecurity: String) {
+        self.accounts.push(PoolAccount {
+            roblosecurity,
+            rate_limiter: Arc::new(RateLimiter::new()),
+        });
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub(crate) fn accounts(&self) -> &[PoolAccount] {
+        &self.accounts
+    }
+
+    /// The account whose turn it is. Accounts that are rate limited right now are passed
+    /// over while another one isn't, so a limited account doesn't hold up its share of the
+    /// uploads.
+    pub(crate) async fn next_account(&self) -> &PoolAccount {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.accounts.len() {
+            let account = &self.accounts[(start + offset) % self.accounts.len()];
+            if !account.rate_limiter.is_limited().await {
+                return account;
+            }
+        }
+        &self.accounts[start % self.accounts.len()]
+    }
+
+    /// Wall time the accounts were rate limited, added up over all of them.
+    pub(crate) fn total_limited(&self) -> Duration {
+        self.accounts
+            .iter()
+            .map(|account| account.rate_limiter.total_limited())
+            .sum()
+    }
+}
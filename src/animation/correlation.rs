@@ -0,0 +1,40 @@
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Identifies one asset's lifecycle (download, retries, rate limits, upload) in a run.
+/// Every log line about the asset carries it, so grepping for it shows the whole history.
+///
+/// # Notes:
+/// The ID is `<run>-<asset id>`, where the run part is fixed for the process, so the same asset
+/// gets the same ID wherever it's logged without passing it around.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    pub fn new(asset_id: u64) -> Self {
+        Self(format!("{}-{}", run_id(), asset_id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A short ID for this process, taken from the start time.
+fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        format!("{:x}", millis & 0xff_ffff)
+    })
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+
+/// Environment variable the cookie is read from when it isn't passed explicitly.
+pub const COOKIE_ENV_VAR: &str = "ROBLOSECURITY";
+
+/// Service name the cookie is stored under in the OS keychain.
+#[cfg(feature = "keyring")]
+pub const KEYRING_SERVICE: &str = "xixspoof";
+
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "roblosecurity";
+
+/// The cookie in the ROBLOSECURITY environment variable, if it is set and not empty.
+pub fn cookie_from_env() -> Option<String> {
+    std::env::var(COOKIE_ENV_VAR)
+        .ok()
+        .map(|cookie| cookie.trim().to_string())
+        .filter(|cookie| !cookie.is_empty())
+}
+
+/// Reads the cookie from a file holding only the cookie. Surrounding whitespace is ignored,
+/// and a file with several cookies (see cookies_from_file) gives the first.
+///
+/// # Notes:
+/// Keeps the cookie out of shell history and process lists, unlike --cookie. Restrict the
+/// file to your own user.
+pub fn cookie_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+    Ok(cookies_from_file(path)?.remove(0))
+}
+
+/// Reads one cookie per line, e.g. for an upload pool of several accounts. Blank lines and
+/// lines starting with `#` are skipped.
+pub fn cookies_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<String>> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read cookie file '{}': {}", path.display(), e))?;
+
+    let cookies: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if cookies.is_empty() {
+        anyhow::bail!("Cookie file '{}' is empty", path.display());
+    }
+    Ok(cookies)
+}
+
+/// The cookie stored in the OS keychain with store_cookie_in_keyring, if there is one.
+#[cfg(feature = "keyring")]
+pub fn cookie_from_keyring() -> anyhow::Result<Option<String>> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    match entry.get_password() {
+        Ok(cookie) => Ok(Some(cookie)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to read the cookie from the keychain: {}",
+            e
+        )),
+    }
+}
+
+/// Saves the cookie in the OS keychain (Keychain on macOS, Credential Manager on Windows,
+/// Secret Service on Linux), replacing any stored before.
+#[cfg(feature = "keyring")]
+pub fn store_cookie_in_keyring(cookie: &str) -> anyhow::Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?
+        .set_password(cookie.trim())
+        .map_err(|e| anyhow::anyhow!("Failed to save the cookie in the keychain: {}", e))
+}
+
+/// Removes the stored cookie from the OS keychain. Returns false if none was stored.
+#[cfg(feature = "keyring")]
+pub fn delete_cookie_from_keyring() -> anyhow::Result<bool> {
+    match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to remove the cookie from the keychain: {}",
+            e
+        )),
+    }
+}
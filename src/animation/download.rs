@@ -0,0 +1,173 @@
+use bytes::{Bytes, BytesMut};
+use roboat::RoboatError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+use super::correlation::CorrelationId;
+use super::metrics::MetricsRecorder;
+use crate::AnimationUploader;
+
+/// Numbers the spooled files of this process so concurrent downloads never share one.
+static NEXT_SPOOL_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// A downloaded animation, kept in memory or streamed to a temp file when it is large.
+#[derive(Debug)]
+pub enum AnimationFile {
+    Memory(Bytes),
+    Spooled(SpooledFile),
+}
+
+impl AnimationFile {
+    /// The animation's data. A spooled file is read back from disk on every call, so the copy
+    /// only lives as long as the caller keeps it.
+    pub async fn bytes(&self) -> std::io::Result<Bytes> {
+        match self {
+            AnimationFile::Memory(data) => Ok(data.clone()),
+            AnimationFile::Spooled(file) => tokio::fs::read(&file.path).await.map(Bytes::from),
+        }
+    }
+
+    /// Size of the animation in bytes.
+    pub fn len(&self) -> u64 {
+        match self {
+            AnimationFile::Memory(data) => data.len() as u64,
+            AnimationFile::Spooled(file) => file.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A download streamed to the system temp directory. The file is deleted when this is dropped.
+#[derive(Debug)]
+pub struct SpooledFile {
+    path: PathBuf,
+    len: u64,
+}
+
+impl SpooledFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            debug!("Failed to remove '{}': {}", self.path.display(), e);
+        }
+    }
+}
+
+impl AnimationUploader {
+    /// Downloads animations without uploading them, saving each one as `<id>.rbxm` in `out_dir`.
+    /// Returns the paths that were written; animations that fail to download are reported and skipped.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// The files are the KeyframeSequence binaries Roblox serves, so they can be opened in Studio
+    /// or uploaded again later.
+    pub async fn download_animations<P: AsRef<Path>>(
+        &self,
+        asset_ids: Vec<u64>,
+        out_dir: P,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir).map_err(|e| {
+            anyhow::anyhow!("Failed to create directory '{}': {}", out_dir.display(), e)
+        })?;
+
+        let animations = self.fetch_animation_assets(asset_ids).await?;
+        let mut written = Vec::new();
+
+        for animation in animations {
+            let correlation_id = CorrelationId::new(animation.id);
+            let Some(location) = animation.location() else {
+                warn!(correlation_id = %correlation_id, "No download location for animation");
+                continue;
+            };
+
+            let animation_file = match self.file_bytes_from_url(location.to_string()).await {
+                Ok(animation_file) => animation_file,
+                Err(e) => {
+                    warn!(correlation_id = %correlation_id, "Failed to download animation: {}", e);
+                    continue;
+                }
+            };
+
+            let path = out_dir.join(format!("{}.rbxm", animation.id));
+            fs::write(&path, &animation_file)
+                .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+
+    /// Downloads an animation file, streaming it to a temp file instead of memory once it
+    /// grows past UploaderConfig::spool_threshold.
+    ///
+    /// # Notes:
+    /// The file is written as the body arrives, so at most the threshold is buffered in memory
+    /// per download. Failing to write the temp file is reported as a malformed response.
+    pub async fn download_animation_file(&self, url: String) -> Result<AnimationFile, RoboatError> {
+        let Some(threshold) = self.config.spool_threshold else {
+            return self
+                .file_bytes_from_url(url)
+                .await
+                .map(AnimationFile::Memory);
+        };
+
+        let mut response = self.download_response(&url).await?;
+        let mut buffer = BytesMut::new();
+        while let Some(chunk) = response.chunk().await.map_err(RoboatError::ReqwestError)? {
+            buffer.extend_from_slice(&chunk);
+            if buffer.len() as u64 > threshold {
+                let spooled = spool_response(response, buffer).await?;
+                MetricsRecorder::add(&self.metrics.bytes_downloaded, spooled.len);
+                return Ok(AnimationFile::Spooled(spooled));
+            }
+        }
+        MetricsRecorder::add(&self.metrics.bytes_downloaded, buffer.len() as u64);
+        Ok(AnimationFile::Memory(buffer.freeze()))
+    }
+}
+
+/// Writes what was buffered of a download, then the rest of its body, to a new temp file.
+async fn spool_response(
+    mut response: reqwest::Response,
+    buffer: BytesMut,
+) -> Result<SpooledFile, RoboatError> {
+    let path = std::env::temp_dir().join(format!(
+        "xixspoof-{}-{}.rbxm",
+        std::process::id(),
+        NEXT_SPOOL_FILE.fetch_add(1, Ordering::Relaxed)
+    ));
+    // Created before the first write so the file is cleaned up whatever fails next
+    let mut spooled = SpooledFile { path, len: 0 };
+
+    let write_error = |e: std::io::Error| {
+        warn!("Failed to write '{}': {}", spooled.path.display(), e);
+        RoboatError::MalformedResponse
+    };
+    let mut file = tokio::fs::File::create(&spooled.path)
+        .await
+        .map_err(write_error)?;
+    file.write_all(&buffer).await.map_err(write_error)?;
+    let mut len = buffer.len() as u64;
+    drop(buffer);
+
+    while let Some(chunk) = response.chunk().await.map_err(RoboatError::ReqwestError)? {
+        file.write_all(&chunk).await.map_err(write_error)?;
+        len += chunk.len() as u64;
+    }
+    file.flush().await.map_err(write_error)?;
+
+    spooled.len = len;
+    Ok(spooled)
+}
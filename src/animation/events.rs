@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use super::correlation::CorrelationId;
+use super::keyframe_sequence::AnimationEvent;
+use crate::AnimationUploader;
+
+/// How far apart, in seconds, an event can be in the two animations and still count as the
+/// same. Keyframe times go through a float round trip when the file is serialized again.
+const EVENT_TIME_TOLERANCE: f32 = 0.001;
+
+/// An uploaded animation whose events don't match its source's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventMismatch {
+    pub old_id: String,
+    pub new_id: String,
+    /// What differs, e.g. "event Footstep moved from 0.50s to 0.55s".
+    pub problems: Vec<String>,
+}
+
+/// Compares the events of a source animation with its reupload, pairing events by name and
+/// value in time order. Returns what differs, empty when they match.
+///
+/// # Examples
+///
+/// ```rust
+/// let before = KeyframeSequenceDom::from_bytes(&source)?.events();
+/// let after = KeyframeSequenceDom::from_bytes(&reuploaded)?.events();
+/// for problem in compare_events(&before, &after) {
+///     println!("{}", problem);
+/// }
+/// ```
+pub fn compare_events(before: &[AnimationEvent], after: &[AnimationEvent]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut matched = vec![false; after.len()];
+
+    for event in before {
+        let pair = (0..after.len()).find(|&index| {
+            !matched[index] && after[index].name == event.name && after[index].value == event.value
+        });
+        match pair {
+            Some(index) => {
+                matched[index] = true;
+                let moved_to = after[index].time;
+                if (moved_to - event.time).abs() > EVENT_TIME_TOLERANCE {
+                    problems.push(format!(
+                        "event {} moved from {:.2}s to {:.2}s",
+                        event.name, event.time, moved_to
+                    ));
+                }
+            }
+            None => problems.push(format!(
+                "event {} at {:.2}s is missing",
+                event.name, event.time
+            )),
+        }
+    }
+
+    for (event, _) in after.iter().zip(matched).filter(|(_, matched)| !matched) {
+        problems.push(format!(
+            "unexpected event {} at {:.2}s",
+            event.name, event.time
+        ));
+    }
+
+    problems
+}
+
+impl AnimationUploader {
+    /// Downloads both sides of each old → new entry and compares their animation events
+    /// (named keyframes and KeyframeMarkers), which scripts rely on to play footsteps,
+    /// hit frames and the like. Returns the entries whose events differ, sorted by old ID.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let verified = uploader.verify_uploaded_animations(mapping, UploadTarget::Group(1234)).await?;
+    /// for mismatch in uploader.check_animation_events(&verified).await? {
+    ///     println!("{} -> {}: {}", mismatch.old_id, mismatch.new_id, mismatch.problems.join(", "));
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// Entries where either animation can't be downloaded or decoded are logged and left out,
+    /// since there is nothing to compare. Every animation is downloaded, so on big mappings
+    /// this takes about as long as the uploads' downloads did.
+    pub async fn check_animation_events(
+        &self,
+        animation_mapping: &HashMap<String, String>,
+    ) -> anyhow::Result<Vec<EventMismatch>> {
+        let mut entries: Vec<(&String, &String)> = animation_mapping.iter().collect();
+        entries.sort();
+
+        let asset_ids: Vec<u64> = entries
+            .iter()
+            .flat_map(|(old_id, new_id)| [old_id.parse().ok(), new_id.parse().ok()])
+            .flatten()
+            .collect();
+        let locations: HashMap<String, String> = self
+            .fetch_animation_assets(asset_ids)
+            .await?
+            .into_iter()
+            .filter_map(|asset| {
+                let location = asset.location()?.to_string();
+                Some((asset.id.to_string(), location))
+            })
+            .collect();
+
+        let mut mismatches = Vec::new();
+        for (old_id, new_id) in entries {
+            let correlation_id = old_id
+                .parse()
+                .map(|asset_id| CorrelationId::new(asset_id).to_string())
+                .unwrap_or_default();
+
+            let before = match self.animation_events(&locations, old_id).await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!(
+                        correlation_id,
+                        "Not checking the events of {}: {}", old_id, e
+                    );
+                    continue;
+                }
+            };
+            let after = match self.animation_events(&locations, new_id).await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!(
+                        correlation_id,
+                        "Not checking the events of {}: {}", new_id, e
+                    );
+                    continue;
+                }
+            };
+
+            let problems = compare_events(&before, &after);
+            if !problems.is_empty() {
+                warn!(
+                    correlation_id,
+                    "Events of {} changed in {}: {}",
+                    old_id,
+                    new_id,
+                    problems.join(", ")
+                );
+                mismatches.push(EventMismatch {
+                    old_id: old_id.clone(),
+                    new_id: new_id.clone(),
+                    problems,
+                });
+            }
+        }
+
+        info!(
+            "Checked the events of {} animations, {} changed",
+            animation_mapping.len(),
+            mismatches.len()
+        );
+        Ok(mismatches)
+    }
+
+    async fn animation_events(
+        &self,
+        locations: &HashMap<String, String>,
+        asset_id: &str,
+    ) -> anyhow::Result<Vec<AnimationEvent>> {
+        let location = locations
+            .get(asset_id)
+            .ok_or_else(|| anyhow::anyhow!("it can't be downloaded"))?;
+        let data = self.file_bytes_from_url(location.clone()).await?;
+        Ok(self.decode_keyframe_sequence(data)?.events())
+    }
+}
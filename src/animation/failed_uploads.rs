@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::report::FailedAsset;
+
+/// Where the CLI keeps the animations that failed in the last run, for --retry-failed.
+pub const DEFAULT_FAILED_UPLOADS_FILE: &str = "failed-uploads.json";
+
+/// The animations that failed to download or upload in the last run, so a follow-up run can
+/// retry only those.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedUploads {
+    pub assets: Vec<FailedAsset>,
+}
+
+impl FailedUploads {
+    pub fn new(assets: Vec<FailedAsset>) -> Self {
+        Self { assets }
+    }
+
+    /// Loads the failures from a JSON file. A missing file means nothing failed.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read failed uploads '{}': {}", path.display(), e)
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!("Failed to parse failed uploads '{}': {}", path.display(), e)
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents).map_err(|e| {
+            anyhow::anyhow!("Failed to write failed uploads '{}': {}", path.display(), e)
+        })
+    }
+
+    pub fn contains(&self, asset_id: u64) -> bool {
+        self.assets.iter().any(|asset| asset.asset_id == asset_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
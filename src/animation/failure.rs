@@ -0,0 +1,97 @@
+use roboat::RoboatError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::blocklist::is_moderation_rejection;
+
+/// Whether a failed download or upload is worth trying again.
+///
+/// # Examples
+///
+/// ```rust
+/// match uploader.upload_animation(data, UploadTarget::Group(1234)).await {
+///     Err(e) if FailureKind::classify(&e).is_permanent() => skip(asset_id),
+///     Err(_) => retry_later(asset_id),
+///     Ok(new_id) => mapping.insert(asset_id, new_id),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Moderation rejected the animation, and will again.
+    Moderated,
+    /// The source asset doesn't exist anymore, or has nothing to download.
+    Deleted,
+    /// The cookie isn't allowed to do this, e.g. it is invalid or lacks a group permission.
+    Forbidden,
+    /// Rate limits, server errors, timeouts and anything else that may work on a later try.
+    #[default]
+    Transient,
+}
+
+impl FailureKind {
+    /// Sorts an error from a download or upload into a kind. Anything not known to be
+    /// permanent is transient.
+    pub fn classify(error: &RoboatError) -> Self {
+        if is_moderation_rejection(error) {
+            return FailureKind::Moderated;
+        }
+
+        match error {
+            RoboatError::InvalidRoblosecurity
+            | RoboatError::RoblosecurityNotSet
+            | RoboatError::UnknownStatus403Format
+            // A missing upload permission shows up as a BadRequest
+            | RoboatError::BadRequest => FailureKind::Forbidden,
+            RoboatError::UnidentifiedStatusCode(status) => Self::from_status(*status),
+            RoboatError::UnknownRobloxErrorCode { message, .. } => {
+                let message = message.to_lowercase();
+                if message.contains("not found") || message.contains("does not exist") {
+                    FailureKind::Deleted
+                } else if message.contains("not authorized")
+                    || message.contains("permission")
+                    || message.contains("forbidden")
+                {
+                    FailureKind::Forbidden
+                } else {
+                    FailureKind::Transient
+                }
+            }
+            RoboatError::ReqwestError(e) => e
+                .status()
+                .map_or(FailureKind::Transient, |status| {
+                    Self::from_status(status.as_u16())
+                }),
+            _ => FailureKind::Transient,
+        }
+    }
+
+    /// The kind of an HTTP error status: 404 and 410 are deleted, 401 and 403 forbidden.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            404 | 410 => FailureKind::Deleted,
+            401 | 403 => FailureKind::Forbidden,
+            _ => FailureKind::Transient,
+        }
+    }
+
+    /// Retrying won't change the outcome.
+    pub fn is_permanent(&self) -> bool {
+        *self != FailureKind::Transient
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Moderated => "moderated",
+            FailureKind::Deleted => "deleted",
+            FailureKind::Forbidden => "forbidden",
+            FailureKind::Transient => "transient",
+        }
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
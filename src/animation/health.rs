@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{info, warn};
+
+use super::model::{AnimationAsset, AssetCreator};
+use super::roblox_assets::ROBLOX_USER_ID;
+use crate::AnimationUploader;
+
+/// Whether a referenced animation will load in-game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceStatus {
+    Ok,
+    /// The asset doesn't exist (anymore).
+    Deleted,
+    /// The asset was taken down by moderation.
+    Moderated,
+    /// Another user or group owns the asset, so games of the expected owner can't play it.
+    Private,
+    /// Asset delivery reported an error that isn't one of the above.
+    Unavailable,
+}
+
+impl ReferenceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceStatus::Ok => "ok",
+            ReferenceStatus::Deleted => "deleted",
+            ReferenceStatus::Moderated => "moderated",
+            ReferenceStatus::Private => "private",
+            ReferenceStatus::Unavailable => "unavailable",
+        }
+    }
+
+    pub fn is_broken(&self) -> bool {
+        *self != ReferenceStatus::Ok
+    }
+}
+
+impl fmt::Display for ReferenceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The verdict on one referenced animation, from AnimationUploader::check_references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceCheck {
+    pub asset_id: u64,
+    pub status: ReferenceStatus,
+    /// Filled in when the owner was looked up.
+    pub owner: Option<AssetCreator>,
+    /// The asset delivery error, or why the owner is unknown.
+    pub detail: Option<String>,
+}
+
+impl AnimationUploader {
+    /// Classifies each referenced animation as OK, deleted, moderated, private to another owner
+    /// or otherwise unavailable, using the batch metadata API. Assets that turn out not to be
+    /// animations are left out.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let asset_ids = parser.animation_refs().iter().map(|r| r.asset_id).collect();
+    /// for check in uploader.check_references(asset_ids, Some(AssetCreator::Group(123))).await? {
+    ///     if check.status.is_broken() {
+    ///         println!("{} is {}", check.asset_id, check.status);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// With `game_owner`, every downloadable animation also has its owner looked up (one
+    /// request each): games can only play animations their owner or Roblox owns, so anything
+    /// else is reported as private. Without it, only what the batch API says is checked.
+    pub async fn check_references(
+        &self,
+        asset_ids: Vec<u64>,
+        game_owner: Option<AssetCreator>,
+    ) -> anyhow::Result<Vec<ReferenceCheck>> {
+        let assets: HashMap<u64, AnimationAsset> = self
+            .fetch_assets(asset_ids.clone())
+            .await?
+            .into_iter()
+            .map(|asset| (asset.id, asset))
+            .collect();
+
+        let mut checks = Vec::new();
+        for asset_id in asset_ids {
+            let Some(asset) = assets.get(&asset_id) else {
+                checks.push(ReferenceCheck {
+                    asset_id,
+                    status: ReferenceStatus::Deleted,
+                    owner: None,
+                    detail: None,
+                });
+                continue;
+            };
+            if !asset.is_animation() {
+                continue;
+            }
+
+            if asset.location().is_none() {
+                checks.push(ReferenceCheck {
+                    asset_id,
+                    status: status_from_errors(&asset.errors),
+                    owner: None,
+                    detail: asset.errors.first().cloned(),
+                });
+                continue;
+            }
+
+            let Some(game_owner) = game_owner else {
+                checks.push(ReferenceCheck {
+                    asset_id,
+                    status: ReferenceStatus::Ok,
+                    owner: None,
+                    detail: None,
+                });
+                continue;
+            };
+
+            let check = match self.asset_creator_with_retry(&asset_id.to_string()).await {
+                Ok(owner) => {
+                    let playable =
+                        owner == game_owner || owner == AssetCreator::User(ROBLOX_USER_ID);
+                    ReferenceCheck {
+                        asset_id,
+                        status: if playable {
+                            ReferenceStatus::Ok
+                        } else {
+                            ReferenceStatus::Private
+                        },
+                        owner: Some(owner),
+                        detail: None,
+                    }
+                }
+                Err(e) => {
+                    warn!("Could not get the owner of animation {}: {}", asset_id, e);
+                    ReferenceCheck {
+                        asset_id,
+                        status: ReferenceStatus::Ok,
+                        owner: None,
+                        detail: Some(format!("owner unknown: {}", e)),
+                    }
+                }
+            };
+            checks.push(check);
+        }
+
+        let broken = checks
+            .iter()
+            .filter(|check| check.status.is_broken())
+            .count();
+        info!("Checked {} animations, {} broken", checks.len(), broken);
+        Ok(checks)
+    }
+}
+
+/// Reads the asset delivery errors of an asset that has no download location.
+fn status_from_errors(errors: &[String]) -> ReferenceStatus {
+    let errors = errors.join(" ").to_lowercase();
+    if errors.contains("moderat") {
+        ReferenceStatus::Moderated
+    } else if errors.contains("not authorized")
+        || errors.contains("permission")
+        || errors.contains("403")
+    {
+        ReferenceStatus::Private
+    } else if errors.contains("not found") || errors.contains("404") || errors.is_empty() {
+        ReferenceStatus::Deleted
+    } else {
+        ReferenceStatus::Unavailable
+    }
+}
@@ -0,0 +1,39 @@
+use roboat::RoboatError;
+use std::fmt;
+
+use crate::AnimationUploader;
+
+/// The Roblox account a cookie belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub user_id: u64,
+    pub username: String,
+}
+
+impl fmt::Display for AuthenticatedUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.username, self.user_id)
+    }
+}
+
+impl AnimationUploader {
+    /// Validates the cookie and returns the account it belongs to.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// An expired cookie otherwise only shows up as upload errors partway through a run, so
+    /// call this before starting one.
+    pub async fn authenticated_user(&self) -> anyhow::Result<AuthenticatedUser> {
+        let user_id = self.api.user_id(&self.roblosecurity).await.map_err(|e| match e {
+            RoboatError::InvalidRoblosecurity | RoboatError::RoblosecurityNotSet => {
+                anyhow::anyhow!(
+                    "The .ROBLOSECURITY cookie is invalid or expired, copy a fresh one from your browser"
+                )
+            }
+            e => anyhow::anyhow!("Failed to check the cookie: {}", e),
+        })?;
+        let username = self.api.username(&self.roblosecurity).await?;
+
+        Ok(AuthenticatedUser { user_id, username })
+    }
+}
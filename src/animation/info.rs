@@ -1,58 +1,253 @@
 use bytes::Bytes;
-use reqwest::header::{HeaderMap, HeaderValue};
 use roboat::{
-    ClientBuilder, RoboatError,
+    RoboatError,
     assetdelivery::{AssetBatchPayload, AssetBatchResponse},
 };
 use std::collections::HashMap;
-use tokio::time::Duration;
-
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::failure::FailureKind;
+use super::metrics::MetricsRecorder;
+use super::model::{AnimationAsset, AssetCreator};
+use super::target::UploadTarget;
 use crate::AnimationUploader;
 
-const DEFAULT_TIMEOUT_SECS: u64 = 10;
 const BATCH_SIZE: usize = 250;
-const MAX_FETCH_RETRIES: u32 = 9;
 
 impl AnimationUploader {
+    /// Looks up whether an asset is owned by a user or a group.
+    pub async fn asset_creator(&self, asset_id: u64) -> anyhow::Result<AssetCreator> {
+        let (_, creator) = self.asset_details(asset_id).await?;
+        Ok(creator)
+    }
+
+    /// Looks up an asset's display name and owner.
+    /// * Requires a cookie
+    pub async fn asset_details(
+        &self,
+        asset_id: u64,
+    ) -> anyhow::Result<(Option<String>, AssetCreator)> {
+        self.api.asset_details(&self.roblosecurity, asset_id).await
+    }
+
+    /// Fills in the name and owner of every animation that doesn't have them yet.
+    /// Lookups that fail are reported and leave the animation unchanged.
+    /// * Requires a cookie
+    pub async fn fill_asset_details(&self, animations: &mut [AnimationAsset]) {
+        for animation in animations {
+            if animation.name.is_some() && animation.creator.is_some() {
+                continue;
+            }
+
+            match self.asset_details(animation.id).await {
+                Ok((name, creator)) => {
+                    animation.name = name;
+                    animation.creator = Some(creator);
+                }
+                Err(e) => warn!("Failed to get details of animation {}: {}", animation.id, e),
+            }
+        }
+    }
+
+    /// Keeps only the animations owned by `creator`, looking up the owner of each animation
+    /// the batch API didn't return one for. Animations whose owner can't be found are dropped.
+    /// * Requires a cookie
+    pub async fn animations_owned_by(
+        &self,
+        animations: Vec<AnimationAsset>,
+        creator: AssetCreator,
+    ) -> Vec<AnimationAsset> {
+        let mut owned = Vec::new();
+
+        for mut animation in animations {
+            if animation.creator.is_none() {
+                match self
+                    .asset_creator_with_retry(&animation.id.to_string())
+                    .await
+                {
+                    Ok(found) => animation.creator = Some(found),
+                    Err(e) => {
+                        warn!("Failed to get owner of animation {}: {}", animation.id, e);
+                        continue;
+                    }
+                }
+            }
+
+            if animation.creator == Some(creator) {
+                owned.push(animation);
+            }
+        }
+
+        owned
+    }
+
+    /// Splits the animations into the ones to reupload and the ones the upload target (a group,
+    /// or the cookie's user) already owns, which reuploading would
+    /// only duplicate. Owners the batch API didn't return are looked up one by one.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let (to_upload, owned) = uploader.split_already_owned(animations, UploadTarget::Group(123)).await?;
+    /// let mapping = uploader.reupload_animations(to_upload, &options).await?;
+    /// ```
+    ///
+    /// # Notes:
+    /// An animation whose owner can't be found is kept for reuploading, so a failed lookup
+    /// never leaves a foreign animation in the place.
+    pub async fn split_already_owned<T: Into<UploadTarget>>(
+        &self,
+        animations: Vec<AnimationAsset>,
+        target: T,
+    ) -> anyhow::Result<(Vec<AnimationAsset>, Vec<AnimationAsset>)> {
+        let owner = self.target_owner(target).await?;
+
+        let mut to_upload = Vec::new();
+        let mut owned = Vec::new();
+        for mut animation in animations {
+            if animation.creator.is_none() {
+                match self
+                    .asset_creator_with_retry(&animation.id.to_string())
+                    .await
+                {
+                    Ok(found) => animation.creator = Some(found),
+                    Err(e) => warn!("Failed to get owner of animation {}: {}", animation.id, e),
+                }
+            }
+
+            if animation.creator == Some(owner) {
+                owned.push(animation);
+            } else {
+                to_upload.push(animation);
+            }
+        }
+
+        Ok((to_upload, owned))
+    }
+
     /// Fetches animation metadata for multiple assets.
     pub async fn fetch_animation_assets(
         &self,
         asset_ids: Vec<u64>,
-    ) -> anyhow::Result<Vec<AssetBatchResponse>> {
+    ) -> anyhow::Result<Vec<AnimationAsset>> {
+        self.fetch_animation_assets_with_cancel(asset_ids, &CancellationToken::new())
+            .await
+    }
+
+    /// Like fetch_animation_assets, but returns an error as soon as `cancel_token` is
+    /// cancelled instead of finishing the remaining batches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let cancel_token = CancellationToken::new();
+    /// cancel_button.on_click({
+    ///     let cancel_token = cancel_token.clone();
+    ///     move || cancel_token.cancel()
+    /// });
+    /// let animations = uploader
+    ///     .fetch_animation_assets_with_cancel(asset_ids, &cancel_token)
+    ///     .await?;
+    /// ```
+    pub async fn fetch_animation_assets_with_cancel(
+        &self,
+        asset_ids: Vec<u64>,
+        cancel_token: &CancellationToken,
+    ) -> anyhow::Result<Vec<AnimationAsset>> {
         let mut animations = Vec::new();
+        let mut other_assets = 0;
+        MetricsRecorder::add(&self.metrics.assets_scanned, asset_ids.len() as u64);
 
         for batch in asset_ids.chunks(BATCH_SIZE) {
-            let batch_animations = fetch_single_batch(self, batch).await?;
-            animations.extend(batch_animations);
+            let batch_animations = tokio::select! {
+                batch_animations = fetch_single_batch(self, batch) => batch_animations?,
+                _ = cancel_token.cancelled() => anyhow::bail!("Fetching animations was cancelled"),
+            };
+            for asset in batch_animations
+                .into_iter()
+                .filter_map(|response| AnimationAsset::try_from(response).ok())
+            {
+                if asset.is_animation() {
+                    animations.push(asset);
+                } else {
+                    debug!(
+                        "Skipping asset {} of type {:?}",
+                        asset.id, asset.asset_type_id
+                    );
+                    other_assets += 1;
+                }
+            }
         }
 
+        MetricsRecorder::add(&self.metrics.assets_fetched, animations.len() as u64);
+        if other_assets > 0 {
+            info!(
+                "Skipped {} referenced assets that aren't animations",
+                other_assets
+            );
+        }
         Ok(animations)
     }
 
+    /// Fetches batch metadata (download locations, name, owner, asset type) for assets of any
+    /// type, e.g. the models loaded with require(). Assets the API returns nothing for are
+    /// left out.
+    pub async fn fetch_assets(&self, asset_ids: Vec<u64>) -> anyhow::Result<Vec<AnimationAsset>> {
+        let mut assets = Vec::new();
+        for batch in asset_ids.chunks(BATCH_SIZE) {
+            assets.extend(
+                fetch_single_batch(self, batch)
+                    .await?
+                    .into_iter()
+                    .filter_map(|response| AnimationAsset::try_from(response).ok()),
+            );
+        }
+        Ok(assets)
+    }
+
     /// Downloads file bytes from a URL with retry logic.
     pub async fn file_bytes_from_url(&self, url: String) -> Result<Bytes, RoboatError> {
-        const MAX_RETRIES: usize = 3;
-        const TIMEOUT_SECS: u64 = 15;
+        let response = self.download_response(&url).await?;
+        let data = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+        MetricsRecorder::add(&self.metrics.bytes_downloaded, data.len() as u64);
+        Ok(data)
+    }
 
-        let client = reqwest::Client::new();
+    /// Sends a download request with retry logic, returning the response before its body is
+    /// read.
+    pub(crate) async fn download_response(
+        &self,
+        url: &str,
+    ) -> Result<reqwest::Response, RoboatError> {
+        let max_attempts = self.config.retry.max_download_retries + 1;
 
-        for attempt in 1..=MAX_RETRIES {
+        for attempt in 1..=max_attempts {
             let result =
-                tokio::time::timeout(Duration::from_secs(TIMEOUT_SECS), client.get(&url).send())
-                    .await;
+                tokio::time::timeout(self.config.download_timeout, self.api.download(url)).await;
 
             match result {
+                Ok(Ok(response)) if response.status().is_success() => {
+                    return Ok(response);
+                }
                 Ok(Ok(response)) => {
-                    return response.bytes().await.map_err(RoboatError::ReqwestError);
+                    // A deleted or private asset stays that way, only retry the rest
+                    let status = response.status().as_u16();
+                    if FailureKind::from_status(status).is_permanent() || attempt == max_attempts {
+                        return Err(RoboatError::UnidentifiedStatusCode(status));
+                    }
+                    warn!("Getting file from animation url returned HTTP {}", status);
                 }
                 Ok(Err(e)) => {
-                    if attempt == MAX_RETRIES {
-                        return Err(RoboatError::ReqwestError(e));
+                    if attempt == max_attempts {
+                        return Err(e);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Getting file from animation url error: {:?}", e);
-                    if attempt == MAX_RETRIES {
+                    warn!("Getting file from animation url error: {:?}", e);
+                    if attempt == max_attempts {
                         return Err(RoboatError::InternalServerError);
                     }
                 }
@@ -103,20 +298,13 @@ async fn attempt_batch_fetch(
     loop {
         let payload = create_batch_payloads(asset_ids);
 
-        match check_asset_metadata(
-            uploader,
-            payload,
-            place_id,
-            Duration::from_secs(DEFAULT_TIMEOUT_SECS),
-        )
-        .await
-        {
+        match check_asset_metadata(uploader, payload, place_id).await {
             Ok(Some(responses)) => {
                 process_batch_responses(uploader, responses, success_responses, failed_ids).await;
                 break;
             }
             Ok(None) => {
-                println!("No responses received from batch fetch");
+                debug!("No responses received from batch fetch");
                 break;
             }
             Err(e) => {
@@ -156,11 +344,11 @@ async fn handle_failed_asset(
 ) {
     match fetch_asset_place_id(uploader, asset_id, failed_ids).await {
         Ok(place_id) => {
-            println!("Found place_id: {} for asset: {}", place_id, asset_id);
+            debug!("Found place_id: {} for asset: {}", place_id, asset_id);
             failed_ids.entry(place_id).or_default().push(asset_id);
         }
         Err(e) => {
-            eprintln!("Failed to get place_id for asset {}: {}", asset_id, e);
+            warn!("Failed to get place_id for asset {}: {}", asset_id, e);
         }
     }
 }
@@ -172,8 +360,9 @@ async fn handle_fetch_error(
     attempts: &mut u32,
 ) -> anyhow::Result<bool> {
     *attempts += 1;
+    let retry = &uploader.config.retry;
 
-    if *attempts > MAX_FETCH_RETRIES {
+    if *attempts > retry.max_fetch_retries {
         return Err(anyhow::anyhow!("Max retries exceeded"));
     }
 
@@ -181,7 +370,7 @@ async fn handle_fetch_error(
     if let Some(roboat_error) = error.downcast_ref::<RoboatError>()
         && matches!(roboat_error, RoboatError::TooManyRequests)
     {
-        let sleep_time = (*attempts as u64) * 30;
+        let sleep_time = retry.rate_limit_wait(*attempts);
         uploader.rate_limiter.set_rate_limit(sleep_time).await;
         uploader.rate_limiter.wait_if_limited().await;
         return Ok(true);
@@ -189,11 +378,11 @@ async fn handle_fetch_error(
 
     // Handle retryable errors
     if should_retry_error(error) {
-        println!(
+        warn!(
             "Request failed, retrying (attempt {}/{}): {}",
-            attempts, MAX_FETCH_RETRIES, error
+            attempts, retry.max_fetch_retries, error
         );
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        tokio::time::sleep(retry.fetch_retry_delay).await;
         return Ok(true);
     }
 
@@ -210,22 +399,22 @@ async fn resolve_failed_assets(
     for (place_id, vec_assets) in asset_and_places {
         let payload = create_batch_payloads(&vec_assets);
 
-        match check_asset_metadata(uploader, payload, place_id, Duration::from_secs(5)).await {
+        match check_asset_metadata(uploader, payload, place_id).await {
             Ok(Some(responses)) => {
                 for response in responses {
                     if response.errors.is_none() {
-                        println!("Successfully resolved asset: {:?}", response.request_id);
+                        debug!("Successfully resolved asset: {:?}", response.request_id);
                         resolved_responses.push(response);
                     } else {
-                        eprintln!(
+                        warn!(
                             "Failed to resolve asset {:?} with place_id {}",
                             response.request_id, place_id
                         );
                     }
                 }
             }
-            Ok(None) => println!("No response for place_id {}", place_id),
-            Err(e) => eprintln!("Error resolving assets for place_id {}: {:?}", place_id, e),
+            Ok(None) => debug!("No response for place_id {}", place_id),
+            Err(e) => warn!("Error resolving assets for place_id {}: {:?}", place_id, e),
         }
     }
 
@@ -245,7 +434,7 @@ async fn get_initial_place_id(
         match fetch_asset_place_id(uploader, asset_id, &mut empty_map).await {
             Ok(place_id) => return Ok(place_id),
             Err(e) => {
-                eprintln!("Error getting place for asset {}: {:?}", asset_id, e);
+                warn!("Error getting place for asset {}: {:?}", asset_id, e);
             }
         }
     }
@@ -264,7 +453,7 @@ async fn fetch_asset_place_id(
     // Check cache first
     for (&place_id, assets) in cached_places.iter() {
         if assets.contains(&asset_id) {
-            println!(
+            debug!(
                 "Found place_id {} in cache for asset {}",
                 place_id, asset_id
             );
@@ -276,7 +465,7 @@ async fn fetch_asset_place_id(
     let mut attempt = 0;
     loop {
         attempt += 1;
-        println!(
+        debug!(
             "Attempt {} to fetch place_id for asset {}",
             attempt, asset_id
         );
@@ -284,7 +473,7 @@ async fn fetch_asset_place_id(
         match get_place_id_from_asset(uploader, asset_id, cached_places).await {
             Ok(place_id) => {
                 cached_places.entry(place_id).or_default().push(asset_id);
-                println!(
+                debug!(
                     "Successfully fetched place_id {} for asset {} after {} attempts",
                     place_id, asset_id, attempt
                 );
@@ -292,15 +481,15 @@ async fn fetch_asset_place_id(
             }
             Err(e) => {
                 if let Some(RoboatError::TooManyRequests) = e.downcast_ref::<RoboatError>() {
-                    let sleep_time = 4 + (attempt % 10);
-                    println!(
+                    let sleep_time = Duration::from_secs(4 + (attempt % 10));
+                    warn!(
                         "Rate limited while fetching place_id (attempt {}), \
-                         setting global rate limit for {} seconds...",
+                         setting global rate limit for {:?}...",
                         attempt, sleep_time
                     );
                     uploader.rate_limiter.set_rate_limit(sleep_time).await;
                     uploader.rate_limiter.wait_if_limited().await;
-                    println!("Rate limit wait complete, retrying place_id fetch...");
+                    debug!("Rate limit wait complete, retrying place_id fetch...");
                 } else {
                     return Err(anyhow::anyhow!(
                         "Failed to get place_id after {} attempts: {}",
@@ -319,62 +508,31 @@ async fn get_place_id_from_asset(
     asset_id: u64,
     cached_places: &mut HashMap<u64, Vec<u64>>,
 ) -> anyhow::Result<u64> {
-    let client = ClientBuilder::new()
-        .roblosecurity(uploader.roblosecurity.to_string())
-        .build();
-
-    let asset_info = client.get_asset_info(asset_id).await?;
-
-    // Check if owned by user
-    if let Some(user_id) = asset_info.creation_context.creator.user_id {
-        let user_id_parsed = user_id
-            .parse::<u64>()
-            .map_err(|e| anyhow::anyhow!("Failed to parse user_id '{}': {}", user_id, e))?;
+    let place_id = get_root_place_id(uploader, uploader.asset_creator(asset_id).await?).await?;
 
-        let place_id = get_user_place_id(user_id_parsed).await?;
-        cached_places.entry(place_id).or_default().push(asset_id);
-        return Ok(place_id);
-    }
-
-    // Check if owned by group
-    if let Some(group_id) = asset_info.creation_context.creator.group_id {
-        let group_id_parsed = group_id
-            .parse::<u64>()
-            .map_err(|e| anyhow::anyhow!("Failed to parse group_id '{}': {}", group_id, e))?;
-
-        let place_id = get_group_place_id(group_id_parsed).await?;
-        cached_places.entry(place_id).or_default().push(asset_id);
-        return Ok(place_id);
-    }
-
-    Err(anyhow::anyhow!(
-        "No user_id or group_id found for asset {}",
-        asset_id
-    ))
-}
-
-/// Gets the root place ID for a user.
-async fn get_user_place_id(user_id: u64) -> anyhow::Result<u64> {
-    let client = ClientBuilder::new().build();
-    let games_response = client.user_games(user_id).await?;
-
-    games_response
-        .data
-        .first()
-        .map(|place| place.root_place.id)
-        .ok_or_else(|| anyhow::anyhow!("Couldn't find place for user {}", user_id))
+    cached_places.entry(place_id).or_default().push(asset_id);
+    Ok(place_id)
 }
 
-/// Gets the root place ID for a group.
-async fn get_group_place_id(group_id: u64) -> anyhow::Result<u64> {
-    let client = ClientBuilder::new().build();
-    let games_response = client.group_games(group_id).await?;
-
-    games_response
-        .data
+/// Gets the root place ID of a user's or group's first game.
+async fn get_root_place_id(
+    uploader: &AnimationUploader,
+    creator: AssetCreator,
+) -> anyhow::Result<u64> {
+    uploader
+        .api
+        .root_place_ids(creator)
+        .await?
         .first()
-        .map(|place| place.root_place.id)
-        .ok_or_else(|| anyhow::anyhow!("Couldn't find place for group {}", group_id))
+        .copied()
+        .ok_or_else(|| match creator {
+            AssetCreator::User(user_id) => {
+                anyhow::anyhow!("Couldn't find place for user {}", user_id)
+            }
+            AssetCreator::Group(group_id) => {
+                anyhow::anyhow!("Couldn't find place for group {}", group_id)
+            }
+        })
 }
 
 // [ASSET METADATA API]
@@ -384,29 +542,12 @@ async fn check_asset_metadata(
     uploader: &AnimationUploader,
     asset_ids: Vec<AssetBatchPayload>,
     place_id: u64,
-    timeout_secs: Duration,
 ) -> anyhow::Result<Option<Vec<AssetBatchResponse>>> {
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Roblox-Place-Id",
-        HeaderValue::from_str(&place_id.to_string())?,
-    );
-
-    let timeout_client = reqwest::ClientBuilder::new()
-        .timeout(timeout_secs)
-        .default_headers(headers)
-        .build()
-        .map_err(RoboatError::ReqwestError)?;
-
-    let client = ClientBuilder::new()
-        .roblosecurity(uploader.roblosecurity.clone())
-        .reqwest_client(timeout_client)
-        .build();
-
-    match client.post_asset_metadata_batch(asset_ids).await {
-        Ok(x) => Ok(Some(x)),
-        Err(e) => Err(e.into()),
-    }
+    let responses = uploader
+        .api
+        .asset_metadata_batch(&uploader.roblosecurity, asset_ids, place_id)
+        .await?;
+    Ok(Some(responses))
 }
 
 // [HELPER FUNCTIONS]
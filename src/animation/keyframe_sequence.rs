@@ -0,0 +1,371 @@
+use bytes::Bytes;
+use rbx_dom_weak::types::{Ref, Variant};
+use rbx_dom_weak::{Instance, InstanceBuilder, WeakDom};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::correlation::CorrelationId;
+use crate::AnimationUploader;
+use crate::studio::props;
+
+/// R6 joint (Pose) names and the R15 joints they become. Head keeps its name but moves under
+/// UpperTorso.
+const R6_TO_R15: [(&str, &str); 6] = [
+    ("Torso", "LowerTorso"),
+    ("Head", "Head"),
+    ("Left Arm", "LeftUpperArm"),
+    ("Right Arm", "RightUpperArm"),
+    ("Left Leg", "LeftUpperLeg"),
+    ("Right Leg", "RightUpperLeg"),
+];
+
+/// R15 joints that have no R6 counterpart. Their poses are dropped with everything under them.
+const R15_ONLY: [&str; 8] = [
+    "LeftLowerArm",
+    "LeftHand",
+    "RightLowerArm",
+    "RightHand",
+    "LeftLowerLeg",
+    "LeftFoot",
+    "RightLowerLeg",
+    "RightFoot",
+];
+
+/// Which rig an animation is retargeted from and to before it is reuploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Retarget {
+    /// Play R6 animations on R15 characters.
+    #[value(name = "r6-to-r15")]
+    R6ToR15,
+    /// Play R15 animations on R6 characters.
+    #[value(name = "r15-to-r6")]
+    R15ToR6,
+}
+
+/// A moment scripts can listen for while an animation plays: a renamed Keyframe
+/// (KeyframeReached) or a KeyframeMarker (GetMarkerReachedSignal).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEvent {
+    pub name: String,
+    /// Time of the keyframe the event is on, in seconds.
+    pub time: f32,
+    /// The KeyframeMarker's Value, empty for keyframe names.
+    pub value: String,
+}
+
+/// A KeyframeSequence as Roblox serves animation assets: a model whose root is the sequence,
+/// with Keyframes holding a tree of Poses named after the rig's parts.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut sequence = KeyframeSequenceDom::from_bytes(&animation_file)?;
+/// sequence.retarget(Retarget::R6ToR15);
+/// let animation_file = sequence.to_bytes()?;
+/// ```
+pub struct KeyframeSequenceDom {
+    dom: WeakDom,
+    root: Ref,
+}
+
+impl KeyframeSequenceDom {
+    /// Reads a KeyframeSequence from a binary (.rbxm) or XML (.rbxmx) model.
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let dom = if data.starts_with(b"<roblox!") {
+            rbx_binary::from_reader(data)
+                .map_err(|e| anyhow::anyhow!("Failed to parse animation: {}", e))?
+        } else {
+            rbx_xml::from_reader_default(data)
+                .map_err(|e| anyhow::anyhow!("Failed to parse XML animation: {}", e))?
+        };
+
+        let root = dom
+            .root()
+            .children()
+            .iter()
+            .copied()
+            .find(|child| {
+                dom.get_by_ref(*child)
+                    .is_some_and(|instance| instance.class == "KeyframeSequence")
+            })
+            .ok_or_else(|| anyhow::anyhow!("Animation has no KeyframeSequence"))?;
+
+        Ok(Self { dom, root })
+    }
+
+    /// Writes the KeyframeSequence as a binary model, ready to upload.
+    pub fn to_bytes(&self) -> anyhow::Result<Bytes> {
+        let mut data = Vec::new();
+        rbx_binary::to_writer(&mut data, &self.dom, &[self.root])
+            .map_err(|e| anyhow::anyhow!("Failed to serialize animation: {}", e))?;
+        Ok(Bytes::from(data))
+    }
+
+    /// Length of the animation in seconds: the time of its last keyframe.
+    pub fn length(&self) -> f32 {
+        self.keyframes()
+            .filter_map(keyframe_time)
+            .fold(0.0, f32::max)
+    }
+
+    pub fn keyframe_count(&self) -> usize {
+        self.keyframes().count()
+    }
+
+    /// The animation's events, sorted by time and name. Keyframes still named "Keyframe"
+    /// aren't events.
+    pub fn events(&self) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        for keyframe in self.keyframes() {
+            let time = keyframe_time(keyframe).unwrap_or(0.0);
+            if keyframe.name != "Keyframe" {
+                events.push(AnimationEvent {
+                    name: keyframe.name.clone(),
+                    time,
+                    value: String::new(),
+                });
+            }
+
+            for marker in keyframe
+                .children()
+                .iter()
+                .filter_map(|child| self.dom.get_by_ref(*child))
+                .filter(|child| child.class == "KeyframeMarker")
+            {
+                let value = match props::find(marker, "Value") {
+                    Some((_, Variant::String(value))) => value.clone(),
+                    _ => String::new(),
+                };
+                events.push(AnimationEvent {
+                    name: marker.name.clone(),
+                    time,
+                    value,
+                });
+            }
+        }
+
+        events.sort_by(|a, b| a.time.total_cmp(&b.time).then_with(|| a.name.cmp(&b.name)));
+        events
+    }
+
+    /// The names of every joint (Pose) the animation moves, sorted, e.g. "Head", "Torso".
+    pub fn joint_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .dom
+            .descendants_of(self.root)
+            .filter(|instance| instance.class == "Pose")
+            .map(|instance| instance.name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// True when the animation has no keyframes or moves no joints, so it would play as
+    /// nothing.
+    pub fn is_empty(&self) -> bool {
+        self.keyframe_count() == 0
+            || !self
+                .dom
+                .descendants_of(self.root)
+                .any(|instance| instance.class == "Pose")
+    }
+
+    /// Renames and regroups the Poses of every Keyframe for the other rig. Returns how many
+    /// poses were retargeted.
+    ///
+    /// # Notes:
+    /// Pose rotations are kept as they are, which is close enough for most animations but not
+    /// exact, since the rigs' joints don't sit in the same places.
+    /// * R6 to R15: Head and the arms move under a new UpperTorso pose at rest, and elbows,
+    ///   knees, hands and feet stay at rest.
+    /// * R15 to R6: UpperTorso's rotation is dropped, as are the poses of the lower limbs,
+    ///   hands and feet.
+    pub fn retarget(&mut self, retarget: Retarget) -> usize {
+        let keyframes: Vec<Ref> = self.children(self.root);
+
+        keyframes
+            .into_iter()
+            .map(|keyframe| match retarget {
+                Retarget::R6ToR15 => self.retarget_r6_to_r15(keyframe),
+                Retarget::R15ToR6 => self.retarget_r15_to_r6(keyframe),
+            })
+            .sum()
+    }
+
+    fn retarget_r6_to_r15(&mut self, keyframe: Ref) -> usize {
+        let mut retargeted = 0;
+        let mut lower_torso = None;
+        let mut upper_body = Vec::new();
+
+        for (pose, name) in self.poses_in(keyframe) {
+            let Some((_, new_name)) = R6_TO_R15.iter().find(|(r6_name, _)| *r6_name == name) else {
+                continue;
+            };
+            self.rename(pose, new_name);
+            retargeted += 1;
+
+            match *new_name {
+                "LowerTorso" => lower_torso = Some(pose),
+                "Head" | "LeftUpperArm" | "RightUpperArm" => upper_body.push(pose),
+                _ => {}
+            }
+        }
+
+        // R15 hangs the head and arms off UpperTorso, which R6 doesn't have
+        if let Some(lower_torso) = lower_torso
+            && !upper_body.is_empty()
+        {
+            let upper_torso = self.dom.insert(
+                lower_torso,
+                InstanceBuilder::new("Pose").with_name("UpperTorso"),
+            );
+            for pose in upper_body {
+                self.dom.transfer_within(pose, upper_torso);
+            }
+        }
+
+        retargeted
+    }
+
+    fn retarget_r15_to_r6(&mut self, keyframe: Ref) -> usize {
+        let mut retargeted = 0;
+        let poses = self.poses_in(keyframe);
+
+        // Hang UpperTorso's children off LowerTorso (the R6 Torso) directly
+        for (pose, name) in &poses {
+            if name != "UpperTorso" {
+                continue;
+            }
+            let Some(parent) = self.dom.get_by_ref(*pose).map(|pose| pose.parent()) else {
+                continue;
+            };
+            for child in self.children(*pose) {
+                self.dom.transfer_within(child, parent);
+            }
+            self.dom.destroy(*pose);
+            retargeted += 1;
+        }
+
+        for (pose, name) in poses {
+            // Already gone with a dropped ancestor
+            if self.dom.get_by_ref(pose).is_none() {
+                continue;
+            }
+            if R15_ONLY.contains(&name.as_str()) {
+                self.dom.destroy(pose);
+                retargeted += 1;
+            } else if let Some((r6_name, _)) =
+                R6_TO_R15.iter().find(|(_, r15_name)| *r15_name == name)
+            {
+                self.rename(pose, r6_name);
+                retargeted += 1;
+            }
+        }
+
+        retargeted
+    }
+
+    fn keyframes(&self) -> impl Iterator<Item = &Instance> {
+        self.dom
+            .get_by_ref(self.root)
+            .into_iter()
+            .flat_map(|root| root.children())
+            .filter_map(|child| self.dom.get_by_ref(*child))
+            .filter(|child| child.class == "Keyframe")
+    }
+
+    /// Every Pose under a Keyframe, parents before children.
+    fn poses_in(&self, keyframe: Ref) -> Vec<(Ref, String)> {
+        self.dom
+            .descendants_of(keyframe)
+            .filter(|instance| instance.class == "Pose")
+            .map(|instance| (instance.referent(), instance.name.clone()))
+            .collect()
+    }
+
+    fn children(&self, referent: Ref) -> Vec<Ref> {
+        self.dom
+            .get_by_ref(referent)
+            .map(|instance| instance.children().to_vec())
+            .unwrap_or_default()
+    }
+
+    fn rename(&mut self, referent: Ref, name: &str) {
+        if let Some(instance) = self.dom.get_by_ref_mut(referent) {
+            instance.name = name.to_string();
+        }
+    }
+}
+
+fn keyframe_time(keyframe: &Instance) -> Option<f32> {
+    match props::find(keyframe, "Time") {
+        Some((_, Variant::Float32(time))) => Some(*time),
+        Some((_, Variant::Float64(time))) => Some(*time as f32),
+        _ => None,
+    }
+}
+
+/// Parses a downloaded animation, retargets it and serializes it again for upload.
+pub fn retarget_animation(data: &[u8], retarget: Retarget) -> anyhow::Result<Bytes> {
+    let mut sequence = KeyframeSequenceDom::from_bytes(data)?;
+    sequence.retarget(retarget);
+    sequence.to_bytes()
+}
+
+impl AnimationUploader {
+    /// Decodes a downloaded animation file into its KeyframeSequence, e.g. to check its length
+    /// and joints before uploading it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let data = uploader.file_bytes_from_url(location).await?;
+    /// let sequence = uploader.decode_keyframe_sequence(data)?;
+    /// if sequence.is_empty() {
+    ///     println!("Skipping an empty animation");
+    /// }
+    /// ```
+    pub fn decode_keyframe_sequence(&self, data: Bytes) -> anyhow::Result<KeyframeSequenceDom> {
+        KeyframeSequenceDom::from_bytes(&data)
+    }
+
+    /// Downloads and decodes animations, returning the result for each asset ID in the order
+    /// given. Assets that aren't animations or can't be downloaded get an error.
+    /// * Requires a cookie
+    pub async fn inspect_animations(
+        &self,
+        asset_ids: Vec<u64>,
+    ) -> anyhow::Result<Vec<(u64, anyhow::Result<KeyframeSequenceDom>)>> {
+        let animations = self.fetch_animation_assets(asset_ids.clone()).await?;
+        let mut inspected = Vec::new();
+
+        for asset_id in asset_ids {
+            let location = animations
+                .iter()
+                .find(|animation| animation.id == asset_id)
+                .and_then(|animation| animation.location());
+            let Some(location) = location else {
+                inspected.push((
+                    asset_id,
+                    Err(anyhow::anyhow!(
+                        "Not found, not an animation or not downloadable"
+                    )),
+                ));
+                continue;
+            };
+
+            let sequence = match self.file_bytes_from_url(location.to_string()).await {
+                Ok(data) => self.decode_keyframe_sequence(data),
+                Err(e) => {
+                    let correlation_id = CorrelationId::new(asset_id);
+                    warn!(correlation_id = %correlation_id, "Failed to download animation: {}", e);
+                    Err(anyhow::anyhow!("Failed to download: {}", e))
+                }
+            };
+            inspected.push((asset_id, sequence));
+        }
+
+        Ok(inspected)
+    }
+}
@@ -0,0 +1,193 @@
+use crate::artifacts::{MappingV2, SCHEMA_VERSION, parse_artifact};
+use crate::report::{UploadReceipt, upload_receipts};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Old → new asset IDs, the shape load_mapping returns.
+pub type Mapping = HashMap<String, String>;
+
+/// Where the CLI writes the inverse of the mapping a run applied, unless told otherwise.
+pub const DEFAULT_INVERSE_MAPPING_FILE: &str = "inverse-mapping.json";
+
+/// Where the CLI saves the mapping of the uploads that finished before a run was interrupted.
+pub const DEFAULT_INTERRUPTED_MAPPING_FILE: &str = "interrupted-mapping.json";
+
+/// How merge_mappings resolves an old ID that maps to different new IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictResolution {
+    /// Refuse to merge while any conflict exists.
+    Fail,
+    /// Keep the new ID from the first mapping that has it.
+    First,
+    /// Keep the new ID from the last mapping that has it.
+    Last,
+}
+
+/// An old ID that different mappings send to different new IDs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingConflict {
+    pub old_id: String,
+    /// Every distinct new ID, in the order the mappings were given.
+    pub new_ids: Vec<String>,
+}
+
+/// Loads an old → new animation mapping from a MappingV2 file.
+pub fn load_mapping<P: AsRef<Path>>(path: P) -> anyhow::Result<HashMap<String, String>> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read mapping '{}': {}", path.display(), e))?;
+
+    let mapping: MappingV2 = parse_artifact(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to parse mapping '{}': {}", path.display(), e))?;
+    Ok(mapping.mappings.into_iter().collect())
+}
+
+/// Saves an old → new animation mapping as a MappingV2 file, sorted by old ID.
+pub fn save_mapping<P: AsRef<Path>>(
+    path: P,
+    animation_mapping: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    write_mapping(path.as_ref(), animation_mapping, BTreeMap::new(), false)
+}
+
+/// Saves the new → old inverse of an old → new mapping, marked so that
+/// load_revert_mapping uses it as it is.
+///
+/// # Notes:
+/// See invert_mapping for new IDs shared by several old IDs.
+pub fn save_inverse_mapping<P: AsRef<Path>>(
+    path: P,
+    animation_mapping: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    write_mapping(
+        path.as_ref(),
+        &invert_mapping(animation_mapping),
+        BTreeMap::new(),
+        true,
+    )
+}
+
+/// Loads the new → old mapping revert swaps IDs back with.
+///
+/// # Notes:
+/// Files saved by save_inverse_mapping are already new → old and are used as they are, any
+/// other mapping is taken as old → new and inverted.
+pub fn load_revert_mapping<P: AsRef<Path>>(path: P) -> anyhow::Result<HashMap<String, String>> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read mapping '{}': {}", path.display(), e))?;
+
+    let mapping: MappingV2 = parse_artifact(&contents)
+        .map_err(|e| anyhow::anyhow!("Failed to parse mapping '{}': {}", path.display(), e))?;
+    let inverse = mapping.inverse;
+    let mappings: HashMap<String, String> = mapping.mappings.into_iter().collect();
+    if inverse {
+        Ok(mappings)
+    } else {
+        Ok(invert_mapping(&mappings))
+    }
+}
+
+/// Saves a mapping of freshly uploaded animations, with a Creator Hub link for every new ID
+/// under the destination they were uploaded to.
+pub fn save_mapping_with_receipts<P: AsRef<Path>>(
+    path: P,
+    animation_mapping: &HashMap<String, String>,
+    group_id: Option<u64>,
+) -> anyhow::Result<()> {
+    write_mapping(
+        path.as_ref(),
+        animation_mapping,
+        upload_receipts(animation_mapping, group_id),
+        false,
+    )
+}
+
+fn write_mapping(
+    path: &Path,
+    animation_mapping: &HashMap<String, String>,
+    receipts: BTreeMap<String, Vec<UploadReceipt>>,
+    inverse: bool,
+) -> anyhow::Result<()> {
+    let mapping = MappingV2 {
+        schema_version: SCHEMA_VERSION,
+        mappings: animation_mapping
+            .iter()
+            .map(|(old_id, new_id)| (old_id.clone(), new_id.clone()))
+            .collect(),
+        receipts,
+        inverse,
+    };
+    let contents = serde_json::to_string_pretty(&mapping)?;
+
+    fs::write(path, contents)
+        .map_err(|e| anyhow::anyhow!("Failed to write mapping '{}': {}", path.display(), e))
+}
+
+/// Turns an old → new mapping around into new → old, to swap the new IDs back with
+/// apply_mapping and undo a migration.
+///
+/// # Notes:
+/// The upload cache can map several old IDs to the same new ID. Those can't be told apart
+/// anymore, so the new ID is mapped back to the lowest of them and a warning names the
+/// others.
+pub fn invert_mapping(animation_mapping: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut entries: Vec<(&String, &String)> = animation_mapping.iter().collect();
+    entries.sort_by_key(|(old_id, _)| (old_id.parse::<u64>().ok(), *old_id));
+
+    let mut inverse: HashMap<String, String> = HashMap::new();
+    for (old_id, new_id) in entries {
+        match inverse.get(new_id) {
+            Some(kept) => warn!(
+                "{} and {} both map to {}, it is mapped back to {} only",
+                kept, old_id, new_id, kept
+            ),
+            None => {
+                inverse.insert(new_id.clone(), old_id.clone());
+            }
+        }
+    }
+    inverse
+}
+
+/// Merges mappings from several partial runs into one.
+///
+/// # Notes:
+/// Conflicts are always returned so they can be reported. With `ConflictResolution::Fail`
+/// conflicting old IDs are left out of the merged mapping.
+pub fn merge_mappings(
+    mappings: &[HashMap<String, String>],
+    resolution: ConflictResolution,
+) -> (HashMap<String, String>, Vec<MappingConflict>) {
+    let mut candidates: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+    for mapping in mappings {
+        for (old_id, new_id) in mapping {
+            let new_ids = candidates.entry(old_id).or_default();
+            if !new_ids.contains(&new_id) {
+                new_ids.push(new_id);
+            }
+        }
+    }
+
+    let mut merged = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (old_id, new_ids) in candidates {
+        if new_ids.len() > 1 {
+            conflicts.push(MappingConflict {
+                old_id: old_id.clone(),
+                new_ids: new_ids.iter().map(|id| id.to_string()).collect(),
+            });
+        }
+
+        let chosen = match resolution {
+            ConflictResolution::Fail if new_ids.len() > 1 => continue,
+            ConflictResolution::Fail | ConflictResolution::First => new_ids[0],
+            ConflictResolution::Last => new_ids[new_ids.len() - 1],
+        };
+        merged.insert(old_id.clone(), chosen.clone());
+    }
+
+    (merged, conflicts)
+}
@@ -0,0 +1,189 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counters of everything an AnimationUploader did, from AnimationUploader::run_metrics.
+///
+/// # Examples
+///
+/// ```rust
+/// let mapping = uploader.clone().reupload_animations(animations, &options).await?;
+/// let metrics = uploader.run_metrics();
+/// println!("{}", metrics);
+/// metrics.save("metrics.json")?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunMetrics {
+    /// Asset IDs looked up with the batch metadata API.
+    pub assets_scanned: u64,
+    /// Animations the lookups returned.
+    pub assets_fetched: u64,
+    /// Animations uploaded, not counting cache hits.
+    pub uploaded: u64,
+    /// Animations reused from the upload cache instead of uploaded again.
+    pub reused: u64,
+    pub failed: u64,
+    pub bytes_downloaded: u64,
+    /// Bytes sent in upload attempts, including retried ones.
+    pub bytes_uploaded: u64,
+    /// How long the uploader was held back by rate limits, in wall time.
+    #[serde(rename = "rate_limit_wait_secs", serialize_with = "as_secs")]
+    pub rate_limit_wait: Duration,
+    /// Time since the uploader was created.
+    #[serde(rename = "wall_time_secs", serialize_with = "as_secs")]
+    pub wall_time: Duration,
+    /// The uploaded animations that took longest to download and upload, slowest first.
+    pub slowest: Vec<AssetTiming>,
+    /// The largest uploaded animations, largest first.
+    pub largest: Vec<AssetTiming>,
+}
+
+/// How many of the slowest and largest animations RunMetrics lists.
+pub const OUTLIER_COUNT: usize = 5;
+
+/// How long one animation took to download and upload, recorded for every uploaded animation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AssetTiming {
+    pub asset_id: u64,
+    /// Zero for animations embedded in the place.
+    #[serde(rename = "download_secs", serialize_with = "as_secs")]
+    pub download: Duration,
+    /// Includes the retries and the rate limit waits in between.
+    #[serde(rename = "upload_secs", serialize_with = "as_secs")]
+    pub upload: Duration,
+    pub bytes: u64,
+    pub attempts: u32,
+}
+
+impl AssetTiming {
+    pub fn total(&self) -> Duration {
+        self.download + self.upload
+    }
+}
+
+impl fmt::Display for AssetTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {:.1}s download, {:.1}s upload, {} attempts)",
+            self.asset_id,
+            human_bytes(self.bytes),
+            self.download.as_secs_f64(),
+            self.upload.as_secs_f64(),
+            self.attempts
+        )
+    }
+}
+
+impl RunMetrics {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write metrics '{}': {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for RunMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} scanned, {} fetched, {} uploaded, {} reused, {} failed, {} downloaded, {} uploaded, \
+             {:.1}s rate limited, {:.1}s total",
+            self.assets_scanned,
+            self.assets_fetched,
+            self.uploaded,
+            self.reused,
+            self.failed,
+            human_bytes(self.bytes_downloaded),
+            human_bytes(self.bytes_uploaded),
+            self.rate_limit_wait.as_secs_f64(),
+            self.wall_time.as_secs_f64()
+        )
+    }
+}
+
+fn as_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// A byte count in B, KiB or MiB.
+fn human_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let value = bytes as f64;
+    if value < KIB {
+        format!("{} B", bytes)
+    } else if value < KIB * KIB {
+        format!("{:.1} KiB", value / KIB)
+    } else {
+        format!("{:.1} MiB", value / (KIB * KIB))
+    }
+}
+
+/// The counters behind RunMetrics, updated by every task of the uploader.
+#[derive(Debug)]
+pub(crate) struct MetricsRecorder {
+    started: Instant,
+    pub(crate) assets_scanned: AtomicU64,
+    pub(crate) assets_fetched: AtomicU64,
+    pub(crate) uploaded: AtomicU64,
+    pub(crate) reused: AtomicU64,
+    pub(crate) failed: AtomicU64,
+    pub(crate) bytes_downloaded: AtomicU64,
+    pub(crate) bytes_uploaded: AtomicU64,
+    timings: Mutex<Vec<AssetTiming>>,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            assets_scanned: AtomicU64::new(0),
+            assets_fetched: AtomicU64::new(0),
+            uploaded: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            bytes_downloaded: AtomicU64::new(0),
+            bytes_uploaded: AtomicU64::new(0),
+            timings: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn add(counter: &AtomicU64, amount: u64) {
+        counter.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_timing(&self, timing: AssetTiming) {
+        self.timings.lock().unwrap().push(timing);
+    }
+
+    pub(crate) fn snapshot(&self, rate_limit_wait: Duration) -> RunMetrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let timings = self.timings.lock().unwrap();
+
+        let mut slowest = timings.clone();
+        slowest.sort_by_key(|timing| std::cmp::Reverse(timing.total()));
+        slowest.truncate(OUTLIER_COUNT);
+        let mut largest = timings.clone();
+        largest.sort_by_key(|timing| std::cmp::Reverse(timing.bytes));
+        largest.truncate(OUTLIER_COUNT);
+
+        RunMetrics {
+            assets_scanned: load(&self.assets_scanned),
+            assets_fetched: load(&self.assets_fetched),
+            uploaded: load(&self.uploaded),
+            reused: load(&self.reused),
+            failed: load(&self.failed),
+            bytes_downloaded: load(&self.bytes_downloaded),
+            bytes_uploaded: load(&self.bytes_uploaded),
+            rate_limit_wait,
+            wall_time: self.started.elapsed(),
+            slowest,
+            largest,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::api::{MockApiAsset, MockCall, MockRobloxApi, mock_location};
+use super::model::{ANIMATION_ASSET_TYPE_ID, AssetCreator};
+
+/// A MockRobloxApi described in JSON, so a test outside Rust (or the CLI's --mock-api) can
+/// set one up.
+///
+/// # Examples
+///
+/// ```json
+/// {
+///     "user_id": 1,
+///     "username": "Owner",
+///     "assets": {
+///         "100": { "creator": { "User": 2 }, "data": "d2Fsaw==" },
+///         "101": { "creator": { "Group": 5 } }
+///     },
+///     "rate_limits": { "upload_animation": 2 }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockFixture {
+    pub user_id: Option<u64>,
+    pub username: Option<String>,
+    /// Assets by ID.
+    #[serde(default)]
+    pub assets: BTreeMap<u64, MockFixtureAsset>,
+    /// Groups the user may not create items in.
+    #[serde(default)]
+    pub groups_without_permission: Vec<u64>,
+    /// How many times each call answers with a 429 before it goes through.
+    #[serde(default)]
+    pub rate_limits: HashMap<MockCall, u32>,
+}
+
+/// An asset of a MockFixture.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockFixtureAsset {
+    pub creator: AssetCreator,
+    pub name: Option<String>,
+    /// Defaults to an animation.
+    #[serde(default = "animation_asset_type_id")]
+    pub asset_type_id: u64,
+    /// What a download returns, base64 encoded. An asset without data has no download
+    /// location.
+    pub data: Option<String>,
+}
+
+fn animation_asset_type_id() -> u64 {
+    ANIMATION_ASSET_TYPE_ID
+}
+
+impl MockRobloxApi {
+    /// Builds a mock from a MockFixture JSON file.
+    pub fn from_fixture<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read fixture '{}': {}", path.display(), e))?;
+        let fixture: MockFixture = serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse fixture '{}': {}", path.display(), e))?;
+        MockRobloxApi::try_from(fixture)
+    }
+}
+
+impl TryFrom<MockFixture> for MockRobloxApi {
+    type Error = anyhow::Error;
+
+    fn try_from(fixture: MockFixture) -> Result<Self, Self::Error> {
+        let mut api = MockRobloxApi::new();
+        if let Some(user_id) = fixture.user_id {
+            api = api.with_user(user_id, fixture.username.unwrap_or_default());
+        }
+
+        for (asset_id, asset) in fixture.assets {
+            let data = asset
+                .data
+                .map(|data| STANDARD.decode(data))
+                .transpose()
+                .map_err(|e| anyhow::anyhow!("Invalid data of asset {}: {}", asset_id, e))?;
+            api = api.with_asset_details(
+                asset_id,
+                MockApiAsset {
+                    creator: asset.creator,
+                    name: asset.name,
+                    location: data.as_ref().map(|_| mock_location(asset_id)),
+                    asset_type_id: asset.asset_type_id,
+                    data: data.map(Bytes::from),
+                },
+            );
+        }
+        for group_id in fixture.groups_without_permission {
+            api = api.without_group_permission(group_id);
+        }
+        for (call, times) in fixture.rate_limits {
+            api = api.with_rate_limit(call, times);
+        }
+        Ok(api)
+    }
+}
@@ -1,18 +1,56 @@
 use crate::AnimationUploader;
 use crate::StudioParser;
+use crate::animation::model::AnimationAsset;
+use crate::animation::roblox_assets::is_roblox_asset;
 use roboat::RoboatError;
-use roboat::assetdelivery::AssetBatchResponse;
+use tracing::info;
 
 pub type UploadTask = tokio::task::JoinHandle<Result<(Option<String>, String), RoboatError>>;
+pub mod api;
+pub mod blocklist;
+pub mod config;
+pub mod cookie_pool;
+pub mod correlation;
+pub mod credentials;
+pub mod download;
+pub mod events;
+pub mod failed_uploads;
+pub mod failure;
+pub mod health;
+pub mod identity;
 pub mod info;
+pub mod keyframe_sequence;
+pub mod mapping;
+pub mod metrics;
+#[cfg(feature = "mock-server")]
+pub mod mock_fixture;
+pub mod model;
+pub mod moderation;
+pub mod options;
+pub mod owners;
+pub mod permissions;
+pub mod pipeline;
+pub mod place;
+pub mod progress;
+#[cfg(feature = "record-replay")]
+pub mod record_replay;
+pub mod roblox_assets;
+pub mod scan_file;
+pub mod target;
 pub mod tasks;
+pub mod upload_cache;
+pub mod upload_dir;
 pub mod uploader;
+pub mod verify;
 
 // Implement uploader code into the studio struct
 impl StudioParser {
     pub fn animation_uploader(&self) -> Result<AnimationUploader, RoboatError> {
         match &self.roblosecurity {
-            Some(cookie) => Ok(AnimationUploader::new(cookie.clone())),
+            Some(cookie) => {
+                AnimationUploader::with_config(cookie.clone(), self.uploader_config.clone())
+                    .map_err(RoboatError::ReqwestError)
+            }
             None => Err(RoboatError::InvalidRoblosecurity),
         }
     }
@@ -20,8 +58,19 @@ impl StudioParser {
     pub async fn fetch_animation_assets(
         &self,
         asset_ids: Vec<u64>,
-    ) -> anyhow::Result<Vec<AssetBatchResponse>> {
+    ) -> anyhow::Result<Vec<AnimationAsset>> {
         let uploader = self.animation_uploader()?;
-        uploader.fetch_animation_assets(asset_ids).await
+        let mut animations = uploader.fetch_animation_assets(asset_ids).await?;
+        if !self.include_roblox_assets {
+            let before = animations.len();
+            animations.retain(|animation| !is_roblox_asset(animation));
+            if animations.len() < before {
+                info!(
+                    "Skipped {} default Roblox animations",
+                    before - animations.len()
+                );
+            }
+        }
+        Ok(animations)
     }
 }
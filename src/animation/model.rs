@@ -0,0 +1,87 @@
+use roboat::assetdelivery::AssetBatchResponse;
+use serde::{Deserialize, Serialize};
+
+/// Roblox's asset type ID for animations.
+pub const ANIMATION_ASSET_TYPE_ID: u64 = 24;
+
+/// The owner of an asset on Roblox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AssetCreator {
+    User(u64),
+    Group(u64),
+}
+
+/// An animation asset as returned by the crate's public APIs.
+/// Owned by this crate so roboat can be upgraded or swapped without breaking callers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnimationAsset {
+    pub id: u64,
+    /// CDN URLs the asset file can be downloaded from.
+    pub locations: Vec<String>,
+    /// Errors reported by the asset delivery API for this asset.
+    pub errors: Vec<String>,
+    /// Filled in when known; the batch API doesn't return names.
+    pub name: Option<String>,
+    /// Filled in when known; the batch API doesn't return owners.
+    pub creator: Option<AssetCreator>,
+    /// Roblox asset type ID reported by the batch API, e.g. 24 for animations.
+    #[serde(default)]
+    pub asset_type_id: Option<u64>,
+}
+
+impl AnimationAsset {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            locations: Vec::new(),
+            errors: Vec::new(),
+            name: None,
+            creator: None,
+            asset_type_id: None,
+        }
+    }
+
+    /// The first CDN location, used to download the asset.
+    pub fn location(&self) -> Option<&str> {
+        self.locations.first().map(String::as_str)
+    }
+
+    /// False only when the batch API reported another asset type, e.g. a sound or image whose
+    /// ID was found next to animation IDs in a script.
+    pub fn is_animation(&self) -> bool {
+        self.asset_type_id
+            .is_none_or(|asset_type_id| asset_type_id == ANIMATION_ASSET_TYPE_ID)
+    }
+}
+
+impl TryFrom<AssetBatchResponse> for AnimationAsset {
+    type Error = anyhow::Error;
+
+    fn try_from(response: AssetBatchResponse) -> Result<Self, Self::Error> {
+        let request_id = response
+            .request_id
+            .ok_or_else(|| anyhow::anyhow!("Batch response has no request_id"))?;
+        let id = request_id
+            .parse::<u64>()
+            .map_err(|e| anyhow::anyhow!("Failed to parse request_id '{}': {}", request_id, e))?;
+
+        Ok(Self {
+            id,
+            locations: response
+                .locations
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|location| location.location)
+                .collect(),
+            errors: response
+                .errors
+                .unwrap_or_default()
+                .iter()
+                .map(|error| format!("{:?}", error))
+                .collect(),
+            name: None,
+            creator: None,
+            asset_type_id: response.asset_type_id,
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use roboat::RoboatError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::AnimationUploader;
+
+/// Where Roblox moderation is with an uploaded asset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModerationState {
+    Approved,
+    /// Still being reviewed; the asset may fail to load in-game until it is approved.
+    Reviewing,
+    Rejected,
+}
+
+/// The outcome of wait_for_moderation. Every ID ends up in exactly one list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModerationResults {
+    pub approved: Vec<u64>,
+    pub rejected: Vec<u64>,
+    /// Still under review when the timeout ran out.
+    pub pending: Vec<u64>,
+    /// The lookup failed for a reason other than a rate limit, e.g. the asset doesn't exist
+    /// or belongs to someone else.
+    pub failed: Vec<u64>,
+}
+
+impl AnimationUploader {
+    /// Looks up the moderation state of an asset the cookie's user can manage.
+    /// * Requires a cookie
+    pub async fn moderation_state(&self, asset_id: u64) -> anyhow::Result<ModerationState> {
+        self.api
+            .moderation_state(&self.roblosecurity, asset_id)
+            .await
+            .map_err(|e| match e {
+                RoboatError::UnidentifiedStatusCode(status) => anyhow::anyhow!(
+                    "Failed to get moderation state of {}: HTTP {}",
+                    asset_id,
+                    status
+                ),
+                // Kept as a RoboatError so rate limits can be told apart
+                e => e.into(),
+            })
+    }
+
+    /// Polls the moderation state of newly uploaded assets every `interval` until each is
+    /// approved or rejected, or `timeout` has passed.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// Rate limited lookups count as still reviewing and are retried on the next poll. Any
+    /// other failed lookup (a missing asset, one the cookie's user can't manage...) puts the
+    /// ID in `failed` instead.
+    pub async fn wait_for_moderation(
+        &self,
+        asset_ids: Vec<u64>,
+        interval: Duration,
+        timeout: Duration,
+    ) -> ModerationResults {
+        let started = Instant::now();
+        let mut pending: BTreeSet<u64> = asset_ids.into_iter().collect();
+        let mut results = ModerationResults::default();
+
+        loop {
+            for asset_id in pending.clone() {
+                self.rate_limiter.wait_if_limited().await;
+                match self.moderation_state(asset_id).await {
+                    Ok(ModerationState::Approved) => {
+                        pending.remove(&asset_id);
+                        results.approved.push(asset_id);
+                    }
+                    Ok(ModerationState::Rejected) => {
+                        warn!("Animation {} was rejected by moderation", asset_id);
+                        pending.remove(&asset_id);
+                        results.rejected.push(asset_id);
+                    }
+                    Ok(ModerationState::Reviewing) => {}
+                    Err(e) => {
+                        if let Some(RoboatError::TooManyRequests) = e.downcast_ref::<RoboatError>()
+                        {
+                            self.rate_limiter
+                                .set_rate_limit(self.config.retry.rate_limit_wait(1))
+                                .await;
+                        } else {
+                            warn!("Failed to get moderation state of {}: {}", asset_id, e);
+                            pending.remove(&asset_id);
+                            results.failed.push(asset_id);
+                        }
+                    }
+                }
+            }
+
+            if pending.is_empty() || started.elapsed() + interval > timeout {
+                break;
+            }
+            info!("{} animations still under moderation review", pending.len());
+            tokio::time::sleep(interval).await;
+        }
+
+        results.pending = pending.into_iter().collect();
+        results.approved.sort();
+        results.rejected.sort();
+        results.failed.sort();
+        results
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::keyframe_sequence::Retarget;
+use super::progress::{ProgressCallback, UploadProgress};
+use super::target::UploadTarget;
+use super::upload_cache::UploadCache;
+
+pub(crate) const DEFAULT_CONCURRENT_TASKS: u64 = 50;
+pub(crate) const DEFAULT_UPLOAD_RETRIES: usize = 4;
+
+/// What a batch upload does when some of its animations fail to upload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FailurePolicy {
+    /// Stop at the first failed upload, cancel the rest and return its error.
+    Abort,
+    /// Upload everything that can be uploaded and return the mapping of what succeeded.
+    #[default]
+    #[value(name = "continue")]
+    ContinueAndReport,
+    /// Upload everything, but return an error instead of a partial mapping if any upload
+    /// failed, so the place is left untouched.
+    #[value(name = "rollback")]
+    RequireAllOrRollback,
+}
+
+/// Settings for AnimationUploader::reupload_animations.
+///
+/// # Examples
+///
+/// ```rust
+/// let options = UploadOptions::new()
+///     .target(UploadTarget::Group(1234))
+///     .concurrency(10)
+///     .pacing(Duration::from_millis(500))
+///     .name("{id} (reupload)");
+/// let mapping = uploader.reupload_animations(animations, &options).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    pub(crate) target: UploadTarget,
+    pub(crate) concurrency: u64,
+    pub(crate) pacing: Option<Duration>,
+    pub(crate) max_retries: usize,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) verify: bool,
+    pub(crate) check_events: bool,
+    pub(crate) on_progress: Option<ProgressCallback>,
+    pub(crate) upload_cache: Option<Arc<Mutex<UploadCache>>>,
+    pub(crate) failure_policy: FailurePolicy,
+    pub(crate) retarget: Option<Retarget>,
+    pub(crate) cancel_token: Option<CancellationToken>,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            target: UploadTarget::CurrentUser,
+            concurrency: DEFAULT_CONCURRENT_TASKS,
+            pacing: None,
+            max_retries: DEFAULT_UPLOAD_RETRIES,
+            name: "reuploaded_animation".to_string(),
+            description: "This is a example".to_string(),
+            verify: false,
+            check_events: false,
+            on_progress: None,
+            upload_cache: None,
+            failure_policy: FailurePolicy::default(),
+            retarget: None,
+            cancel_token: None,
+        }
+    }
+}
+
+impl UploadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Who the uploads are owned by. Defaults to UploadTarget::CurrentUser.
+    pub fn target(mut self, target: UploadTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Who the uploads will be owned by.
+    pub fn upload_target(&self) -> UploadTarget {
+        self.target
+    }
+
+    /// Uploads to this group instead of the authenticated user. Same as target with
+    /// `group_id.into()`.
+    pub fn group_id(mut self, group_id: Option<u64>) -> Self {
+        self.target = group_id.into();
+        self
+    }
+
+    /// How many uploads run at the same time. Defaults to 50; 0 is raised to 1, since no
+    /// upload would ever start.
+    pub fn concurrency(mut self, concurrency: u64) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Waits this long after each upload before the task frees its slot.
+    pub fn pacing(mut self, pacing: Duration) -> Self {
+        self.pacing = Some(pacing);
+        self
+    }
+
+    /// How many times a failed upload is retried, so it is attempted up to `max_retries + 1`
+    /// times. Defaults to 4.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Name of the uploaded animations. `{id}` is replaced with the source animation ID.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Checks the uploaded animations with verify_uploaded_animations before returning,
+    /// dropping any that don't resolve or have the wrong owner.
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// With verify, also downloads each verified upload and its source and compares their
+    /// animation events (see check_animation_events), warning about the ones that changed.
+    /// They stay in the mapping.
+    pub fn check_events(mut self, check_events: bool) -> Self {
+        self.check_events = check_events;
+        self
+    }
+
+    /// Called after each upload finishes, e.g. to drive a progress bar.
+    /// When set, the per-animation success lines aren't printed; failures still are.
+    pub fn on_progress<F: Fn(&UploadProgress) + Send + Sync + 'static>(
+        mut self,
+        callback: F,
+    ) -> Self {
+        self.on_progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Reuses the new ID of an identical animation already uploaded to the same destination
+    /// instead of uploading it again, and records every new upload in the cache. Before the
+    /// uploads start, cached uploads that no longer download (deleted, rejected by
+    /// moderation) are dropped from it, so they are uploaded again instead.
+    pub fn upload_cache(mut self, upload_cache: Arc<Mutex<UploadCache>>) -> Self {
+        self.upload_cache = Some(upload_cache);
+        self
+    }
+
+    /// What to do when some uploads fail. Defaults to FailurePolicy::ContinueAndReport.
+    ///
+    /// # Notes:
+    /// With RequireAllOrRollback, animations dropped by verify also count as failed. Animations
+    /// that did upload stay on Roblox (and in the upload cache, so a retry reuses them); only
+    /// the mapping is withheld.
+    pub fn failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Retargets every animation to another rig before it is uploaded, e.g. so R6 animations
+    /// play on R15 characters. Embedded KeyframeSequences are retargeted too.
+    pub fn retarget(mut self, retarget: Retarget) -> Self {
+        self.retarget = Some(retarget);
+        self
+    }
+
+    /// Stops the upload early once the token is cancelled, e.g. from a GUI's cancel button or
+    /// on Ctrl-C. Uploads still in flight are cancelled, queued ones never start, and the
+    /// mapping of those that finished is returned, whatever the failure policy.
+    pub fn cancel_token(mut self, cancel_token: CancellationToken) -> Self {
+        self.cancel_token = Some(cancel_token);
+        self
+    }
+
+    /// The name for an upload of the given source animation.
+    pub(crate) fn name_for(&self, source_id: &str) -> String {
+        self.name.replace("{id}", source_id)
+    }
+}
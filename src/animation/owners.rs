@@ -0,0 +1,88 @@
+use roboat::RoboatError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use tracing::warn;
+
+use super::model::{AnimationAsset, AssetCreator};
+use crate::AnimationUploader;
+
+/// The animations one creator owns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerSummary {
+    /// None for animations whose owner couldn't be looked up.
+    pub creator: Option<AssetCreator>,
+    /// The user or group name, when it could be looked up.
+    pub name: Option<String>,
+    pub asset_ids: Vec<u64>,
+}
+
+impl fmt::Display for OwnerSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.creator {
+            Some(AssetCreator::User(user_id)) => write!(f, "user {}", user_id)?,
+            Some(AssetCreator::Group(group_id)) => write!(f, "group {}", group_id)?,
+            None => write!(f, "unknown owner")?,
+        }
+        if let Some(name) = &self.name {
+            write!(f, " \"{}\"", name)?;
+        }
+        write!(f, ": {} animations", self.asset_ids.len())
+    }
+}
+
+impl AnimationUploader {
+    /// Looks up the name of a user or group.
+    pub async fn creator_name(&self, creator: AssetCreator) -> anyhow::Result<String> {
+        match self.api.creator_name(creator).await {
+            Ok(name) => Ok(name),
+            Err(RoboatError::UnidentifiedStatusCode(404)) => {
+                anyhow::bail!("{:?} doesn't exist", creator)
+            }
+            Err(e) => anyhow::bail!("Failed to look up {:?}: {}", creator, e),
+        }
+    }
+
+    /// Groups animations by their current owner, with the owner's name, most animations first.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// Looks up the owner of every animation the batch API didn't return one for, so this
+    /// makes one request per animation on top of one per owner. Animations whose owner can't
+    /// be found are grouped under an unknown owner.
+    pub async fn owner_report(&self, mut animations: Vec<AnimationAsset>) -> Vec<OwnerSummary> {
+        self.fill_asset_details(&mut animations).await;
+
+        let mut by_owner: BTreeMap<Option<AssetCreator>, Vec<u64>> = BTreeMap::new();
+        for animation in &animations {
+            by_owner
+                .entry(animation.creator)
+                .or_default()
+                .push(animation.id);
+        }
+
+        let mut names: HashMap<AssetCreator, String> = HashMap::new();
+        for creator in by_owner.keys().flatten() {
+            match self.creator_name(*creator).await {
+                Ok(name) => {
+                    names.insert(*creator, name);
+                }
+                Err(e) => warn!("Failed to get the name of {:?}: {}", creator, e),
+            }
+        }
+
+        let mut summaries: Vec<OwnerSummary> = by_owner
+            .into_iter()
+            .map(|(creator, mut asset_ids)| {
+                asset_ids.sort();
+                OwnerSummary {
+                    name: creator.and_then(|creator| names.get(&creator).cloned()),
+                    creator,
+                    asset_ids,
+                }
+            })
+            .collect();
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.asset_ids.len()));
+        summaries
+    }
+}
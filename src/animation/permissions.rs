@@ -0,0 +1,57 @@
+use roboat::RoboatError;
+
+use crate::AnimationUploader;
+
+impl AnimationUploader {
+    /// Checks that the cookie's user may create items in a group, which uploading animations
+    /// to it needs. Errors with what to fix if not. Every account of the upload pool is
+    /// checked.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// Without this, a missing permission only shows up as a BadRequest from every upload task.
+    pub async fn check_group_upload_permission(&self, group_id: u64) -> anyhow::Result<()> {
+        for (index, account) in self.cookie_pool.accounts().iter().enumerate() {
+            self.check_group_permission_for(&account.roblosecurity, group_id)
+                .await
+                .map_err(|e| match index {
+                    0 => e,
+                    _ => anyhow::anyhow!("Cookie {} of the upload pool: {}", index + 1, e),
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn check_group_permission_for(
+        &self,
+        roblosecurity: &str,
+        group_id: u64,
+    ) -> anyhow::Result<()> {
+        let can_create = self
+            .api
+            .can_create_group_items(roblosecurity, group_id)
+            .await
+            .map_err(|e| match e {
+                RoboatError::UnidentifiedStatusCode(404) => {
+                    anyhow::anyhow!("Group {} doesn't exist", group_id)
+                }
+                RoboatError::InvalidRoblosecurity => anyhow::anyhow!(
+                    "The .ROBLOSECURITY cookie is invalid or expired, copy a fresh one from your browser"
+                ),
+                RoboatError::UnidentifiedStatusCode(status) => anyhow::anyhow!(
+                    "Failed to check group {} permissions: HTTP {}",
+                    group_id,
+                    status
+                ),
+                e => anyhow::anyhow!("Failed to check group {} permissions: {}", group_id, e),
+            })?;
+
+        if !can_create {
+            anyhow::bail!(
+                "Your account can't upload to group {}: its role needs the \"Create and edit group items\" permission",
+                group_id
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use crate::AnimationUploader;
+use crate::StudioParser;
+use crate::animation::model::AssetCreator;
+use crate::animation::options::UploadOptions;
+use crate::pipeline::{BoxFuture, Rewriter, Scanner, Uploader};
+use crate::report::ReplacementReport;
+use crate::script::types::AssetUrlStyle;
+use crate::studio::workplace_editor::ApplyScope;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// The animation asset kind for the Migrator: scans Animation instances and scripts,
+/// reuploads through AnimationUploader and rewrites with StudioParser::apply_mapping.
+pub struct AnimationPipeline {
+    uploader: Arc<AnimationUploader>,
+    options: UploadOptions,
+    owned_by: Option<AssetCreator>,
+    normalize_style: Option<AssetUrlStyle>,
+    apply_scope: ApplyScope,
+}
+
+impl AnimationPipeline {
+    pub fn new(uploader: Arc<AnimationUploader>, options: UploadOptions) -> Self {
+        Self {
+            uploader,
+            options,
+            owned_by: None,
+            normalize_style: None,
+            apply_scope: ApplyScope::All,
+        }
+    }
+
+    /// Only reuploads animations owned by this creator (e.g. the group being migrated away from).
+    pub fn owned_by(mut self, creator: AssetCreator) -> Self {
+        self.owned_by = Some(creator);
+        self
+    }
+
+    pub fn normalize_style(mut self, normalize_style: Option<AssetUrlStyle>) -> Self {
+        self.normalize_style = normalize_style;
+        self
+    }
+
+    pub fn apply_scope(mut self, apply_scope: ApplyScope) -> Self {
+        self.apply_scope = apply_scope;
+        self
+    }
+}
+
+impl Scanner for AnimationPipeline {
+    fn kind(&self) -> &'static str {
+        "animation"
+    }
+
+    fn scan(&self, parser: &StudioParser) -> Vec<u64> {
+        let mut asset_ids: HashSet<u64> = parser.animation_instance_ids().into_iter().collect();
+        asset_ids.extend(
+            parser
+                .script_asset_references()
+                .into_iter()
+                .map(|reference| reference.asset_id),
+        );
+
+        let mut asset_ids: Vec<u64> = asset_ids.into_iter().collect();
+        asset_ids.sort();
+        asset_ids
+    }
+}
+
+impl Uploader for AnimationPipeline {
+    fn upload(
+        &self,
+        asset_ids: Vec<u64>,
+    ) -> BoxFuture<'_, anyhow::Result<HashMap<String, String>>> {
+        Box::pin(async move {
+            // Script references can be any asset type, fetch_animation_assets keeps the animations
+            let mut animations = self.uploader.fetch_animation_assets(asset_ids).await?;
+            if let Some(creator) = self.owned_by {
+                animations = self.uploader.animations_owned_by(animations, creator).await;
+            }
+
+            self.uploader
+                .clone()
+                .reupload_animations(animations, &self.options)
+                .await
+        })
+    }
+}
+
+impl Rewriter for AnimationPipeline {
+    fn rewrite(
+        &self,
+        parser: &mut StudioParser,
+        mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
+        parser.apply_mapping(mapping, self.normalize_style, self.apply_scope)
+    }
+}
@@ -0,0 +1,118 @@
+use roboat::RoboatError;
+use serde::{Deserialize, Serialize};
+
+use crate::AnimationUploader;
+
+/// A saved version of a place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceVersion {
+    #[serde(rename = "assetVersionNumber")]
+    pub version_number: u64,
+    /// When the version was saved, as an ISO 8601 timestamp.
+    pub created: String,
+    /// Whether the version was published, rather than only saved.
+    #[serde(default)]
+    pub is_published: bool,
+}
+
+/// One page of saved versions, as the saved-versions endpoint returns them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceVersionsPage {
+    pub data: Vec<PlaceVersion>,
+    /// Where the next page starts, None or empty on the last page.
+    pub next_page_cursor: Option<String>,
+}
+
+impl AnimationUploader {
+    /// Looks up the universe (experience) a place belongs to.
+    pub async fn place_universe_id(&self, place_id: u64) -> anyhow::Result<u64> {
+        self.api
+            .place_universe_id(place_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to look up place {}: {}", place_id, e))?
+            .ok_or_else(|| anyhow::anyhow!("Place {} doesn't exist", place_id))
+    }
+
+    /// Lists every saved version of a place, newest first.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// The cookie's user needs edit access to the place. Team Create autosaves are versions too.
+    pub async fn list_place_versions(&self, place_id: u64) -> anyhow::Result<Vec<PlaceVersion>> {
+        let mut versions = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page = match self
+                .api
+                .place_versions_page(&self.roblosecurity, place_id, cursor.take())
+                .await
+            {
+                Ok(page) => page,
+                Err(
+                    RoboatError::InvalidRoblosecurity | RoboatError::UnidentifiedStatusCode(403),
+                ) => {
+                    anyhow::bail!(
+                        "Your account can't see the versions of place {}: it needs edit access to the place",
+                        place_id
+                    )
+                }
+                Err(e) => anyhow::bail!("Failed to list versions of place {}: {}", place_id, e),
+            };
+            versions.extend(page.data);
+
+            match page.next_page_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Downloads a place file, the same file Studio opens: the given saved version, or the
+    /// latest one if `version` is None.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// The cookie's user needs edit access to the place. The place is checked to belong to
+    /// `universe_id` first, so a mistyped ID fails before anything is downloaded.
+    pub async fn download_place(
+        &self,
+        universe_id: u64,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let place_universe_id = self.place_universe_id(place_id).await?;
+        if place_universe_id != universe_id {
+            anyhow::bail!(
+                "Place {} belongs to universe {}, not {}",
+                place_id,
+                place_universe_id,
+                universe_id
+            );
+        }
+
+        match self
+            .api
+            .download_place(&self.roblosecurity, place_id, version)
+            .await
+        {
+            Ok(data) => Ok(data.to_vec()),
+            Err(RoboatError::InvalidRoblosecurity | RoboatError::UnidentifiedStatusCode(403)) => {
+                anyhow::bail!(
+                    "Your account can't download place {}: it needs edit access to the place",
+                    place_id
+                )
+            }
+            Err(RoboatError::UnidentifiedStatusCode(404)) if version.is_some() => anyhow::bail!(
+                "Place {} has no version {}",
+                place_id,
+                version.unwrap_or_default()
+            ),
+            Err(e) => anyhow::bail!("Failed to download place {}: {}", place_id, e),
+        }
+    }
+}
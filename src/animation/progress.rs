@@ -0,0 +1,69 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Reported after each upload task finishes, successfully or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// The source animation the task was for.
+    pub asset_id: u64,
+    pub succeeded: bool,
+    /// Tasks that finished so far, including failed ones.
+    pub done: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+/// Callback set with UploadOptions::on_progress.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&UploadProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F: Fn(&UploadProgress) + Send + Sync + 'static>(callback: F) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Counts finished upload tasks across a run and reports them to the callback.
+pub(crate) struct ProgressTracker {
+    total: usize,
+    done: AtomicUsize,
+    failed: AtomicUsize,
+    callback: Option<ProgressCallback>,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(total: usize, callback: Option<ProgressCallback>) -> Self {
+        Self {
+            total,
+            done: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            callback,
+        }
+    }
+
+    pub(crate) fn record(&self, asset_id: u64, succeeded: bool) {
+        let failed = if succeeded {
+            self.failed.load(Ordering::SeqCst)
+        } else {
+            self.failed.fetch_add(1, Ordering::SeqCst) + 1
+        };
+        let done = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if let Some(ProgressCallback(callback)) = &self.callback {
+            callback(&UploadProgress {
+                asset_id,
+                succeeded,
+                done,
+                failed,
+                total: self.total,
+            });
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use roboat::RoboatError;
+use roboat::assetdelivery::{AssetBatchPayload, AssetBatchResponse};
+use roboat::ide::ide_types::NewAnimation;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::api::RobloxApi;
+use super::model::AssetCreator;
+use super::moderation::ModerationState;
+use super::place::PlaceVersionsPage;
+use super::upload_cache::content_hash;
+use crate::pipeline::BoxFuture;
+
+/// What a recorded call returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResult {
+    Ok(serde_json::Value),
+    Err(RecordedError),
+}
+
+/// A recorded error, kept to what the uploader tells apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedError {
+    TooManyRequests,
+    InternalServerError,
+    InvalidRoblosecurity,
+    Status(u16),
+    Other(String),
+}
+
+impl From<&RoboatError> for RecordedError {
+    fn from(error: &RoboatError) -> Self {
+        match error {
+            RoboatError::TooManyRequests => RecordedError::TooManyRequests,
+            RoboatError::InternalServerError => RecordedError::InternalServerError,
+            RoboatError::InvalidRoblosecurity => RecordedError::InvalidRoblosecurity,
+            RoboatError::UnidentifiedStatusCode(status) => RecordedError::Status(*status),
+            error => RecordedError::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<RecordedError> for RoboatError {
+    fn from(error: RecordedError) -> Self {
+        match error {
+            RecordedError::TooManyRequests => RoboatError::TooManyRequests,
+            RecordedError::InternalServerError => RoboatError::InternalServerError,
+            RecordedError::InvalidRoblosecurity => RoboatError::InvalidRoblosecurity,
+            RecordedError::Status(status) => RoboatError::UnidentifiedStatusCode(status),
+            RecordedError::Other(message) => {
+                RoboatError::UnknownRobloxErrorCode { code: 0, message }
+            }
+        }
+    }
+}
+
+/// A download as recorded: its status and body, base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedDownload {
+    status: u16,
+    body: String,
+}
+
+/// The fixtures file: the latest result of every call, keyed by the call and its
+/// arguments.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiFixtures {
+    pub calls: BTreeMap<String, RecordedResult>,
+}
+
+impl ApiFixtures {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read fixtures '{}': {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse fixtures '{}': {}", path.display(), e))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write fixtures '{}': {}", path.display(), e))
+    }
+}
+
+/// A RobloxApi that records what another one answers, and replays it offline later, so the
+/// scanning and upload pipeline can be developed and tested without the real API or a valid
+/// cookie.
+///
+/// # Examples
+///
+/// ```rust
+/// // Once, with a real cookie
+/// let recorder = Arc::new(RecordReplayApi::record(Arc::new(RoboatApi::new(config, client))));
+/// let uploader = AnimationUploader::builder()
+///     .roblosecurity(cookie)
+///     .api(recorder.clone())
+///     .build()?;
+/// uploader.fetch_animation_assets(vec![1234]).await?;
+/// recorder.save("fixtures.json")?;
+///
+/// // Then in tests, offline
+/// let replay = Arc::new(RecordReplayApi::replay("fixtures.json")?);
+/// ```
+///
+/// # Notes:
+/// Cookies are never recorded, so fixtures can be committed. Calls are keyed by their
+/// arguments (uploads by name, group and a hash of the data), and a replayed call that was
+/// never recorded fails with an UnknownRobloxErrorCode saying so.
+pub struct RecordReplayApi {
+    /// The API calls are forwarded to while recording, None when replaying.
+    inner: Option<Arc<dyn RobloxApi>>,
+    fixtures: Mutex<ApiFixtures>,
+}
+
+impl RecordReplayApi {
+    /// Forwards every call to `inner` and records what it answers.
+    pub fn record(inner: Arc<dyn RobloxApi>) -> Self {
+        Self {
+            inner: Some(inner),
+            fixtures: Mutex::new(ApiFixtures::default()),
+        }
+    }
+
+    /// Answers every call from a fixtures file written by save.
+    pub fn replay<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Ok(Self::from_fixtures(ApiFixtures::load(path)?))
+    }
+
+    /// Answers every call from fixtures already loaded.
+    pub fn from_fixtures(fixtures: ApiFixtures) -> Self {
+        Self {
+            inner: None,
+            fixtures: Mutex::new(fixtures),
+        }
+    }
+
+    /// What was recorded (or loaded) so far.
+    pub fn fixtures(&self) -> ApiFixtures {
+        self.fixtures.lock().unwrap().clone()
+    }
+
+    /// Writes what was recorded so far to a fixtures file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        self.fixtures.lock().unwrap().save(path)
+    }
+
+    /// Forwards the call and records its result, or replays the recorded one.
+    async fn answer<'a, T: Serialize + DeserializeOwned>(
+        &'a self,
+        key: String,
+        forward: impl FnOnce(&'a dyn RobloxApi) -> BoxFuture<'a, Result<T, RoboatError>>,
+    ) -> Result<T, RoboatError> {
+        let Some(inner) = &self.inner else {
+            let recorded = self.fixtures.lock().unwrap().calls.get(&key).cloned();
+            return match recorded {
+                Some(RecordedResult::Ok(value)) => {
+                    serde_json::from_value(value).map_err(|_| RoboatError::MalformedResponse)
+                }
+                Some(RecordedResult::Err(error)) => Err(error.into()),
+                None => Err(RoboatError::UnknownRobloxErrorCode {
+                    code: 0,
+                    message: format!("No recorded response for {}", key),
+                }),
+            };
+        };
+
+        let result = forward(inner.as_ref()).await;
+        let recorded = match &result {
+            Ok(value) => RecordedResult::Ok(
+                serde_json::to_value(value).map_err(|_| RoboatError::MalformedResponse)?,
+            ),
+            Err(error) => RecordedResult::Err(error.into()),
+        };
+        self.fixtures.lock().unwrap().calls.insert(key, recorded);
+        result
+    }
+}
+
+impl RobloxApi for RecordReplayApi {
+    fn asset_metadata_batch<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        payloads: Vec<AssetBatchPayload>,
+        place_id: u64,
+    ) -> BoxFuture<'a, Result<Vec<AssetBatchResponse>, RoboatError>> {
+        Box::pin(async move {
+            let asset_ids: Vec<&str> = payloads
+                .iter()
+                .map(|payload| payload.asset_id.as_deref().unwrap_or_default())
+                .collect();
+            let key = format!("asset_metadata_batch {} {}", place_id, asset_ids.join(","));
+            self.answer(key, |api| {
+                api.asset_metadata_batch(roblosecurity, payloads, place_id)
+            })
+            .await
+        })
+    }
+
+    fn asset_details<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, anyhow::Result<(Option<String>, AssetCreator)>> {
+        Box::pin(async move {
+            let key = format!("asset_details {}", asset_id);
+            let details = self
+                .answer(key, |api| {
+                    Box::pin(async move {
+                        api.asset_details(roblosecurity, asset_id)
+                            .await
+                            .map_err(|e| match e.downcast::<RoboatError>() {
+                                Ok(e) => e,
+                                Err(e) => RoboatError::UnknownRobloxErrorCode {
+                                    code: 0,
+                                    message: e.to_string(),
+                                },
+                            })
+                    })
+                })
+                .await?;
+            Ok(details)
+        })
+    }
+
+    fn root_place_ids(
+        &self,
+        creator: AssetCreator,
+    ) -> BoxFuture<'_, Result<Vec<u64>, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("root_place_ids {:?}", creator);
+            self.answer(key, |api| api.root_place_ids(creator)).await
+        })
+    }
+
+    fn asset_location<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<Option<String>, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("asset_location {}", asset_id);
+            self.answer(key, |api| api.asset_location(roblosecurity, asset_id))
+                .await
+        })
+    }
+
+    fn download<'a>(
+        &'a self,
+        url: &'a str,
+    ) -> BoxFuture<'a, Result<reqwest::Response, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("download {}", url);
+            // The body is read while recording, so the response is rebuilt from it either way
+            let recorded = self
+                .answer(key, |api| {
+                    Box::pin(async move {
+                        let response = api.download(url).await?;
+                        let status = response.status().as_u16();
+                        let body = response.bytes().await.map_err(RoboatError::ReqwestError)?;
+                        Ok(RecordedDownload {
+                            status,
+                            body: STANDARD.encode(&body),
+                        })
+                    })
+                })
+                .await?;
+
+            let body = STANDARD
+                .decode(recorded.body)
+                .map_err(|_| RoboatError::MalformedResponse)?;
+            let response = http::Response::builder()
+                .status(recorded.status)
+                .body(Bytes::from(body))
+                .map_err(|_| RoboatError::MalformedResponse)?;
+            Ok(reqwest::Response::from(response))
+        })
+    }
+
+    fn can_create_group_items<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        group_id: u64,
+    ) -> BoxFuture<'a, Result<bool, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("can_create_group_items {}", group_id);
+            self.answer(key, |api| {
+                api.can_create_group_items(roblosecurity, group_id)
+            })
+            .await
+        })
+    }
+
+    fn moderation_state<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        asset_id: u64,
+    ) -> BoxFuture<'a, Result<ModerationState, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("moderation_state {}", asset_id);
+            self.answer(key, |api| api.moderation_state(roblosecurity, asset_id))
+                .await
+        })
+    }
+
+    fn upload_model<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        data: Bytes,
+        name: &'a str,
+        group_id: Option<u64>,
+    ) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move {
+            let key = format!(
+                "upload_model {:?} {} {}",
+                group_id,
+                name,
+                content_hash(&data)
+            );
+            self.answer(key, |api| {
+                api.upload_model(roblosecurity, data, name, group_id)
+            })
+            .await
+        })
+    }
+
+    fn upload_animation<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        animation: NewAnimation,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move {
+            let key = format!(
+                "upload_animation {:?} {} {}",
+                animation.group_id,
+                animation.name,
+                content_hash(&animation.animation_data)
+            );
+            self.answer(key, |api| api.upload_animation(roblosecurity, animation))
+                .await
+        })
+    }
+
+    fn user_id<'a>(&'a self, roblosecurity: &'a str) -> BoxFuture<'a, Result<u64, RoboatError>> {
+        Box::pin(async move {
+            self.answer("user_id".to_string(), |api| api.user_id(roblosecurity))
+                .await
+        })
+    }
+
+    fn username<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+    ) -> BoxFuture<'a, Result<String, RoboatError>> {
+        Box::pin(async move {
+            self.answer("username".to_string(), |api| api.username(roblosecurity))
+                .await
+        })
+    }
+
+    fn creator_name(&self, creator: AssetCreator) -> BoxFuture<'_, Result<String, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("creator_name {:?}", creator);
+            self.answer(key, |api| api.creator_name(creator)).await
+        })
+    }
+
+    fn place_universe_id(&self, place_id: u64) -> BoxFuture<'_, Result<Option<u64>, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("place_universe_id {}", place_id);
+            self.answer(key, |api| api.place_universe_id(place_id))
+                .await
+        })
+    }
+
+    fn place_versions_page<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        cursor: Option<String>,
+    ) -> BoxFuture<'a, Result<PlaceVersionsPage, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("place_versions_page {} {:?}", place_id, cursor);
+            self.answer(key, |api| {
+                api.place_versions_page(roblosecurity, place_id, cursor)
+            })
+            .await
+        })
+    }
+
+    fn download_place<'a>(
+        &'a self,
+        roblosecurity: &'a str,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> BoxFuture<'a, Result<Bytes, RoboatError>> {
+        Box::pin(async move {
+            let key = format!("download_place {} {:?}", place_id, version);
+            let data = self
+                .answer(key, |api| {
+                    Box::pin(async move {
+                        let data = api.download_place(roblosecurity, place_id, version).await?;
+                        Ok(STANDARD.encode(&data))
+                    })
+                })
+                .await?;
+            STANDARD
+                .decode(data)
+                .map(Bytes::from)
+                .map_err(|_| RoboatError::MalformedResponse)
+        })
+    }
+}
@@ -0,0 +1,34 @@
+use crate::animation::model::{AnimationAsset, AssetCreator};
+
+/// The Roblox account, which owns the default character animations and catalog emotes.
+pub const ROBLOX_USER_ID: u64 = 1;
+
+/// The animations of the default R15 and R6 Animate scripts. Every character plays these, and
+/// Roblox makes them usable in any game, so reuploading them only breaks the link to the
+/// originals.
+pub const DEFAULT_ANIMATION_IDS: &[u64] = &[
+    // R15 Animate: idle, walk, run, swim, swimidle, jump, fall, climb, sit and tools
+    507766388, 507766666, 507777826, 507767714, 507784897, 507785072, 507765000, 507767968,
+    507765644, 2506281703, 507768375, 522638767, 522635514,
+    // R15 emotes: wave, point, dance, dance2, dance3, laugh, cheer
+    507770239, 507770453, 507771019, 507771955, 507772104, 507776043, 507776720, 507776879,
+    507777268, 507777451, 507777623, 507770818, 507770677,
+    // R6 Animate: idle, walk/run, jump, fall, climb, sit and tools
+    180435571, 180435792, 180426354, 125750702, 180436148, 180436334, 178130996, 182393478,
+    129967390, 129967478,
+    // R6 emotes: wave, point, dance, dance2, dance3, laugh, cheer
+    128777973, 128853357, 182435998, 182491037, 182491065, 182436842, 182491248, 182491277,
+    182436935, 182491368, 182491423, 129423131, 129423030,
+];
+
+/// True when the ID is one of the default Animate script animations.
+pub fn is_default_animation(asset_id: u64) -> bool {
+    DEFAULT_ANIMATION_IDS.contains(&asset_id)
+}
+
+/// True when the animation is a default one or is owned by the Roblox account, e.g. a catalog
+/// emote. The owner is only checked when it has been fetched.
+pub fn is_roblox_asset(animation: &AnimationAsset) -> bool {
+    is_default_animation(animation.id)
+        || animation.creator == Some(AssetCreator::User(ROBLOX_USER_ID))
+}
@@ -0,0 +1,57 @@
+use super::model::AnimationAsset;
+use crate::artifacts::{SCHEMA_VERSION, ScanReportV2, parse_artifact};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The animations a scan found, saved so they can be uploaded in a later step.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanFile {
+    /// The place the scan was run on.
+    pub source: String,
+    pub animations: Vec<AnimationAsset>,
+}
+
+impl ScanFile {
+    pub fn new<S: Into<String>>(source: S, animations: Vec<AnimationAsset>) -> Self {
+        Self {
+            source: source.into(),
+            animations,
+        }
+    }
+
+    /// Loads a ScanReportV2 file.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read scan '{}': {}", path.display(), e))?;
+
+        let scan: ScanReportV2 = parse_artifact(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse scan '{}': {}", path.display(), e))?;
+        Ok(Self::new(scan.source, scan.animations))
+    }
+
+    /// Saves the scan as a ScanReportV2 file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let scan = ScanReportV2 {
+            schema_version: SCHEMA_VERSION,
+            source: self.source.clone(),
+            animations: self.animations.clone(),
+        };
+        let contents = serde_json::to_string_pretty(&scan)?;
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write scan '{}': {}", path.display(), e))
+    }
+
+    /// The scanned asset IDs.
+    ///
+    /// # Notes:
+    /// Download locations in a scan expire, so uploads should refetch them from these IDs.
+    pub fn asset_ids(&self) -> Vec<u64> {
+        self.animations
+            .iter()
+            .map(|animation| animation.id)
+            .collect()
+    }
+}
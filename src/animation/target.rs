@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::model::AssetCreator;
+use crate::AnimationUploader;
+
+/// Who new uploads are owned by.
+///
+/// # Examples
+///
+/// ```rust
+/// let options = UploadOptions::new().target(UploadTarget::Group(1234));
+/// // Existing group_id arguments convert: None is the cookie's user
+/// let target: UploadTarget = args.group.into();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadTarget {
+    /// The user the cookie belongs to.
+    #[default]
+    CurrentUser,
+    /// A group the cookie's user may create items in.
+    Group(u64),
+}
+
+impl UploadTarget {
+    /// The group to upload to, or None for the cookie's user.
+    pub fn group_id(&self) -> Option<u64> {
+        match self {
+            UploadTarget::CurrentUser => None,
+            UploadTarget::Group(group_id) => Some(*group_id),
+        }
+    }
+}
+
+impl From<Option<u64>> for UploadTarget {
+    fn from(group_id: Option<u64>) -> Self {
+        match group_id {
+            Some(group_id) => UploadTarget::Group(group_id),
+            None => UploadTarget::CurrentUser,
+        }
+    }
+}
+
+impl fmt::Display for UploadTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadTarget::CurrentUser => write!(f, "user"),
+            UploadTarget::Group(group_id) => write!(f, "group {}", group_id),
+        }
+    }
+}
+
+impl AnimationUploader {
+    /// The creator uploads to `target` end up owned by, looking up the cookie's user for
+    /// UploadTarget::CurrentUser.
+    /// * Requires a cookie
+    pub async fn target_owner<T: Into<UploadTarget>>(
+        &self,
+        target: T,
+    ) -> anyhow::Result<AssetCreator> {
+        match target.into() {
+            UploadTarget::CurrentUser => Ok(AssetCreator::User(
+                self.api.user_id(&self.roblosecurity).await?,
+            )),
+            UploadTarget::Group(group_id) => Ok(AssetCreator::Group(group_id)),
+        }
+    }
+
+    /// Checks that uploads to `target` can work before any are made: the cookie has to be
+    /// valid, and for a group its user (and every other account of the upload pool) needs
+    /// permission to create items in it. Returns the owner the uploads will have.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let owner = uploader.validate_upload_target(UploadTarget::Group(1234)).await?;
+    /// ```
+    pub async fn validate_upload_target<T: Into<UploadTarget>>(
+        &self,
+        target: T,
+    ) -> anyhow::Result<AssetCreator> {
+        let target = target.into();
+        self.check_pool_target(target)?;
+        match target {
+            UploadTarget::CurrentUser => {
+                Ok(AssetCreator::User(self.authenticated_user().await?.user_id))
+            }
+            UploadTarget::Group(group_id) => {
+                self.check_group_upload_permission(group_id).await?;
+                Ok(AssetCreator::Group(group_id))
+            }
+        }
+    }
+
+    /// Uploads spread over several accounts would each be owned by the account that made them,
+    /// so a pool of more than one cookie has to upload to a group.
+    pub(crate) fn check_pool_target(&self, target: UploadTarget) -> anyhow::Result<()> {
+        if target == UploadTarget::CurrentUser && self.cookie_pool.len() > 1 {
+            anyhow::bail!(
+                "Uploading with {} cookies needs a group to upload to: each account would own the animations it uploads",
+                self.cookie_pool.len()
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,54 +1,97 @@
 use bytes::Bytes;
 use roboat::RoboatError;
-use roboat::assetdelivery::AssetBatchResponse;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::Semaphore;
-use tokio::time::Duration;
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, error, info, info_span, warn};
 
+use super::blocklist::is_moderation_rejection;
+use super::config::jitter;
+use super::correlation::CorrelationId;
+use super::download::AnimationFile;
+use super::failure::FailureKind;
+use super::keyframe_sequence::retarget_animation;
+use super::metrics::{AssetTiming, MetricsRecorder};
+use super::model::{AnimationAsset, AssetCreator};
+use super::options::{FailurePolicy, UploadOptions};
+use super::progress::ProgressTracker;
+use super::upload_cache::{CachedUpload, content_hash};
 use super::uploader::AnimationUploader;
 use crate::animation::UploadTask;
 
-const MAX_UPLOAD_RETRIES: usize = 5;
-
 // ============================================================================
 // RATE LIMITER
 // ============================================================================
 
 /// Handles rate limiting across all concurrent tasks
 pub struct RateLimiter {
-    until: tokio::sync::Mutex<Option<tokio::time::Instant>>,
+    /// When the current limit ends and how long it was set for.
+    until: tokio::sync::Mutex<Option<(tokio::time::Instant, Duration)>>,
+    /// Wall time covered by limits so far, in milliseconds.
+    limited_ms: AtomicU64,
 }
 
 impl RateLimiter {
     pub fn new() -> Self {
         Self {
             until: tokio::sync::Mutex::new(None),
+            limited_ms: AtomicU64::new(0),
         }
     }
 
-    /// Sets a rate limit that all tasks must wait for
-    pub async fn set_rate_limit(&self, duration_secs: u64) {
-        let wake_time = tokio::time::Instant::now() + Duration::from_secs(duration_secs);
-        *self.until.lock().await = Some(wake_time);
-        println!(
-            "Global rate limit set: all tasks sleeping {} seconds",
-            duration_secs
-        );
+    /// Sets a rate limit that all tasks must wait for. A longer limit already set by another
+    /// task is kept.
+    pub async fn set_rate_limit(&self, duration: Duration) {
+        let now = tokio::time::Instant::now();
+        let wake_time = now + duration;
+        let mut until = self.until.lock().await;
+        if until.is_some_and(|(current, _)| current >= wake_time) {
+            return;
+        }
+        // Only the part not already covered by the current limit counts towards the total
+        let covered_until = until.map_or(now, |(current, _)| current.max(now));
+        let extension = wake_time.saturating_duration_since(covered_until);
+        self.limited_ms
+            .fetch_add(extension.as_millis() as u64, Ordering::Relaxed);
+        *until = Some((wake_time, duration));
+        warn!("Global rate limit set: all tasks sleeping {:?}", duration);
     }
 
-    /// Waits if rate limit is currently active
+    /// Waits if rate limit is currently active. Tasks that waited wake up spread over a quarter
+    /// of the limit's length after it ends, so they don't all hit the API again at once.
     pub async fn wait_if_limited(&self) {
+        let mut waited = None;
         loop {
             let until = *self.until.lock().await;
-            if let Some(wake_time) = until
+            if let Some((wake_time, duration)) = until
                 && tokio::time::Instant::now() < wake_time
             {
                 tokio::time::sleep_until(wake_time).await;
+                waited = Some(duration);
                 continue;
             }
             break;
         }
+
+        if let Some(duration) = waited {
+            tokio::time::sleep(jitter(duration / 4)).await;
+        }
+    }
+
+    /// Whether a rate limit is in effect right now.
+    pub async fn is_limited(&self) -> bool {
+        self.until
+            .lock()
+            .await
+            .is_some_and(|(wake_time, _)| tokio::time::Instant::now() < wake_time)
+    }
+
+    /// Wall time covered by rate limits so far, however many tasks waited on them.
+    pub fn total_limited(&self) -> Duration {
+        Duration::from_millis(self.limited_ms.load(Ordering::Relaxed))
     }
 
     /// Clones the Arc for sharing across tasks
@@ -57,84 +100,210 @@ impl RateLimiter {
     }
 }
 
+// ============================================================================
+// RETRY BUDGET
+// ============================================================================
+
+/// How much of a retry each successful upload earns back.
+const RETRY_BUDGET_REFUND: f64 = 0.1;
+
+/// Upload retries shared by all tasks of a batch, set with RetryPolicy::retry_budget
+pub struct RetryBudget {
+    tokens: Mutex<f64>,
+    max_tokens: f64,
+}
+
+impl RetryBudget {
+    pub fn new(max_tokens: u32) -> Self {
+        Self {
+            tokens: Mutex::new(max_tokens as f64),
+            max_tokens: max_tokens as f64,
+        }
+    }
+
+    /// Spends one retry, or returns false when the budget is used up
+    pub fn try_spend(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+
+    /// Earns back part of a retry after a successful upload
+    pub fn refund(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + RETRY_BUDGET_REFUND).min(self.max_tokens);
+    }
+}
+
 // ============================================================================
 // PUBLIC FUNCTIONS - Upload Task Management
 // ============================================================================
 
-/// Spawns all upload tasks for concurrent animation uploads
+/// Where an upload task gets the animation file from.
+enum AnimationSource {
+    /// Downloaded from a CDN location.
+    Url(String),
+    /// Already in memory, e.g. a KeyframeSequence embedded in the place.
+    Embedded(Bytes),
+}
+
+/// Spawns all upload tasks for concurrent animation uploads.
+/// Animations in `embedded` are published from that data instead of being downloaded.
+/// Pass the same options.cancel_token to collect_upload_results, which aborts the tasks once
+/// it is cancelled.
 pub fn spawn_upload_tasks(
     uploader: Arc<AnimationUploader>,
-    animations: Vec<AssetBatchResponse>,
-    group_id: Option<u64>,
-    max_concurrent_tasks: u64,
+    animations: Vec<AnimationAsset>,
+    mut embedded: HashMap<u64, Bytes>,
+    options: UploadOptions,
+    cache_destination: Option<AssetCreator>,
     total_animations: usize,
-) -> Vec<UploadTask> {
-    let semaphore = Arc::new(Semaphore::new(max_concurrent_tasks as usize));
-    let roblosecurity = Arc::new(uploader.roblosecurity.clone());
-    let rate_limiter = uploader.rate_limiter.clone_arc();
-
-    animations
+) -> Vec<(u64, UploadTask)> {
+    let sources: Vec<(usize, u64, AnimationSource)> = animations
         .into_iter()
         .enumerate()
         .filter_map(|(index, animation)| {
-            let location = animation
-                .locations
-                .as_ref()
-                .and_then(|locs| locs.first())
-                .and_then(|loc| loc.location.as_ref())?
-                .to_string();
-
-            Some(spawn_single_upload_task(
-                roblosecurity.clone(),
-                index,
-                animation.request_id.clone(),
-                location,
-                group_id,
-                semaphore.clone(),
-                rate_limiter.clone(),
-                total_animations,
-            ))
+            let source = match embedded.remove(&animation.id) {
+                Some(data) => AnimationSource::Embedded(data),
+                None => match animation.location() {
+                    Some(location) => AnimationSource::Url(location.to_string()),
+                    None => {
+                        warn!(
+                            correlation_id = %CorrelationId::new(animation.id),
+                            "No download location for animation, not even from assetdelivery v1"
+                        );
+                        uploader.record_failure(
+                            animation.id,
+                            "no download location from the batch API or assetdelivery v1",
+                            None,
+                            FailureKind::Deleted,
+                        );
+                        return None;
+                    }
+                },
+            };
+            Some((index, animation.id, source))
+        })
+        .collect();
+
+    let context = Arc::new(UploadContext {
+        semaphore: Semaphore::new(options.concurrency as usize),
+        retry_budget: uploader.config.retry.retry_budget.map(RetryBudget::new),
+        uploader,
+        progress: ProgressTracker::new(sources.len(), options.on_progress.clone()),
+        options,
+        cache_destination,
+        total_animations,
+    });
+
+    sources
+        .into_iter()
+        .map(|(index, asset_id, source)| {
+            (
+                asset_id,
+                spawn_single_upload_task(context.clone(), index, asset_id, source),
+            )
         })
         .collect()
 }
 
-/// Collects results from all upload tasks
+/// Collects results from all upload tasks, handling failed uploads as `failure_policy` says.
+/// Moderation rejections are recorded on the uploader so they can be blocklisted. Once
+/// `cancel_token` is cancelled the remaining tasks are aborted and the uploads that already
+/// finished are returned.
 pub async fn collect_upload_results(
-    tasks: Vec<UploadTask>,
+    uploader: &AnimationUploader,
+    tasks: Vec<(u64, UploadTask)>,
+    failure_policy: FailurePolicy,
+    cancel_token: Option<CancellationToken>,
 ) -> Result<HashMap<String, String>, RoboatError> {
     let mut animation_hashmap = HashMap::new();
     let mut errors = Vec::new();
+    let mut failed = 0;
     let total_tasks = tasks.len();
+    let mut tasks = tasks.into_iter();
 
-    for task in tasks {
-        match task.await {
+    while let Some((asset_id, mut task)) = tasks.next() {
+        let correlation_id = CorrelationId::new(asset_id);
+        let result = match &cancel_token {
+            Some(cancel_token) => tokio::select! {
+                result = &mut task => result,
+                _ = cancel_token.cancelled() => {
+                    let remaining = std::iter::once((asset_id, task)).chain(tasks).collect();
+                    let cancelled =
+                        cancel_upload_tasks(&mut animation_hashmap, remaining).await;
+                    warn!(
+                        "Interrupted, {} uploads cancelled, keeping the {} that finished",
+                        cancelled,
+                        animation_hashmap.len()
+                    );
+                    return Ok(animation_hashmap);
+                }
+            },
+            None => task.await,
+        };
+        match result {
             Ok(Ok((Some(request_id), new_animation_id))) => {
                 animation_hashmap.insert(request_id, new_animation_id);
             }
             Ok(Ok((None, _))) => {
-                eprintln!("Warning: Upload succeeded but no request_id available");
+                warn!(
+                    correlation_id = %correlation_id,
+                    "Upload succeeded but no request_id available"
+                );
             }
             Ok(Err(e)) => {
+                error!(correlation_id = %correlation_id, "Upload failed: {}", e);
                 if matches!(e, RoboatError::BadRequest) {
-                    eprintln!(
+                    error!(
                         "Upload API error: Cookie may lack required permissions\n\
                          For group uploads, ensure the cookie has ALL Asset and Experience permissions"
                     );
                 }
+                if is_moderation_rejection(&e) {
+                    uploader
+                        .moderation_rejections
+                        .lock()
+                        .unwrap()
+                        .insert(asset_id, e.to_string());
+                }
+                failed += 1;
                 errors.push(e);
             }
             Err(join_error) => {
-                eprintln!("Task execution failed: {}", join_error);
+                error!(
+                    correlation_id = %correlation_id,
+                    "Task execution failed: {}", join_error
+                );
+                failed += 1;
+            }
+        }
+
+        if failed > 0 && failure_policy == FailurePolicy::Abort {
+            let cancelled = tasks.len();
+            for (_, task) in tasks {
+                task.abort();
             }
+            error!(
+                "Aborting batch upload after a failure, {} uploads cancelled",
+                cancelled
+            );
+            return Err(first_error(errors));
         }
     }
 
-    if !errors.is_empty() {
-        eprintln!(
+    if failed > 0 {
+        warn!(
             "Upload summary: {} failed out of {} total tasks",
-            errors.len(),
-            total_tasks
+            failed, total_tasks
         );
+        if failure_policy == FailurePolicy::RequireAllOrRollback {
+            error!("Not every upload succeeded, discarding the mapping so nothing is rewritten");
+            return Err(first_error(errors));
+        }
     }
 
     Ok(animation_hashmap)
@@ -144,95 +313,291 @@ pub async fn collect_upload_results(
 // HELPER FUNCTIONS
 // ============================================================================
 
-/// Spawns a single upload task with all necessary context
+/// Reads an animation for uploading, reporting an unreadable spooled file as malformed.
+async fn read_animation_file(animation_file: &AnimationFile) -> Result<Bytes, RoboatError> {
+    animation_file.bytes().await.map_err(|e| {
+        error!("Failed to read downloaded animation: {}", e);
+        RoboatError::MalformedResponse
+    })
+}
+
+/// Aborts upload tasks and adds the ones that had already finished to the mapping. Returns how
+/// many were cancelled or failed.
+async fn cancel_upload_tasks(
+    animation_hashmap: &mut HashMap<String, String>,
+    tasks: Vec<(u64, UploadTask)>,
+) -> usize {
+    for (_, task) in &tasks {
+        task.abort();
+    }
+
+    let mut cancelled = 0;
+    for (_, task) in tasks {
+        // A task that finished before the abort still returns its result
+        match task.await {
+            Ok(Ok((Some(request_id), new_animation_id))) => {
+                animation_hashmap.insert(request_id, new_animation_id);
+            }
+            _ => cancelled += 1,
+        }
+    }
+    cancelled
+}
+
+/// The error a failed batch returns. A task that panicked has no RoboatError of its own.
+fn first_error(errors: Vec<RoboatError>) -> RoboatError {
+    errors
+        .into_iter()
+        .next()
+        .unwrap_or(RoboatError::InternalServerError)
+}
+
+/// State shared by all upload tasks of one run.
+struct UploadContext {
+    uploader: Arc<AnimationUploader>,
+    options: UploadOptions,
+    semaphore: Semaphore,
+    retry_budget: Option<RetryBudget>,
+    progress: ProgressTracker,
+    /// Who uploads go to, when options.upload_cache is set.
+    cache_destination: Option<AssetCreator>,
+    total_animations: usize,
+}
+
+/// Spawns a single upload task and reports its outcome to the progress tracker
 fn spawn_single_upload_task(
-    roblosecurity: Arc<String>,
+    context: Arc<UploadContext>,
     index: usize,
-    request_id: Option<String>,
-    location: String,
-    group_id: Option<u64>,
-    semaphore: Arc<Semaphore>,
-    rate_limiter: Arc<RateLimiter>,
-    total_animations: usize,
+    asset_id: u64,
+    source: AnimationSource,
 ) -> UploadTask {
+    let span = info_span!("upload", correlation_id = %CorrelationId::new(asset_id));
     tokio::spawn(async move {
-        // Acquire semaphore permit
-        let _permit = semaphore.acquire().await.unwrap();
-
-        // Create uploader instance
-        let uploader = AnimationUploader::new((*roblosecurity).clone());
-
-        // Download animation file
-        let animation_file = uploader.file_bytes_from_url(location).await?;
-
-        // Wait for rate limit if needed
-        rate_limiter.wait_if_limited().await;
-
-        // Upload with retry logic
-        let new_animation_id = upload_animation_with_retry(
-            &uploader,
-            animation_file,
-            group_id,
-            &rate_limiter,
-            index,
-            total_animations,
-            request_id.clone().unwrap_or_else(|| "unknown".to_string()),
-        )
-        .await?;
-
-        Ok((request_id, new_animation_id))
+        let url = match &source {
+            AnimationSource::Url(location) => Some(location.clone()),
+            AnimationSource::Embedded(_) => None,
+        };
+        let result = run_upload_task(&context, index, asset_id, source)
+            .instrument(span)
+            .await;
+        context.progress.record(asset_id, result.is_ok());
+        match &result {
+            Ok(_) => context.uploader.clear_failure(asset_id),
+            Err(e) => context.uploader.record_failure(
+                asset_id,
+                e.to_string(),
+                url,
+                FailureKind::classify(e),
+            ),
+        }
+        result
     })
 }
 
-/// Uploads animation with automatic retry logic for rate limits and server errors
+/// Downloads (unless embedded) and uploads one animation
+async fn run_upload_task(
+    context: &UploadContext,
+    index: usize,
+    asset_id: u64,
+    source: AnimationSource,
+) -> Result<(Option<String>, String), RoboatError> {
+    let request_id = Some(asset_id.to_string());
+    let options = &context.options;
+    let verbose = options.on_progress.is_none();
+
+    // Acquire semaphore permit
+    let _permit = context.semaphore.acquire().await.unwrap();
+
+    let uploader = &context.uploader;
+
+    // Download animation file, unless its data is embedded in the place
+    let download_started = Instant::now();
+    let mut animation_file = match source {
+        AnimationSource::Url(location) => {
+            if verbose {
+                debug!("Downloading animation");
+            }
+            uploader
+                .download_animation_file(location)
+                .await
+                .inspect_err(|e| error!("Download failed: {}", e))?
+        }
+        AnimationSource::Embedded(data) => {
+            if verbose {
+                debug!("Publishing embedded KeyframeSequence");
+            }
+            AnimationFile::Memory(data)
+        }
+    };
+    let download = download_started.elapsed();
+
+    if let Some(retarget) = options.retarget {
+        if verbose {
+            debug!("Retargeting animation");
+        }
+        let data = read_animation_file(&animation_file).await?;
+        animation_file = retarget_animation(&data, retarget)
+            .map(AnimationFile::Memory)
+            .map_err(|e| {
+                error!("Retargeting failed: {}", e);
+                RoboatError::MalformedResponse
+            })?;
+    }
+
+    // An identical animation uploaded to the same destination before can be reused
+    let cache_entry = match (&options.upload_cache, context.cache_destination) {
+        (Some(cache), Some(destination)) => {
+            let hash = content_hash(&read_animation_file(&animation_file).await?);
+            if let Some(cached) = cache.lock().unwrap().find(&hash, destination) {
+                if verbose {
+                    info!(
+                        "Reusing animation {} uploaded earlier from {}",
+                        cached.new_id, cached.source_id
+                    );
+                }
+                MetricsRecorder::add(&uploader.metrics.reused, 1);
+                return Ok((request_id, cached.new_id.clone()));
+            }
+            Some((cache, hash, destination))
+        }
+        _ => None,
+    };
+
+    let upload_started = Instant::now();
+    // Upload with retry logic
+    let (new_animation_id, attempts) = upload_animation_with_retry(
+        uploader,
+        &animation_file,
+        options,
+        context.retry_budget.as_ref(),
+        index,
+        context.total_animations,
+        request_id.clone().unwrap_or_else(|| "unknown".to_string()),
+    )
+    .await?;
+    MetricsRecorder::add(&uploader.metrics.uploaded, 1);
+    uploader.metrics.record_timing(AssetTiming {
+        asset_id,
+        download,
+        upload: upload_started.elapsed(),
+        bytes: animation_file.len(),
+        attempts,
+    });
+
+    if let Some((cache, hash, destination)) = cache_entry {
+        cache.lock().unwrap().insert(
+            hash,
+            CachedUpload {
+                source_id: asset_id,
+                new_id: new_animation_id.clone(),
+                destination,
+            },
+        );
+    }
+
+    // Space uploads out before freeing the semaphore slot
+    if let Some(pacing) = options.pacing {
+        tokio::time::sleep(pacing).await;
+    }
+
+    Ok((request_id, new_animation_id))
+}
+
+/// Uploads animation with automatic retry logic for rate limits and server errors. Returns
+/// the new ID and how many attempts it took.
 async fn upload_animation_with_retry(
     uploader: &AnimationUploader,
-    animation_file: Bytes,
-    group_id: Option<u64>,
-    rate_limiter: &Arc<RateLimiter>,
+    animation_file: &AnimationFile,
+    options: &UploadOptions,
+    retry_budget: Option<&RetryBudget>,
     index: usize,
     total_animations: usize,
     request_id: String,
-) -> Result<String, RoboatError> {
+) -> Result<(String, u32), RoboatError> {
     let mut last_error = None;
+    // max_retries counts the retries after the first attempt
+    let max_attempts = options.max_retries + 1;
 
-    for attempt in 1..=MAX_UPLOAD_RETRIES {
+    for attempt in 1..=max_attempts {
+        // Each attempt goes to the next account of the pool, after its rate limit if it has one
+        let account = uploader.cookie_pool.next_account().await;
+        account.rate_limiter.wait_if_limited().await;
+
+        // Read back for each attempt so a spooled file isn't held in memory between them
+        let animation_data = read_animation_file(animation_file).await?;
         match uploader
-            .upload_animation(animation_file.clone(), group_id)
+            .upload_animation_as(
+                account,
+                animation_data,
+                options.target,
+                options.name_for(&request_id),
+                options.description.clone(),
+            )
             .await
         {
             Ok(new_animation_id) => {
-                println!(
-                    "Success uploading animation {} - {}/{} ({} remaining)",
-                    request_id,
-                    index + 1,
-                    total_animations,
-                    total_animations - (index + 1)
-                );
-                return Ok(new_animation_id);
+                if let Some(retry_budget) = retry_budget {
+                    retry_budget.refund();
+                }
+                // A progress callback reports successes instead
+                if options.on_progress.is_none() {
+                    info!(
+                        "Success uploading animation {} - {}/{} ({} remaining)",
+                        request_id,
+                        index + 1,
+                        total_animations,
+                        total_animations - (index + 1)
+                    );
+                }
+                return Ok((new_animation_id, attempt as u32));
             }
             Err(e) => {
-                eprintln!(
+                warn!(
                     "Upload attempt {}/{} failed for animation {}: {}",
-                    attempt, MAX_UPLOAD_RETRIES, request_id, e
+                    attempt, max_attempts, request_id, e
                 );
 
                 // Handle rate limits and server errors
-                if matches!(
+                let rate_limited = matches!(
                     e,
                     RoboatError::TooManyRequests | RoboatError::InternalServerError
-                ) {
-                    let sleep_time = (attempt as u64) * 30;
-                    rate_limiter.set_rate_limit(sleep_time).await;
-                    rate_limiter.wait_if_limited().await;
+                );
+                if rate_limited {
+                    let sleep_time = uploader.config.retry.rate_limit_wait(attempt as u32);
+                    warn!("Rate limited, waiting {:?}", sleep_time);
+                    account.rate_limiter.set_rate_limit(sleep_time).await;
                 }
 
+                // Moderation, a deleted source or a missing permission won't change on a retry
+                let kind = FailureKind::classify(&e);
+                if kind.is_permanent() {
+                    warn!(
+                        "Not retrying animation {}: the failure is permanent ({})",
+                        request_id, kind
+                    );
+                    return Err(e);
+                }
+
+                let too_many_requests = matches!(e, RoboatError::TooManyRequests);
                 last_error = Some(e);
+                if attempt == max_attempts {
+                    break;
+                }
 
-                // Small delay between retries (except last attempt)
-                if attempt < MAX_UPLOAD_RETRIES {
-                    tokio::time::sleep(Duration::from_millis(1000)).await;
+                // Stop retrying once the whole batch has used up its retries. A 429 doesn't
+                // count: one wave of them across the concurrent tasks would spend it all
+                if let Some(retry_budget) = retry_budget
+                    && !too_many_requests
+                    && !retry_budget.try_spend()
+                {
+                    warn!(
+                        "Retry budget used up, giving up on animation {}",
+                        request_id
+                    );
+                    break;
                 }
+
+                tokio::time::sleep(uploader.config.retry.retry_wait(attempt as u32)).await;
             }
         }
     }
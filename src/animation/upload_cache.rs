@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::model::AssetCreator;
+use crate::studio::backup::write_atomically;
+
+/// Where the CLI keeps the upload cache unless told otherwise.
+pub const DEFAULT_UPLOAD_CACHE_FILE: &str = "upload-cache.json";
+
+/// An animation uploaded by an earlier run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedUpload {
+    pub source_id: u64,
+    pub new_id: String,
+    /// Who the new animation was uploaded to.
+    pub destination: AssetCreator,
+}
+
+/// Every animation uploaded so far, keyed by a hash of its file. Persisted between runs so an
+/// animation used by several places is uploaded once per destination instead of once per run.
+///
+/// # Notes:
+/// Entries are matched on the file contents, not only the source ID, so a source animation
+/// that was edited since is uploaded again, and identical animations under different source
+/// IDs share one upload. Uploads are checked to still download before a run reuses them
+/// (see UploadOptions::upload_cache).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadCache {
+    pub uploads: BTreeMap<String, Vec<CachedUpload>>,
+}
+
+impl UploadCache {
+    /// Loads the cache from a JSON file. A missing file is an empty cache.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read upload cache '{}': {}", path.display(), e)
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!("Failed to parse upload cache '{}': {}", path.display(), e)
+        })
+    }
+
+    /// Saves the cache through a temporary file, so a run killed mid-write doesn't lose it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(path, contents.as_bytes()).map_err(|e| {
+            anyhow::anyhow!("Failed to write upload cache '{}': {}", path.display(), e)
+        })
+    }
+
+    /// An earlier upload of the same file to the same destination.
+    pub fn find(&self, content_hash: &str, destination: AssetCreator) -> Option<&CachedUpload> {
+        self.uploads
+            .get(content_hash)?
+            .iter()
+            .find(|upload| upload.destination == destination)
+    }
+
+    /// Every upload to a destination.
+    pub fn uploads_to(&self, destination: AssetCreator) -> Vec<&CachedUpload> {
+        self.uploads
+            .values()
+            .flatten()
+            .filter(|upload| upload.destination == destination)
+            .collect()
+    }
+
+    /// Keeps only the uploads `keep` returns true for.
+    pub fn retain<F: FnMut(&CachedUpload) -> bool>(&mut self, mut keep: F) {
+        for uploads in self.uploads.values_mut() {
+            uploads.retain(&mut keep);
+        }
+        self.uploads.retain(|_, uploads| !uploads.is_empty());
+    }
+
+    pub fn insert(&mut self, content_hash: String, upload: CachedUpload) {
+        let uploads = self.uploads.entry(content_hash).or_default();
+        if !uploads
+            .iter()
+            .any(|cached| cached.destination == upload.destination)
+        {
+            uploads.push(upload);
+        }
+    }
+}
+
+/// Hashes an animation file for the upload cache (64-bit FNV-1a, as hex).
+///
+/// # Notes:
+/// Stable across runs and Rust versions, unlike std's DefaultHasher.
+pub fn content_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
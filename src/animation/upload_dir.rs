@@ -0,0 +1,80 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::options::UploadOptions;
+use super::target::UploadTarget;
+use crate::AnimationUploader;
+
+impl AnimationUploader {
+    /// Uploads every .rbxm/.rbxmx animation file in a folder (not its subfolders), e.g. one
+    /// written by download_animations or exported from Studio.
+    /// Returns a file name → new animation ID mapping; files that fail are reported and skipped.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// Each animation is named after its file, without the extension.
+    pub async fn upload_animations_from_dir<P: AsRef<Path>, T: Into<UploadTarget>>(
+        &self,
+        dir: P,
+        target: T,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let target = target.into();
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", dir.display(), e))?;
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .is_some_and(|extension| {
+                            extension.eq_ignore_ascii_case("rbxm")
+                                || extension.eq_ignore_ascii_case("rbxmx")
+                        })
+            })
+            .collect();
+        paths.sort();
+
+        if let UploadTarget::Group(group_id) = target
+            && !paths.is_empty()
+        {
+            self.check_group_upload_permission(group_id).await?;
+        }
+
+        let description = UploadOptions::default().description;
+        let mut file_mapping = HashMap::new();
+
+        for path in paths {
+            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+
+            let animation_data = match fs::read(&path) {
+                Ok(animation_data) => Bytes::from(animation_data),
+                Err(e) => {
+                    warn!("Failed to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            self.rate_limiter.wait_if_limited().await;
+            match self
+                .upload_animation_with_details(animation_data, target, name, description.clone())
+                .await
+            {
+                Ok(new_animation_id) => {
+                    info!("Uploaded {} as {}", file_name, new_animation_id);
+                    file_mapping.insert(file_name, new_animation_id);
+                }
+                Err(e) => warn!("Failed to upload {}: {}", file_name, e),
+            }
+        }
+
+        Ok(file_mapping)
+    }
+}
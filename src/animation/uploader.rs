@@ -1,66 +1,563 @@
 use bytes::Bytes;
-use roboat::ClientBuilder;
 use roboat::RoboatError;
 use roboat::ide::ide_types::NewAnimation;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
 
+use super::api::{RobloxApi, RoboatApi};
+use super::config::{RetryPolicy, UploaderConfig};
+use super::cookie_pool::{CookiePool, PoolAccount};
+use super::correlation::CorrelationId;
+use super::failure::FailureKind;
+use super::metrics::{MetricsRecorder, RunMetrics};
+use super::model::{AnimationAsset, AssetCreator};
+use super::options::{FailurePolicy, UploadOptions};
+use super::target::UploadTarget;
 use super::tasks::{RateLimiter, collect_upload_results, spawn_upload_tasks};
+use super::upload_cache::UploadCache;
+use crate::report::FailedAsset;
 
-const DEFAULT_CONCURRENT_TASKS: u64 = 50;
+/// assetdelivery v1 lookups fill_missing_locations runs at the same time.
+const LOCATION_LOOKUPS: usize = 10;
 
 pub struct AnimationUploader {
     pub roblosecurity: String,
-    pub(super) rate_limiter: Arc<RateLimiter>,
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+    /// Accounts batch uploads are spread over, starting with this cookie.
+    pub(super) cookie_pool: CookiePool,
+    /// Source IDs rejected by moderation during reupload_all_animations, with the reason.
+    pub(super) moderation_rejections: Mutex<HashMap<u64, String>>,
+    /// Source IDs that failed to download or upload, with the reason and download URL.
+    pub(super) failed_uploads: Mutex<HashMap<u64, FailedAsset>>,
+    pub(crate) metrics: MetricsRecorder,
+    pub(crate) config: UploaderConfig,
+    /// Roblox API calls go through this, RoboatApi unless a fake is set on the builder.
+    pub(crate) api: Arc<dyn RobloxApi>,
 }
 
 impl AnimationUploader {
-    /// Creates a new AnimationUploader with a roblosecurity cookie.
-    pub fn new(roblosecurity: String) -> Self {
-        Self {
+    /// Creates a new AnimationUploader with a roblosecurity cookie and the default settings.
+    /// Fails like AnimationUploaderBuilder::build if the HTTP client can't be built.
+    pub fn new(roblosecurity: String) -> anyhow::Result<Self> {
+        Self::builder().roblosecurity(roblosecurity).build()
+    }
+
+    pub(crate) fn with_config(
+        roblosecurity: String,
+        config: UploaderConfig,
+    ) -> Result<Self, reqwest::Error> {
+        let http_client = config.client_builder().build()?;
+        let rate_limiter = Arc::new(RateLimiter::new());
+        let cookie_pool = CookiePool::new(PoolAccount {
+            roblosecurity: roblosecurity.clone(),
+            rate_limiter: rate_limiter.clone(),
+        });
+
+        // Built once from the config and shared by every request
+        let api = Arc::new(RoboatApi::new(config.clone(), http_client));
+
+        Ok(Self {
             roblosecurity,
-            rate_limiter: Arc::new(RateLimiter::new()),
-        }
+            rate_limiter,
+            cookie_pool,
+            moderation_rejections: Mutex::new(HashMap::new()),
+            failed_uploads: Mutex::new(HashMap::new()),
+            metrics: MetricsRecorder::new(),
+            config,
+            api,
+        })
+    }
+
+    /// Creates a builder for configuring retries and timeouts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let uploader = AnimationUploader::builder()
+    ///     .roblosecurity("cookie")
+    ///     .retry_policy(RetryPolicy::new().max_retries(3))
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()?;
+    /// ```
+    pub fn builder() -> AnimationUploaderBuilder {
+        AnimationUploaderBuilder::new()
+    }
+
+    pub fn config(&self) -> &UploaderConfig {
+        &self.config
+    }
+
+    /// How many accounts batch uploads are spread over, this cookie included.
+    pub fn account_count(&self) -> usize {
+        self.cookie_pool.len()
+    }
+
+    /// Source IDs whose uploads were rejected by moderation so far, with the reason.
+    pub fn moderation_rejections(&self) -> HashMap<u64, String> {
+        self.moderation_rejections.lock().unwrap().clone()
+    }
+
+    /// Animations that failed to download or upload so far and haven't uploaded since, sorted
+    /// by ID.
+    pub fn failed_uploads(&self) -> Vec<FailedAsset> {
+        let mut failed: Vec<FailedAsset> = self
+            .failed_uploads
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        failed.sort_by_key(|asset| asset.asset_id);
+        failed
+    }
+
+    /// What the uploader did so far: assets looked up and uploaded, bytes transferred and time
+    /// spent rate limited.
+    pub fn run_metrics(&self) -> RunMetrics {
+        self.metrics.snapshot(self.cookie_pool.total_limited())
+    }
+
+    pub(crate) fn record_failure<S: Into<String>>(
+        &self,
+        asset_id: u64,
+        reason: S,
+        url: Option<String>,
+        kind: FailureKind,
+    ) {
+        MetricsRecorder::add(&self.metrics.failed, 1);
+        self.failed_uploads.lock().unwrap().insert(
+            asset_id,
+            FailedAsset {
+                asset_id,
+                reason: reason.into(),
+                kind,
+                url,
+            },
+        );
+    }
+
+    pub(super) fn clear_failure(&self, asset_id: u64) {
+        self.failed_uploads.lock().unwrap().remove(&asset_id);
+    }
+
+    /// Uploads a single animation to Roblox. `target` is an UploadTarget, or a group ID
+    /// option where None is the cookie's user.
+    pub async fn upload_animation<T: Into<UploadTarget>>(
+        &self,
+        animation_data: Bytes,
+        target: T,
+    ) -> Result<String, RoboatError> {
+        let defaults = UploadOptions::default();
+        self.upload_animation_with_details(
+            animation_data,
+            target,
+            defaults.name,
+            defaults.description,
+        )
+        .await
+    }
+
+    /// Uploads a single animation to Roblox with the given name and description.
+    pub async fn upload_animation_with_details<T: Into<UploadTarget>>(
+        &self,
+        animation_data: Bytes,
+        target: T,
+        name: String,
+        description: String,
+    ) -> Result<String, RoboatError> {
+        let primary = &self.cookie_pool.accounts()[0];
+        self.upload_animation_as(primary, animation_data, target, name, description)
+            .await
     }
 
-    /// Uploads a single animation to Roblox.
-    pub async fn upload_animation(
+    /// Uploads a single animation with one account of the pool.
+    pub(super) async fn upload_animation_as<T: Into<UploadTarget>>(
         &self,
+        account: &PoolAccount,
         animation_data: Bytes,
-        group_id: Option<u64>,
+        target: T,
+        name: String,
+        description: String,
     ) -> Result<String, RoboatError> {
-        let client = ClientBuilder::new()
-            .roblosecurity(self.roblosecurity.clone())
-            .build();
+        MetricsRecorder::add(&self.metrics.bytes_uploaded, animation_data.len() as u64);
 
         let animation = NewAnimation {
-            group_id,
-            name: "reuploaded_animation".to_string(),
-            description: "This is a example".to_string(),
+            group_id: target.into().group_id(),
+            name,
+            description,
             animation_data,
         };
 
-        client.upload_new_animation(animation).await
+        self.api
+            .upload_animation(&account.roblosecurity, animation)
+            .await
     }
 
     /// Reuploads multiple animations concurrently.
-    pub async fn reupload_all_animations(
+    #[deprecated(note = "use reupload_animations with UploadOptions")]
+    pub async fn reupload_all_animations<T: Into<UploadTarget>>(
         self: Arc<Self>,
-        animations: Vec<roboat::assetdelivery::AssetBatchResponse>,
-        group_id: Option<u64>,
+        animations: Vec<AnimationAsset>,
+        target: T,
         task_count: Option<u64>,
     ) -> Result<HashMap<String, String>, RoboatError> {
-        let max_concurrent_tasks = task_count.unwrap_or(DEFAULT_CONCURRENT_TASKS);
-        let total_animations = animations.len();
+        let mut options = UploadOptions::new().target(target.into());
+        if let Some(task_count) = task_count {
+            options = options.concurrency(task_count);
+        }
+
+        self.upload_all(animations, HashMap::new(), options).await
+    }
+
+    /// Reuploads multiple animations concurrently using the given options.
+    /// Returns the old → new mapping of the animations that uploaded (and verified, if enabled).
+    pub async fn reupload_animations(
+        self: Arc<Self>,
+        animations: Vec<AnimationAsset>,
+        options: &UploadOptions,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        self.reupload_animations_with_embedded(animations, HashMap::new(), options)
+            .await
+    }
+
+    /// Like reupload_animations, but animations with data in `embedded` (see
+    /// StudioParser::embedded_keyframe_sequences) are published from it instead of downloaded.
+    ///
+    /// # Notes:
+    /// Embedded animations don't need a download location, so they still publish when the
+    /// original asset can no longer be downloaded; pass them as AnimationAsset::new(id).
+    /// Failed uploads are handled as options.failure_policy says.
+    /// Uploads to a group first check the cookie's user may create items in it.
+    /// With several cookies (see AnimationUploaderBuilder::extra_cookies) the uploads are
+    /// spread over their accounts, which only works for a group target.
+    /// Assets the batch API reported as another type (sounds, images) are skipped, so their
+    /// IDs never end up in the mapping and are never rewritten.
+    pub async fn reupload_animations_with_embedded(
+        self: Arc<Self>,
+        animations: Vec<AnimationAsset>,
+        embedded: HashMap<u64, Bytes>,
+        options: &UploadOptions,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let (animations, other_assets): (Vec<_>, Vec<_>) = animations
+            .into_iter()
+            .partition(AnimationAsset::is_animation);
+        for asset in other_assets {
+            warn!(
+                "Not reuploading {}: asset type {:?} isn't an animation",
+                asset.id, asset.asset_type_id
+            );
+        }
+
+        if !animations.is_empty() {
+            self.check_pool_target(options.target)?;
+            if let UploadTarget::Group(group_id) = options.target {
+                self.check_group_upload_permission(group_id).await?;
+            }
+        }
+
+        let animation_mapping = self
+            .clone()
+            .upload_all(animations, embedded, options.clone())
+            .await?;
+
+        if !options.verify {
+            return Ok(animation_mapping);
+        }
+
+        let uploaded = animation_mapping.len();
+        let verified = self
+            .verify_uploaded_animations(animation_mapping, options.target)
+            .await?;
+        if options.failure_policy == FailurePolicy::RequireAllOrRollback
+            && verified.len() < uploaded
+        {
+            anyhow::bail!(
+                "{} of {} uploaded animations failed verification, not returning a partial mapping",
+                uploaded - verified.len(),
+                uploaded
+            );
+        }
+        if options.check_events {
+            // Mismatches are logged by the check, the uploads themselves are fine
+            self.check_animation_events(&verified).await?;
+        }
+        Ok(verified)
+    }
+
+    async fn upload_all(
+        self: Arc<Self>,
+        mut animations: Vec<AnimationAsset>,
+        embedded: HashMap<u64, Bytes>,
+        mut options: UploadOptions,
+    ) -> Result<HashMap<String, String>, RoboatError> {
+        self.fill_missing_locations(&mut animations, &embedded)
+            .await;
+
+        // Cached uploads are only reused for the account or group they were uploaded to
+        let cache_destination = match (&options.upload_cache, options.target) {
+            (None, _) => None,
+            (Some(_), UploadTarget::Group(group_id)) => Some(AssetCreator::Group(group_id)),
+            (Some(_), UploadTarget::CurrentUser) => Some(AssetCreator::User(
+                self.api.user_id(&self.roblosecurity).await?,
+            )),
+        };
+
+        let pruned = match (&options.upload_cache, cache_destination) {
+            (Some(cache), Some(destination)) => self.prune_upload_cache(cache, destination).await,
+            _ => Ok(()),
+        };
+        if let Err(e) = pruned {
+            warn!(
+                "Not reusing cached uploads, failed to check they still download: {}",
+                e
+            );
+            options.upload_cache = None;
+        }
 
+        let failure_policy = options.failure_policy;
+        let cancel_token = options.cancel_token.clone();
+        let total_animations = animations.len();
         let tasks = spawn_upload_tasks(
             self.clone(),
             animations,
-            group_id,
-            max_concurrent_tasks,
+            embedded,
+            options,
+            cache_destination,
             total_animations,
         );
 
-        collect_upload_results(tasks).await
+        collect_upload_results(&self, tasks, failure_policy, cancel_token).await
+    }
+
+    /// Drops the cached uploads to `destination` the batch API no longer returns a download
+    /// location for, so a deleted or rejected upload isn't written into another place.
+    async fn prune_upload_cache(
+        &self,
+        cache: &Mutex<UploadCache>,
+        destination: AssetCreator,
+    ) -> anyhow::Result<()> {
+        let new_ids: Vec<u64> = cache
+            .lock()
+            .unwrap()
+            .uploads_to(destination)
+            .iter()
+            .filter_map(|upload| upload.new_id.parse().ok())
+            .collect();
+        if new_ids.is_empty() {
+            return Ok(());
+        }
+
+        let live: HashSet<String> = self
+            .fetch_animation_assets(new_ids)
+            .await?
+            .into_iter()
+            .filter(|asset| asset.location().is_some())
+            .map(|asset| asset.id.to_string())
+            .collect();
+
+        let mut dropped = 0;
+        cache.lock().unwrap().retain(|upload| {
+            let keep = upload.destination != destination || live.contains(&upload.new_id);
+            if !keep {
+                debug!(
+                    "Dropping cached upload {} of {}: it no longer downloads",
+                    upload.new_id, upload.source_id
+                );
+                dropped += 1;
+            }
+            keep
+        });
+        if dropped > 0 {
+            warn!(
+                "{} cached uploads no longer download and will be uploaded again",
+                dropped
+            );
+        }
+        Ok(())
+    }
+
+    /// Asks the v1 assetdelivery endpoint for the download location of animations the batch
+    /// API returned none for, LOCATION_LOOKUPS at a time. Those it has none for either are
+    /// left as they are, and spawn_upload_tasks reports them as failed.
+    async fn fill_missing_locations(
+        self: &Arc<Self>,
+        animations: &mut [AnimationAsset],
+        embedded: &HashMap<u64, Bytes>,
+    ) {
+        let missing: Vec<u64> = animations
+            .iter()
+            .filter(|animation| animation.locations.is_empty())
+            .filter(|animation| !embedded.contains_key(&animation.id))
+            .map(|animation| animation.id)
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let mut locations = HashMap::new();
+        let mut lookups = JoinSet::new();
+        for asset_id in missing {
+            if lookups.len() >= LOCATION_LOOKUPS
+                && let Some(Ok((asset_id, Some(location)))) = lookups.join_next().await
+            {
+                locations.insert(asset_id, location);
+            }
+            let uploader = self.clone();
+            lookups.spawn(
+                async move { (asset_id, uploader.asset_location_with_retry(asset_id).await) },
+            );
+        }
+        while let Some(result) = lookups.join_next().await {
+            if let Ok((asset_id, Some(location))) = result {
+                locations.insert(asset_id, location);
+            }
+        }
+
+        for animation in animations.iter_mut() {
+            if let Some(location) = locations.remove(&animation.id) {
+                animation.locations.push(location);
+            }
+        }
+    }
+
+    /// Looks up an asset's location through assetdelivery v1, waiting out rate limits.
+    async fn asset_location_with_retry(&self, asset_id: u64) -> Option<String> {
+        let correlation_id = CorrelationId::new(asset_id).to_string();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            self.rate_limiter.wait_if_limited().await;
+            match self.api.asset_location(&self.roblosecurity, asset_id).await {
+                Ok(location) => {
+                    if location.is_some() {
+                        debug!(
+                            correlation_id,
+                            "Found a download location through assetdelivery v1"
+                        );
+                    }
+                    return location;
+                }
+                Err(RoboatError::TooManyRequests)
+                    if attempt <= self.config.retry.max_fetch_retries =>
+                {
+                    self.rate_limiter
+                        .set_rate_limit(self.config.retry.rate_limit_wait(attempt))
+                        .await;
+                }
+                Err(e) => {
+                    warn!(
+                        correlation_id,
+                        "Failed to ask assetdelivery v1 for a download location: {}", e
+                    );
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Builder for creating AnimationUploader instances with custom settings.
+#[derive(Default)]
+pub struct AnimationUploaderBuilder {
+    roblosecurity: Option<String>,
+    extra_cookies: Vec<String>,
+    config: UploaderConfig,
+    api: Option<Arc<dyn RobloxApi>>,
+}
+
+impl fmt::Debug for AnimationUploaderBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnimationUploaderBuilder")
+            .field("extra_cookies", &self.extra_cookies.len())
+            .field("config", &self.config)
+            .field("custom_api", &self.api.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AnimationUploaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn roblosecurity<S: Into<String>>(mut self, roblosecurity: S) -> Self {
+        self.roblosecurity = Some(roblosecurity.into());
+        self
+    }
+
+    /// Cookies of more accounts to spread batch uploads over, round-robin, each with its own
+    /// rate limit. Everything else still uses the main cookie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut cookies = cookies_from_file("cookies.txt")?;
+    /// let uploader = AnimationUploader::builder()
+    ///     .roblosecurity(cookies.remove(0))
+    ///     .extra_cookies(cookies)
+    ///     .build()?;
+    /// let options = UploadOptions::new().target(UploadTarget::Group(1234));
+    /// ```
+    ///
+    /// # Notes:
+    /// Each account owns what it uploads, so the pool only uploads to a group, and every
+    /// account needs permission to create items in it.
+    pub fn extra_cookies<I: IntoIterator<Item = S>, S: Into<String>>(mut self, cookies: I) -> Self {
+        self.extra_cookies = cookies.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replaces all settings at once.
+    pub fn config(mut self, config: UploaderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.config = self.config.retry_policy(retry);
+        self
+    }
+
+    /// Timeout of Roblox API requests and animation downloads.
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config = self.config.timeout(timeout).download_timeout(timeout);
+        self
+    }
+
+    /// Customises the HTTP client, e.g. to go through a proxy. See UploaderConfig::http_client.
+    pub fn http_client<F: Fn() -> reqwest::ClientBuilder + Send + Sync + 'static>(
+        mut self,
+        factory: F,
+    ) -> Self {
+        self.config = self.config.http_client(factory);
+        self
+    }
+
+    /// Sends Roblox API calls to `api` instead of Roblox, e.g. a MockRobloxApi in tests.
+    /// Animation downloads still use the HTTP client.
+    pub fn api(mut self, api: Arc<dyn RobloxApi>) -> Self {
+        self.api = Some(api);
+        self
+    }
+
+    /// Builds the AnimationUploader. The cookie is required.
+    pub fn build(self) -> anyhow::Result<AnimationUploader> {
+        let roblosecurity = self
+            .roblosecurity
+            .ok_or_else(|| anyhow::anyhow!("Roblosecurity cookie is required"))?;
+
+        let mut uploader = AnimationUploader::with_config(roblosecurity, self.config)
+            .map_err(|e| anyhow::anyhow!("Failed to build the HTTP client: {}", e))?;
+        for cookie in self.extra_cookies {
+            uploader.cookie_pool.push(cookie);
+        }
+        if let Some(api) = self.api {
+            uploader.api = api;
+        }
+        Ok(uploader)
     }
 }
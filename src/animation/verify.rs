@@ -0,0 +1,239 @@
+use roboat::RoboatError;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::correlation::CorrelationId;
+use super::model::{AnimationAsset, AssetCreator};
+use super::target::UploadTarget;
+use crate::AnimationUploader;
+use crate::StudioParser;
+
+/// The verdict of verify_rewritten_file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewriteVerification {
+    /// New IDs found in the saved file.
+    pub checked: Vec<String>,
+    /// New IDs that don't resolve or have the wrong owner.
+    pub failed: Vec<String>,
+}
+
+impl RewriteVerification {
+    pub fn passed(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The verdict of verify_uploads. Entries that failed are in neither map.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadVerification {
+    /// Old → new entries that exist, are owned by the upload target and can be downloaded.
+    pub verified: HashMap<String, String>,
+    /// Old → new entries that exist and are owned by the upload target but have no download
+    /// location yet, usually because moderation is still reviewing them.
+    pub pending: HashMap<String, String>,
+}
+
+impl UploadVerification {
+    /// Verified and pending entries together: every new ID that is safe to write into a
+    /// place.
+    pub fn usable(&self) -> HashMap<String, String> {
+        self.verified
+            .iter()
+            .chain(&self.pending)
+            .map(|(old_id, new_id)| (old_id.clone(), new_id.clone()))
+            .collect()
+    }
+}
+
+impl AnimationUploader {
+    /// Reopens a saved place/model and checks every new ID from the mapping that was written
+    /// into it: it has to resolve and be owned by the upload target.
+    ///
+    /// # Notes:
+    /// This is the final check before publishing, so it reads the file back from disk
+    /// instead of trusting the in-memory DOM.
+    /// * Requires a cookie
+    pub async fn verify_rewritten_file<P: AsRef<Path>, T: Into<UploadTarget>>(
+        &self,
+        path: P,
+        animation_mapping: &HashMap<String, String>,
+        target: T,
+    ) -> anyhow::Result<RewriteVerification> {
+        let parser = StudioParser::builder().file_path(path).build()?;
+
+        let mut written_ids: HashSet<String> = parser
+            .animation_instance_ids()
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect();
+        written_ids.extend(
+            parser
+                .script_asset_references()
+                .into_iter()
+                .map(|reference| reference.asset_id.to_string()),
+        );
+
+        let new_ids: HashSet<&String> = animation_mapping.values().collect();
+        let mut checked: Vec<String> = written_ids
+            .into_iter()
+            .filter(|id| new_ids.contains(id))
+            .collect();
+        checked.sort();
+
+        // Map each ID to itself so verify_uploaded_animations checks the new IDs directly
+        let identity: HashMap<String, String> =
+            checked.iter().map(|id| (id.clone(), id.clone())).collect();
+        let verified = self.verify_uploaded_animations(identity, target).await?;
+
+        let failed = checked
+            .iter()
+            .filter(|id| !verified.contains_key(*id))
+            .cloned()
+            .collect();
+        Ok(RewriteVerification { checked, failed })
+    }
+
+    /// Confirms each newly uploaded animation exists and is owned by the upload target (a
+    /// group, or the cookie's user).
+    ///
+    /// # Notes:
+    /// Returns the old → new entries that passed, including ones moderation is still
+    /// reviewing (see verify_uploads). Anything that fails is reported and dropped, so a
+    /// bogus ID from a failed upload never gets written into the place.
+    /// * Requires a cookie
+    pub async fn verify_uploaded_animations<T: Into<UploadTarget>>(
+        &self,
+        animation_mapping: HashMap<String, String>,
+        target: T,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        Ok(self
+            .verify_uploads(animation_mapping, target)
+            .await?
+            .usable())
+    }
+
+    /// Like verify_uploaded_animations, but keeps the uploads that can't be downloaded yet
+    /// apart from the ones that can.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let verification = uploader.verify_uploads(mapping, UploadTarget::Group(1234)).await?;
+    /// for (old_id, new_id) in &verification.pending {
+    ///     println!("{} -> {} is still in moderation", old_id, new_id);
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// Existence and ownership come from the asset details, whether it downloads from the
+    /// batch metadata. A freshly uploaded animation has no download location until
+    /// moderation approves it, so a missing location makes an entry pending, not failed.
+    pub async fn verify_uploads<T: Into<UploadTarget>>(
+        &self,
+        animation_mapping: HashMap<String, String>,
+        target: T,
+    ) -> anyhow::Result<UploadVerification> {
+        let total = animation_mapping.len();
+        let new_ids: Vec<u64> = animation_mapping
+            .values()
+            .filter_map(|new_id| new_id.parse::<u64>().ok())
+            .collect();
+
+        let assets: HashMap<String, AnimationAsset> = self
+            .fetch_animation_assets(new_ids)
+            .await?
+            .into_iter()
+            .map(|asset| (asset.id.to_string(), asset))
+            .collect();
+
+        let expected_owner = self.target_owner(target).await?;
+
+        let mut verification = UploadVerification::default();
+        for (old_id, new_id) in animation_mapping {
+            // Logged under the source animation's ID, like the upload it follows
+            let correlation_id = old_id
+                .parse()
+                .map(|asset_id| CorrelationId::new(asset_id).to_string())
+                .unwrap_or_default();
+
+            // The batch API leaves out assets it answers with an error, which is what an
+            // upload still in review gets, so it only tells whether the asset downloads
+            let asset = assets.get(&new_id);
+            if asset.is_some_and(|asset| !asset.is_animation()) {
+                warn!(
+                    correlation_id,
+                    "Verification failed: new asset {} (replacing {}) isn't an animation",
+                    new_id,
+                    old_id
+                );
+                continue;
+            }
+            let downloadable = asset.is_some_and(|asset| asset.location().is_some());
+
+            match self.asset_creator_with_retry(&new_id).await {
+                Ok(owner) if owner == expected_owner => {
+                    if downloadable {
+                        verification.verified.insert(old_id, new_id);
+                    } else {
+                        info!(
+                            correlation_id,
+                            "New animation {} has no download location yet, pending moderation",
+                            new_id
+                        );
+                        verification.pending.insert(old_id, new_id);
+                    }
+                }
+                Ok(owner) => warn!(
+                    correlation_id,
+                    "Verification failed: new animation {} is owned by {:?}, expected {:?}",
+                    new_id,
+                    owner,
+                    expected_owner
+                ),
+                // Also how an ID that doesn't exist fails
+                Err(e) => warn!(
+                    correlation_id,
+                    "Verification failed: could not get owner of new animation {}: {}", new_id, e
+                ),
+            }
+        }
+
+        info!(
+            "Verified {}/{} uploaded animations, {} of them pending moderation",
+            verification.verified.len() + verification.pending.len(),
+            total,
+            verification.pending.len()
+        );
+        Ok(verification)
+    }
+
+    /// Looks up an asset's creator, waiting out rate limits.
+    pub(super) async fn asset_creator_with_retry(
+        &self,
+        asset_id: &str,
+    ) -> anyhow::Result<AssetCreator> {
+        let asset_id = asset_id.parse::<u64>()?;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            match self.asset_creator(asset_id).await {
+                Ok(creator) => return Ok(creator),
+                Err(e) => {
+                    if attempt <= self.config.retry.max_verify_retries
+                        && let Some(RoboatError::TooManyRequests) = e.downcast_ref::<RoboatError>()
+                    {
+                        self.rate_limiter
+                            .set_rate_limit(self.config.retry.verify_wait(attempt))
+                            .await;
+                        self.rate_limiter.wait_if_limited().await;
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
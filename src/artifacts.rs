@@ -0,0 +1,190 @@
+use crate::animation::model::AnimationAsset;
+use crate::report::{FailedAsset, Replacement, SkippedAsset, UploadReceipt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The schema version written into every JSON artifact.
+///
+/// # Notes:
+/// 2 added `receipts` to mappings and `failed`/`receipts` to summaries. Version 1 readers
+/// reject unknown fields, so the bump keeps them from misreading newer files.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// An old → new mapping file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MappingV2 {
+    pub schema_version: u32,
+    pub mappings: BTreeMap<String, String>,
+    /// Creator Hub links of the new IDs, grouped by upload destination.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub receipts: BTreeMap<String, Vec<UploadReceipt>>,
+    /// Set on the new → old files a rewrite saves (inverse-mapping.json), so revert can use
+    /// them as they are.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inverse: bool,
+}
+
+/// A scan file written by the scan command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanReportV2 {
+    pub schema_version: u32,
+    pub source: String,
+    pub animations: Vec<AnimationAsset>,
+}
+
+/// The report of a run (--report).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SummaryV2 {
+    pub schema_version: u32,
+    pub replacements: Vec<Replacement>,
+    pub skipped: Vec<SkippedAsset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedAsset>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub receipts: BTreeMap<String, Vec<UploadReceipt>>,
+}
+
+/// The kinds of JSON artifacts the crate writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Mapping,
+    ScanReport,
+    Summary,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ArtifactKind::Mapping => "mapping",
+            ArtifactKind::ScanReport => "scan report",
+            ArtifactKind::Summary => "summary",
+        }
+    }
+}
+
+/// Parses a current-version artifact, rejecting unknown fields and other schema versions.
+pub fn parse_artifact<T: DeserializeOwned>(contents: &str) -> anyhow::Result<T> {
+    let value: Value = serde_json::from_str(contents)?;
+    match value.get("schema_version").and_then(Value::as_u64) {
+        Some(version) if version == SCHEMA_VERSION as u64 => Ok(serde_json::from_value(value)?),
+        Some(version) if version < SCHEMA_VERSION as u64 => anyhow::bail!(
+            "Schema version {} is outdated, run migrate-artifacts to upgrade this file",
+            version
+        ),
+        Some(version) => anyhow::bail!(
+            "Unsupported schema_version {} (expected {})",
+            version,
+            SCHEMA_VERSION
+        ),
+        None => anyhow::bail!("No schema_version, run migrate-artifacts to upgrade this file"),
+    }
+}
+
+/// Upgrades an artifact written before schema versions existed, or at an older version.
+/// Returns the kind and the upgraded JSON, or None if it is already current.
+///
+/// # Notes:
+/// Unversioned files are recognised by shape: a flat object of strings is a mapping, an object
+/// with `source` and `animations` is a scan report, and one with `replacements` is a summary.
+/// Version 1 files only lack the fields version 2 added, which default to empty.
+pub fn upgrade_artifact(contents: &str) -> anyhow::Result<Option<(ArtifactKind, String)>> {
+    let value: Value = serde_json::from_str(contents)?;
+    let Some(object) = value.as_object() else {
+        anyhow::bail!("Not a JSON object");
+    };
+
+    match object.get("schema_version").map(Value::as_u64) {
+        None => upgrade_unversioned(value).map(Some),
+        Some(Some(1)) => upgrade_v1(value).map(Some),
+        Some(Some(version)) if version == SCHEMA_VERSION as u64 => Ok(None),
+        Some(_) => anyhow::bail!(
+            "Unsupported schema_version {} (expected {})",
+            object["schema_version"],
+            SCHEMA_VERSION
+        ),
+    }
+}
+
+fn upgrade_unversioned(value: Value) -> anyhow::Result<(ArtifactKind, String)> {
+    let object = value.as_object().expect("checked by upgrade_artifact");
+    if object.contains_key("replacements") {
+        upgrade_summary(value)
+    } else if object.contains_key("animations") && object.contains_key("source") {
+        upgrade_scan(value)
+    } else if object.values().all(Value::is_string) {
+        let mapping = MappingV2 {
+            schema_version: SCHEMA_VERSION,
+            mappings: serde_json::from_value(value)?,
+            receipts: BTreeMap::new(),
+            inverse: false,
+        };
+        Ok((
+            ArtifactKind::Mapping,
+            serde_json::to_string_pretty(&mapping)?,
+        ))
+    } else {
+        anyhow::bail!("Unrecognised artifact");
+    }
+}
+
+fn upgrade_v1(value: Value) -> anyhow::Result<(ArtifactKind, String)> {
+    let object = value.as_object().expect("checked by upgrade_artifact");
+    if object.contains_key("replacements") {
+        upgrade_summary(value)
+    } else if object.contains_key("animations") && object.contains_key("source") {
+        upgrade_scan(value)
+    } else if object.contains_key("mappings") {
+        let mapping: MappingV2 = serde_json::from_value(with_version(value))?;
+        Ok((
+            ArtifactKind::Mapping,
+            serde_json::to_string_pretty(&mapping)?,
+        ))
+    } else {
+        anyhow::bail!("Unrecognised artifact");
+    }
+}
+
+fn upgrade_summary(value: Value) -> anyhow::Result<(ArtifactKind, String)> {
+    let summary: SummaryV2 = serde_json::from_value(with_version(value))?;
+    Ok((
+        ArtifactKind::Summary,
+        serde_json::to_string_pretty(&summary)?,
+    ))
+}
+
+fn upgrade_scan(value: Value) -> anyhow::Result<(ArtifactKind, String)> {
+    let scan: ScanReportV2 = serde_json::from_value(with_version(value))?;
+    Ok((
+        ArtifactKind::ScanReport,
+        serde_json::to_string_pretty(&scan)?,
+    ))
+}
+
+fn with_version(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_mappings_upgrade_and_are_rejected_until_then() {
+        let v1 = r#"{"schema_version": 1, "mappings": {"1": "2"}}"#;
+        assert!(parse_artifact::<MappingV2>(v1).is_err());
+
+        let (kind, upgraded) = upgrade_artifact(v1).unwrap().unwrap();
+        assert_eq!(kind, ArtifactKind::Mapping);
+        let mapping: MappingV2 = parse_artifact(&upgraded).unwrap();
+        assert_eq!(mapping.mappings["1"], "2");
+        assert!(upgrade_artifact(&upgraded).unwrap().is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Everything Roblox writes in front of an asset ID, matched case-insensitively and with
+/// whitespace allowed before the ID (see ID_PATTERN):
+/// * `rbxassetid://123`, also with whitespace before the slashes
+/// * `http(s)://www.roblox.com/asset/?id=123`, also without the scheme, `www.` or the slash
+///   before `?`, and with other query parameters such as `&version=2` around the ID
+/// * `https://assetdelivery.roblox.com/v1/asset/?id=123` and `/v1/assetId/123/version/2`
+/// * marketplace links: `roblox.com/library/123/Name`, `roblox.com/catalog/123/Name` and
+///   `create.roblox.com/marketplace/asset/123/Name` (or `/store/asset/`)
+const PREFIX: &str = r#"rbxassetid:[ \t]*//|(?:https?://)?(?:[a-z0-9-]+\.)?roblox\.com/(?:(?:v\d+/)?asset/?\?(?:[^\s"'&#]*&)*id[ \t]*=|v\d+/assetid/|library/|catalog/|(?:marketplace|store)/asset/)"#;
+
+/// Hand-edited values put spaces or tabs between the prefix and the ID (`rbxassetid:// 123`).
+/// They are left out of the prefix group, so rewriting the ID keeps them. Newlines aren't
+/// allowed, so an ID on the next line of a script isn't taken for this one's.
+const ID_PATTERN: &str = r"[ \t]*(?P<id>\d+)";
+
+static URL_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"(?i)(?P<prefix>{}){}", PREFIX, ID_PATTERN)).unwrap());
+
+static PREFIX_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"(?i)(?P<prefix>{})[ \t]*$", PREFIX)).unwrap());
+
+/// Parses an asset ID out of any spelling Roblox uses for one: a bare ID, `rbxassetid://`,
+/// asset and assetdelivery URLs (versioned or not) and marketplace links.
+///
+/// # Examples
+///
+/// ```rust
+/// # use animation_replace_roblox::asset_id::parse_any;
+/// assert_eq!(parse_any("rbxassetid://507771019"), Some(507771019));
+/// assert_eq!(parse_any("https://www.roblox.com/asset/?id=507771019"), Some(507771019));
+/// assert_eq!(
+///     parse_any("https://assetdelivery.roblox.com/v1/asset/?id=507771019&version=3"),
+///     Some(507771019)
+/// );
+/// assert_eq!(parse_any("https://create.roblox.com/store/asset/507771019/Wave"), Some(507771019));
+/// // Hand-edited spellings found in real games
+/// assert_eq!(parse_any("RBXASSETID://507771019"), Some(507771019));
+/// assert_eq!(parse_any("rbxassetid:// 507771019"), Some(507771019));
+/// assert_eq!(parse_any("  rbxassetid://507771019?v=2 "), Some(507771019));
+/// assert_eq!(parse_any("http://www.roblox.com/asset/?ID= 507771019"), Some(507771019));
+/// assert_eq!(parse_any("rbxasset://textures/face.png"), None);
+/// ```
+///
+/// # Notes:
+/// Only the first ID in the value is returned. Use url_pattern to find every ID in a longer
+/// piece of text.
+pub fn parse_any(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return value.parse().ok();
+    }
+
+    URL_PATTERN
+        .captures(value)?
+        .name("id")?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+/// Matches an asset URL in any spelling parse_any accepts. The `prefix` group is everything
+/// before the ID and the `id` group the ID's digits.
+pub fn url_pattern() -> &'static Regex {
+    &URL_PATTERN
+}
+
+/// Matches an asset URL prefix at the end of a string, for IDs concatenated onto it
+/// (`"rbxassetid://" .. 123`). The prefix is in the `prefix` group.
+pub(crate) fn prefix_pattern() -> &'static Regex {
+    &PREFIX_PATTERN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_uppercase_prefixes() {
+        assert_eq!(parse_any("RBXASSETID://507771019"), Some(507771019));
+        assert_eq!(
+            parse_any("HTTPS://WWW.ROBLOX.COM/ASSET/?ID=507771019"),
+            Some(507771019)
+        );
+    }
+
+    #[test]
+    fn parses_space_after_slashes() {
+        assert_eq!(parse_any("rbxassetid:// 507771019"), Some(507771019));
+        assert_eq!(parse_any("rbxassetid: //507771019"), Some(507771019));
+    }
+
+    #[test]
+    fn parses_tab_before_id() {
+        assert_eq!(parse_any("rbxassetid://\t507771019"), Some(507771019));
+        assert_eq!(
+            parse_any("https://www.roblox.com/asset/?id=\t507771019"),
+            Some(507771019)
+        );
+    }
+
+    #[test]
+    fn ignores_version_suffix() {
+        assert_eq!(parse_any("rbxassetid://507771019?v=2"), Some(507771019));
+        assert_eq!(parse_any("  rbxassetid://507771019?v=2 "), Some(507771019));
+    }
+
+    #[test]
+    fn parses_space_after_id_parameter() {
+        assert_eq!(
+            parse_any("http://www.roblox.com/asset/?ID= 507771019"),
+            Some(507771019)
+        );
+    }
+
+    #[test]
+    fn newline_between_prefix_and_id_does_not_match() {
+        assert_eq!(parse_any("rbxassetid://\n507771019"), None);
+        assert!(!url_pattern().is_match("local url = \"rbxassetid://\"\n507771019"));
+    }
+
+    #[test]
+    fn keeps_whitespace_out_of_the_prefix() {
+        let captures = url_pattern().captures("rbxassetid://  507771019").unwrap();
+        assert_eq!(&captures["prefix"], "rbxassetid://");
+        assert_eq!(&captures["id"], "507771019");
+    }
+
+    #[test]
+    fn prefix_pattern_allows_trailing_whitespace() {
+        assert!(prefix_pattern().is_match("rbxassetid://"));
+        assert!(prefix_pattern().is_match("rbxassetid:// "));
+        assert!(prefix_pattern().is_match("http://www.roblox.com/asset/?id=\t"));
+        assert!(!prefix_pattern().is_match("rbxassetid://\n"));
+    }
+}
@@ -0,0 +1,143 @@
+use super::output::{print_result, print_status, warn_about_packages};
+use super::settings::{Settings, save_inverse_mapping};
+use super::{CommandResult, Failure};
+use animation_replace_roblox::StudioParser;
+use animation_replace_roblox::animation::mapping::{load_mapping, load_revert_mapping};
+use animation_replace_roblox::output::OutputFormat;
+use animation_replace_roblox::script::types::AssetUrlStyle;
+use animation_replace_roblox::studio::checkpoint::Checkpoints;
+use animation_replace_roblox::studio::workplace_editor::ApplyScope;
+use std::fs;
+
+/// How the apply command writes a mapping into a place.
+pub(crate) struct ApplyOptions {
+    pub(crate) normalize_style: Option<AssetUrlStyle>,
+    pub(crate) apply_scope: ApplyScope,
+    pub(crate) no_backup: bool,
+    pub(crate) checkpoint_every: Option<usize>,
+    pub(crate) format: OutputFormat,
+}
+
+pub(crate) async fn run_apply(
+    file_path: &str,
+    mapping: &str,
+    output: &str,
+    apply_options: &ApplyOptions,
+    settings: &Settings,
+) -> CommandResult {
+    let animation_mapping = match load_mapping(shellexpand::tilde(mapping).as_ref()) {
+        Ok(animation_mapping) => animation_mapping,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let builder = StudioParser::builder()
+        .file_path(file_path)
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .backup_on_save(!apply_options.no_backup);
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+    warn_about_packages(file_path, &parser);
+
+    let checkpoints = apply_options
+        .checkpoint_every
+        .map(|every| Checkpoints::next_to(output, every));
+    let report = match &checkpoints {
+        Some(checkpoints) => {
+            parser
+                .apply_mapping_with_checkpoints(
+                    &animation_mapping,
+                    apply_options.normalize_style,
+                    apply_options.apply_scope,
+                    checkpoints,
+                )
+                .await
+        }
+        None => parser.apply_mapping(
+            &animation_mapping,
+            apply_options.normalize_style,
+            apply_options.apply_scope,
+        ),
+    };
+    print_result(&report, apply_options.format);
+
+    match parser.save_to_rbxl(output) {
+        Ok(()) => {
+            print_status(
+                apply_options.format,
+                &format!(
+                    "Applied {} replacements to {}",
+                    report.replacements.len(),
+                    output
+                ),
+            );
+            // The finished file supersedes the checkpoint
+            if let Some(checkpoints) = &checkpoints {
+                let _ = fs::remove_file(checkpoints.path());
+            }
+            save_inverse_mapping(&animation_mapping, settings)
+        }
+        Err(e) => {
+            eprintln!("Failed to save {}: {}", output, e);
+            Err(Failure::Save)
+        }
+    }
+}
+
+pub(crate) fn run_revert(
+    file_path: &str,
+    mapping: &str,
+    output: &str,
+    no_backup: bool,
+    settings: &Settings,
+) -> CommandResult {
+    let inverse_mapping = match load_revert_mapping(shellexpand::tilde(mapping).as_ref()) {
+        Ok(inverse_mapping) => inverse_mapping,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let builder = StudioParser::builder()
+        .file_path(file_path)
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .backup_on_save(!no_backup);
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+    warn_about_packages(file_path, &parser);
+
+    let report = parser.apply_mapping(&inverse_mapping, None, ApplyScope::All);
+    for replacement in &report.replacements {
+        println!("Reverted {}", replacement);
+    }
+
+    match parser.save_to_rbxl(output) {
+        Ok(()) => {
+            println!(
+                "Reverted {} replacements in {}",
+                report.replacements.len(),
+                output
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to save {}: {}", output, e);
+            Err(Failure::Save)
+        }
+    }
+}
@@ -0,0 +1,623 @@
+use animation_replace_roblox::animation::mapping::ConflictResolution;
+use animation_replace_roblox::output::OutputFormat;
+use animation_replace_roblox::script::types::AssetUrlStyle;
+use animation_replace_roblox::studio::publish::PlaceVersionType;
+use animation_replace_roblox::studio::workplace_editor::ApplyScope;
+use animation_replace_roblox::{FailurePolicy, Retarget};
+use clap::{ArgAction, Parser, Subcommand};
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
+pub(crate) struct Args {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// .ROBLOSECURITY cookie string. Prefer --cookie-file or the ROBLOSECURITY environment
+    /// variable, which stay out of shell history and process lists
+    #[arg(long, short)]
+    pub(crate) cookie: Option<String>,
+
+    /// file PATH of the .rbxl file [REQUIRED unless --places-dir or --place-id is used]. Several
+    /// paths or a glob like "places/*.rbxl" migrate the places together in batch mode
+    #[arg(long, short, num_args = 1.., required_unless_present_any = ["places_dir", "place_id"])]
+    pub(crate) file: Vec<String>,
+
+    /// Download the place to migrate from Roblox instead of --file (needs edit access and --output)
+    #[arg(long, conflicts_with = "file", requires = "output")]
+    pub(crate) place_id: Option<u64>,
+
+    /// With --place-id, migrate this saved version instead of the latest (see place-versions)
+    #[arg(long, requires = "place_id")]
+    pub(crate) place_version: Option<u64>,
+
+    /// After saving and --verify-rewritten, upload the migrated place back to --place-id as a
+    /// saved (default) or published version
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "saved", requires_all = ["place_id", "api_key"])]
+    pub(crate) publish: Option<PlaceVersionType>,
+
+    /// Publish with --publish even when some animations failed to upload, verify or save
+    #[arg(long, requires = "publish")]
+    pub(crate) publish_partial: bool,
+
+    /// Open Cloud API key with universe-places write permission, for --publish
+    #[arg(long)]
+    pub(crate) api_key: Option<String>,
+
+    /// Only scan and rewrite this service or subtree, e.g. "ReplicatedStorage.AnimationsFolder",
+    /// or everything a selector matches, e.g. 'Workspace//Model[Name=~"^NPC"]'
+    #[arg(long)]
+    pub(crate) scope: Option<String>,
+
+    /// Which changes to write: Animation instances, script sources, or both
+    #[arg(long, value_enum, default_value = "all")]
+    pub(crate) apply_scope: ApplyScope,
+
+    /// Batch mode: migrate every .rbxl/.rbxlx place in this directory (recursively)
+    #[arg(long)]
+    pub(crate) places_dir: Option<String>,
+
+    /// Batch mode: write the migrated places here, mirroring the --places-dir layout
+    #[arg(long)]
+    pub(crate) output_dir: Option<String>,
+
+    /// Batch mode: overwrite the places in --places-dir instead of using --output-dir
+    #[arg(long, conflicts_with = "output_dir")]
+    pub(crate) in_place: bool,
+
+    /// Save the copy instead replacing file [AVOID DATA LOSS]
+    #[arg(long, short)]
+    pub(crate) output: Option<String>,
+
+    /// Required if the the game will be published to a Group [Id of the group]
+    #[arg(long, short)]
+    pub(crate) group: Option<u64>,
+
+    /// How many concurrent tasks using semaphore. [defaulted to 5]
+    #[arg(long, short, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) threads: Option<u64>,
+
+    /// Rewrite replaced script references into one URL style [rbxassetid, asset-url]
+    #[arg(long)]
+    pub(crate) normalize_style: Option<AssetUrlStyle>,
+
+    /// Also migrate the .rbxm/.rbxmx plugins in this directory ("default" for Studio's plugins folder)
+    #[arg(long)]
+    pub(crate) plugins_dir: Option<String>,
+
+    /// Also migrate the .lua/.luau files in this directory (e.g. a Rojo project's src/)
+    #[arg(long)]
+    pub(crate) scripts_dir: Option<String>,
+
+    /// Abort before uploading if more than this many animations are found [GUARDRAIL]
+    #[arg(long)]
+    pub(crate) max_uploads: Option<usize>,
+
+    /// Don't back up the file before overwriting it
+    #[arg(long)]
+    pub(crate) no_backup: bool,
+
+    /// Also publish an Animation from the KeyframeSequence of the same name in its rig's
+    /// AnimSaves folder, not only from one parented to the Animation
+    #[arg(long)]
+    pub(crate) use_anim_saves: bool,
+
+    /// Save the old -> new animation mapping of this run to a JSON file
+    #[arg(long)]
+    pub(crate) save_mapping: Option<String>,
+
+    /// JSON file of animations rejected by moderation, skipped on later runs
+    /// [default: blocklist.json in the config's cache_dir or the current directory]
+    #[arg(long)]
+    pub(crate) blocklist: Option<String>,
+
+    /// List the animations found and ask before uploading them
+    #[arg(long)]
+    pub(crate) interactive: bool,
+
+    /// Reupload animations the upload target (--group or the cookie's user) already owns
+    /// instead of skipping them as already owned
+    #[arg(long)]
+    pub(crate) reupload_owned: bool,
+
+    /// Only upload the animations that failed to download or upload in the last run
+    /// (failed-uploads.json in the config's cache_dir or the current directory)
+    #[arg(long)]
+    pub(crate) retry_failed: bool,
+
+    /// When interrupted with Ctrl-C, still rewrite the files with the uploads that finished
+    /// instead of only saving their mapping
+    #[arg(long)]
+    pub(crate) apply_on_interrupt: bool,
+
+    /// Stop uploading once the run has taken this long, e.g. "90s", "30m" or "1h30m", then save
+    /// the mapping of the finished uploads and apply it (only saved with --on-upload-failure
+    /// abort or rollback)
+    #[arg(long, value_parser = parse_duration)]
+    pub(crate) deadline: Option<Duration>,
+
+    /// After saving, check every new ID written into the files resolves and has the right owner
+    #[arg(long)]
+    pub(crate) verify_rewritten: bool,
+
+    /// Write a JSON report of every instance and script line that was changed
+    #[arg(long)]
+    pub(crate) report: Option<String>,
+
+    /// Post a summary (uploaded and failed counts, mapping file) to this Discord or Slack
+    /// webhook when the run finishes or aborts
+    #[arg(long)]
+    pub(crate) notify_webhook: Option<String>,
+
+    /// Write the run's summary statistics (assets fetched, uploaded and failed, bytes
+    /// transferred, time rate limited) to a JSON file, e.g. for CI dashboards
+    #[arg(long)]
+    pub(crate) metrics: Option<String>,
+
+    /// More log output: -v for debug, -vv for trace
+    #[arg(long, short, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub(crate) verbose: u8,
+
+    /// Less log output: -q for warnings and errors only, -qq for errors only
+    #[arg(long, short, global = true, action = ArgAction::Count)]
+    pub(crate) quiet: u8,
+
+    /// Write logs as JSON lines
+    #[arg(long, global = true)]
+    pub(crate) log_json: bool,
+
+    /// How many times a failed upload, download or lookup is retried
+    #[arg(long, global = true)]
+    pub(crate) max_retries: Option<u32>,
+
+    /// What to do when some uploads fail: abort at the first failure, continue and rewrite
+    /// what uploaded, or rollback (rewrite nothing unless every upload succeeded)
+    #[arg(long, global = true, value_enum, default_value_t = FailurePolicy::ContinueAndReport)]
+    pub(crate) on_upload_failure: FailurePolicy,
+
+    /// Retarget animations to another rig before reuploading them [r6-to-r15, r15-to-r6]
+    #[arg(long, global = true, value_enum)]
+    pub(crate) retarget: Option<Retarget>,
+
+    /// Timeout in seconds of each Roblox API request and download
+    #[arg(long, global = true)]
+    pub(crate) timeout: Option<u64>,
+
+    /// Send every request through this HTTP(S) proxy, e.g. http://proxy.corp:8080
+    #[arg(long, global = true)]
+    pub(crate) proxy: Option<String>,
+
+    /// Answer every Roblox API call from this MockRobloxApi fixture (JSON) instead of Roblox,
+    /// with rate limits waited out in milliseconds. For end-to-end tests
+    #[cfg(feature = "mock-server")]
+    #[arg(long, global = true, hide = true)]
+    pub(crate) mock_api: Option<String>,
+
+    /// Keep a JSON file of uploads, so an animation already uploaded to the same account or
+    /// group is reused instead of uploaded again, once it is checked to still download
+    /// [default path: upload-cache.json in the config's cache_dir or the current directory]
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "")]
+    pub(crate) upload_cache: Option<String>,
+
+    /// Where the new -> old mapping of every rewrite is saved, to undo it with revert
+    /// [default: inverse-mapping.json in the config's cache_dir or the current directory]
+    #[arg(long, global = true)]
+    pub(crate) inverse_mapping: Option<String>,
+
+    /// Read the .ROBLOSECURITY cookie from this file (--cookie wins if both are given). A file
+    /// with one cookie per line spreads uploads to --group over all of their accounts
+    #[arg(long, global = true)]
+    pub(crate) cookie_file: Option<String>,
+
+    /// Project config with defaults for the cookie variable, group, concurrency, excluded
+    /// IDs, upload name and cache directory [default: xixspoof.toml, if it exists]
+    #[arg(long, global = true)]
+    pub(crate) config: Option<String>,
+
+    /// Also scan and rewrite inside packages. Package updates revert those edits, so only use
+    /// this if you will republish the packages afterwards
+    #[arg(long, global = true)]
+    pub(crate) include_packages: bool,
+
+    /// Also reupload the default Animate script animations and Roblox-owned emotes, which
+    /// every game can already play
+    #[arg(long, global = true)]
+    pub(crate) include_roblox_assets: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Work with old -> new mapping files
+    Mapping {
+        #[command(subcommand)]
+        command: MappingCommand,
+    },
+    /// Summarize the animations a place uses
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    /// List the asset references added, removed or changed between two versions of a place
+    Diff {
+        /// The place before, e.g. the backup a migration kept
+        old: String,
+
+        /// The place after
+        new: String,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Print counts of a place's instances by class, scripts and asset references, and what
+    /// its file size is made of
+    Stats {
+        /// The .rbxl/.rbxlx file to count
+        file: String,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Find the animations a place uses and save them to a scan file
+    Scan {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// file PATH of the .rbxl file
+        #[arg(long, short)]
+        file: String,
+
+        /// Where to write the scan file
+        #[arg(long, short)]
+        output: String,
+
+        /// Also write the instances the migration will modify for review: a .csv, or a .rbxl/.rbxlx
+        /// copy of the place with ObjectValue markers in ServerStorage.AnimationMigrationReview
+        #[arg(long)]
+        heatmap: Option<String>,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Reupload the animations in a scan file and save the old -> new mapping
+    Upload {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// Scan file written by the scan command
+        #[arg(long, short)]
+        scan: String,
+
+        /// Where to write the mapping
+        #[arg(long, short)]
+        mapping: String,
+
+        /// Upload to this group [Id of the group]
+        #[arg(long, short)]
+        group: Option<u64>,
+
+        /// How many concurrent uploads
+        #[arg(long, short, value_parser = clap::value_parser!(u64).range(1..))]
+        threads: Option<u64>,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Decode animations and print their length, keyframe count and joints. Exits with an
+    /// error when any of them can't be decoded or is empty
+    Inspect {
+        /// .ROBLOSECURITY cookie string, needed to download animations by ID
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// Animation IDs to download and inspect
+        #[arg(required_unless_present = "file")]
+        ids: Vec<u64>,
+
+        /// Local .rbxm/.rbxmx animation files to inspect
+        #[arg(long, short)]
+        file: Vec<String>,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Save animations to disk as <id>.rbxm files without uploading them
+    Download {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// Animation IDs to download
+        #[arg(required_unless_present = "scan")]
+        ids: Vec<u64>,
+
+        /// Also download every animation in a scan file written by the scan command
+        #[arg(long, short)]
+        scan: Option<String>,
+
+        /// Directory to save the files in
+        #[arg(long, short)]
+        output_dir: String,
+    },
+    /// Upload every .rbxm/.rbxmx animation file in a folder and save a file name -> new ID mapping
+    UploadDir {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// Folder with the animation files
+        #[arg(long, short)]
+        dir: String,
+
+        /// Where to write the file name -> new ID mapping
+        #[arg(long, short)]
+        mapping: String,
+
+        /// Upload to this group [Id of the group]
+        #[arg(long, short)]
+        group: Option<u64>,
+    },
+    /// Rewrite a place with a mapping file (no cookie needed)
+    Apply {
+        /// file PATH of the .rbxl file
+        #[arg(long, short)]
+        file: String,
+
+        /// Mapping file written by the upload command
+        #[arg(long, short)]
+        mapping: String,
+
+        /// Save the copy instead replacing file
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// Rewrite replaced script references into one URL style [rbxassetid, asset-url]
+        #[arg(long)]
+        normalize_style: Option<AssetUrlStyle>,
+
+        /// Which changes to write: Animation instances, script sources, or both
+        #[arg(long, value_enum, default_value = "all")]
+        apply_scope: ApplyScope,
+
+        /// Don't back up the file before overwriting it
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Save a copy next to the output (e.g. Place.checkpoint.rbxl) after every N applied
+        /// mapping entries, so a crash partway through a huge mapping doesn't lose the work
+        #[arg(long)]
+        checkpoint_every: Option<usize>,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Swap the new IDs of a mapping back to the old ones, undoing a migration
+    Revert {
+        /// file PATH of the .rbxl file
+        #[arg(long, short)]
+        file: String,
+
+        /// Mapping file the place was migrated with (old -> new), or the inverse mapping a
+        /// rewrite saved (new -> old), which is used as is
+        #[arg(long, short)]
+        mapping: String,
+
+        /// Save the copy instead replacing file
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// Don't back up the file before overwriting it
+        #[arg(long)]
+        no_backup: bool,
+    },
+    /// Check that every new ID in a mapping file resolves and has the right owner
+    Verify {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// Mapping file to check
+        #[arg(long, short, required_unless_present = "file")]
+        mapping: Option<String>,
+
+        /// Check every animation this place references instead of a mapping: reports the ones
+        /// that are deleted, moderated or owned by someone other than --group (or the cookie's
+        /// user), which will fail in-game
+        #[arg(long, short, conflicts_with_all = ["mapping", "output", "wait_moderation"])]
+        file: Option<String>,
+
+        /// The group the animations should be owned by, if not the cookie's user
+        #[arg(long, short)]
+        group: Option<u64>,
+
+        /// Write the entries that passed to this mapping file
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// Then poll the new IDs until moderation approves or rejects them, for up to MINUTES
+        /// (30 by default). Rejected ones are left out of --output
+        #[arg(long, value_name = "MINUTES", num_args = 0..=1, default_missing_value = "30")]
+        wait_moderation: Option<u64>,
+
+        /// Also download each new animation and its source and compare their events (named
+        /// keyframes and KeyframeMarkers). Entries whose events changed are reported and left
+        /// out of --output
+        #[arg(long, conflicts_with = "file")]
+        check_events: bool,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Move a place's animations from one group to another and rewrite the place
+    Migrate {
+        /// .ROBLOSECURITY cookie string (needs upload permissions in --to-group)
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// file PATH of the .rbxl file
+        #[arg(long, short)]
+        file: String,
+
+        /// Only animations owned by this group are migrated
+        #[arg(long)]
+        from_group: u64,
+
+        /// Group the animations are reuploaded to
+        #[arg(long)]
+        to_group: u64,
+
+        /// Save the copy instead replacing file
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// How many concurrent uploads
+        #[arg(long, short, value_parser = clap::value_parser!(u64).range(1..))]
+        threads: Option<u64>,
+
+        /// Don't back up the file before overwriting it
+        #[arg(long)]
+        no_backup: bool,
+    },
+    /// List the modules scripts load with require(assetId), and with --reupload upload them to
+    /// a new owner and rewrite the require() calls with the new IDs
+    Modules {
+        /// .ROBLOSECURITY cookie string (only needed with --reupload)
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// file PATH of the .rbxl file
+        #[arg(long, short)]
+        file: String,
+
+        /// Reupload the modules and rewrite the place
+        #[arg(long)]
+        reupload: bool,
+
+        /// Upload to this group [Id of the group]
+        #[arg(long, short)]
+        group: Option<u64>,
+
+        /// Save the copy instead replacing file
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// Don't back up the file before overwriting it
+        #[arg(long)]
+        no_backup: bool,
+    },
+    /// List the saved versions of a place, to pick one for --place-version
+    PlaceVersions {
+        /// .ROBLOSECURITY cookie string (needs edit access to the place)
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// The place to list versions of
+        #[arg(long)]
+        place_id: u64,
+    },
+    /// Store the cookie in the OS keychain, so no command needs --cookie
+    #[cfg(feature = "keyring")]
+    Keychain {
+        #[command(subcommand)]
+        command: KeychainCommand,
+    },
+    /// Upgrade mapping, scan and report files written by older versions to the current schema
+    MigrateArtifacts {
+        /// Files to upgrade in place (a backup of each is kept)
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Subcommand, Debug)]
+pub(crate) enum KeychainCommand {
+    /// Save a cookie, read from --cookie-file, $ROBLOSECURITY or a prompt
+    Store,
+    /// Remove the stored cookie
+    Forget,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum MappingCommand {
+    /// Merge mapping files from several partial runs into one
+    Merge {
+        /// Mapping files to merge, in order
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<String>,
+
+        /// Where to write the merged mapping
+        #[arg(long, short)]
+        output: String,
+
+        /// What to do when an old ID is mapped to different new IDs
+        #[arg(long, value_enum, default_value = "fail")]
+        on_conflict: ConflictResolution,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ReportCommand {
+    /// Group every animation by the user or group that owns it, to see which creator
+    /// accounts the game depends on
+    Owners {
+        /// .ROBLOSECURITY cookie string
+        #[arg(long, short)]
+        cookie: Option<String>,
+
+        /// file PATH of the .rbxl file to scan
+        #[arg(long, short, required_unless_present = "scan", conflicts_with = "scan")]
+        file: Option<String>,
+
+        /// Scan file written by the scan command, instead of --file
+        #[arg(long, short)]
+        scan: Option<String>,
+
+        /// How to print the result
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+/// Parses a duration like "90s", "30m", "2h" or "1h30m". A bare number is seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit '{}' in '{}'", c, value)),
+        };
+        let amount: u64 = number
+            .parse()
+            .map_err(|_| format!("missing number before '{}' in '{}'", c, value))?;
+        total += amount * unit;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        return Err(format!(
+            "'{}' isn't a duration like 90s, 30m or 1h30m",
+            value
+        ));
+    }
+    Ok(Duration::from_secs(total))
+}
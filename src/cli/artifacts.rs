@@ -0,0 +1,105 @@
+use super::args::MappingCommand;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::mapping::{
+    ConflictResolution, load_mapping, merge_mappings, save_mapping,
+};
+use animation_replace_roblox::artifacts::{SCHEMA_VERSION, upgrade_artifact};
+use animation_replace_roblox::studio::backup::backup_file;
+use std::fs;
+
+pub(crate) fn run_mapping_command(command: MappingCommand) -> CommandResult {
+    match command {
+        MappingCommand::Merge {
+            files,
+            output,
+            on_conflict,
+        } => {
+            let mut mappings = Vec::new();
+            for file in &files {
+                match load_mapping(shellexpand::tilde(file).as_ref()) {
+                    Ok(mapping) => mappings.push(mapping),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return Err(Failure::Config);
+                    }
+                }
+            }
+
+            let (merged, conflicts) = merge_mappings(&mappings, on_conflict);
+            for conflict in &conflicts {
+                eprintln!(
+                    "Conflict: {} is mapped to {}",
+                    conflict.old_id,
+                    conflict.new_ids.join(", ")
+                );
+            }
+
+            if on_conflict == ConflictResolution::Fail && !conflicts.is_empty() {
+                eprintln!(
+                    "{} conflicts found, not writing {}. Use --on-conflict first|last to resolve them.",
+                    conflicts.len(),
+                    output
+                );
+                return Err(Failure::Config);
+            }
+
+            match save_mapping(shellexpand::tilde(&output).as_ref(), &merged) {
+                Ok(()) => {
+                    println!("Merged {} entries into {}", merged.len(), output);
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    Err(Failure::Save)
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn run_migrate_artifacts(files: &[String]) -> CommandResult {
+    let mut outcome = Ok(());
+    for file in files {
+        let path = shellexpand::tilde(file).to_string();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                outcome = Err(Failure::Config);
+                continue;
+            }
+        };
+
+        let (kind, upgraded) = match upgrade_artifact(&contents) {
+            Ok(Some(upgraded)) => upgraded,
+            Ok(None) => {
+                println!("{} is already at schema version {}", path, SCHEMA_VERSION);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Failed to upgrade {}: {}", path, e);
+                outcome = Err(Failure::Config);
+                continue;
+            }
+        };
+
+        if let Err(e) = backup_file(&path) {
+            eprintln!("{}", e);
+            outcome = Err(Failure::Save);
+            continue;
+        }
+        match fs::write(&path, upgraded) {
+            Ok(()) => println!(
+                "Upgraded {} ({}) to schema version {}",
+                path,
+                kind.as_str(),
+                SCHEMA_VERSION
+            ),
+            Err(e) => {
+                eprintln!("Failed to write {}: {}", path, e);
+                outcome = Err(Failure::Save);
+            }
+        }
+    }
+    outcome
+}
@@ -0,0 +1,110 @@
+use super::Failure;
+use super::settings::Settings;
+use animation_replace_roblox::UploadTarget;
+#[cfg(feature = "keyring")]
+use animation_replace_roblox::animation::credentials::cookie_from_keyring;
+use animation_replace_roblox::animation::credentials::{COOKIE_ENV_VAR, cookie_from_env};
+use animation_replace_roblox::animation::uploader::AnimationUploader;
+
+/// Finds the cookie: --cookie, --cookie-file, $ROBLOSECURITY, the config's cookie_env, then
+/// the OS keychain.
+fn resolve_cookie(cookie: Option<String>, settings: &Settings) -> Result<String, Failure> {
+    if let Some(cookie) = cookie {
+        return Ok(cookie);
+    }
+    if let Some(cookie) = settings.file_cookies.first() {
+        return Ok(cookie.clone());
+    }
+    if let Some(cookie) = cookie_from_env().or_else(|| settings.config.cookie()) {
+        return Ok(cookie);
+    }
+
+    #[cfg(feature = "keyring")]
+    match cookie_from_keyring() {
+        Ok(Some(cookie)) => return Ok(cookie),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+
+    eprintln!(
+        "No cookie: pass --cookie-file, set ${} or use --cookie",
+        COOKIE_ENV_VAR
+    );
+    Err(Failure::Config)
+}
+
+/// Picks the cookie with resolve_cookie and checks it before anything else runs, so an
+/// expired one fails fast instead of as upload errors partway through.
+pub(crate) async fn authenticate(
+    cookie: Option<String>,
+    settings: &Settings,
+) -> Result<String, Failure> {
+    let cookie = resolve_cookie(cookie, settings)?;
+
+    match new_uploader(cookie.clone(), settings)?
+        .authenticated_user()
+        .await
+    {
+        Ok(user) => {
+            eprintln!("Running as {}", user);
+            Ok(cookie)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Auth)
+        }
+    }
+}
+
+/// authenticate, then checks the cookie's user can upload to `target`, so a missing group
+/// permission fails before anything is scanned or downloaded.
+pub(crate) async fn authenticate_for_upload(
+    cookie: Option<String>,
+    target: UploadTarget,
+    settings: &Settings,
+) -> Result<String, Failure> {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let uploader = new_uploader(cookie.clone(), settings)?;
+    match uploader.validate_upload_target(target).await {
+        Ok(_) => {
+            eprintln!("Uploading to {}", target);
+            if uploader.account_count() > 1 {
+                eprintln!(
+                    "Spreading uploads over {} accounts",
+                    uploader.account_count()
+                );
+            }
+            Ok(cookie)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Auth)
+        }
+    }
+}
+
+/// An uploader for the cookie. When the cookie came from --cookie-file, the file's other
+/// cookies are added as its upload pool.
+pub(crate) fn new_uploader(
+    cookie: String,
+    settings: &Settings,
+) -> Result<AnimationUploader, Failure> {
+    let extra_cookies = match settings.file_cookies.split_first() {
+        Some((first, rest)) if *first == cookie => rest.to_vec(),
+        _ => Vec::new(),
+    };
+    let builder = AnimationUploader::builder()
+        .roblosecurity(cookie)
+        .extra_cookies(extra_cookies)
+        .config(settings.uploader_config.clone());
+    #[cfg(feature = "mock-server")]
+    let builder = match &settings.mock_api {
+        Some(api) => builder.api(api.clone()),
+        None => builder,
+    };
+    builder.build().map_err(|e| {
+        eprintln!("{}", e);
+        Failure::Config
+    })
+}
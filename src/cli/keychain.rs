@@ -0,0 +1,60 @@
+use super::args::KeychainCommand;
+use super::settings::Settings;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::credentials::cookie_from_env;
+use animation_replace_roblox::animation::credentials::{
+    delete_cookie_from_keyring, store_cookie_in_keyring,
+};
+use std::io::Write;
+
+pub(crate) fn run_keychain_command(command: KeychainCommand, settings: &Settings) -> CommandResult {
+    match command {
+        KeychainCommand::Store => {
+            let cookie = settings
+                .file_cookies
+                .first()
+                .cloned()
+                .or_else(cookie_from_env);
+            let cookie = match cookie {
+                Some(cookie) => cookie,
+                None => {
+                    // Not prompt(), cookies are case sensitive
+                    print!("Paste the .ROBLOSECURITY cookie: ");
+                    let _ = std::io::stdout().flush();
+                    let mut cookie = String::new();
+                    let _ = std::io::stdin().read_line(&mut cookie);
+                    cookie.trim().to_string()
+                }
+            };
+            if cookie.is_empty() {
+                eprintln!("No cookie given");
+                return Err(Failure::Config);
+            }
+
+            match store_cookie_in_keyring(&cookie) {
+                Ok(()) => {
+                    println!("Saved the cookie in the keychain");
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    Err(Failure::Save)
+                }
+            }
+        }
+        KeychainCommand::Forget => match delete_cookie_from_keyring() {
+            Ok(true) => {
+                println!("Removed the cookie from the keychain");
+                Ok(())
+            }
+            Ok(false) => {
+                println!("No cookie is stored in the keychain");
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                Err(Failure::Save)
+            }
+        },
+    }
+}
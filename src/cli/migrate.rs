@@ -0,0 +1,204 @@
+use super::auth::{authenticate, authenticate_for_upload, new_uploader};
+use super::output::warn_about_packages;
+use super::settings::{Settings, save_inverse_mapping};
+use super::{CommandResult, Failure};
+use animation_replace_roblox::modules::pipeline::ModulePipeline;
+use animation_replace_roblox::{
+    AnimationPipeline, AssetCreator, Migrator, StudioParser, UploadOptions,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub(crate) async fn run_place_versions(
+    cookie: Option<String>,
+    place_id: u64,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let uploader = new_uploader(cookie, settings)?;
+    match uploader.list_place_versions(place_id).await {
+        Ok(versions) => {
+            println!("{} saved versions of place {}", versions.len(), place_id);
+            for version in versions {
+                println!(
+                    "  {:>6}  {}{}",
+                    version.version_number,
+                    version.created,
+                    if version.is_published {
+                        "  (published)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Other)
+        }
+    }
+}
+
+/// The "studio swap": reuploads every animation the place uses that `from_group` owns to the
+/// group in `upload_options`, then rewrites the place.
+pub(crate) async fn run_migrate(
+    cookie: Option<String>,
+    file_path: &str,
+    from_group: u64,
+    upload_options: &UploadOptions,
+    output: &str,
+    no_backup: bool,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate_for_upload(cookie, upload_options.upload_target(), settings).await?;
+
+    let builder = StudioParser::builder()
+        .file_path(file_path)
+        .roblosecurity(&cookie)
+        .uploader_config(settings.uploader_config.clone())
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .backup_on_save(!no_backup);
+
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+    warn_about_packages(file_path, &parser);
+
+    let uploader = Arc::new(new_uploader(cookie, settings)?);
+    let migrator = Migrator::new().register(
+        AnimationPipeline::new(uploader, upload_options.clone())
+            .owned_by(AssetCreator::Group(from_group)),
+    );
+
+    let migration = match migrator.migrate(&mut parser).await {
+        Ok(migration) => migration,
+        Err(e) => {
+            eprintln!("Failed to migrate animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    for replacement in &migration.report.replacements {
+        println!("Replaced {}", replacement);
+    }
+    let migrated = migration
+        .mappings
+        .values()
+        .map(|mapping| mapping.len())
+        .sum::<usize>();
+    if migrated == 0 {
+        println!("Nothing owned by group {} to migrate", from_group);
+        return Ok(());
+    }
+
+    match parser.save_to_rbxl(output) {
+        Ok(()) => {
+            println!("Migrated {} animations into {}", migrated, output);
+            let applied: HashMap<String, String> =
+                migration.mappings.into_values().flatten().collect();
+            save_inverse_mapping(&applied, settings)
+        }
+        Err(e) => {
+            eprintln!("Failed to save {}: {}", output, e);
+            Err(Failure::Save)
+        }
+    }
+}
+
+/// What the modules command does after listing the required modules.
+pub(crate) struct ModulesOptions {
+    pub(crate) reupload: bool,
+    pub(crate) group: Option<u64>,
+    pub(crate) output: String,
+    pub(crate) no_backup: bool,
+}
+
+pub(crate) async fn run_modules(
+    cookie: Option<String>,
+    file_path: &str,
+    options: &ModulesOptions,
+    settings: &Settings,
+) -> CommandResult {
+    let builder = StudioParser::builder()
+        .file_path(file_path)
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .backup_on_save(!options.no_backup);
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let references = parser.require_references();
+    if references.is_empty() {
+        println!("No require(assetId) calls in {}", file_path);
+        return Ok(());
+    }
+    for reference in &references {
+        println!(
+            "require({}) in {} line {}",
+            reference.asset_id,
+            parser.instance_path(reference.script),
+            reference.line
+        );
+    }
+    if !options.reupload {
+        return Ok(());
+    }
+
+    let cookie = authenticate(cookie, settings).await?;
+    let uploader = Arc::new(new_uploader(cookie, settings)?);
+    let migrator = Migrator::new()
+        .register(ModulePipeline::new(uploader.clone()).target(options.group.into()));
+    let migration = match migrator.migrate(&mut parser).await {
+        Ok(migration) => migration,
+        Err(e) => {
+            eprintln!("Failed to reupload modules: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    for failed in uploader.failed_uploads() {
+        eprintln!(
+            "Module {} failed ({}): {}",
+            failed.asset_id, failed.kind, failed.reason
+        );
+    }
+    for replacement in &migration.report.replacements {
+        println!("Replaced {}", replacement);
+    }
+    let module_mapping = migration
+        .mappings
+        .get("module")
+        .cloned()
+        .unwrap_or_default();
+    if module_mapping.is_empty() {
+        eprintln!("No modules were reuploaded");
+        return Err(Failure::Other);
+    }
+
+    if let Err(e) = parser.save_to_rbxl(&options.output) {
+        eprintln!("Failed to save {}: {}", options.output, e);
+        return Err(Failure::Save);
+    }
+    println!(
+        "Reuploaded {} modules into {}",
+        module_mapping.len(),
+        options.output
+    );
+    if uploader.failed_uploads().is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::PartialUpload)
+    }
+}
@@ -0,0 +1,49 @@
+pub(crate) mod apply;
+pub(crate) mod args;
+pub(crate) mod artifacts;
+pub(crate) mod auth;
+#[cfg(feature = "keyring")]
+pub(crate) mod keychain;
+pub(crate) mod migrate;
+pub(crate) mod output;
+pub(crate) mod report;
+pub(crate) mod run;
+pub(crate) mod scan;
+pub(crate) mod settings;
+pub(crate) mod upload;
+pub(crate) mod verify;
+
+use std::process::ExitCode;
+
+/// Why a command failed. Each kind exits with its own code so scripts and CI can react.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Failure {
+    /// Anything else, e.g. a Roblox API request that kept failing (exit code 1).
+    Other,
+    /// Invalid arguments, or an input file that can't be read (exit code 2).
+    Config,
+    /// The cookie is invalid or lacks a permission (exit code 3).
+    Auth,
+    /// Some animations failed to upload or verify; the rest were still applied (exit code 4).
+    PartialUpload,
+    /// A place, mapping or report couldn't be saved (exit code 5).
+    Save,
+    /// The run was stopped with Ctrl-C (exit code 130).
+    Interrupted,
+}
+
+impl Failure {
+    pub(crate) fn exit_code(self) -> ExitCode {
+        ExitCode::from(match self {
+            Failure::Other => 1,
+            Failure::Config => 2,
+            Failure::Auth => 3,
+            Failure::PartialUpload => 4,
+            Failure::Save => 5,
+            Failure::Interrupted => 130,
+        })
+    }
+}
+
+/// The outcome of a command. Errors are printed where they happen.
+pub(crate) type CommandResult = Result<(), Failure>;
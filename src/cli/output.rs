@@ -0,0 +1,219 @@
+use animation_replace_roblox::animation::model::AnimationAsset;
+use animation_replace_roblox::output::{OutputFormat, Tabular, render};
+use animation_replace_roblox::report::upload_receipts;
+use animation_replace_roblox::{AnimationRef, AssetCreator, StudioParser, UploadOptions};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+
+/// Lists the packages with animations in them. Unless --include-packages is set they are
+/// skipped, since a package update reverts edits made inside it.
+pub(crate) fn warn_about_packages(file: &str, parser: &StudioParser) {
+    let packages = parser.package_animations();
+    if packages.is_empty() {
+        return;
+    }
+
+    if parser.include_packages {
+        println!(
+            "{}: including {} packages, republish them after saving or the next package update reverts the changes",
+            file,
+            packages.len()
+        );
+        return;
+    }
+
+    eprintln!(
+        "WARNING: {}: skipping {} packages, package updates would revert their changes:",
+        file,
+        packages.len()
+    );
+    for package in &packages {
+        let asset_ids: Vec<String> = package.asset_ids.iter().map(u64::to_string).collect();
+        eprintln!(
+            "  {} ({}): {}",
+            package.path,
+            package.package_id.as_deref().unwrap_or("unknown package"),
+            asset_ids.join(", ")
+        );
+    }
+    eprintln!("Pass --include-packages to rewrite them anyway, then republish the packages.");
+}
+
+/// Shows upload progress as one bar ("uploaded 132/890, 3 failed, ETA 4m") instead of a line per
+/// animation. Returns a hidden bar, and leaves the options alone, when stderr isn't a terminal.
+pub(crate) fn attach_progress_bar(upload_options: UploadOptions) -> (UploadOptions, ProgressBar) {
+    if !std::io::stderr().is_terminal() {
+        return (upload_options, ProgressBar::hidden());
+    }
+
+    let progress_bar = ProgressBar::new(0);
+    progress_bar.set_style(
+        ProgressStyle::with_template("{bar:40} uploaded {pos}/{len}, {msg}, ETA {eta}").unwrap(),
+    );
+    progress_bar.set_message("0 failed");
+
+    let hook = progress_bar.clone();
+    let upload_options = upload_options.on_progress(move |progress| {
+        hook.set_length(progress.total as u64);
+        hook.set_position(progress.done as u64);
+        hook.set_message(format!("{} failed", progress.failed));
+    });
+
+    (upload_options, progress_bar)
+}
+
+/// How many Creator Hub links are printed after an upload for spot-checking.
+const RECEIPT_SAMPLE_SIZE: usize = 5;
+
+/// Prints Creator Hub links for a few of the new animations so they can be checked in the browser.
+/// The full list is in the mapping and report receipts.
+pub(crate) fn print_receipt_sample(
+    animation_mapping: &HashMap<String, String>,
+    group: Option<u64>,
+) {
+    for (destination, receipts) in upload_receipts(animation_mapping, group) {
+        println!(
+            "Uploaded {} animations to {}, e.g.:",
+            receipts.len(),
+            destination
+        );
+        for receipt in receipts.iter().take(RECEIPT_SAMPLE_SIZE) {
+            println!("  {} -> {}", receipt.old_id, receipt.url);
+        }
+    }
+}
+
+/// Prints a command's result to stdout in the chosen format.
+pub(crate) fn print_result<T: Tabular>(result: &T, format: OutputFormat) {
+    match render(result, format) {
+        Ok(rendered) => print!("{}", rendered),
+        Err(e) => eprintln!("Failed to print the result: {}", e),
+    }
+}
+
+/// Prints a status message to stdout, or to stderr when stdout carries JSON/CSV for another
+/// tool.
+pub(crate) fn print_status(format: OutputFormat, message: &str) {
+    match format {
+        OutputFormat::Table => println!("{}", message),
+        OutputFormat::Json | OutputFormat::Csv => eprintln!("{}", message),
+    }
+}
+
+/// Where the place uses an animation: instance paths and script lines.
+pub(crate) fn used_by(parser: &StudioParser, reference: &AnimationRef) -> Vec<String> {
+    let mut used_by: Vec<String> = reference
+        .instances
+        .iter()
+        .map(|instance| parser.instance_path(*instance))
+        .collect();
+    used_by.extend(reference.scripts.iter().map(|script| {
+        format!(
+            "{} line {}",
+            parser.instance_path(script.script),
+            script.line
+        )
+    }));
+    used_by.extend(
+        reference.attributes.iter().map(|(instance, name)| {
+            format!("{} attribute {}", parser.instance_path(*instance), name)
+        }),
+    );
+    used_by.extend(
+        reference.properties.iter().map(|(instance, property)| {
+            format!("{} {}", parser.instance_path(*instance), property)
+        }),
+    );
+    used_by.extend(reference.linked_sources.iter().map(|linked| {
+        format!(
+            "{} LinkedSource {} line {} (not rewritten)",
+            parser.instance_path(linked.script),
+            linked.linked_source,
+            linked.line
+        )
+    }));
+    used_by
+}
+
+/// Reads one answer from stdin, lowercased. EOF counts as an empty answer.
+fn prompt(question: &str) -> String {
+    print!("{}", question);
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    answer.trim().to_lowercase()
+}
+
+/// One line describing an animation: ID, name, owner and where the place uses it.
+fn describe_animation(animation: &AnimationAsset, parser: Option<&StudioParser>) -> String {
+    let name = animation.name.as_deref().unwrap_or("<unknown name>");
+    let owner = match animation.creator {
+        Some(AssetCreator::User(user_id)) => format!("user {}", user_id),
+        Some(AssetCreator::Group(group_id)) => format!("group {}", group_id),
+        None => "unknown owner".to_string(),
+    };
+
+    let mut used_by = Vec::new();
+    if let Some(parser) = parser {
+        used_by.extend(parser.animation_instance_paths(animation.id));
+        used_by.extend(
+            parser
+                .script_asset_references()
+                .into_iter()
+                .filter(|reference| reference.asset_id == animation.id)
+                .map(|reference| {
+                    format!(
+                        "{} line {}",
+                        parser.instance_path(reference.script),
+                        reference.line
+                    )
+                }),
+        );
+    }
+    if used_by.is_empty() {
+        used_by.push("outside the place".to_string());
+    }
+
+    format!(
+        "{} \"{}\" ({}) used by {}",
+        animation.id,
+        name,
+        owner,
+        used_by.join(", ")
+    )
+}
+
+/// Lists the animations and asks whether to upload all of them, none, or ask for each one.
+pub(crate) fn confirm_uploads(
+    animations: Vec<AnimationAsset>,
+    parser: Option<&StudioParser>,
+) -> Vec<AnimationAsset> {
+    let total = animations.len();
+    for (index, animation) in animations.iter().enumerate() {
+        println!(
+            "[{}/{}] {}",
+            index + 1,
+            total,
+            describe_animation(animation, parser)
+        );
+    }
+
+    match prompt(&format!(
+        "Upload all {} animations? [y]es / [n]o / [a]sk for each: ",
+        total
+    ))
+    .as_str()
+    {
+        "y" | "yes" => animations,
+        "a" | "ask" => animations
+            .into_iter()
+            .filter(|animation| {
+                let answer = prompt(&format!("Upload {}? [y/N]: ", animation.id));
+                answer == "y" || answer == "yes"
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,92 @@
+use super::auth::{authenticate, new_uploader};
+use super::output::{print_result, print_status, warn_about_packages};
+use super::scan::scan_place;
+use super::settings::Settings;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::StudioParser;
+use animation_replace_roblox::animation::metrics::{AssetTiming, OUTLIER_COUNT};
+use animation_replace_roblox::animation::scan_file::ScanFile;
+use animation_replace_roblox::animation::uploader::AnimationUploader;
+use animation_replace_roblox::output::{OutputFormat, OwnersResult};
+
+pub(crate) async fn run_owner_report(
+    cookie: Option<String>,
+    file: Option<&str>,
+    scan: Option<&str>,
+    format: OutputFormat,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let animations = match (file, scan) {
+        (Some(file), _) => {
+            let file_path = shellexpand::tilde(file).to_string();
+            let builder = StudioParser::builder()
+                .file_path(&file_path)
+                .roblosecurity(&cookie)
+                .uploader_config(settings.uploader_config.clone())
+                .include_packages(settings.include_packages)
+                .include_roblox_assets(settings.include_roblox_assets);
+            match builder.build() {
+                Ok(parser) => {
+                    warn_about_packages(&file_path, &parser);
+                    scan_place(&parser).await?
+                }
+                Err(e) => {
+                    eprintln!("Error loading file: {}", e);
+                    return Err(Failure::Config);
+                }
+            }
+        }
+        (None, Some(scan)) => match ScanFile::load(shellexpand::tilde(scan).as_ref()) {
+            Ok(scan) => scan.animations,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Failure::Config);
+            }
+        },
+        (None, None) => return Err(Failure::Config),
+    };
+
+    let total = animations.len();
+    let uploader = new_uploader(cookie, settings)?;
+    let owners = uploader.owner_report(animations).await;
+    print_status(
+        format,
+        &format!("{} animations owned by {} creators", total, owners.len()),
+    );
+    print_result(&OwnersResult { owners }, format);
+    Ok(())
+}
+
+fn print_outliers(label: &str, timings: &[AssetTiming]) {
+    println!("{} animations:", label);
+    for timing in timings {
+        println!("  {}", timing);
+    }
+}
+
+/// Prints the run's summary statistics and saves them as JSON when --metrics is set.
+pub(crate) fn print_run_metrics(uploader: &AnimationUploader, path: Option<&str>) -> CommandResult {
+    let metrics = uploader.run_metrics();
+    println!("Run summary: {}", metrics);
+    // Only worth a look when there was more than a handful to compare
+    if metrics.uploaded > OUTLIER_COUNT as u64 {
+        print_outliers("Slowest", &metrics.slowest);
+        print_outliers("Largest", &metrics.largest);
+    }
+
+    let Some(path) = path else {
+        return Ok(());
+    };
+    match metrics.save(shellexpand::tilde(path).as_ref()) {
+        Ok(()) => {
+            println!("Saved metrics to {}", path);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Save)
+        }
+    }
+}
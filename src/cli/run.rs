@@ -0,0 +1,914 @@
+use super::args::Args;
+use super::auth::{authenticate_for_upload, new_uploader};
+use super::output::{
+    attach_progress_bar, confirm_uploads, print_receipt_sample, warn_about_packages,
+};
+use super::report::print_run_metrics;
+use super::scan::scan_place;
+use super::settings::{Settings, attach_upload_cache, save_inverse_mapping, save_upload_cache};
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::blocklist::ModerationBlocklist;
+use animation_replace_roblox::animation::failed_uploads::{
+    DEFAULT_FAILED_UPLOADS_FILE, FailedUploads,
+};
+use animation_replace_roblox::animation::mapping::{
+    DEFAULT_INTERRUPTED_MAPPING_FILE, save_mapping_with_receipts,
+};
+use animation_replace_roblox::animation::model::AnimationAsset;
+use animation_replace_roblox::animation::roblox_assets::is_roblox_asset;
+use animation_replace_roblox::animation::uploader::AnimationUploader;
+use animation_replace_roblox::notify::{RunNotification, notify_webhook};
+use animation_replace_roblox::report::{
+    Replacement, SkippedAsset, upload_destination, upload_receipts,
+};
+use animation_replace_roblox::script::fs_scanner;
+use animation_replace_roblox::studio::content_scanner::{
+    check_output_dir, common_dir, expand_paths,
+};
+use animation_replace_roblox::{ContentScanner, FailurePolicy, ReplacementReport, StudioParser};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::sync::CancellationToken;
+
+/// What run leaves behind for the --notify-webhook message.
+#[derive(Default)]
+struct RunNotice {
+    uploader: Option<Arc<AnimationUploader>>,
+    mapping: Option<String>,
+}
+
+/// Runs the main command, then posts how it ended to --notify-webhook if set.
+pub(crate) async fn run_and_notify(args: Args, settings: &Settings) -> CommandResult {
+    let webhook = args.notify_webhook.clone();
+    let files = args.file.clone();
+    let mut notice = RunNotice::default();
+    let result = run(args, settings, &mut notice).await;
+
+    let Some(webhook) = webhook else {
+        return result;
+    };
+    let outcome = match result {
+        Ok(()) => "finished",
+        Err(Failure::PartialUpload) => "finished with failures",
+        Err(Failure::Interrupted) => "interrupted",
+        Err(_) => "failed",
+    };
+    let notification = RunNotification {
+        outcome: outcome.to_string(),
+        files,
+        metrics: notice.uploader.map(|uploader| uploader.run_metrics()),
+        mapping: notice.mapping,
+    };
+    // A webhook that's down shouldn't change the outcome of the run
+    if let Err(e) = notify_webhook(&webhook, &notification).await {
+        eprintln!("{}", e);
+    }
+    result
+}
+
+/// Scans, reuploads and rewrites the place (the default command).
+async fn run(args: Args, settings: &Settings, notice: &mut RunNotice) -> CommandResult {
+    let started = tokio::time::Instant::now();
+    let files: Vec<String> = args
+        .file
+        .iter()
+        .map(|file| shellexpand::tilde(file).to_string())
+        .collect();
+    let mut files = match expand_paths(&files) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+    // Several files are migrated together like --places-dir
+    let file_path = match files.len() {
+        1 => files.pop().map(|file| file.to_string_lossy().to_string()),
+        _ => None,
+    };
+    let mut seen_ids: HashSet<u64> = HashSet::new();
+
+    if args.places_dir.is_some() && !files.is_empty() {
+        eprintln!("Pass either --places-dir or several --file places, not both");
+        return Err(Failure::Config);
+    }
+    let batch_mode = args.places_dir.is_some() || !files.is_empty();
+    if batch_mode && args.output_dir.is_none() && !args.in_place {
+        eprintln!("Batch mode needs --output-dir, or --in-place to overwrite the source places");
+        return Err(Failure::Config);
+    }
+    if batch_mode && args.output.is_some() {
+        eprintln!("Batch mode writes each place to --output-dir, not --output");
+        return Err(Failure::Config);
+    }
+    if !batch_mode && (args.output_dir.is_some() || args.in_place) {
+        eprintln!(
+            "--output-dir and --in-place are for batch mode (--places-dir or several --file places)"
+        );
+        return Err(Failure::Config);
+    }
+    // Refuse a bad --output-dir now rather than after every animation has been uploaded
+    if let Some(output_dir) = &args.output_dir {
+        let source_dir = match args.places_dir.as_deref() {
+            Some(dir) => PathBuf::from(shellexpand::tilde(dir).as_ref()),
+            None => common_dir(&files),
+        };
+        if let Err(e) = check_output_dir(&source_dir, shellexpand::tilde(output_dir).as_ref()) {
+            eprintln!("Invalid --output-dir: {}", e);
+            return Err(Failure::Config);
+        }
+    }
+    let cookie = authenticate_for_upload(args.cookie.clone(), args.group.into(), settings).await?;
+
+    // The universe is needed to download and publish the place
+    let remote_place = match args.place_id {
+        Some(place_id) => match new_uploader(cookie.clone(), settings)?
+            .place_universe_id(place_id)
+            .await
+        {
+            Ok(universe_id) => Some((universe_id, place_id)),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Failure::Config);
+            }
+        },
+        None => None,
+    };
+
+    // Build the parser with the roboat client
+    let mut builder = StudioParser::builder()
+        .roblosecurity(&cookie)
+        .uploader_config(settings.uploader_config.clone())
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .backup_on_save(!args.no_backup);
+    if let Some(api_key) = &args.api_key {
+        builder = builder.open_cloud_api_key(api_key);
+    }
+    let place = match (&file_path, remote_place) {
+        (Some(file_path), _) => Some((file_path.clone(), Ok(builder.file_path(file_path)))),
+        (None, Some((universe_id, place_id))) => {
+            println!("Downloading place {} (universe {})", place_id, universe_id);
+            let builder = match args.place_version {
+                Some(version) => {
+                    builder
+                        .from_place_version(universe_id, place_id, version)
+                        .await
+                }
+                None => builder.from_place_id(universe_id, place_id).await,
+            };
+            Some((format!("place {}", place_id), builder))
+        }
+        (None, None) => None,
+    };
+
+    let mut parser = match place {
+        Some((name, builder)) => {
+            let parser = builder
+                .and_then(|builder| builder.build())
+                .and_then(|parser| match &args.scope {
+                    Some(scope) => parser.scoped(scope),
+                    None => Ok(parser),
+                });
+
+            match parser {
+                Ok(parser) => {
+                    warn_about_packages(&name, &parser);
+                    Some(parser)
+                }
+                Err(e) => {
+                    eprintln!("Error loading file: {}", e);
+                    return Err(Failure::Config);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let mut all_animations: Vec<AnimationAsset> = Vec::new();
+    if let Some(parser) = &parser {
+        all_animations = scan_place(parser).await?;
+        seen_ids.extend(all_animations.iter().map(|animation| animation.id));
+    }
+
+    let mut embedded = HashMap::new();
+    if let Some(parser) = &parser {
+        match parser.embedded_keyframe_sequences(args.use_anim_saves) {
+            Ok(found) => embedded = found,
+            Err(e) => eprintln!("Failed to read embedded KeyframeSequences: {}", e),
+        }
+        if !embedded.is_empty() {
+            println!(
+                "{} animations have embedded KeyframeSequences and will be published without downloading",
+                embedded.len()
+            );
+        }
+
+        // Embedded animations publish even when the asset can't be downloaded anymore
+        let mut embedded_ids: Vec<u64> = embedded.keys().copied().collect();
+        embedded_ids.sort();
+        for asset_id in embedded_ids {
+            if seen_ids.insert(asset_id) {
+                all_animations.push(AnimationAsset::new(asset_id));
+            }
+        }
+    }
+
+    let scanner = match args.places_dir.as_deref() {
+        Some(dir) => {
+            let dir = shellexpand::tilde(dir).to_string();
+            Some(ContentScanner::places_from_dir(&dir, Some(cookie.clone())))
+        }
+        None if !files.is_empty() => {
+            Some(Ok(ContentScanner::from_paths(files, Some(cookie.clone()))))
+        }
+        None => None,
+    };
+    let mut places = match scanner {
+        Some(Ok(scanner)) => {
+            let mut scanner = scanner.with_uploader_config(settings.uploader_config.clone());
+            for (_, place) in &mut scanner.files {
+                place.backup_on_save = !args.no_backup;
+                place.include_packages = settings.include_packages;
+                place.include_roblox_assets = settings.include_roblox_assets;
+            }
+            if let Some(scope) = &args.scope {
+                scanner
+                    .files
+                    .retain_mut(|(path, place)| match place.set_scope(scope) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            eprintln!("Skipping {}: {}", path.display(), e);
+                            false
+                        }
+                    });
+            }
+            Some(scanner)
+        }
+        Some(Err(e)) => {
+            eprintln!("Error loading places: {}", e);
+            return Err(Failure::Config);
+        }
+        None => None,
+    };
+
+    if let Some(places) = &places {
+        println!("Scanning {} place files", places.files.len());
+        for (path, place) in &places.files {
+            warn_about_packages(&path.display().to_string(), place);
+        }
+        match places.animations().await {
+            Ok(animations) => {
+                for animation in animations {
+                    if !seen_ids.insert(animation.id) {
+                        // Skip this animation (it's a duplicate)
+                        continue;
+                    }
+                    all_animations.push(animation);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch place animations: {:?}", e);
+            }
+        }
+    }
+
+    let mut plugins = match args.plugins_dir.as_deref() {
+        Some(dir) => {
+            let dir = if dir == "default" {
+                ContentScanner::default_plugins_dir()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            } else {
+                shellexpand::tilde(dir).to_string()
+            };
+
+            match ContentScanner::from_dir(&dir, Some(cookie.clone())) {
+                Ok(scanner) => {
+                    let mut scanner =
+                        scanner.with_uploader_config(settings.uploader_config.clone());
+                    for (_, plugin) in &mut scanner.files {
+                        plugin.backup_on_save = !args.no_backup;
+                        plugin.include_packages = settings.include_packages;
+                        plugin.include_roblox_assets = settings.include_roblox_assets;
+                    }
+                    Some(scanner)
+                }
+                Err(e) => {
+                    eprintln!("Error loading plugins: {}", e);
+                    return Err(Failure::Config);
+                }
+            }
+        }
+        None => None,
+    };
+
+    if let Some(plugins) = &plugins {
+        println!("Scanning {} plugin files", plugins.files.len());
+        match plugins.animations().await {
+            Ok(animations) => {
+                for animation in animations {
+                    if !seen_ids.insert(animation.id) {
+                        // Skip this animation (it's a duplicate)
+                        continue;
+                    }
+                    all_animations.push(animation);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch plugin animations: {:?}", e);
+            }
+        }
+    }
+
+    println!(
+        "Total Animations fetched from game {}",
+        all_animations.len()
+    );
+    let scripts_dir = args
+        .scripts_dir
+        .as_deref()
+        .map(|dir| shellexpand::tilde(dir).to_string());
+
+    if let Some(dir) = &scripts_dir {
+        let asset_ids = match fs_scanner::asset_ids_in_dir(dir) {
+            Ok(asset_ids) => asset_ids,
+            Err(e) => {
+                eprintln!("Error scanning scripts directory: {}", e);
+                return Err(Failure::Config);
+            }
+        };
+
+        println!(
+            "Got all animations from scripts directory: {}",
+            asset_ids.len()
+        );
+        match new_uploader(cookie.clone(), settings)?
+            .fetch_animation_assets(asset_ids)
+            .await
+        {
+            Ok(animations) => {
+                for animation in animations {
+                    if !seen_ids.insert(animation.id) {
+                        // Skip this animation (it's a duplicate)
+                        continue;
+                    }
+                    all_animations.push(animation);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch scripts directory animations: {:?}", e);
+            }
+        }
+    }
+
+    let blocklist_path = args.blocklist.clone().unwrap_or_default();
+    let mut blocklist = match ModerationBlocklist::load(&blocklist_path) {
+        Ok(blocklist) => blocklist,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let mut report = ReplacementReport::new();
+    all_animations.retain(|animation| {
+        if !settings.config.is_excluded(animation.id) {
+            return true;
+        }
+        println!(
+            "Skipping animation {}: excluded in the config",
+            animation.id
+        );
+        report.skipped.push(SkippedAsset {
+            asset_id: animation.id,
+            reason: "excluded in the config".to_string(),
+        });
+        false
+    });
+    if !settings.include_roblox_assets {
+        all_animations.retain(|animation| {
+            if !is_roblox_asset(animation) {
+                return true;
+            }
+            println!(
+                "Skipping animation {}: a default Roblox animation",
+                animation.id
+            );
+            report.skipped.push(SkippedAsset {
+                asset_id: animation.id,
+                reason: "default Roblox animation".to_string(),
+            });
+            false
+        });
+    }
+    all_animations.retain(|animation| match blocklist.get(animation.id) {
+        Some(blocked) => {
+            println!(
+                "Skipping animation {}: rejected by moderation before ({})",
+                animation.id, blocked.reason
+            );
+            if let Some(parser) = &parser {
+                for path in parser.animation_instance_paths(animation.id) {
+                    println!("  still used by {}", path);
+                }
+            }
+            report.skipped.push(SkippedAsset {
+                asset_id: animation.id,
+                reason: format!("blocklisted: {}", blocked.reason),
+            });
+            false
+        }
+        None => true,
+    });
+
+    let failed_uploads_path = settings.config.cache_path(DEFAULT_FAILED_UPLOADS_FILE);
+    if args.retry_failed {
+        let failed_uploads = match FailedUploads::load(&failed_uploads_path) {
+            Ok(failed_uploads) => failed_uploads,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Failure::Config);
+            }
+        };
+        if failed_uploads.is_empty() {
+            println!("No failed uploads to retry");
+            return Ok(());
+        }
+        all_animations.retain(|animation| failed_uploads.contains(animation.id));
+        println!(
+            "Retrying {} animations that failed in the last run",
+            all_animations.len()
+        );
+    }
+
+    if let Some(max_uploads) = args.max_uploads
+        && all_animations.len() > max_uploads
+    {
+        eprintln!(
+            "Found {} animations, which is more than --max-uploads {}. Aborting before uploading.",
+            all_animations.len(),
+            max_uploads
+        );
+        return Err(Failure::Config);
+    }
+
+    let uploader = Arc::new(new_uploader(cookie, settings)?);
+    notice.uploader = Some(uploader.clone());
+    if !args.reupload_owned {
+        let (to_upload, owned) = match uploader
+            .split_already_owned(all_animations, args.group)
+            .await
+        {
+            Ok(split) => split,
+            Err(e) => {
+                eprintln!("Failed to check animation owners: {:?}", e);
+                return Err(Failure::Other);
+            }
+        };
+        all_animations = to_upload;
+        for animation in owned {
+            println!(
+                "Skipping animation {}: already owned by {}",
+                animation.id,
+                upload_destination(args.group)
+            );
+            report.skipped.push(SkippedAsset {
+                asset_id: animation.id,
+                reason: "already owned".to_string(),
+            });
+        }
+    }
+    if args.interactive {
+        uploader.fill_asset_details(&mut all_animations).await;
+        all_animations = confirm_uploads(all_animations, parser.as_ref());
+        if all_animations.is_empty() {
+            println!("Nothing to upload");
+            return Ok(());
+        }
+    }
+
+    let mut upload_options = settings.config.upload_options().group_id(args.group);
+    if let Some(threads) = args.threads {
+        upload_options = upload_options.concurrency(threads);
+    }
+    if let Some(max_retries) = args.max_retries {
+        upload_options = upload_options.max_retries(max_retries as usize);
+    }
+    upload_options = upload_options.failure_policy(args.on_upload_failure);
+    if let Some(retarget) = args.retarget {
+        upload_options = upload_options.retarget(retarget);
+    }
+    let (upload_options, upload_cache) = attach_upload_cache(upload_options, settings);
+    let (upload_options, progress_bar) = attach_progress_bar(upload_options);
+    let cancel_token = CancellationToken::new();
+    let uploading = Arc::new(AtomicBool::new(true));
+    handle_ctrl_c(cancel_token.clone(), uploading.clone());
+    let deadline_hit = Arc::new(AtomicBool::new(false));
+    if let Some(deadline) = args.deadline {
+        watch_deadline(
+            started + deadline,
+            cancel_token.clone(),
+            uploading.clone(),
+            deadline_hit.clone(),
+        );
+    }
+    let upload_options = upload_options.cancel_token(cancel_token.clone());
+
+    let requested = all_animations.len();
+    let mut outcome = Ok(());
+    let upload_result = uploader
+        .clone()
+        .reupload_animations_with_embedded(all_animations, embedded, &upload_options)
+        .await;
+    uploading.store(false, Ordering::SeqCst);
+    // Read as the uploads end, so the deadline passing while verifying doesn't count
+    let interrupted = cancel_token.is_cancelled();
+    let deadline_hit = deadline_hit.load(Ordering::SeqCst);
+    progress_bar.finish();
+    save_upload_cache(upload_cache, settings);
+
+    let rejections = uploader.moderation_rejections();
+    if !rejections.is_empty() {
+        for (asset_id, reason) in rejections {
+            println!("Blocklisting animation {}: {}", asset_id, reason);
+            report.skipped.push(SkippedAsset {
+                asset_id,
+                reason: format!("rejected by moderation: {}", reason),
+            });
+            blocklist.insert(asset_id, reason);
+        }
+        if let Err(e) = blocklist.save(&blocklist_path) {
+            eprintln!("{}", e);
+        }
+    }
+
+    // Overwritten every run, so a successful retry leaves nothing to retry
+    let failed_uploads = uploader.failed_uploads();
+    if !failed_uploads.is_empty() {
+        for failed in &failed_uploads {
+            eprintln!(
+                "Animation {} failed ({}): {}",
+                failed.asset_id, failed.kind, failed.reason
+            );
+        }
+        let permanent = failed_uploads
+            .iter()
+            .filter(|failed| failed.kind.is_permanent())
+            .count();
+        eprintln!(
+            "{} animations failed, {} of them permanently (moderated, deleted or forbidden)",
+            failed_uploads.len(),
+            permanent
+        );
+        if permanent < failed_uploads.len() {
+            eprintln!("Rerun with --retry-failed to retry just the failed ones");
+        }
+    }
+    report.failed.extend(failed_uploads.iter().cloned());
+    if let Err(e) = FailedUploads::new(failed_uploads).save(&failed_uploads_path) {
+        eprintln!("{}", e);
+    }
+
+    let mut applied_mapping: HashMap<String, String> = HashMap::new();
+    match upload_result {
+        Ok(animation_mapping) => {
+            let uploaded_ids: Vec<String> = animation_mapping.keys().cloned().collect();
+
+            // Never write IDs that didn't upload correctly into the place
+            let animation_mapping = match uploader
+                .verify_uploaded_animations(animation_mapping, args.group)
+                .await
+            {
+                Ok(verified) => verified,
+                Err(e) => {
+                    eprintln!("Failed to verify uploaded animations: {:?}", e);
+                    return Err(Failure::Other);
+                }
+            };
+
+            for old_id in uploaded_ids {
+                if animation_mapping.contains_key(&old_id) {
+                    continue;
+                }
+                let Ok(asset_id) = old_id.parse::<u64>() else {
+                    continue;
+                };
+                if let Some(parser) = &parser {
+                    for path in parser.animation_instance_paths(asset_id) {
+                        eprintln!("Not replacing {} in {}: verification failed", old_id, path);
+                    }
+                }
+            }
+
+            if animation_mapping.len() < requested {
+                eprintln!(
+                    "{} of {} animations failed to upload or verify",
+                    requested - animation_mapping.len(),
+                    requested
+                );
+                outcome = Err(Failure::PartialUpload);
+            }
+
+            if interrupted {
+                let checkpoint = settings.config.cache_path(DEFAULT_INTERRUPTED_MAPPING_FILE);
+                match save_mapping_with_receipts(&checkpoint, &animation_mapping, args.group) {
+                    Ok(()) => {
+                        println!(
+                            "{}, saved the mapping of the {} finished uploads to {}",
+                            if deadline_hit {
+                                "Deadline reached"
+                            } else {
+                                "Interrupted"
+                            },
+                            animation_mapping.len(),
+                            checkpoint.display()
+                        );
+                        notice.mapping = Some(checkpoint.display().to_string());
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return Err(Failure::Save);
+                    }
+                }
+                if deadline_hit {
+                    // Running out of time is a partial upload, which the policy decides about
+                    if args.on_upload_failure != FailurePolicy::ContinueAndReport {
+                        println!(
+                            "Not rewriting any files (--on-upload-failure), use the apply command to apply it"
+                        );
+                        return Err(Failure::PartialUpload);
+                    }
+                    outcome = Err(Failure::PartialUpload);
+                } else {
+                    if !args.apply_on_interrupt {
+                        println!(
+                            "Not rewriting any files, use the apply command or --apply-on-interrupt to apply it"
+                        );
+                        return Err(Failure::Interrupted);
+                    }
+                    outcome = Err(Failure::Interrupted);
+                }
+            }
+
+            report.receipts = upload_receipts(&animation_mapping, args.group);
+            print_receipt_sample(&animation_mapping, args.group);
+
+            if let Some(path) = &args.save_mapping {
+                match save_mapping_with_receipts(
+                    shellexpand::tilde(path).as_ref(),
+                    &animation_mapping,
+                    args.group,
+                ) {
+                    Ok(()) => {
+                        println!("Saved mapping to {}", path);
+                        notice.mapping = Some(path.clone());
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        outcome = Err(Failure::Save);
+                    }
+                }
+            }
+
+            if let Some(parser) = &mut parser {
+                report.merge(parser.apply_mapping(
+                    &animation_mapping,
+                    args.normalize_style,
+                    args.apply_scope,
+                ));
+            }
+
+            if let Some(places) = &mut places {
+                report.merge(places.apply_mapping(
+                    &animation_mapping,
+                    args.normalize_style,
+                    args.apply_scope,
+                ));
+            }
+
+            if let Some(plugins) = &mut plugins {
+                report.merge(plugins.apply_mapping(
+                    &animation_mapping,
+                    args.normalize_style,
+                    args.apply_scope,
+                ));
+            }
+
+            if let Some(dir) = &scripts_dir
+                && args.apply_scope.includes_scripts()
+            {
+                match fs_scanner::rewrite_dir(
+                    dir,
+                    &animation_mapping,
+                    args.normalize_style,
+                    !args.no_backup,
+                ) {
+                    Ok(changed) => {
+                        for (path, replacements) in &changed {
+                            for replacement in replacements {
+                                report.replacements.push(Replacement {
+                                    file: Some(path.to_string_lossy().to_string()),
+                                    instance_path: None,
+                                    property: "Source".to_string(),
+                                    old_id: replacement.old_id.clone(),
+                                    new_id: replacement.new_id.clone(),
+                                    line: Some(replacement.line),
+                                });
+                            }
+                        }
+                        println!("Rewrote {} files in {}", changed.len(), dir);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to rewrite scripts directory: {}", e);
+                        outcome = Err(Failure::Save);
+                    }
+                }
+            }
+
+            for replacement in &report.replacements {
+                println!("Replaced {}", replacement);
+            }
+
+            applied_mapping = animation_mapping;
+        }
+        Err(e) => {
+            eprintln!("Failed to upload animations: {:?}", e);
+            if args.on_upload_failure != FailurePolicy::ContinueAndReport {
+                eprintln!("Not rewriting any files (--on-upload-failure)");
+                return Err(Failure::PartialUpload);
+            }
+            outcome = Err(Failure::Other);
+        }
+    }
+
+    let mut saved_files: Vec<PathBuf> = Vec::new();
+    let mut place_saved = false;
+    if let Some(parser) = &parser
+        && let Some(destination) = args.output.as_deref().or(file_path.as_deref())
+    {
+        if let Err(e) = parser.save_to_rbxl(destination) {
+            eprintln!("Failed to save {}: {}", destination, e);
+            return Err(Failure::Save);
+        }
+        saved_files.push(PathBuf::from(shellexpand::tilde(destination).as_ref()));
+        place_saved = true;
+    }
+
+    if let Some(places) = &places {
+        match &args.output_dir {
+            Some(output_dir) => match places.save_all_to(shellexpand::tilde(output_dir).as_ref()) {
+                Ok(written) => {
+                    println!("Wrote {} places to {}", written.len(), output_dir);
+                    saved_files.extend(written);
+                }
+                Err(e) => {
+                    eprintln!("Failed to save places: {}", e);
+                    outcome = Err(Failure::Save);
+                }
+            },
+            None => match places.save_all() {
+                Ok(()) => saved_files.extend(places.files.iter().map(|(path, _)| path.clone())),
+                Err(e) => {
+                    eprintln!("Failed to save places: {}", e);
+                    outcome = Err(Failure::Save);
+                }
+            },
+        }
+    }
+
+    if let Some(plugins) = plugins
+        && let Err(e) = plugins.save_all()
+    {
+        eprintln!("Failed to save plugins: {}", e);
+        outcome = Err(Failure::Save);
+    }
+
+    if let Some(path) = &args.report {
+        match report.save(shellexpand::tilde(path).as_ref()) {
+            Ok(()) => println!("Saved report to {}", path),
+            Err(e) => {
+                eprintln!("{}", e);
+                outcome = Err(Failure::Save);
+            }
+        }
+    }
+
+    if !applied_mapping.is_empty()
+        && let Err(failure) = save_inverse_mapping(&applied_mapping, settings)
+    {
+        outcome = Err(failure);
+    }
+
+    if args.verify_rewritten && !applied_mapping.is_empty() {
+        let mut passed = true;
+        for path in &saved_files {
+            match uploader
+                .verify_rewritten_file(path, &applied_mapping, args.group)
+                .await
+            {
+                Ok(verification) => {
+                    println!(
+                        "{}: {} new IDs checked, {} failed",
+                        path.display(),
+                        verification.checked.len(),
+                        verification.failed.len()
+                    );
+                    for failed in &verification.failed {
+                        eprintln!("  {} does not resolve or has the wrong owner", failed);
+                    }
+                    passed &= verification.passed();
+                }
+                Err(e) => {
+                    eprintln!("Failed to verify {}: {}", path.display(), e);
+                    passed = false;
+                }
+            }
+        }
+        println!(
+            "Rewrite verification: {}",
+            if passed { "PASS" } else { "FAIL" }
+        );
+        if !passed {
+            outcome = Err(Failure::PartialUpload);
+        }
+    }
+
+    // Last, so a place that failed any of the checks above doesn't go live half-migrated
+    if let Some(version_type) = args.publish
+        && let Some(parser) = &parser
+        && let Some((universe_id, place_id)) = remote_place
+        && place_saved
+    {
+        if outcome.is_err() && !args.publish_partial {
+            eprintln!(
+                "Not publishing place {}: the migration didn't finish cleanly (pass --publish-partial to publish anyway)",
+                place_id
+            );
+        } else {
+            match parser
+                .publish_to_place(universe_id, place_id, version_type)
+                .await
+            {
+                Ok(version) => println!(
+                    "{} version {} of place {}",
+                    version_type.as_str(),
+                    version,
+                    place_id
+                ),
+                Err(e) => {
+                    eprintln!("Failed to publish place {}: {}", place_id, e);
+                    outcome = Err(Failure::Other);
+                }
+            }
+        }
+    }
+
+    if let Err(failure) = print_run_metrics(&uploader, args.metrics.as_deref()) {
+        outcome = Err(failure);
+    }
+    outcome
+}
+
+/// Lets Ctrl-C stop the uploads without losing the ones that finished. While `uploading` is
+/// set the first Ctrl-C cancels the uploads; any other Ctrl-C quits at once, as it would
+/// without the handler.
+fn handle_ctrl_c(cancel_token: CancellationToken, uploading: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if !uploading.load(Ordering::SeqCst) || cancel_token.is_cancelled() {
+                std::process::exit(130);
+            }
+            eprintln!(
+                "Interrupted, cancelling the uploads in flight and keeping the finished ones \
+                 (Ctrl-C again to quit now)"
+            );
+            cancel_token.cancel();
+        }
+    });
+}
+
+/// Cancels the uploads once `deadline` passes, like Ctrl-C does, and records that it did.
+/// Nothing happens if the uploads already ended by then.
+fn watch_deadline(
+    deadline: tokio::time::Instant,
+    cancel_token: CancellationToken,
+    uploading: Arc<AtomicBool>,
+    deadline_hit: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => {
+                if !uploading.load(Ordering::SeqCst) {
+                    return;
+                }
+                eprintln!("Deadline reached, cancelling the uploads in flight and keeping the finished ones");
+                deadline_hit.store(true, Ordering::SeqCst);
+                cancel_token.cancel();
+            }
+            _ = cancel_token.cancelled() => {}
+        }
+    });
+}
@@ -0,0 +1,305 @@
+use super::auth::{authenticate, new_uploader};
+use super::output::{print_result, print_status, used_by, warn_about_packages};
+use super::settings::Settings;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::model::AnimationAsset;
+use animation_replace_roblox::animation::scan_file::ScanFile;
+use animation_replace_roblox::output::{
+    InspectResult, InspectedAnimation, OutputFormat, ScanResult, ScannedAnimation,
+};
+use animation_replace_roblox::studio::place_diff::PlaceDiff;
+use animation_replace_roblox::{KeyframeSequenceDom, StudioParser};
+use std::fs;
+
+/// Finds the Animation instances and script references in a place, deduplicated.
+pub(crate) async fn scan_place(parser: &StudioParser) -> Result<Vec<AnimationAsset>, Failure> {
+    match parser.all_animations().await {
+        Ok(found) => Ok(found
+            .into_iter()
+            .filter_map(|reference| reference.asset)
+            .collect()),
+        Err(e) => {
+            eprintln!("Failed to fetch animations: {:?}", e);
+            Err(Failure::Other)
+        }
+    }
+}
+
+pub(crate) async fn run_scan(
+    cookie: Option<String>,
+    file: &str,
+    output: &str,
+    heatmap: Option<&str>,
+    format: OutputFormat,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let file_path = shellexpand::tilde(file).to_string();
+    let builder = StudioParser::builder()
+        .file_path(&file_path)
+        .roblosecurity(&cookie)
+        .uploader_config(settings.uploader_config.clone())
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets);
+
+    let mut parser = match builder.build() {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+    warn_about_packages(&file_path, &parser);
+
+    let references = match parser.all_animations().await {
+        Ok(references) => references,
+        Err(e) => {
+            eprintln!("Failed to fetch animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+    let result = ScanResult {
+        source: file_path.clone(),
+        animations: references
+            .iter()
+            .map(|reference| ScannedAnimation {
+                id: reference.asset_id,
+                name: reference.name().map(str::to_string),
+                creator: reference.creator(),
+                used_by: used_by(&parser, reference),
+            })
+            .collect(),
+        models: parser.model_usage(&references),
+    };
+    print_result(&result, format);
+    if !result.models.is_empty() {
+        print_status(format, "Animations per model:");
+        for usage in &result.models {
+            print_status(format, &format!("  {}", usage));
+        }
+    }
+    let animations: Vec<AnimationAsset> = references
+        .into_iter()
+        .filter_map(|reference| reference.asset)
+        .collect();
+
+    let mut outcome = Ok(());
+    if let Some(heatmap) = heatmap {
+        let heatmap_path = shellexpand::tilde(heatmap).to_string();
+        if heatmap_path == file_path {
+            eprintln!("--heatmap can't overwrite the scanned place");
+            outcome = Err(Failure::Config);
+        } else {
+            match parser.save_heatmap(&heatmap_path) {
+                Ok(count) => print_status(
+                    format,
+                    &format!("Marked {} affected instances in {}", count, heatmap),
+                ),
+                Err(e) => {
+                    eprintln!("Failed to write heatmap: {}", e);
+                    outcome = Err(Failure::Save);
+                }
+            }
+        }
+    }
+
+    let scan = ScanFile::new(file_path, animations);
+    match scan.save(shellexpand::tilde(output).as_ref()) {
+        Ok(()) => print_status(
+            format,
+            &format!("Saved {} animations to {}", scan.animations.len(), output),
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Save);
+        }
+    }
+    outcome
+}
+
+pub(crate) async fn run_inspect(
+    cookie: Option<String>,
+    ids: Vec<u64>,
+    files: &[String],
+    format: OutputFormat,
+    settings: &Settings,
+) -> CommandResult {
+    let mut decoded: Vec<(String, anyhow::Result<KeyframeSequenceDom>)> = Vec::new();
+
+    for file in files {
+        let path = shellexpand::tilde(file).to_string();
+        let sequence = fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read: {}", e))
+            .and_then(|data| KeyframeSequenceDom::from_bytes(&data));
+        decoded.push((file.clone(), sequence));
+    }
+
+    if !ids.is_empty() {
+        let cookie = authenticate(cookie, settings).await?;
+        match new_uploader(cookie, settings)?
+            .inspect_animations(ids)
+            .await
+        {
+            Ok(inspected) => decoded.extend(
+                inspected
+                    .into_iter()
+                    .map(|(asset_id, sequence)| (asset_id.to_string(), sequence)),
+            ),
+            Err(e) => {
+                eprintln!("Failed to fetch animations: {:?}", e);
+                return Err(Failure::Other);
+            }
+        }
+    }
+
+    let animations: Vec<InspectedAnimation> = decoded
+        .into_iter()
+        .map(|(source, sequence)| match sequence {
+            Ok(sequence) => InspectedAnimation {
+                source,
+                length: Some(sequence.length()),
+                keyframe_count: sequence.keyframe_count(),
+                joint_names: sequence.joint_names(),
+                error: sequence.is_empty().then(|| "empty".to_string()),
+            },
+            Err(e) => InspectedAnimation {
+                source,
+                length: None,
+                keyframe_count: 0,
+                joint_names: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        })
+        .collect();
+
+    let broken = animations
+        .iter()
+        .filter(|animation| animation.error.is_some())
+        .count();
+    let result = InspectResult { animations };
+    print_result(&result, format);
+    print_status(
+        format,
+        &format!(
+            "{} of {} animations are broken or empty",
+            broken,
+            result.animations.len()
+        ),
+    );
+
+    if broken > 0 {
+        return Err(Failure::Other);
+    }
+    Ok(())
+}
+
+pub(crate) async fn run_download(
+    cookie: Option<String>,
+    mut ids: Vec<u64>,
+    scan: Option<&str>,
+    output_dir: &str,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    if let Some(scan) = scan {
+        match ScanFile::load(shellexpand::tilde(scan).as_ref()) {
+            Ok(scan) => ids.extend(scan.asset_ids()),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Failure::Config);
+            }
+        }
+    }
+    ids.sort();
+    ids.dedup();
+
+    let requested = ids.len();
+    let uploader = new_uploader(cookie, settings)?;
+    match uploader
+        .download_animations(ids, shellexpand::tilde(output_dir).as_ref())
+        .await
+    {
+        Ok(written) => {
+            println!(
+                "Downloaded {} of {} animations to {}",
+                written.len(),
+                requested,
+                output_dir
+            );
+            if written.len() < requested {
+                return Err(Failure::PartialUpload);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to download animations: {:?}", e);
+            Err(Failure::Other)
+        }
+    }
+}
+
+pub(crate) fn run_diff(
+    old: &str,
+    new: &str,
+    format: OutputFormat,
+    settings: &Settings,
+) -> CommandResult {
+    let mut parsers = Vec::new();
+    for file in [old, new] {
+        let builder = StudioParser::builder()
+            .file_path(shellexpand::tilde(file).to_string())
+            .include_packages(settings.include_packages)
+            .include_roblox_assets(settings.include_roblox_assets);
+        match builder.build() {
+            Ok(parser) => parsers.push(parser),
+            Err(e) => {
+                eprintln!("Error loading {}: {}", file, e);
+                return Err(Failure::Config);
+            }
+        }
+    }
+
+    let diff = PlaceDiff::between(&parsers[0], &parsers[1]);
+    print_result(&diff, format);
+    print_status(
+        format,
+        &format!(
+            "{} asset references differ{}",
+            diff.changes.len(),
+            if diff.only_ids_changed() {
+                ", all of them only in the asset ID"
+            } else {
+                ""
+            }
+        ),
+    );
+    Ok(())
+}
+
+pub(crate) fn run_stats(file: &str, format: OutputFormat, settings: &Settings) -> CommandResult {
+    let file_path = shellexpand::tilde(file).to_string();
+    let data = match std::fs::read(&file_path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file, e);
+            return Err(Failure::Config);
+        }
+    };
+    let parser = match StudioParser::builder()
+        .from_bytes(data.clone())
+        .include_packages(settings.include_packages)
+        .build()
+    {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let stats = parser.stats().with_file(&data);
+    print_result(&stats, format);
+    Ok(())
+}
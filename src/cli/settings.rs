@@ -0,0 +1,149 @@
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::config::{RetryPolicy, UploaderConfig};
+use animation_replace_roblox::animation::mapping;
+use animation_replace_roblox::animation::upload_cache::UploadCache;
+use animation_replace_roblox::config::DEFAULT_CONFIG_FILE;
+use animation_replace_roblox::{Config, UploadOptions};
+#[cfg(feature = "mock-server")]
+use animation_replace_roblox::{MockRobloxApi, RobloxApi};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Settings from the global flags, shared by every command.
+pub(crate) struct Settings {
+    pub(crate) uploader_config: UploaderConfig,
+    pub(crate) include_packages: bool,
+    pub(crate) include_roblox_assets: bool,
+    /// None without --upload-cache.
+    pub(crate) upload_cache: Option<String>,
+    pub(crate) inverse_mapping: String,
+    pub(crate) config: Config,
+    /// Every cookie in --cookie-file. The ones after the first are the upload pool.
+    pub(crate) file_cookies: Vec<String>,
+    /// The fake every uploader calls instead of Roblox, with --mock-api.
+    #[cfg(feature = "mock-server")]
+    pub(crate) mock_api: Option<Arc<dyn RobloxApi>>,
+}
+
+/// The uploader settings picked with --max-retries, --timeout and --proxy.
+pub(crate) fn uploader_config(
+    max_retries: Option<u32>,
+    timeout: Option<u64>,
+    proxy: Option<&str>,
+) -> Result<UploaderConfig, reqwest::Error> {
+    let mut config = UploaderConfig::new();
+    if let Some(max_retries) = max_retries {
+        config = config.retry_policy(RetryPolicy::new().max_retries(max_retries));
+    }
+    if let Some(timeout) = timeout {
+        let timeout = Duration::from_secs(timeout);
+        config = config.timeout(timeout).download_timeout(timeout);
+    }
+    if let Some(proxy) = proxy {
+        let proxy = reqwest::Proxy::all(proxy)?;
+        config = config.http_client(move || reqwest::Client::builder().proxy(proxy.clone()));
+    }
+    Ok(config)
+}
+
+/// Loads --mock-api, and cuts the waits of `config` so rate limits the fixture answers with
+/// don't hold a test up for minutes.
+#[cfg(feature = "mock-server")]
+pub(crate) fn mock_api(
+    path: &str,
+    max_retries: Option<u32>,
+    config: UploaderConfig,
+) -> anyhow::Result<(Arc<dyn RobloxApi>, UploaderConfig)> {
+    let api = MockRobloxApi::from_fixture(shellexpand::tilde(path).as_ref())?;
+    let wait = Duration::from_millis(10);
+    let mut retry = RetryPolicy::new()
+        .rate_limit_backoff(wait)
+        .verify_backoff(wait)
+        .retry_delay(wait)
+        .fetch_retry_delay(wait);
+    if let Some(max_retries) = max_retries {
+        retry = retry.max_retries(max_retries);
+    }
+    Ok((Arc::new(api), config.retry_policy(retry)))
+}
+
+/// Loads --config, or xixspoof.toml from the current directory if there is one.
+pub(crate) fn load_config(path: Option<&str>) -> anyhow::Result<Config> {
+    let config = match path {
+        Some(path) => Config::from_path(shellexpand::tilde(path).as_ref())?,
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => Config::from_path(DEFAULT_CONFIG_FILE)?,
+        None => return Ok(Config::default()),
+    };
+
+    if let Some(cache_dir) = &config.cache_dir {
+        fs::create_dir_all(cache_dir).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to create cache_dir '{}': {}",
+                cache_dir.display(),
+                e
+            )
+        })?;
+    }
+    Ok(config)
+}
+
+/// The path given on the command line, else `file_name` in the config's cache_dir.
+pub(crate) fn cache_file(path: Option<String>, config: &Config, file_name: &str) -> String {
+    match path {
+        Some(path) => shellexpand::tilde(&path).to_string(),
+        None => config.cache_path(file_name).to_string_lossy().to_string(),
+    }
+}
+
+/// Loads the --upload-cache file into the options, when it is set.
+/// Returns the cache so it can be saved after the upload.
+pub(crate) fn attach_upload_cache(
+    upload_options: UploadOptions,
+    settings: &Settings,
+) -> (UploadOptions, Option<Arc<Mutex<UploadCache>>>) {
+    let Some(path) = &settings.upload_cache else {
+        return (upload_options, None);
+    };
+
+    match UploadCache::load(path) {
+        Ok(cache) => {
+            let cache = Arc::new(Mutex::new(cache));
+            (upload_options.upload_cache(cache.clone()), Some(cache))
+        }
+        Err(e) => {
+            eprintln!("{}, uploading without it", e);
+            (upload_options, None)
+        }
+    }
+}
+
+/// Saves the new -> old mapping of a rewrite, so revert (or apply) can swap the IDs back.
+pub(crate) fn save_inverse_mapping(
+    animation_mapping: &HashMap<String, String>,
+    settings: &Settings,
+) -> CommandResult {
+    match mapping::save_inverse_mapping(&settings.inverse_mapping, animation_mapping) {
+        Ok(()) => {
+            eprintln!("Saved the inverse mapping to {}", settings.inverse_mapping);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Save)
+        }
+    }
+}
+
+pub(crate) fn save_upload_cache(
+    upload_cache: Option<Arc<Mutex<UploadCache>>>,
+    settings: &Settings,
+) {
+    if let (Some(cache), Some(path)) = (upload_cache, &settings.upload_cache)
+        && let Err(e) = cache.lock().unwrap().save(path)
+    {
+        eprintln!("{}", e);
+    }
+}
@@ -0,0 +1,134 @@
+use super::auth::{authenticate_for_upload, new_uploader};
+use super::output::{print_receipt_sample, print_result, print_status};
+use super::settings::Settings;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::UploadOptions;
+use animation_replace_roblox::animation::mapping::save_mapping_with_receipts;
+use animation_replace_roblox::animation::scan_file::ScanFile;
+use animation_replace_roblox::output::{MappedId, OutputFormat, UploadResult};
+use std::sync::Arc;
+
+pub(crate) async fn run_upload(
+    cookie: Option<String>,
+    scan: &str,
+    mapping: &str,
+    group: Option<u64>,
+    upload_options: &UploadOptions,
+    format: OutputFormat,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate_for_upload(cookie, group.into(), settings).await?;
+
+    let scan = match ScanFile::load(shellexpand::tilde(scan).as_ref()) {
+        Ok(scan) => scan,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let asset_ids: Vec<u64> = scan
+        .asset_ids()
+        .into_iter()
+        .filter(|asset_id| !settings.config.is_excluded(*asset_id))
+        .collect();
+
+    let uploader = Arc::new(new_uploader(cookie, settings)?);
+    // Download locations in the scan may have expired, so fetch fresh ones
+    let animations = match uploader.fetch_animation_assets(asset_ids.clone()).await {
+        Ok(animations) => animations,
+        Err(e) => {
+            eprintln!("Failed to fetch animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    let animation_mapping = match uploader
+        .clone()
+        .reupload_animations(animations, upload_options)
+        .await
+    {
+        Ok(animation_mapping) => animation_mapping,
+        Err(e) => {
+            eprintln!("Failed to upload animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    let mut result = UploadResult::default();
+    for asset_id in asset_ids {
+        match animation_mapping.get(&asset_id.to_string()) {
+            Some(new_id) => result.uploaded.push(MappedId {
+                old_id: asset_id.to_string(),
+                new_id: new_id.clone(),
+            }),
+            None => result.failed.push(asset_id),
+        }
+    }
+    print_result(&result, format);
+    let outcome = if result.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::PartialUpload)
+    };
+
+    match save_mapping_with_receipts(
+        shellexpand::tilde(mapping).as_ref(),
+        &animation_mapping,
+        group,
+    ) {
+        Ok(()) => print_status(
+            format,
+            &format!(
+                "Saved mapping of {} animations to {}",
+                animation_mapping.len(),
+                mapping
+            ),
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Save);
+        }
+    }
+    if format == OutputFormat::Table {
+        print_receipt_sample(&animation_mapping, group);
+    }
+    outcome
+}
+
+pub(crate) async fn run_upload_dir(
+    cookie: Option<String>,
+    dir: &str,
+    mapping: &str,
+    group: Option<u64>,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate_for_upload(cookie, group.into(), settings).await?;
+
+    let uploader = new_uploader(cookie, settings)?;
+    let file_mapping = match uploader
+        .upload_animations_from_dir(shellexpand::tilde(dir).as_ref(), group)
+        .await
+    {
+        Ok(file_mapping) => file_mapping,
+        Err(e) => {
+            eprintln!("Failed to upload animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    match save_mapping_with_receipts(shellexpand::tilde(mapping).as_ref(), &file_mapping, group) {
+        Ok(()) => {
+            println!(
+                "Saved mapping of {} files to {}",
+                file_mapping.len(),
+                mapping
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(Failure::Save)
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use super::auth::{authenticate, new_uploader};
+use super::output::{print_result, print_status, used_by};
+use super::settings::Settings;
+use super::{CommandResult, Failure};
+use animation_replace_roblox::animation::mapping::{load_mapping, save_mapping_with_receipts};
+use animation_replace_roblox::output::{
+    CheckedReference, MappedId, OutputFormat, ReferenceCheckResult, VerifyResult,
+};
+use animation_replace_roblox::{AssetCreator, StudioParser};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// How often verify --wait-moderation checks the new IDs again.
+const MODERATION_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How the verify command checks a mapping.
+pub(crate) struct VerifyOptions {
+    pub(crate) group: Option<u64>,
+    pub(crate) output: Option<String>,
+    pub(crate) wait_moderation: Option<u64>,
+    pub(crate) check_events: bool,
+    pub(crate) format: OutputFormat,
+}
+
+pub(crate) async fn run_verify(
+    cookie: Option<String>,
+    mapping: &str,
+    verify_options: &VerifyOptions,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let animation_mapping = match load_mapping(shellexpand::tilde(mapping).as_ref()) {
+        Ok(animation_mapping) => animation_mapping,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let format = verify_options.format;
+    let total = animation_mapping.len();
+    let uploader = new_uploader(cookie, settings)?;
+    let mut result = VerifyResult::default();
+
+    // Waited for before verifying: an upload under review has no download location yet, so
+    // every new ID of the mapping is polled, not just the ones that already verify
+    let mut to_verify = animation_mapping.clone();
+    if let Some(minutes) = verify_options.wait_moderation {
+        let new_ids: Vec<u64> = animation_mapping
+            .values()
+            .filter_map(|new_id| new_id.parse().ok())
+            .collect();
+        print_status(
+            format,
+            &format!("Waiting up to {} minutes for moderation", minutes),
+        );
+        let moderation = uploader
+            .wait_for_moderation(
+                new_ids,
+                MODERATION_POLL_INTERVAL,
+                Duration::from_secs(minutes * 60),
+            )
+            .await;
+
+        print_status(
+            format,
+            &format!(
+                "Moderation: {} approved, {} rejected, {} still pending, {} failed to look up",
+                moderation.approved.len(),
+                moderation.rejected.len(),
+                moderation.pending.len(),
+                moderation.failed.len()
+            ),
+        );
+        let rejected: HashSet<String> = moderation.rejected.iter().map(u64::to_string).collect();
+        let failed: HashSet<String> = moderation.failed.iter().map(u64::to_string).collect();
+        to_verify.retain(|old_id, new_id| {
+            let mapped = MappedId {
+                old_id: old_id.clone(),
+                new_id: new_id.clone(),
+            };
+            if rejected.contains(new_id) {
+                result.rejected.push(mapped);
+                false
+            } else if failed.contains(new_id) {
+                result.failed.push(mapped);
+                false
+            } else {
+                true
+            }
+        });
+        result.rejected.sort_by(|a, b| a.old_id.cmp(&b.old_id));
+    }
+
+    let verification = match uploader
+        .verify_uploads(to_verify.clone(), verify_options.group)
+        .await
+    {
+        Ok(verification) => verification,
+        Err(e) => {
+            eprintln!("Failed to verify animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+    // Only uploads whose owner checked out but that have no download location yet are
+    // pending; anything else that didn't verify failed
+    let mut verified = verification.usable();
+    let pending: HashSet<&String> = verification.pending.values().collect();
+
+    print_status(
+        format,
+        &format!(
+            "{} of {} animations passed verification",
+            verified.len(),
+            total
+        ),
+    );
+
+    let mut old_ids: Vec<&String> = to_verify.keys().collect();
+    old_ids.sort();
+    for old_id in old_ids {
+        if verified.contains_key(old_id) {
+            continue;
+        }
+        result.failed.push(MappedId {
+            old_id: old_id.clone(),
+            new_id: to_verify[old_id].clone(),
+        });
+    }
+    result.failed.sort_by(|a, b| a.old_id.cmp(&b.old_id));
+
+    if verify_options.check_events {
+        print_status(format, "Comparing animation events");
+        match uploader
+            .check_animation_events(&verification.verified)
+            .await
+        {
+            Ok(mismatches) => {
+                // What changed is logged by the check
+                for mismatch in mismatches {
+                    verified.remove(&mismatch.old_id);
+                    result.events_changed.push(MappedId {
+                        old_id: mismatch.old_id,
+                        new_id: mismatch.new_id,
+                    });
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to compare animation events: {:?}", e);
+                return Err(Failure::Other);
+            }
+        }
+    }
+
+    // Pending IDs stay in the saved mapping, but aren't reported as passed yet
+    let mut entries: Vec<(&String, &String)> = verified.iter().collect();
+    entries.sort();
+    for (old_id, new_id) in entries {
+        let mapped = MappedId {
+            old_id: old_id.clone(),
+            new_id: new_id.clone(),
+        };
+        if pending.contains(new_id) {
+            result.pending.push(mapped);
+        } else {
+            result.passed.push(mapped);
+        }
+    }
+    print_result(&result, format);
+
+    if let Some(output) = &verify_options.output {
+        match save_mapping_with_receipts(
+            shellexpand::tilde(output).as_ref(),
+            &verified,
+            verify_options.group,
+        ) {
+            Ok(()) => print_status(format, &format!("Saved verified mapping to {}", output)),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Err(Failure::Save);
+            }
+        }
+    }
+
+    if result.failed.is_empty() && result.rejected.is_empty() && result.events_changed.is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::PartialUpload)
+    }
+}
+
+/// Checks every animation a place references and reports the ones that won't load in-game.
+pub(crate) async fn run_verify_references(
+    cookie: Option<String>,
+    file: &str,
+    verify_options: &VerifyOptions,
+    settings: &Settings,
+) -> CommandResult {
+    let cookie = authenticate(cookie, settings).await?;
+
+    let file_path = shellexpand::tilde(file).to_string();
+    let parser = match StudioParser::builder()
+        .file_path(&file_path)
+        .include_packages(settings.include_packages)
+        .include_roblox_assets(settings.include_roblox_assets)
+        .build()
+    {
+        Ok(parser) => parser,
+        Err(e) => {
+            eprintln!("Error loading file: {}", e);
+            return Err(Failure::Config);
+        }
+    };
+
+    let format = verify_options.format;
+    let references = parser.animation_refs();
+    let uploader = new_uploader(cookie, settings)?;
+    let game_owner = match verify_options.group {
+        Some(group_id) => AssetCreator::Group(group_id),
+        None => match uploader.authenticated_user().await {
+            Ok(user) => AssetCreator::User(user.user_id),
+            Err(e) => {
+                eprintln!("Failed to get the cookie's user: {}", e);
+                return Err(Failure::Auth);
+            }
+        },
+    };
+
+    let asset_ids = references
+        .iter()
+        .map(|reference| reference.asset_id)
+        .collect();
+    let checks = match uploader.check_references(asset_ids, Some(game_owner)).await {
+        Ok(checks) => checks,
+        Err(e) => {
+            eprintln!("Failed to check animations: {:?}", e);
+            return Err(Failure::Other);
+        }
+    };
+
+    let result = ReferenceCheckResult {
+        source: file_path,
+        references: checks
+            .into_iter()
+            .filter_map(|check| {
+                let reference = references
+                    .iter()
+                    .find(|reference| reference.asset_id == check.asset_id)?;
+                Some(CheckedReference {
+                    id: check.asset_id,
+                    status: check.status,
+                    owner: check.owner,
+                    used_by: used_by(&parser, reference),
+                    detail: check.detail,
+                })
+            })
+            .collect(),
+    };
+    print_result(&result, format);
+
+    let broken = result.broken().count();
+    print_status(
+        format,
+        &format!(
+            "{} of {} animations will fail in-game",
+            broken,
+            result.references.len()
+        ),
+    );
+    if broken == 0 {
+        Ok(())
+    } else {
+        Err(Failure::PartialUpload)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::animation::options::UploadOptions;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The config file the CLI loads from the current directory when --config isn't given.
+pub const DEFAULT_CONFIG_FILE: &str = "xixspoof.toml";
+
+/// Project defaults from a xixspoof.toml, so they don't have to be passed on every run.
+///
+/// # Examples
+///
+/// ```toml
+/// cookie_env = "MY_GAME_COOKIE"
+/// group = 1234
+/// concurrency = 10
+/// exclude = [507771019, 507766388]
+/// name = "{id} (reupload)"
+/// cache_dir = ".xixspoof"
+/// ```
+///
+/// ```rust
+/// let config = Config::from_path("xixspoof.toml")?;
+/// animations.retain(|animation| !config.is_excluded(animation.id));
+/// let mapping = uploader
+///     .reupload_animations(animations, &config.upload_options())
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Environment variable holding the .ROBLOSECURITY cookie.
+    pub cookie_env: Option<String>,
+    /// Group animations are uploaded to, instead of the cookie's user.
+    pub group: Option<u64>,
+    /// How many uploads run at the same time.
+    pub concurrency: Option<u64>,
+    /// Asset IDs that are never reuploaded or rewritten, e.g. Roblox's own animations.
+    pub exclude: Vec<u64>,
+    /// Name of the uploaded animations. `{id}` is replaced with the source animation ID.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Directory the upload cache, blocklist and inverse mapping are kept in, instead of the
+    /// current directory.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads a config from a TOML file. Unknown keys are an error, so a typo doesn't go
+    /// unnoticed, and so is a concurrency of 0.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config '{}': {}", path.display(), e))?;
+        let config: Self = toml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config '{}': {}", path.display(), e))?;
+        if config.concurrency == Some(0) {
+            anyhow::bail!(
+                "Invalid config '{}': concurrency must be at least 1",
+                path.display()
+            );
+        }
+        Ok(config)
+    }
+
+    /// The cookie from the cookie_env variable, if both are set.
+    pub fn cookie(&self) -> Option<String> {
+        let name = self.cookie_env.as_ref()?;
+        std::env::var(name).ok().filter(|cookie| !cookie.is_empty())
+    }
+
+    /// UploadOptions with the group, concurrency, name and description of the config.
+    pub fn upload_options(&self) -> UploadOptions {
+        let mut options = UploadOptions::new().group_id(self.group);
+        if let Some(concurrency) = self.concurrency {
+            options = options.concurrency(concurrency);
+        }
+        if let Some(name) = &self.name {
+            options = options.name(name);
+        }
+        if let Some(description) = &self.description {
+            options = options.description(description);
+        }
+        options
+    }
+
+    pub fn is_excluded(&self, asset_id: u64) -> bool {
+        self.exclude.contains(&asset_id)
+    }
+
+    /// Where a file the CLI keeps between runs goes: inside cache_dir when it is set.
+    pub fn cache_path(&self, file_name: &str) -> PathBuf {
+        match &self.cache_dir {
+            Some(cache_dir) => cache_dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+}
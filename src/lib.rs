@@ -1,4 +1,4 @@
-use rbx_dom_weak::{Instance, WeakDom};
+use rbx_dom_weak::WeakDom;
 
 /// A module for uploading animations
 pub mod animation;
@@ -9,42 +9,128 @@ pub mod studio;
 /// A module for handling parsing and editing on scripts, in studio files.
 pub mod script;
 
+/// A module for the versioned JSON files the crate reads and writes
+pub mod artifacts;
+
+/// A module for the scan → upload → rewrite pipeline shared by every asset kind
+pub mod pipeline;
+
+/// A module for parsing asset IDs out of every URL spelling Roblox uses
+pub mod asset_id;
+
+/// A module for reporting what a run changed
+pub mod report;
+
+/// A module for the project config file (xixspoof.toml)
+pub mod config;
+
+/// A module for printing command results as tables, JSON or CSV
+pub mod output;
+
+/// A module for posting how a run went to a chat webhook
+pub mod notify;
+
+/// A module for ModuleScripts loaded with require(assetId): scanning, reuploading and rewriting
+pub mod modules;
+
+pub use animation::api::{MockCall, MockRobloxApi, RobloxApi, RoboatApi};
+pub use animation::keyframe_sequence::{AnimationEvent, KeyframeSequenceDom, Retarget};
+pub use animation::metrics::RunMetrics;
+pub use animation::model::{AnimationAsset, AssetCreator};
+pub use animation::options::{FailurePolicy, UploadOptions};
+pub use animation::pipeline::AnimationPipeline;
+pub use animation::target::UploadTarget;
 pub use animation::uploader::AnimationUploader;
-pub use studio::dom_parser::StudioParserBuilder;
+pub use config::Config;
+pub use pipeline::{BatchMigrator, Migrator};
+pub use report::ReplacementReport;
+pub use script::rewrite::{RewriteOptions, rewrite_source};
+pub use studio::content_scanner::ContentScanner;
+pub use studio::detectors::RefDetector;
+pub use studio::dom_parser::{PlaceFormat, StudioParserBuilder};
+pub use studio::selector::Selector;
+pub use tokio_util::sync::CancellationToken;
 
-/// Represents an animation with its instance and ID.
-#[derive(Debug, Clone)]
-pub struct Animation<'a> {
-    pub instance: &'a Instance,
-    pub animation_id: String,
+/// An animation a place uses: its asset ID, everything in the place that references it and,
+/// once fetched, its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationRef {
+    pub asset_id: u64,
+    /// Animation instances whose AnimationId is this asset.
+    pub instances: Vec<rbx_dom_weak::types::Ref>,
+    /// References in script sources, with the byte range and line of the ID.
+    pub scripts: Vec<script::types::ScriptAssetReference>,
+    /// Instances with an attribute holding this asset, and the attribute's name.
+    pub attributes: Vec<(rbx_dom_weak::types::Ref, String)>,
+    /// Instances with another property holding this asset (a value object's Value, or what a
+    /// custom RefDetector found), and the property's name.
+    pub properties: Vec<(rbx_dom_weak::types::Ref, String)>,
+    /// References in the remote code of LinkedSource scripts. These can't be rewritten.
+    pub linked_sources: Vec<script::types::LinkedSourceReference>,
+    /// Batch API metadata (CDN locations, name, owner). None until fetched, or when the asset
+    /// isn't an animation.
+    pub asset: Option<AnimationAsset>,
 }
 
-impl<'a> Animation<'a> {
-    pub fn new(instance: &'a Instance, animation_id: String) -> Self {
+impl AnimationRef {
+    pub fn new(asset_id: u64) -> Self {
         Self {
-            instance,
-            animation_id,
+            asset_id,
+            instances: Vec::new(),
+            scripts: Vec::new(),
+            attributes: Vec::new(),
+            properties: Vec::new(),
+            linked_sources: Vec::new(),
+            asset: None,
         }
     }
 
-    pub fn with_info(instance: &'a Instance, animation_id: String) -> Self {
-        Self {
-            instance,
-            animation_id,
-        }
+    /// Who owns the animation, when known.
+    pub fn creator(&self) -> Option<AssetCreator> {
+        self.asset.as_ref()?.creator
+    }
+
+    /// The animation's name, when known.
+    pub fn name(&self) -> Option<&str> {
+        self.asset.as_ref()?.name.as_deref()
     }
 }
+
 /// Parser for Roblox Studio files with optional authentication.
 pub struct StudioParser {
     pub roblosecurity: Option<String>,
     pub dom: WeakDom,
+    /// Back up an existing file before save_to_rbxl overwrites it.
+    pub backup_on_save: bool,
+    /// Subtrees that scanning and rewriting are limited to (see StudioParser::scoped). Empty
+    /// means the whole DOM.
+    pub scope: Vec<rbx_dom_weak::types::Ref>,
+    /// Retries and timeouts of the AnimationUploader used for metadata fetches.
+    pub uploader_config: animation::config::UploaderConfig,
+    /// Scan and rewrite inside packages too (see StudioParser::package_animations).
+    pub include_packages: bool,
+    /// Scan the default Animate script animations and Roblox-owned emotes too (see
+    /// StudioParserBuilder::include_roblox_assets).
+    pub include_roblox_assets: bool,
+    /// Open Cloud API key with place publishing access, for publish_to_place.
+    pub open_cloud_api_key: Option<String>,
+    /// What scans look for in each instance (see StudioParser::register_detector).
+    pub(crate) detectors: Vec<Box<dyn studio::detectors::RefDetector>>,
+    /// Instances the last edit pass changed, with the old → new IDs written into each (see
+    /// StudioParser::rescan_changed).
+    pub(crate) last_edit: indexmap::IndexMap<rbx_dom_weak::types::Ref, Vec<(String, String)>>,
 }
 
-/// Represents a script with its instance, source code and type.
-pub struct Script<'a> {
-    pub instance: &'a mut Instance,
-    pub source: String,
-    pub script_type: ScriptType,
+/// A script in a place, read with StudioParser::scripts. Edits stay on the struct until
+/// commit writes them back to the DOM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    referent: rbx_dom_weak::types::Ref,
+    name: String,
+    /// Dotted path including the script's own name, as instance_path returns it.
+    path: String,
+    source: String,
+    script_type: ScriptType,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1,118 +1,346 @@
-use animation_replace_roblox::StudioParser;
-use animation_replace_roblox::animation::uploader::AnimationUploader;
-use clap::Parser;
-use roboat::assetdelivery::AssetBatchResponse;
-use std::collections::HashSet;
-use std::sync::Arc;
-
-#[derive(Parser, Debug)]
-struct Args {
-    /// .ROBLOSECURITY cookie string [WARNING STRING REQUIRED]
-    #[arg(long, short)]
-    cookie: String,
-
-    /// file PATH of the .rbxl file [REQUIRED]
-    #[arg(long, short)]
-    file: String,
+mod cli;
 
-    /// Save the copy instead replacing file [AVOID DATA LOSS]
-    #[arg(long, short)]
-    output: Option<String>,
-
-    /// Required if the the game will be published to a Group [Id of the group]
-    #[arg(long, short)]
-    group: Option<u64>,
-
-    /// How many concurrent tasks using semaphore. [defaulted to 5]
-    #[arg(long, short)]
-    threads: Option<u64>,
-}
+use animation_replace_roblox::animation::blocklist::DEFAULT_BLOCKLIST_FILE;
+use animation_replace_roblox::animation::credentials::cookies_from_file;
+use animation_replace_roblox::animation::mapping::DEFAULT_INVERSE_MAPPING_FILE;
+use animation_replace_roblox::animation::upload_cache::DEFAULT_UPLOAD_CACHE_FILE;
+use clap::Parser;
+use cli::Failure;
+use cli::apply::{ApplyOptions, run_apply, run_revert};
+use cli::args::{Args, Command, ReportCommand};
+use cli::artifacts::{run_mapping_command, run_migrate_artifacts};
+#[cfg(feature = "keyring")]
+use cli::keychain::run_keychain_command;
+use cli::migrate::{ModulesOptions, run_migrate, run_modules, run_place_versions};
+use cli::output::attach_progress_bar;
+use cli::report::run_owner_report;
+use cli::run::run_and_notify;
+use cli::scan::{run_diff, run_download, run_inspect, run_scan, run_stats};
+#[cfg(feature = "mock-server")]
+use cli::settings::mock_api;
+use cli::settings::{
+    Settings, attach_upload_cache, cache_file, load_config, save_upload_cache, uploader_config,
+};
+use cli::upload::{run_upload, run_upload_dir};
+use cli::verify::{VerifyOptions, run_verify, run_verify_references};
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
-async fn main() {
-    let args = Args::parse();
-    let file_path = shellexpand::tilde(&args.file).to_string();
-    let mut seen_ids: HashSet<String> = HashSet::new();
+async fn main() -> ExitCode {
+    let mut args = Args::parse();
+    init_logging(args.verbose, args.quiet, args.log_json);
+    let config = match load_config(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Failure::Config.exit_code();
+        }
+    };
+    // Flags on the command line win over the config
+    args.group = args.group.or(config.group);
+    args.threads = args.threads.or(config.concurrency);
+    let blocklist = cache_file(args.blocklist.take(), &config, DEFAULT_BLOCKLIST_FILE);
+    args.blocklist = Some(blocklist);
+    let upload_cache = args.upload_cache.take().map(|path| {
+        // A bare --upload-cache uses the default file
+        let path = Some(path).filter(|path| !path.is_empty());
+        cache_file(path, &config, DEFAULT_UPLOAD_CACHE_FILE)
+    });
+    let inverse_mapping = cache_file(
+        args.inverse_mapping.take(),
+        &config,
+        DEFAULT_INVERSE_MAPPING_FILE,
+    );
 
-    // Build the parser with the roboat client
-    let builder = StudioParser::builder()
-        .file_path(&file_path)
-        .roblosecurity(&args.cookie);
+    let file_cookies = match &args.cookie_file {
+        Some(path) => match cookies_from_file(shellexpand::tilde(path).as_ref()) {
+            Ok(cookies) => cookies,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Failure::Config.exit_code();
+            }
+        },
+        None => Vec::new(),
+    };
 
-    let mut parser = match builder.build() {
-        Ok(parser) => parser,
+    let uploader_config = uploader_config(args.max_retries, args.timeout, args.proxy.as_deref());
+    #[cfg(feature = "mock-server")]
+    let (uploader_config, mock_api) = match (uploader_config, &args.mock_api) {
+        (Ok(config), Some(path)) => match mock_api(path, args.max_retries, config) {
+            Ok((api, config)) => (Ok(config), Some(api)),
+            Err(e) => {
+                eprintln!("Invalid --mock-api: {}", e);
+                return Failure::Config.exit_code();
+            }
+        },
+        (uploader_config, _) => (uploader_config, None),
+    };
+    let settings = match uploader_config {
+        Ok(uploader_config) => Settings {
+            uploader_config,
+            include_packages: args.include_packages,
+            include_roblox_assets: args.include_roblox_assets,
+            upload_cache,
+            inverse_mapping,
+            config,
+            file_cookies,
+            #[cfg(feature = "mock-server")]
+            mock_api,
+        },
         Err(e) => {
-            eprintln!("Error loading file: {}", e);
-            return;
+            eprintln!("Invalid --proxy: {}", e);
+            return Failure::Config.exit_code();
         }
     };
 
-    let mut all_animations: Vec<AssetBatchResponse> = Vec::new();
-    let workspace_animations = parser.workspace_animations();
-    match workspace_animations.await {
-        Ok(animations) => {
-            for animation in animations {
-                if let Some(asset_id) = animation.request_id.clone() {
-                    if seen_ids.contains(&asset_id) {
-                        // Skip this animation (it's a duplicate)
-                        continue;
-                    }
-                    seen_ids.insert(asset_id);
-                    all_animations.push(animation);
-                }
+    let result = match args.command.take() {
+        Some(Command::Mapping { command }) => run_mapping_command(command),
+        Some(Command::MigrateArtifacts { files }) => run_migrate_artifacts(&files),
+        #[cfg(feature = "keyring")]
+        Some(Command::Keychain { command }) => run_keychain_command(command, &settings),
+        Some(Command::Report {
+            command:
+                ReportCommand::Owners {
+                    cookie,
+                    file,
+                    scan,
+                    format,
+                },
+        }) => run_owner_report(cookie, file.as_deref(), scan.as_deref(), format, &settings).await,
+        Some(Command::PlaceVersions { cookie, place_id }) => {
+            run_place_versions(cookie, place_id, &settings).await
+        }
+        Some(Command::Diff { old, new, format }) => run_diff(&old, &new, format, &settings),
+        Some(Command::Stats { file, format }) => run_stats(&file, format, &settings),
+        Some(Command::Modules {
+            cookie,
+            file,
+            reupload,
+            group,
+            output,
+            no_backup,
+        }) => {
+            let file_path = shellexpand::tilde(&file).to_string();
+            let options = ModulesOptions {
+                reupload,
+                group: group.or(settings.config.group),
+                output: output.unwrap_or_else(|| file_path.clone()),
+                no_backup,
+            };
+            run_modules(cookie, &file_path, &options, &settings).await
+        }
+        Some(Command::Scan {
+            cookie,
+            file,
+            output,
+            heatmap,
+            format,
+        }) => {
+            run_scan(
+                cookie,
+                &file,
+                &output,
+                heatmap.as_deref(),
+                format,
+                &settings,
+            )
+            .await
+        }
+        Some(Command::Upload {
+            cookie,
+            scan,
+            mapping,
+            group,
+            threads,
+            format,
+        }) => {
+            let group = group.or(settings.config.group);
+            let mut upload_options = settings.config.upload_options().group_id(group);
+            if let Some(threads) = threads {
+                upload_options = upload_options.concurrency(threads);
+            }
+            if let Some(max_retries) = args.max_retries {
+                upload_options = upload_options.max_retries(max_retries as usize);
+            }
+            upload_options = upload_options.failure_policy(args.on_upload_failure);
+            if let Some(retarget) = args.retarget {
+                upload_options = upload_options.retarget(retarget);
             }
+            let (upload_options, upload_cache) = attach_upload_cache(upload_options, &settings);
+            let (upload_options, progress_bar) = attach_progress_bar(upload_options);
+            let result = run_upload(
+                cookie,
+                &scan,
+                &mapping,
+                group,
+                &upload_options,
+                format,
+                &settings,
+            )
+            .await;
+            progress_bar.finish();
+            save_upload_cache(upload_cache, &settings);
+            result
         }
-        Err(e) => {
-            eprintln!("Failed to workspace animations: {:?}", e);
+        Some(Command::Inspect {
+            cookie,
+            ids,
+            file,
+            format,
+        }) => run_inspect(cookie, ids, &file, format, &settings).await,
+        Some(Command::Download {
+            cookie,
+            ids,
+            scan,
+            output_dir,
+        }) => run_download(cookie, ids, scan.as_deref(), &output_dir, &settings).await,
+        Some(Command::UploadDir {
+            cookie,
+            dir,
+            mapping,
+            group,
+        }) => {
+            let group = group.or(settings.config.group);
+            run_upload_dir(cookie, &dir, &mapping, group, &settings).await
         }
-    }
-
-    let script_animations = parser.all_animations_in_scripts();
-
-    match script_animations.await {
-        Ok(animations) => {
-            for animation in animations {
-                if let Some(asset_id) = animation.request_id.clone() {
-                    if seen_ids.contains(&asset_id) {
-                        // Skip this animation (it's a duplicate)
-                        continue;
-                    }
-                    seen_ids.insert(asset_id);
-                    all_animations.push(animation);
+        Some(Command::Apply {
+            file,
+            mapping,
+            output,
+            normalize_style,
+            apply_scope,
+            no_backup,
+            checkpoint_every,
+            format,
+        }) => {
+            let file_path = shellexpand::tilde(&file).to_string();
+            let apply_options = ApplyOptions {
+                normalize_style,
+                apply_scope,
+                no_backup,
+                checkpoint_every,
+                format,
+            };
+            run_apply(
+                &file_path,
+                &mapping,
+                output.as_deref().unwrap_or(&file_path),
+                &apply_options,
+                &settings,
+            )
+            .await
+        }
+        Some(Command::Revert {
+            file,
+            mapping,
+            output,
+            no_backup,
+        }) => {
+            let file_path = shellexpand::tilde(&file).to_string();
+            run_revert(
+                &file_path,
+                &mapping,
+                output.as_deref().unwrap_or(&file_path),
+                no_backup,
+                &settings,
+            )
+        }
+        Some(Command::Verify {
+            cookie,
+            mapping,
+            file,
+            group,
+            output,
+            wait_moderation,
+            check_events,
+            format,
+        }) => {
+            let verify_options = VerifyOptions {
+                group: group.or(settings.config.group),
+                output,
+                wait_moderation,
+                check_events,
+                format,
+            };
+            match (file, mapping) {
+                (Some(file), _) => {
+                    run_verify_references(cookie, &file, &verify_options, &settings).await
                 }
+                (None, Some(mapping)) => {
+                    run_verify(cookie, &mapping, &verify_options, &settings).await
+                }
+                (None, None) => unreachable!("clap requires --mapping without --file"),
             }
         }
+        Some(Command::Migrate {
+            cookie,
+            file,
+            from_group,
+            to_group,
+            output,
+            threads,
+            no_backup,
+        }) => {
+            let file_path = shellexpand::tilde(&file).to_string();
+            let mut upload_options = settings
+                .config
+                .upload_options()
+                .group_id(Some(to_group))
+                .verify(true);
+            if let Some(threads) = threads {
+                upload_options = upload_options.concurrency(threads);
+            }
+            if let Some(max_retries) = args.max_retries {
+                upload_options = upload_options.max_retries(max_retries as usize);
+            }
+            upload_options = upload_options.failure_policy(args.on_upload_failure);
+            if let Some(retarget) = args.retarget {
+                upload_options = upload_options.retarget(retarget);
+            }
+            let (upload_options, upload_cache) = attach_upload_cache(upload_options, &settings);
+            let (upload_options, progress_bar) = attach_progress_bar(upload_options);
 
-        Err(e) => {
-            eprintln!("Failed to fetch animations: {:?}", e);
+            let result = run_migrate(
+                cookie,
+                &file_path,
+                from_group,
+                &upload_options,
+                output.as_deref().unwrap_or(&file_path),
+                no_backup,
+                &settings,
+            )
+            .await;
+            progress_bar.finish();
+            save_upload_cache(upload_cache, &settings);
+            result
         }
-    }
-
-    println!(
-        "Total Animations fetched from game {}",
-        all_animations.len()
-    );
-    let uploader = Arc::new(AnimationUploader::new(args.cookie));
-    match uploader
-        .reupload_all_animations(all_animations, args.group.clone(), args.threads.clone())
-        .await
-    {
-        Ok(animation_mapping) => {
-            // TODO: Instead of scanning and looping through a HashMap of u64, Make a HashMap of
-            // Animations, that includes instances, that way one loop will handle it all.
-            // Also optimize and delete values after updating them.
+        None => run_and_notify(args, &settings).await,
+    };
 
-            parser.update_script_animations(&animation_mapping);
-            parser.update_game_animations(&animation_mapping);
-        }
-        Err(e) => {
-            eprintln!("Failed to upload animations: {:?}", e);
-        }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.exit_code(),
     }
+}
+
+/// Sends library logs to stderr. RUST_LOG overrides the level picked with -v/-q.
+fn init_logging(verbose: u8, quiet: u8, json: bool) {
+    let level = match (verbose, quiet) {
+        (0, 0) => "info",
+        (1, _) => "debug",
+        (_, 0) => "trace",
+        (_, 1) => "warn",
+        _ => "error",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
 
-    if let Some(output) = args.output {
-        parser.save_to_rbxl(output).unwrap();
+    if json {
+        builder.json().init();
     } else {
-        parser.save_to_rbxl(file_path).unwrap();
+        builder.init();
     }
 }
@@ -0,0 +1,155 @@
+use crate::StudioParser;
+use crate::report::{Replacement, ReplacementReport};
+use crate::script::lua_lexer::{LuaTokenKind, tokenize};
+use crate::studio::props;
+use rbx_dom_weak::types::Ref;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+pub mod pipeline;
+pub mod upload;
+
+/// A `require(123456789)` call found in Lua source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRequire {
+    pub asset_id: u64,
+    /// Byte range of the ID's digits in the source.
+    pub span: Range<usize>,
+    /// 1-based line the ID is on.
+    pub line: usize,
+}
+
+/// A `require(123456789)` call found in a script of a place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequireReference {
+    pub script: Ref,
+    pub asset_id: u64,
+    pub span: Range<usize>,
+    pub line: usize,
+}
+
+/// Finds every require() of a module by asset ID in a piece of Lua source.
+///
+/// # Notes:
+/// Only a plain number inside the parentheses counts: `require(123456789)`. Requires of
+/// instances (`require(script.Parent.Util)`), of variables, and method calls such as
+/// `Loader.require(123)` are left alone.
+pub fn source_require_references(source: &str) -> Vec<SourceRequire> {
+    let tokens = tokenize(source);
+    let mut references = Vec::new();
+
+    for (index, window) in tokens.windows(4).enumerate() {
+        let [name, open, value, close] = window else {
+            continue;
+        };
+        if name.content(source) != "require"
+            || open.content(source) != "("
+            || value.kind != LuaTokenKind::Number
+            || close.content(source) != ")"
+        {
+            continue;
+        }
+        if index > 0 && matches!(tokens[index - 1].content(source), "." | ":") {
+            continue;
+        }
+
+        let digits = value.content(source);
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            continue;
+        }
+        if let Ok(asset_id) = digits.parse::<u64>() {
+            references.push(SourceRequire {
+                asset_id,
+                span: value.start..value.end,
+                line: value.line,
+            });
+        }
+    }
+
+    references
+}
+
+impl StudioParser {
+    /// Finds every require() of a module by asset ID in the scanned scripts.
+    pub fn require_references(&self) -> Vec<RequireReference> {
+        let mut references = Vec::new();
+
+        for script_ref in self.get_script_refs() {
+            if let Some(instance) = self.dom.get_by_ref(script_ref)
+                && let Some((_, source)) = props::source(instance)
+            {
+                references.extend(
+                    source_require_references(source)
+                        .into_iter()
+                        .map(|reference| RequireReference {
+                            script: script_ref,
+                            asset_id: reference.asset_id,
+                            span: reference.span,
+                            line: reference.line,
+                        }),
+                );
+            }
+        }
+
+        references
+    }
+
+    /// The sorted, deduplicated asset IDs of the modules the scanned scripts require.
+    pub fn required_module_ids(&self) -> Vec<u64> {
+        let asset_ids: BTreeSet<u64> = self
+            .require_references()
+            .into_iter()
+            .map(|reference| reference.asset_id)
+            .collect();
+        asset_ids.into_iter().collect()
+    }
+
+    /// Rewrites require() IDs in the scanned scripts with an old → new mapping and returns
+    /// every replacement made.
+    pub fn rewrite_requires(
+        &mut self,
+        module_mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
+        let mut report = ReplacementReport::new();
+
+        for script_ref in self.get_script_refs() {
+            let path = self.instance_path(script_ref);
+            let Some(instance) = self.dom.get_by_ref_mut(script_ref) else {
+                continue;
+            };
+            let Some((source_key, source)) = props::source(instance) else {
+                continue;
+            };
+
+            let mut new_source = String::with_capacity(source.len());
+            let mut cursor = 0;
+            for reference in source_require_references(source) {
+                let old_id = reference.asset_id.to_string();
+                let Some(new_id) = module_mapping.get(&old_id) else {
+                    continue;
+                };
+                new_source.push_str(&source[cursor..reference.span.start]);
+                new_source.push_str(new_id);
+                cursor = reference.span.end;
+
+                report.replacements.push(Replacement {
+                    file: None,
+                    instance_path: Some(path.clone()),
+                    property: props::SOURCE.to_string(),
+                    old_id,
+                    new_id: new_id.clone(),
+                    line: Some(reference.line),
+                });
+            }
+            if cursor == 0 {
+                continue;
+            }
+
+            new_source.push_str(&source[cursor..]);
+            let value = props::source_value(instance, new_source);
+            instance.properties.insert(source_key, value);
+        }
+
+        report
+    }
+}
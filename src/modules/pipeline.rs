@@ -0,0 +1,75 @@
+use crate::AnimationUploader;
+use crate::StudioParser;
+use crate::animation::target::UploadTarget;
+use crate::pipeline::{BoxFuture, Rewriter, Scanner, Uploader};
+use crate::report::ReplacementReport;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The module asset kind for the Migrator: scans require(assetId) calls, reuploads the modules
+/// with AnimationUploader::reupload_modules and rewrites the calls with the new IDs.
+///
+/// # Examples
+///
+/// ```rust
+/// let migration = Migrator::new()
+///     .register(AnimationPipeline::new(uploader.clone(), UploadOptions::new()))
+///     .register(ModulePipeline::new(uploader).target(UploadTarget::Group(1234)))
+///     .migrate(&mut parser)
+///     .await?;
+/// ```
+pub struct ModulePipeline {
+    uploader: Arc<AnimationUploader>,
+    target: UploadTarget,
+}
+
+impl ModulePipeline {
+    pub fn new(uploader: Arc<AnimationUploader>) -> Self {
+        Self {
+            uploader,
+            target: UploadTarget::CurrentUser,
+        }
+    }
+
+    /// Who the modules are uploaded as. Defaults to UploadTarget::CurrentUser.
+    pub fn target(mut self, target: UploadTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Uploads the modules to this group instead of the authenticated user. Same as target
+    /// with `group_id.into()`.
+    pub fn group_id(mut self, group_id: Option<u64>) -> Self {
+        self.target = group_id.into();
+        self
+    }
+}
+
+impl Scanner for ModulePipeline {
+    fn kind(&self) -> &'static str {
+        "module"
+    }
+
+    fn scan(&self, parser: &StudioParser) -> Vec<u64> {
+        parser.required_module_ids()
+    }
+}
+
+impl Uploader for ModulePipeline {
+    fn upload(
+        &self,
+        asset_ids: Vec<u64>,
+    ) -> BoxFuture<'_, anyhow::Result<HashMap<String, String>>> {
+        Box::pin(self.uploader.reupload_modules(asset_ids, self.target))
+    }
+}
+
+impl Rewriter for ModulePipeline {
+    fn rewrite(
+        &self,
+        parser: &mut StudioParser,
+        mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
+        parser.rewrite_requires(mapping)
+    }
+}
@@ -0,0 +1,146 @@
+use bytes::Bytes;
+use roboat::RoboatError;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::AnimationUploader;
+use crate::animation::correlation::CorrelationId;
+use crate::animation::failure::FailureKind;
+use crate::animation::metrics::MetricsRecorder;
+use crate::animation::options::DEFAULT_UPLOAD_RETRIES;
+use crate::animation::target::UploadTarget;
+
+/// Asset type ID of models, which is what modules loaded with require() are published as.
+pub const MODEL_ASSET_TYPE_ID: u64 = 10;
+
+impl AnimationUploader {
+    /// Uploads a model file (.rbxm) as a new model owned by `target`, and returns its asset
+    /// ID. `target` is an UploadTarget, or a group ID option where None is the cookie's user.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// A module loaded with require() has to be a model whose root is a ModuleScript named
+    /// MainModule, which is how Roblox serves one when it is downloaded. Rate limits and
+    /// server errors are waited out and retried like animation uploads, up to 4 times.
+    pub async fn upload_model<T: Into<UploadTarget>>(
+        &self,
+        data: Bytes,
+        name: &str,
+        target: T,
+    ) -> Result<u64, RoboatError> {
+        let group_id = target.into().group_id();
+        // DEFAULT_UPLOAD_RETRIES counts the retries after the first attempt
+        let max_attempts = DEFAULT_UPLOAD_RETRIES + 1;
+
+        let mut attempt = 1;
+        loop {
+            self.rate_limiter.wait_if_limited().await;
+            let error = match self
+                .api
+                .upload_model(&self.roblosecurity, data.clone(), name, group_id)
+                .await
+            {
+                Ok(asset_id) => {
+                    MetricsRecorder::add(&self.metrics.bytes_uploaded, data.len() as u64);
+                    return Ok(asset_id);
+                }
+                Err(e) => e,
+            };
+            warn!(
+                "Model upload attempt {}/{} failed: {}",
+                attempt, max_attempts, error
+            );
+            if attempt == max_attempts || FailureKind::classify(&error).is_permanent() {
+                return Err(error);
+            }
+
+            if matches!(
+                error,
+                RoboatError::TooManyRequests | RoboatError::InternalServerError
+            ) {
+                let sleep_time = self.config.retry.rate_limit_wait(attempt as u32);
+                warn!("Rate limited, waiting {:?}", sleep_time);
+                self.rate_limiter.set_rate_limit(sleep_time).await;
+            } else {
+                tokio::time::sleep(self.config.retry.retry_wait(attempt as u32)).await;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Downloads the modules loaded with require(assetId) and uploads each again as a new
+    /// model owned by `target`. Returns the old → new mapping of
+    /// the ones that uploaded; the rest are reported and skipped.
+    /// * Requires a cookie
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let module_ids = parser.required_module_ids();
+    /// let mapping = uploader
+    ///     .reupload_modules(module_ids, UploadTarget::Group(1234))
+    ///     .await?;
+    /// parser.rewrite_requires(&mapping);
+    /// ```
+    pub async fn reupload_modules<T: Into<UploadTarget>>(
+        &self,
+        module_ids: Vec<u64>,
+        target: T,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let target = target.into();
+        let mut mapping = HashMap::new();
+
+        for asset in self.fetch_assets(module_ids).await? {
+            let correlation_id = CorrelationId::new(asset.id);
+            if asset
+                .asset_type_id
+                .is_some_and(|asset_type_id| asset_type_id != MODEL_ASSET_TYPE_ID)
+            {
+                warn!(correlation_id = %correlation_id, "Required asset is not a model, skipping");
+                continue;
+            }
+            let Some(location) = asset.location() else {
+                warn!(correlation_id = %correlation_id, "No download location for module");
+                self.record_failure(asset.id, "no download location", None, FailureKind::Deleted);
+                continue;
+            };
+
+            let data = match self.file_bytes_from_url(location.to_string()).await {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!(correlation_id = %correlation_id, "Failed to download module: {}", e);
+                    self.record_failure(
+                        asset.id,
+                        e.to_string(),
+                        Some(location.to_string()),
+                        FailureKind::classify(&e),
+                    );
+                    continue;
+                }
+            };
+
+            let name = asset
+                .name
+                .clone()
+                .unwrap_or_else(|| "MainModule".to_string());
+            match self.upload_model(data, &name, target).await {
+                Ok(new_id) => {
+                    info!(correlation_id = %correlation_id, "Reuploaded module as {}", new_id);
+                    mapping.insert(asset.id.to_string(), new_id.to_string());
+                }
+                Err(e) => {
+                    warn!(correlation_id = %correlation_id, "Failed to upload module: {}", e);
+                    // Statuses sort the same as for animations: 404 deleted, 401/403 forbidden
+                    self.record_failure(
+                        asset.id,
+                        e.to_string(),
+                        Some(location.to_string()),
+                        FailureKind::classify(&e),
+                    );
+                }
+            }
+        }
+
+        Ok(mapping)
+    }
+}
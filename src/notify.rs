@@ -0,0 +1,90 @@
+use reqwest::header::CONTENT_TYPE;
+use std::time::Duration;
+
+use crate::RunMetrics;
+
+/// How a run ended, posted to a chat webhook with notify_webhook.
+#[derive(Debug, Clone, Default)]
+pub struct RunNotification {
+    /// e.g. "finished", "failed" or "interrupted".
+    pub outcome: String,
+    /// The files the run was for.
+    pub files: Vec<String>,
+    /// None when the run ended before anything was uploaded.
+    pub metrics: Option<RunMetrics>,
+    /// Where the old -> new mapping was saved, if it was.
+    pub mapping: Option<String>,
+}
+
+impl RunNotification {
+    /// The message posted, e.g. "xixspoof run finished for place.rbxl: 95 uploaded, 3 reused,
+    /// 2 failed. Mapping saved to mapping.json".
+    pub fn message(&self) -> String {
+        let mut message = format!("xixspoof run {}", self.outcome);
+        if !self.files.is_empty() {
+            message.push_str(&format!(" for {}", self.files.join(", ")));
+        }
+        match &self.metrics {
+            Some(metrics) => message.push_str(&format!(
+                ": {} uploaded, {} reused, {} failed",
+                metrics.uploaded, metrics.reused, metrics.failed
+            )),
+            None => message.push_str(" before uploading anything"),
+        }
+        if let Some(mapping) = &self.mapping {
+            message.push_str(&format!(". Mapping saved to {}", mapping));
+        }
+        message
+    }
+}
+
+/// Posts a run's outcome to a Discord or Slack incoming webhook.
+///
+/// # Examples
+///
+/// ```rust
+/// let notification = RunNotification {
+///     outcome: "finished".to_string(),
+///     files: vec!["place.rbxl".to_string()],
+///     metrics: Some(uploader.run_metrics()),
+///     mapping: Some("mapping.json".to_string()),
+/// };
+/// notify_webhook("https://discord.com/api/webhooks/...", &notification).await?;
+/// ```
+///
+/// # Notes:
+/// Discord webhooks (discord.com or discordapp.com) get the message as `content`, anything
+/// else as `text`, which Slack and most Slack-compatible webhooks accept.
+pub async fn notify_webhook(url: &str, notification: &RunNotification) -> anyhow::Result<()> {
+    let field = if is_discord_webhook(url) {
+        "content"
+    } else {
+        "text"
+    };
+    let body = serde_json::json!({ field: notification.message() });
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .timeout(Duration::from_secs(10))
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to post to webhook: {}", e))?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Webhook returned {}", response.status());
+    }
+    Ok(())
+}
+
+fn is_discord_webhook(url: &str) -> bool {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .is_some_and(|host| {
+            ["discord.com", "discordapp.com"]
+                .iter()
+                .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+        })
+}
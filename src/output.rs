@@ -0,0 +1,432 @@
+use crate::animation::health::ReferenceStatus;
+use crate::animation::model::AssetCreator;
+use crate::animation::owners::OwnerSummary;
+use crate::report::{ModelUsage, ReplacementReport};
+use crate::studio::place_diff::{AssetRefChange, PlaceDiff};
+use crate::studio::stats::PlaceStats;
+use serde::{Deserialize, Serialize};
+
+/// How a command prints its result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for reading in a terminal.
+    #[default]
+    Table,
+    /// Pretty-printed JSON of the whole result.
+    Json,
+    /// One CSV row per entry, with a header row.
+    Csv,
+}
+
+/// A command result that can be printed as rows, besides being serialized whole to JSON.
+pub trait Tabular: Serialize {
+    fn columns(&self) -> Vec<&'static str>;
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Renders a result in the given format, ending with a newline.
+///
+/// # Examples
+///
+/// ```rust
+/// let report = parser.apply_mapping(&mapping, None, ApplyScope::All);
+/// print!("{}", render(&report, OutputFormat::Csv)?);
+/// ```
+pub fn render<T: Tabular>(value: &T, format: OutputFormat) -> anyhow::Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(value)? + "\n"),
+        OutputFormat::Csv => {
+            let mut out = String::new();
+            for row in std::iter::once(columns_row(value)).chain(value.rows()) {
+                let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = std::iter::once(columns_row(value))
+                .chain(value.rows())
+                .collect();
+            let mut widths = vec![0; rows[0].len()];
+            for row in &rows {
+                for (width, field) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(field.chars().count());
+                }
+            }
+
+            let mut out = String::new();
+            for row in &rows {
+                let fields: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(field, width)| format!("{:<width$}", field, width = width))
+                    .collect();
+                out.push_str(fields.join("  ").trim_end());
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+fn columns_row<T: Tabular>(value: &T) -> Vec<String> {
+    value
+        .columns()
+        .into_iter()
+        .map(|column| column.to_string())
+        .collect()
+}
+
+/// Quotes a CSV field when it contains a comma, quote or newline.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn creator_label(creator: Option<AssetCreator>) -> String {
+    match creator {
+        Some(AssetCreator::User(user_id)) => format!("user {}", user_id),
+        Some(AssetCreator::Group(group_id)) => format!("group {}", group_id),
+        None => String::new(),
+    }
+}
+
+/// An animation found by the scan command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScannedAnimation {
+    pub id: u64,
+    pub name: Option<String>,
+    pub creator: Option<AssetCreator>,
+    /// Instance paths and script lines that reference the animation.
+    pub used_by: Vec<String>,
+}
+
+/// What the scan command found in a place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub source: String,
+    pub animations: Vec<ScannedAnimation>,
+    /// The animations grouped by the model using them (see StudioParser::model_usage).
+    #[serde(default)]
+    pub models: Vec<ModelUsage>,
+}
+
+impl Tabular for ScanResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["id", "name", "owner", "used_by"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.animations
+            .iter()
+            .map(|animation| {
+                vec![
+                    animation.id.to_string(),
+                    animation.name.clone().unwrap_or_default(),
+                    creator_label(animation.creator),
+                    animation.used_by.join("; "),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// An old → new ID pair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappedId {
+    pub old_id: String,
+    pub new_id: String,
+}
+
+/// What the upload command uploaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadResult {
+    pub uploaded: Vec<MappedId>,
+    /// Source IDs that weren't uploaded.
+    pub failed: Vec<u64>,
+}
+
+impl Tabular for UploadResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["old_id", "new_id", "status"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let uploaded = self.uploaded.iter().map(|mapped| {
+            vec![
+                mapped.old_id.clone(),
+                mapped.new_id.clone(),
+                "uploaded".to_string(),
+            ]
+        });
+        let failed = self
+            .failed
+            .iter()
+            .map(|old_id| vec![old_id.to_string(), String::new(), "failed".to_string()]);
+        uploaded.chain(failed).collect()
+    }
+}
+
+/// What the verify command found.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyResult {
+    pub passed: Vec<MappedId>,
+    /// Entries whose new ID doesn't resolve or has the wrong owner.
+    pub failed: Vec<MappedId>,
+    /// New IDs rejected by moderation, with --wait-moderation.
+    pub rejected: Vec<MappedId>,
+    /// New IDs still under moderation review when --wait-moderation ran out.
+    pub pending: Vec<MappedId>,
+    /// Entries whose animation events differ from the source's, with --check-events.
+    #[serde(default)]
+    pub events_changed: Vec<MappedId>,
+}
+
+impl Tabular for VerifyResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["old_id", "new_id", "status"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        [
+            (&self.passed, "passed"),
+            (&self.failed, "failed"),
+            (&self.rejected, "rejected"),
+            (&self.pending, "pending"),
+            (&self.events_changed, "events_changed"),
+        ]
+        .into_iter()
+        .flat_map(|(entries, status)| {
+            entries.iter().map(move |mapped| {
+                vec![
+                    mapped.old_id.clone(),
+                    mapped.new_id.clone(),
+                    status.to_string(),
+                ]
+            })
+        })
+        .collect()
+    }
+}
+
+/// One animation a place references, as checked by verify --file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckedReference {
+    pub id: u64,
+    pub status: ReferenceStatus,
+    pub owner: Option<AssetCreator>,
+    /// Instance paths and script lines that reference the animation.
+    pub used_by: Vec<String>,
+    /// The asset delivery error, or why the owner is unknown.
+    pub detail: Option<String>,
+}
+
+/// What verify --file found: every animation the place references and whether it will load.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceCheckResult {
+    pub source: String,
+    pub references: Vec<CheckedReference>,
+}
+
+impl ReferenceCheckResult {
+    /// The references that will fail in-game.
+    pub fn broken(&self) -> impl Iterator<Item = &CheckedReference> {
+        self.references
+            .iter()
+            .filter(|reference| reference.status.is_broken())
+    }
+}
+
+impl Tabular for ReferenceCheckResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["id", "status", "owner", "used_by", "detail"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.references
+            .iter()
+            .map(|reference| {
+                vec![
+                    reference.id.to_string(),
+                    reference.status.to_string(),
+                    creator_label(reference.owner),
+                    reference.used_by.join("; "),
+                    reference.detail.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// How many classes the table and CSV output of PlaceStats list. JSON has all of them.
+const STATS_TOP_CLASSES: usize = 15;
+
+impl Tabular for PlaceStats {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["stat", "value"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        if let Some(file_bytes) = self.file_bytes {
+            rows.push(vec!["file bytes".to_string(), file_bytes.to_string()]);
+        }
+        for (label, value) in [
+            ("instances", self.instances as u64),
+            ("classes", self.classes.len() as u64),
+            ("scripts", self.scripts as u64),
+            ("script bytes", self.script_bytes),
+            ("animation references", self.animation_refs as u64),
+            ("sound references", self.sound_refs as u64),
+            ("image references", self.image_refs as u64),
+            ("script asset references", self.script_refs as u64),
+        ] {
+            rows.push(vec![label.to_string(), value.to_string()]);
+        }
+        for (class, count) in self.top_classes(STATS_TOP_CLASSES) {
+            rows.push(vec![format!("class {}", class), count.to_string()]);
+        }
+        for (chunk, bytes) in &self.chunks {
+            rows.push(vec![format!("chunk {} bytes", chunk), bytes.to_string()]);
+        }
+        rows
+    }
+}
+
+impl Tabular for ReplacementReport {
+    fn columns(&self) -> Vec<&'static str> {
+        vec![
+            "file",
+            "instance_path",
+            "property",
+            "old_id",
+            "new_id",
+            "line",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.replacements
+            .iter()
+            .map(|replacement| {
+                vec![
+                    replacement.file.clone().unwrap_or_default(),
+                    replacement.instance_path.clone().unwrap_or_default(),
+                    replacement.property.clone(),
+                    replacement.old_id.clone(),
+                    replacement.new_id.clone(),
+                    replacement
+                        .line
+                        .map(|line| line.to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// The owners found by the report owners command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnersResult {
+    pub owners: Vec<OwnerSummary>,
+}
+
+impl Tabular for OwnersResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["owner", "name", "animations"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.owners
+            .iter()
+            .map(|owner| {
+                vec![
+                    match owner.creator {
+                        Some(creator) => creator_label(Some(creator)),
+                        None => "unknown".to_string(),
+                    },
+                    owner.name.clone().unwrap_or_default(),
+                    owner.asset_ids.len().to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// One animation checked by the inspect command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InspectedAnimation {
+    /// The asset ID or file the animation came from.
+    pub source: String,
+    /// Length in seconds. None when the animation couldn't be decoded.
+    pub length: Option<f32>,
+    pub keyframe_count: usize,
+    pub joint_names: Vec<String>,
+    /// Why the animation couldn't be downloaded or decoded, or that it is empty.
+    pub error: Option<String>,
+}
+
+/// What the inspect command found.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InspectResult {
+    pub animations: Vec<InspectedAnimation>,
+}
+
+impl Tabular for InspectResult {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["source", "length", "keyframes", "joints", "error"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.animations
+            .iter()
+            .map(|animation| {
+                vec![
+                    animation.source.clone(),
+                    animation
+                        .length
+                        .map(|length| format!("{:.2}s", length))
+                        .unwrap_or_default(),
+                    animation.keyframe_count.to_string(),
+                    animation.joint_names.join("; "),
+                    animation.error.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Tabular for PlaceDiff {
+    fn columns(&self) -> Vec<&'static str> {
+        vec!["change", "location", "old_id", "new_id"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.changes
+            .iter()
+            .map(|change| {
+                let (kind, old_id, new_id) = match change {
+                    AssetRefChange::Added { asset_id, .. } => {
+                        ("added", String::new(), asset_id.to_string())
+                    }
+                    AssetRefChange::Removed { asset_id, .. } => {
+                        ("removed", asset_id.to_string(), String::new())
+                    }
+                    AssetRefChange::Changed { old_id, new_id, .. } => {
+                        ("changed", old_id.to_string(), new_id.to_string())
+                    }
+                };
+                vec![
+                    kind.to_string(),
+                    change.location().to_string(),
+                    old_id,
+                    new_id,
+                ]
+            })
+            .collect()
+    }
+}
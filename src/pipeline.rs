@@ -0,0 +1,168 @@
+use crate::StudioParser;
+use crate::report::ReplacementReport;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tracing::info;
+
+/// Boxed future returned by Uploader, so asset kinds can be stored as trait objects.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Finds the asset IDs of one kind (animations, sounds, images...) referenced by a file.
+pub trait Scanner: Send + Sync {
+    /// Short name of the asset kind, used in logs and as the key in Migration::mappings.
+    fn kind(&self) -> &'static str;
+
+    /// Returns the sorted, deduplicated asset IDs referenced by the file.
+    fn scan(&self, parser: &StudioParser) -> Vec<u64>;
+}
+
+/// Reuploads assets of one kind and returns the old → new mapping.
+pub trait Uploader: Send + Sync {
+    fn upload(&self, asset_ids: Vec<u64>)
+    -> BoxFuture<'_, anyhow::Result<HashMap<String, String>>>;
+}
+
+/// Writes an old → new mapping of one kind back into a file.
+pub trait Rewriter: Send + Sync {
+    fn rewrite(
+        &self,
+        parser: &mut StudioParser,
+        mapping: &HashMap<String, String>,
+    ) -> ReplacementReport;
+}
+
+/// An asset kind the Migrator can run. Implemented for anything that is a Scanner, Uploader
+/// and Rewriter.
+pub trait AssetKind: Scanner + Uploader + Rewriter {}
+
+impl<T: Scanner + Uploader + Rewriter> AssetKind for T {}
+
+/// What Migrator::migrate did.
+#[derive(Debug, Default)]
+pub struct Migration {
+    /// Old → new mapping for each asset kind, keyed by Scanner::kind.
+    pub mappings: HashMap<&'static str, HashMap<String, String>>,
+    pub report: ReplacementReport,
+}
+
+/// Runs scan → upload → rewrite for every registered asset kind.
+///
+/// # Examples
+///
+/// ```rust
+/// let migration = Migrator::new()
+///     .register(AnimationPipeline::new(uploader, UploadOptions::new()))
+///     .migrate(&mut parser)
+///     .await?;
+/// ```
+///
+/// # Notes:
+/// New asset kinds only need a module implementing Scanner, Uploader and Rewriter;
+/// the orchestration here doesn't change.
+#[derive(Default)]
+pub struct Migrator {
+    kinds: Vec<Box<dyn AssetKind>>,
+}
+
+impl Migrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an asset kind. Kinds run in the order they were registered.
+    pub fn register<K: AssetKind + 'static>(mut self, kind: K) -> Self {
+        self.kinds.push(Box::new(kind));
+        self
+    }
+
+    /// Migrates every registered asset kind in the file. The file isn't saved.
+    pub async fn migrate(&self, parser: &mut StudioParser) -> anyhow::Result<Migration> {
+        let mut migration = Migration::default();
+
+        for kind in &self.kinds {
+            let asset_ids = kind.scan(parser);
+            info!("Found {} {} references", asset_ids.len(), kind.kind());
+            if asset_ids.is_empty() {
+                continue;
+            }
+
+            let mapping = kind.upload(asset_ids).await?;
+            migration.report.merge(kind.rewrite(parser, &mapping));
+            migration.mappings.insert(kind.kind(), mapping);
+        }
+
+        Ok(migration)
+    }
+}
+
+/// Runs a Migrator over several files at once: every file is scanned first, each asset is
+/// uploaded once however many files use it, and every file is rewritten with the same mapping.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut places = ContentScanner::from_paths(expand_paths(&["places/*.rbxl"])?, Some(cookie));
+/// let migration = BatchMigrator::new(
+///     Migrator::new().register(AnimationPipeline::new(uploader, UploadOptions::new())),
+/// )
+/// .migrate(&mut places.files)
+/// .await?;
+/// places.save_all()?;
+/// ```
+///
+/// # Notes:
+/// Nothing is rewritten until every kind uploaded, so a failed upload leaves all files as
+/// they were. The files aren't saved.
+pub struct BatchMigrator {
+    migrator: Migrator,
+}
+
+impl BatchMigrator {
+    pub fn new(migrator: Migrator) -> Self {
+        Self { migrator }
+    }
+
+    /// Migrates every registered asset kind across all files. Replacements in the report are
+    /// marked with the file they were made in.
+    pub async fn migrate(
+        &self,
+        files: &mut [(PathBuf, StudioParser)],
+    ) -> anyhow::Result<Migration> {
+        let mut migration = Migration::default();
+
+        for kind in &self.migrator.kinds {
+            let asset_ids: BTreeSet<u64> = files
+                .iter()
+                .flat_map(|(_, parser)| kind.scan(parser))
+                .collect();
+            info!(
+                "Found {} {} references across {} files",
+                asset_ids.len(),
+                kind.kind(),
+                files.len()
+            );
+            if asset_ids.is_empty() {
+                continue;
+            }
+
+            let mapping = kind.upload(asset_ids.into_iter().collect()).await?;
+            migration.mappings.insert(kind.kind(), mapping);
+        }
+
+        for kind in &self.migrator.kinds {
+            let Some(mapping) = migration.mappings.get(kind.kind()) else {
+                continue;
+            };
+            for (path, parser) in files.iter_mut() {
+                let report = kind.rewrite(parser, mapping);
+                migration
+                    .report
+                    .merge(report.with_file(&path.to_string_lossy()));
+            }
+        }
+
+        Ok(migration)
+    }
+}
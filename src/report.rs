@@ -0,0 +1,237 @@
+use crate::animation::failure::FailureKind;
+use crate::animation::target::UploadTarget;
+use crate::artifacts::{SCHEMA_VERSION, SummaryV2, parse_artifact};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// One asset ID that was replaced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replacement {
+    /// The place/model or script file the replacement was made in, when not the main place.
+    pub file: Option<String>,
+    /// Dotted path of the instance, e.g. "Workspace.NPCs.Guard.Idle". None for files on disk.
+    pub instance_path: Option<String>,
+    /// The property that changed, e.g. "AnimationId" or "Source".
+    pub property: String,
+    pub old_id: String,
+    pub new_id: String,
+    /// 1-based script line, for replacements inside source code.
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {} in ", self.old_id, self.new_id)?;
+        match (&self.file, &self.instance_path) {
+            (Some(file), Some(path)) => write!(f, "{} ({})", path, file)?,
+            (Some(file), None) => write!(f, "{}", file)?,
+            (None, Some(path)) => write!(f, "{}", path)?,
+            (None, None) => write!(f, "<unknown>")?,
+        }
+        write!(f, " {}", self.property)?;
+        if let Some(line) = self.line {
+            write!(f, " line {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// An animation that was left alone on purpose.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedAsset {
+    pub asset_id: u64,
+    pub reason: String,
+}
+
+/// An animation that failed to download or upload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedAsset {
+    pub asset_id: u64,
+    pub reason: String,
+    /// Whether retrying could help. Reports from before it was recorded read as transient.
+    #[serde(default)]
+    pub kind: FailureKind,
+    /// The CDN location it was downloaded from, when it had one.
+    pub url: Option<String>,
+}
+
+/// A newly uploaded asset with a link to it on the Creator Hub, for spot-checking.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadReceipt {
+    pub old_id: String,
+    pub new_id: String,
+    pub url: String,
+}
+
+impl UploadReceipt {
+    pub fn new(old_id: &str, new_id: &str) -> Self {
+        Self {
+            old_id: old_id.to_string(),
+            new_id: new_id.to_string(),
+            url: creator_hub_url(new_id),
+        }
+    }
+}
+
+/// The Creator Hub page of an asset.
+pub fn creator_hub_url(asset_id: &str) -> String {
+    format!(
+        "https://create.roblox.com/dashboard/creations/store/{}/configure",
+        asset_id
+    )
+}
+
+/// Names where uploads went: "group <id>", or "user" for the authenticated user.
+pub fn upload_destination(group_id: Option<u64>) -> String {
+    UploadTarget::from(group_id).to_string()
+}
+
+/// Builds receipts for an old → new mapping uploaded to one destination, sorted by old ID.
+pub fn upload_receipts(
+    mapping: &HashMap<String, String>,
+    group_id: Option<u64>,
+) -> BTreeMap<String, Vec<UploadReceipt>> {
+    let mut receipts: Vec<UploadReceipt> = mapping
+        .iter()
+        .map(|(old_id, new_id)| UploadReceipt::new(old_id, new_id))
+        .collect();
+    receipts.sort_by(|a, b| a.old_id.cmp(&b.old_id));
+
+    let mut grouped = BTreeMap::new();
+    if !receipts.is_empty() {
+        grouped.insert(upload_destination(group_id), receipts);
+    }
+    grouped
+}
+
+/// The animations used inside one model (or, outside of any model, one service), from
+/// StudioParser::model_usage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Dotted path of the model, e.g. "Workspace.NPCs.Zombie".
+    pub model: String,
+    /// Asset IDs the model's instances and scripts reference, sorted.
+    pub animations: Vec<u64>,
+    /// The ones among them that can't be downloaded (deleted, moderated or private).
+    pub broken: Vec<u64>,
+}
+
+impl ModelUsage {
+    /// Groups (model path, asset ID, broken) uses per model, sorted by model path. An asset used
+    /// more than once in a model is counted once.
+    pub fn aggregate<I: IntoIterator<Item = (String, u64, bool)>>(uses: I) -> Vec<Self> {
+        let mut models: BTreeMap<String, BTreeMap<u64, bool>> = BTreeMap::new();
+        for (model, asset_id, broken) in uses {
+            models
+                .entry(model)
+                .or_default()
+                .entry(asset_id)
+                .or_insert(broken);
+        }
+
+        models
+            .into_iter()
+            .map(|(model, animations)| Self {
+                model,
+                broken: animations
+                    .iter()
+                    .filter(|(_, broken)| **broken)
+                    .map(|(asset_id, _)| *asset_id)
+                    .collect(),
+                animations: animations.into_keys().collect(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ModelUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} uses {} animation{}",
+            self.model,
+            self.animations.len(),
+            if self.animations.len() == 1 { "" } else { "s" }
+        )?;
+        if !self.broken.is_empty() {
+            write!(f, ", {} broken", self.broken.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything a run changed, so it can be audited afterwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplacementReport {
+    pub replacements: Vec<Replacement>,
+    pub skipped: Vec<SkippedAsset>,
+    /// Animations that failed to download or upload, so they weren't replaced.
+    pub failed: Vec<FailedAsset>,
+    /// Uploaded assets, grouped by destination (see upload_destination).
+    pub receipts: BTreeMap<String, Vec<UploadReceipt>>,
+}
+
+impl ReplacementReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends another report's entries to this one.
+    pub fn merge(&mut self, other: ReplacementReport) {
+        self.replacements.extend(other.replacements);
+        self.skipped.extend(other.skipped);
+        self.failed.extend(other.failed);
+        for (destination, receipts) in other.receipts {
+            self.receipts
+                .entry(destination)
+                .or_default()
+                .extend(receipts);
+        }
+    }
+
+    /// Marks every replacement as made in the given file.
+    pub fn with_file(mut self, file: &str) -> Self {
+        for replacement in &mut self.replacements {
+            replacement.file = Some(file.to_string());
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replacements.is_empty() && self.skipped.is_empty() && self.failed.is_empty()
+    }
+
+    /// Loads a report saved with save.
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read report '{}': {}", path.display(), e))?;
+
+        let summary: SummaryV2 = parse_artifact(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse report '{}': {}", path.display(), e))?;
+        Ok(Self {
+            replacements: summary.replacements,
+            skipped: summary.skipped,
+            failed: summary.failed,
+            receipts: summary.receipts,
+        })
+    }
+
+    /// Saves the report as a SummaryV2 file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let summary = SummaryV2 {
+            schema_version: SCHEMA_VERSION,
+            replacements: self.replacements.clone(),
+            skipped: self.skipped.clone(),
+            failed: self.failed.clone(),
+            receipts: self.receipts.clone(),
+        };
+        let contents = serde_json::to_string_pretty(&summary)?;
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write report '{}': {}", path.display(), e))
+    }
+}
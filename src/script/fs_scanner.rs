@@ -0,0 +1,95 @@
+use crate::script::script_editor::apply_mapping_to_source;
+use crate::script::script_parser::source_asset_references;
+use crate::script::types::{AssetUrlStyle, SourceReplacement};
+use crate::studio::backup::{backup_file, write_atomically};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Recursively finds every .lua/.luau file in a directory (e.g. a Rojo project's src/).
+/// Hidden directories such as .git are skipped.
+pub fn lua_files<P: AsRef<Path>>(dir: P) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read directory '{}': {}", dir.display(), e))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            let is_hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+
+            if path.is_dir() {
+                if !is_hidden {
+                    pending.push(path);
+                }
+            } else if is_lua_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Returns every asset ID referenced by the .lua/.luau files in a directory.
+pub fn asset_ids_in_dir<P: AsRef<Path>>(dir: P) -> anyhow::Result<Vec<u64>> {
+    let mut ids: HashSet<u64> = HashSet::new();
+
+    for path in lua_files(dir)? {
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", path.display(), e))?;
+        ids.extend(
+            source_asset_references(&source)
+                .into_iter()
+                .map(|reference| reference.asset_id),
+        );
+    }
+
+    let mut ids: Vec<u64> = ids.into_iter().collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Rewrites the .lua/.luau files in a directory with the old → new mapping.
+/// Returns the files that changed with the replacements made in each.
+///
+/// # Notes:
+/// Each changed file is written through a temporary file, and with `backup` first copied to
+/// `<name>.bak-<unix timestamp>` like places are.
+pub fn rewrite_dir<P: AsRef<Path>>(
+    dir: P,
+    animation_mapping: &HashMap<String, String>,
+    normalize_style: Option<AssetUrlStyle>,
+    backup: bool,
+) -> anyhow::Result<Vec<(PathBuf, Vec<SourceReplacement>)>> {
+    let mut changed = Vec::new();
+
+    for path in lua_files(dir)? {
+        let source = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{}': {}", path.display(), e))?;
+        let (new_source, replacements) =
+            apply_mapping_to_source(&source, animation_mapping, normalize_style);
+
+        if new_source != source {
+            if backup {
+                backup_file(&path)?;
+            }
+            write_atomically(&path, new_source.as_bytes())?;
+            changed.push((path, replacements));
+        }
+    }
+
+    Ok(changed)
+}
+
+fn is_lua_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext == "lua" || ext == "luau")
+}
@@ -0,0 +1,117 @@
+use crate::script::script_parser::source_asset_references;
+use crate::script::types::LinkedSourceReference;
+use crate::studio::props;
+use crate::{AnimationUploader, StudioParser};
+use rbx_dom_weak::types::Ref;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+impl StudioParser {
+    /// Returns every script whose code lives in a LinkedSource script asset instead of its
+    /// Source, with the asset's ID.
+    pub fn linked_source_scripts(&self) -> Vec<(Ref, u64)> {
+        self.get_script_refs()
+            .into_iter()
+            .filter_map(|script_ref| {
+                let instance = self.dom.get_by_ref(script_ref)?;
+                Some((script_ref, props::linked_source(instance)?))
+            })
+            .collect()
+    }
+
+    /// Downloads the linked source of every LinkedSource script and finds the asset IDs in it.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// Each linked asset is downloaded once, however many scripts share it. Assets that can't be
+    /// fetched are skipped with a warning, so one deleted script asset doesn't stop a scan.
+    pub async fn linked_source_references(&self) -> anyhow::Result<Vec<LinkedSourceReference>> {
+        let scripts = self.linked_source_scripts();
+        if scripts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut asset_ids: Vec<u64> = scripts.iter().map(|(_, asset_id)| *asset_id).collect();
+        asset_ids.sort();
+        asset_ids.dedup();
+        info!(
+            "Fetching {} linked sources for {} scripts",
+            asset_ids.len(),
+            scripts.len()
+        );
+
+        let uploader = self.animation_uploader()?;
+        let sources = uploader.fetch_linked_sources(asset_ids).await?;
+
+        let mut references = Vec::new();
+        for (script, linked_source) in scripts {
+            let Some(source) = sources.get(&linked_source) else {
+                continue;
+            };
+            references.extend(
+                source_asset_references(source)
+                    .into_iter()
+                    .map(|reference| LinkedSourceReference {
+                        script,
+                        linked_source,
+                        asset_id: reference.asset_id,
+                        line: reference.line,
+                    }),
+            );
+        }
+        Ok(references)
+    }
+}
+
+impl AnimationUploader {
+    /// Downloads the code of script assets, as a LinkedSource points at. Returns the source of
+    /// each asset that could be fetched.
+    /// * Requires a cookie
+    pub async fn fetch_linked_sources(
+        &self,
+        asset_ids: Vec<u64>,
+    ) -> anyhow::Result<HashMap<u64, String>> {
+        let assets = self.fetch_assets(asset_ids.clone()).await?;
+        let mut sources = HashMap::new();
+
+        for asset_id in asset_ids {
+            let location = assets
+                .iter()
+                .find(|asset| asset.id == asset_id)
+                .and_then(|asset| asset.location());
+            let Some(location) = location else {
+                warn!("Linked source {} not found or not downloadable", asset_id);
+                continue;
+            };
+
+            match self.file_bytes_from_url(location.to_string()).await {
+                Ok(data) => match decode_linked_source(&data) {
+                    Ok(source) => {
+                        sources.insert(asset_id, source);
+                    }
+                    Err(e) => warn!("Failed to read linked source {}: {}", asset_id, e),
+                },
+                Err(e) => warn!("Failed to download linked source {}: {}", asset_id, e),
+            }
+        }
+
+        Ok(sources)
+    }
+}
+
+/// Legacy script assets are served as plain Lua; newer ones as a model holding the script.
+fn decode_linked_source(data: &[u8]) -> anyhow::Result<String> {
+    let dom = if data.starts_with(b"<roblox!") {
+        rbx_binary::from_reader(data)
+            .map_err(|e| anyhow::anyhow!("Failed to parse script model: {}", e))?
+    } else if data.starts_with(b"<roblox") {
+        rbx_xml::from_reader_default(data)
+            .map_err(|e| anyhow::anyhow!("Failed to parse XML script model: {}", e))?
+    } else {
+        return Ok(String::from_utf8_lossy(data).into_owned());
+    };
+
+    dom.descendants_of(dom.root_ref())
+        .find_map(|instance| props::source(instance).map(|(_, source)| source.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("Script model has no script with a Source"))
+}
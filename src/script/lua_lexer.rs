@@ -0,0 +1,266 @@
+/// The kinds of Luau tokens the asset scanners care about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LuaTokenKind {
+    /// A quoted, backtick or long-bracket string.
+    String,
+    /// A numeric literal.
+    Number,
+    /// The `..` concatenation operator.
+    Concat,
+    /// Identifiers, keywords and every other symbol.
+    Other,
+}
+
+/// A token with its byte range in the source and the 1-based line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LuaToken {
+    pub kind: LuaTokenKind,
+    pub start: usize,
+    pub end: usize,
+    /// For strings, the byte range of the contents without the delimiters.
+    pub content_start: usize,
+    pub content_end: usize,
+    pub line: usize,
+}
+
+impl LuaToken {
+    /// The token's contents: the inside of a string, or the full text of anything else.
+    pub fn content<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.content_start..self.content_end]
+    }
+}
+
+/// Splits Luau source into tokens, skipping whitespace and comments.
+///
+/// # Notes:
+/// This is not a full parser. It only needs to know where string and number literals are,
+/// so that IDs inside comments are ignored and IDs built with `..` can be found.
+pub fn tokenize(source: &str) -> Vec<LuaToken> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let start = i;
+        let start_line = line;
+
+        match byte {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i += 2;
+                if let Some(level) = long_bracket_level(bytes, i) {
+                    let (_, end) = skip_long_bracket(bytes, i, level, &mut line);
+                    i = end;
+                } else {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+            }
+            b'"' | b'\'' | b'`' => {
+                i += 1;
+                let content_start = i;
+                while i < bytes.len() && bytes[i] != byte && bytes[i] != b'\n' {
+                    if bytes[i] == b'\\' && i + 1 < bytes.len() {
+                        if bytes[i + 1] == b'\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                let content_end = i;
+                if i < bytes.len() && bytes[i] == byte {
+                    i += 1;
+                }
+                tokens.push(LuaToken {
+                    kind: LuaTokenKind::String,
+                    start,
+                    end: i,
+                    content_start,
+                    content_end,
+                    line: start_line,
+                });
+            }
+            b'[' if long_bracket_level(bytes, i).is_some() => {
+                let level = long_bracket_level(bytes, i).unwrap();
+                let (content_end, end) = skip_long_bracket(bytes, i, level, &mut line);
+                tokens.push(LuaToken {
+                    kind: LuaTokenKind::String,
+                    start,
+                    end,
+                    content_start: start + level + 2,
+                    content_end,
+                    line: start_line,
+                });
+                i = end;
+            }
+            b'.' if bytes.get(i + 1) == Some(&b'.') => {
+                let kind = if bytes.get(i + 2) == Some(&b'.') {
+                    i += 3;
+                    LuaTokenKind::Other
+                } else {
+                    i += 2;
+                    LuaTokenKind::Concat
+                };
+                tokens.push(simple_token(kind, start, i, start_line));
+            }
+            b if b.is_ascii_digit()
+                || (b == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) =>
+            {
+                i += 1;
+                while i < bytes.len() {
+                    let current = bytes[i];
+                    if matches!(current, b'e' | b'E' | b'p' | b'P')
+                        && matches!(bytes.get(i + 1), Some(b'+' | b'-'))
+                        && !source[start..i].starts_with("0x")
+                    {
+                        i += 2;
+                    } else if current.is_ascii_alphanumeric() || current == b'_' || current == b'.'
+                    {
+                        if current == b'.' && bytes.get(i + 1) == Some(&b'.') {
+                            break;
+                        }
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(simple_token(LuaTokenKind::Number, start, i, start_line));
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push(simple_token(LuaTokenKind::Other, start, i, start_line));
+            }
+            _ => {
+                // Step over a whole UTF-8 character so ranges stay on char boundaries
+                i += source[i..].chars().next().map_or(1, char::len_utf8);
+                tokens.push(simple_token(LuaTokenKind::Other, start, i, start_line));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn simple_token(kind: LuaTokenKind, start: usize, end: usize, line: usize) -> LuaToken {
+    LuaToken {
+        kind,
+        start,
+        end,
+        content_start: start,
+        content_end: end,
+        line,
+    }
+}
+
+/// Returns the number of `=` if a long bracket (`[[`, `[==[`) opens at `i`.
+fn long_bracket_level(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+    let mut level = 0;
+    while bytes.get(i + 1 + level) == Some(&b'=') {
+        level += 1;
+    }
+    (bytes.get(i + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+/// Skips a long bracket opening at `i`. Returns (content end, token end).
+fn skip_long_bracket(bytes: &[u8], i: usize, level: usize, line: &mut usize) -> (usize, usize) {
+    let mut j = i + level + 2;
+    while j < bytes.len() {
+        if bytes[j] == b'\n' {
+            *line += 1;
+        } else if bytes[j] == b']'
+            && bytes[j + 1..].iter().take(level).all(|b| *b == b'=')
+            && bytes.get(j + 1 + level) == Some(&b']')
+        {
+            return (j, j + level + 2);
+        }
+        j += 1;
+    }
+    (bytes.len(), bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds_and_contents(source: &str) -> Vec<(LuaTokenKind, &str)> {
+        tokenize(source)
+            .iter()
+            .map(|token| (token.kind, token.content(source)))
+            .collect()
+    }
+
+    #[test]
+    fn skips_leveled_long_comments() {
+        let source = "--[==[ rbxassetid://1 ]] still a comment ]==]\nlocal id = 2";
+        let tokens = tokenize(source);
+
+        assert_eq!(
+            kinds_and_contents(source),
+            vec![
+                (LuaTokenKind::Other, "local"),
+                (LuaTokenKind::Other, "id"),
+                (LuaTokenKind::Other, "="),
+                (LuaTokenKind::Number, "2"),
+            ]
+        );
+        assert_eq!(tokens[0].line, 2);
+    }
+
+    #[test]
+    fn keeps_escaped_quotes_inside_strings() {
+        let source = r#"local s = "say \"rbxassetid://1\"" .. 2"#;
+
+        assert_eq!(
+            kinds_and_contents(source)[3..],
+            [
+                (LuaTokenKind::String, r#"say \"rbxassetid://1\""#),
+                (LuaTokenKind::Concat, ".."),
+                (LuaTokenKind::Number, "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_concatenated_ids() {
+        let source = r#"local url = "rbxassetid://" .. 123"#;
+        let tokens = tokenize(source);
+
+        assert_eq!(
+            kinds_and_contents(source)[3..],
+            [
+                (LuaTokenKind::String, "rbxassetid://"),
+                (LuaTokenKind::Concat, ".."),
+                (LuaTokenKind::Number, "123"),
+            ]
+        );
+        let string = &tokens[3];
+        assert_eq!(&source[string.start..string.end], r#""rbxassetid://""#);
+    }
+
+    #[test]
+    fn ignores_ids_inside_comments() {
+        let source = "-- rbxassetid://1\n--[[ rbxassetid://2\n 3 ]]\nlocal x -- 4";
+        let tokens = tokenize(source);
+
+        assert!(
+            tokens
+                .iter()
+                .all(|token| !matches!(token.kind, LuaTokenKind::String | LuaTokenKind::Number))
+        );
+        assert_eq!(tokens[0].content(source), "local");
+        assert_eq!(tokens[0].line, 4);
+    }
+}
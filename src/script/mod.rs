@@ -1,3 +1,8 @@
+pub mod fs_scanner;
+pub mod linked_source;
+pub mod lua_lexer;
+pub mod rewrite;
 pub mod script_editor;
 pub mod script_parser;
+pub mod scripts;
 pub mod types;
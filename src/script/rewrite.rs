@@ -0,0 +1,140 @@
+use crate::animation::mapping::Mapping;
+use crate::script::script_editor;
+use crate::script::types::{AssetUrlStyle, SourceReplacement};
+
+/// How rewrite_source rewrites asset references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RewriteOptions {
+    /// Rewrite replaced references into one URL style instead of keeping theirs.
+    pub normalize_style: Option<AssetUrlStyle>,
+}
+
+impl RewriteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn normalize_style(mut self, normalize_style: AssetUrlStyle) -> Self {
+        self.normalize_style = Some(normalize_style);
+        self
+    }
+}
+
+/// Applies an old → new mapping to arbitrary Lua text and reports every replacement, with
+/// exactly the semantics apply_mapping uses on scripts in a place.
+///
+/// # Examples
+///
+/// ```rust
+/// let mapping = Mapping::from([("123".to_string(), "456".to_string())]);
+/// let (source, replacements) = rewrite_source(
+///     r#"anim.AnimationId = "rbxassetid://123""#,
+///     &mapping,
+///     &RewriteOptions::new(),
+/// );
+/// assert_eq!(source, r#"anim.AnimationId = "rbxassetid://456""#);
+/// ```
+///
+/// # Notes:
+/// Needs no place file, cookie or network, so tools like a Studio plugin backend or a git
+/// pre-commit hook can call it on their own Lua files.
+pub fn rewrite_source(
+    source: &str,
+    mapping: &Mapping,
+    options: &RewriteOptions,
+) -> (String, Vec<SourceReplacement>) {
+    script_editor::apply_mapping_to_source(source, mapping, options.normalize_style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(pairs: &[(&str, &str)]) -> Mapping {
+        pairs
+            .iter()
+            .map(|(old, new)| (old.to_string(), new.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn rewrites_every_url_style_in_place() {
+        let source = concat!(
+            "a.AnimationId = \"rbxassetid://111111\"\n",
+            "b.AnimationId = 'http://www.roblox.com/asset/?id=222222'\n",
+            "c.AnimationId = \"rbxassetid://\" .. 333333\n",
+        );
+        let mapping = mapping(&[
+            ("111111", "911111"),
+            ("222222", "922222"),
+            ("333333", "933333"),
+        ]);
+
+        let (rewritten, replacements) = rewrite_source(source, &mapping, &RewriteOptions::new());
+
+        assert_eq!(
+            rewritten,
+            concat!(
+                "a.AnimationId = \"rbxassetid://911111\"\n",
+                "b.AnimationId = 'http://www.roblox.com/asset/?id=922222'\n",
+                "c.AnimationId = \"rbxassetid://\" .. 933333\n",
+            )
+        );
+        let lines: Vec<(&str, usize)> = replacements
+            .iter()
+            .map(|replacement| (replacement.old_id.as_str(), replacement.line))
+            .collect();
+        assert_eq!(lines, vec![("111111", 1), ("222222", 2), ("333333", 3)]);
+    }
+
+    #[test]
+    fn normalizes_replaced_urls_only() {
+        let source = concat!(
+            "a = 'http://www.roblox.com/asset/?id=111111'\n",
+            "b = 'http://www.roblox.com/asset/?id=444444'\n",
+            "c = \"http://www.roblox.com/asset/?id=\" .. 333333\n",
+        );
+        let mapping = mapping(&[("111111", "911111"), ("333333", "933333")]);
+        let options = RewriteOptions::new().normalize_style(AssetUrlStyle::RbxAssetId);
+
+        let (rewritten, replacements) = rewrite_source(source, &mapping, &options);
+
+        // 444444 isn't mapped so keeps its style, and a concatenated ID has no URL of its own
+        assert_eq!(
+            rewritten,
+            concat!(
+                "a = 'rbxassetid://911111'\n",
+                "b = 'http://www.roblox.com/asset/?id=444444'\n",
+                "c = \"http://www.roblox.com/asset/?id=\" .. 933333\n",
+            )
+        );
+        assert_eq!(replacements.len(), 2);
+    }
+
+    #[test]
+    fn does_not_rewrite_ids_that_only_start_with_a_mapped_id() {
+        let source = "a = 'rbxassetid://1234567'\nb = 'rbxassetid://123456'";
+        let mapping = mapping(&[("123456", "999999")]);
+
+        let (rewritten, replacements) = rewrite_source(source, &mapping, &RewriteOptions::new());
+
+        assert_eq!(
+            rewritten,
+            "a = 'rbxassetid://1234567'\nb = 'rbxassetid://999999'"
+        );
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].line, 2);
+    }
+
+    #[test]
+    fn leaves_unmapped_ids_untouched() {
+        let source = "-- rbxassetid://111111\nlocal id = \"rbxassetid://555555\"";
+        let mapping = mapping(&[("111111", "911111")]);
+
+        let (rewritten, replacements) = rewrite_source(source, &mapping, &RewriteOptions::new());
+
+        // The mapped ID is only in a comment, and 555555 isn't mapped
+        assert_eq!(rewritten, source);
+        assert!(replacements.is_empty());
+    }
+}
@@ -1,33 +1,200 @@
 use crate::StudioParser;
-use rbx_types::Variant;
+use crate::report::{Replacement, ReplacementReport};
+use crate::script::script_parser::source_asset_references;
+use crate::script::types::{AssetUrlStyle, ScriptEdit, ScriptMatch, SourceReplacement};
+use crate::studio::props;
+use rbx_dom_weak::types::Ref;
+use regex::Regex;
 use std::collections::HashMap;
-use ustr::Ustr;
+
+/// Applies the old → new mapping to a piece of Lua source and reports every replacement.
+/// The public entry point is script::rewrite::rewrite_source, which takes RewriteOptions.
+///
+/// # Notes:
+/// Only IDs in an `rbxassetid://` or asset URL context are replaced, so numbers that merely
+/// contain an old ID (or sit in a comment) are left alone.
+pub(crate) fn apply_mapping_to_source(
+    source: &str,
+    animation_mapping: &HashMap<String, String>,
+    normalize_style: Option<AssetUrlStyle>,
+) -> (String, Vec<SourceReplacement>) {
+    let mut references = source_asset_references(source);
+    references.sort_by_key(|reference| reference.span.start);
+
+    let mut new_source = String::with_capacity(source.len());
+    let mut replacements = Vec::new();
+    let mut cursor = 0;
+
+    for reference in references {
+        let old_id = reference.asset_id.to_string();
+        let Some(new_id) = animation_mapping.get(&old_id) else {
+            continue;
+        };
+
+        // Concatenated IDs have no prefix in the same string, so only their digits change
+        let (range, replacement) = match normalize_style {
+            Some(style) if reference.url_span != reference.span => {
+                (reference.url_span, style.format(new_id))
+            }
+            _ => (reference.span, new_id.clone()),
+        };
+        if range.start < cursor {
+            continue;
+        }
+
+        new_source.push_str(&source[cursor..range.start]);
+        new_source.push_str(&replacement);
+        cursor = range.end;
+
+        replacements.push(SourceReplacement {
+            old_id,
+            new_id: new_id.clone(),
+            line: reference.line,
+        });
+    }
+
+    new_source.push_str(&source[cursor..]);
+    (new_source, replacements)
+}
+
+/// Replaces every match of `pattern` in a piece of source, expanding `$1`/`${name}` in
+/// `replacement` like Regex::replace_all does, and reports each match.
+fn replace_matches(source: &str, pattern: &Regex, replacement: &str) -> (String, Vec<ScriptMatch>) {
+    let mut new_source = String::with_capacity(source.len());
+    let mut matches = Vec::new();
+    let mut cursor = 0;
+
+    for captures in pattern.captures_iter(source) {
+        let whole = captures.get(0).unwrap();
+        let mut new = String::new();
+        captures.expand(replacement, &mut new);
+
+        new_source.push_str(&source[cursor..whole.start()]);
+        new_source.push_str(&new);
+        cursor = whole.end();
+
+        matches.push(ScriptMatch {
+            old: whole.as_str().to_string(),
+            new,
+            line: source[..whole.start()].matches('\n').count() + 1,
+        });
+    }
+
+    new_source.push_str(&source[cursor..]);
+    (new_source, matches)
+}
 
 impl StudioParser {
+    /// Replaces every match of a regex in the source of every scanned script and returns the
+    /// matches per script. `replacement` can use capture groups (`$1`, `${name}`), and `$$`
+    /// for a literal `$`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Point old require() IDs at a new module
+    /// let pattern = Regex::new(r"require\((\s*)1234567(\s*)\)")?;
+    /// let edits = parser.replace_in_scripts(&pattern, "require(${1}7654321${2})");
+    /// for edit in &edits {
+    ///     println!("{}: {} replacements", edit.path, edit.matches.len());
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// Unlike update_script_animations this works on raw text, so matches inside comments and
+    /// strings are replaced too. Scripts without a match are left untouched and not returned.
+    pub fn replace_in_scripts(&mut self, pattern: &Regex, replacement: &str) -> Vec<ScriptEdit> {
+        let mut edits = Vec::new();
+
+        for script_ref in self.get_script_refs() {
+            let path = self.instance_path(script_ref);
+            if let Some(instance) = self.dom.get_by_ref_mut(script_ref)
+                && let Some((source_key, source)) = props::source(instance)
+            {
+                let (new_source, matches) = replace_matches(source, pattern, replacement);
+                if matches.is_empty() {
+                    continue;
+                }
+
+                let value = props::source_value(instance, new_source);
+                instance.properties.insert(source_key, value);
+                edits.push(ScriptEdit {
+                    script: script_ref,
+                    path,
+                    matches,
+                });
+            }
+        }
+
+        edits
+    }
+
     /// Updates animation IDs in script source code using the provided mapping.
-    pub fn update_script_animations(&mut self, animation_mapping: &HashMap<String, String>) {
+    pub fn update_script_animations(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
+        self.update_script_animations_with_style(animation_mapping, None)
+    }
+
+    /// Updates animation IDs in script source code, optionally rewriting every replaced
+    /// reference into the given URL style (e.g. `asset/?id=` to `rbxassetid://`).
+    /// Returns every replacement made.
+    pub fn update_script_animations_with_style(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+    ) -> ReplacementReport {
         // Collect script refs first to avoid borrow checker issues
         let script_refs = self.get_script_refs();
+        let mut report = ReplacementReport::new();
 
         // Now modify each script
         for script_ref in script_refs {
-            if let Some(instance) = self.dom.get_by_ref_mut(script_ref) {
-                if let Some(Variant::String(source)) =
-                    instance.properties.get(&Ustr::from("Source"))
-                {
-                    let mut new_source = source.clone();
-
-                    // Replace animation IDs in the source code
-                    for (old_id, new_id) in animation_mapping {
-                        new_source = new_source.replace(old_id, new_id);
-                    }
-
-                    // Update the source property
-                    instance
-                        .properties
-                        .insert(Ustr::from("Source"), Variant::String(new_source));
-                }
+            report.merge(self.update_script(script_ref, animation_mapping, normalize_style));
+        }
+
+        report
+    }
+
+    /// Rewrites the source of one script with the mapping and returns its replacements.
+    pub(crate) fn update_script(
+        &mut self,
+        script_ref: Ref,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+    ) -> ReplacementReport {
+        let mut report = ReplacementReport::new();
+        let path = self.instance_path(script_ref);
+        if let Some(instance) = self.dom.get_by_ref_mut(script_ref)
+            && let Some((source_key, source)) = props::source(instance)
+        {
+            let (new_source, source_replacements) =
+                apply_mapping_to_source(source, animation_mapping, normalize_style);
+            if source_replacements.is_empty() {
+                return report;
             }
+
+            report
+                .replacements
+                .extend(
+                    source_replacements
+                        .into_iter()
+                        .map(|replacement| Replacement {
+                            file: None,
+                            instance_path: Some(path.clone()),
+                            property: props::SOURCE.to_string(),
+                            old_id: replacement.old_id,
+                            new_id: replacement.new_id,
+                            line: Some(replacement.line),
+                        }),
+                );
+
+            // Update the source property
+            let value = props::source_value(instance, new_source);
+            instance.properties.insert(source_key, value);
         }
+
+        report
     }
 }
@@ -1,59 +1,160 @@
 use crate::StudioParser;
-use rbx_dom_weak::types::Variant;
-use regex::Regex;
-use roboat::assetdelivery::AssetBatchResponse;
-use std::collections::HashSet;
-use ustr::Ustr;
+use crate::animation::model::AnimationAsset;
+use crate::asset_id;
+use crate::script::lua_lexer::{LuaTokenKind, tokenize};
+use crate::script::types::{AssetUrlStyle, ScriptAssetReference, SourceAssetRef};
+use crate::studio::props;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info, warn};
+
+/// Finds every asset ID in a piece of Lua source, with its position and URL style.
+///
+/// # Notes:
+/// Works on Luau tokens rather than raw text, so IDs in comments are ignored and IDs built
+/// by concatenation (`"rbxassetid://" .. 123456789`) are found.
+pub fn source_asset_references(source: &str) -> Vec<SourceAssetRef> {
+    let pattern = asset_id::url_pattern();
+    let prefix_pattern = asset_id::prefix_pattern();
+
+    let tokens = tokenize(source);
+    let mut references = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != LuaTokenKind::String {
+            continue;
+        }
+        let content = token.content(source);
+
+        // IDs written inside a single string literal
+        for cap in pattern.captures_iter(content) {
+            let id_match = cap.name("id").unwrap();
+            let url_match = cap.get(0).unwrap();
+            if let Ok(asset_id) = id_match.as_str().parse::<u64>() {
+                let offset = token.content_start + id_match.start();
+                let url_offset = token.content_start + url_match.start();
+                references.push(SourceAssetRef {
+                    asset_id,
+                    span: offset..offset + id_match.len(),
+                    url_span: url_offset..url_offset + url_match.len(),
+                    line: token.line + source[token.start..offset].matches('\n').count(),
+                    style: AssetUrlStyle::from_prefix(&cap["prefix"]),
+                });
+            }
+        }
+
+        // IDs concatenated onto a prefix string: "rbxassetid://" .. 123456789
+        if let Some(cap) = prefix_pattern.captures(content)
+            && let (Some(concat), Some(value)) = (tokens.get(index + 1), tokens.get(index + 2))
+            && concat.kind == LuaTokenKind::Concat
+            && matches!(value.kind, LuaTokenKind::Number | LuaTokenKind::String)
+        {
+            let digits = value.content(source);
+            if digits.len() >= 6
+                && digits.bytes().all(|b| b.is_ascii_digit())
+                && let Ok(asset_id) = digits.parse::<u64>()
+            {
+                references.push(SourceAssetRef {
+                    asset_id,
+                    span: value.content_start..value.content_end,
+                    url_span: value.content_start..value.content_end,
+                    line: value.line,
+                    style: AssetUrlStyle::from_prefix(&cap["prefix"]),
+                });
+            }
+        }
+    }
+
+    references
+}
 
 impl StudioParser {
-    /// Returns a vector of AssetBatchResponse (Animation Details from batch API) found in the script
+    /// Returns a vector of AnimationAsset (Animation Details from batch API) found in the script
     /// # Notes:
     /// Takes in a script, scans all the IDs into it then has batch_sizes of 250.
     /// It posts 250 Ids at a time to the asset batch API then filters out everything but
     /// animations.
     /// * Requires a cookie
     /// * Batch API does hang sometimes, fixed that with retries and 3 second timeout.
-    pub async fn all_animations_in_scripts(&mut self) -> anyhow::Result<Vec<AssetBatchResponse>> {
-        let script_refs = self.get_script_refs();
+    pub async fn all_animations_in_scripts(&mut self) -> anyhow::Result<Vec<AnimationAsset>> {
+        self.warn_unreadable_scripts();
+        let references = self.script_asset_references();
 
-        // This regex expression is FIND: "rbxassetid://" OR "roblox.com/asset?id=" THEN DIGITS
-        let pattern = Regex::new(r"(?:rbxassetid:\/\/|roblox\.com\/asset\/\?id=)(\d{6,})").unwrap();
+        let mut style_counts: HashMap<AssetUrlStyle, usize> = HashMap::new();
+        for reference in &references {
+            *style_counts.entry(reference.style).or_default() += 1;
+        }
+        for (style, count) in &style_counts {
+            info!("Script references using {}: {}", style.as_str(), count);
+        }
 
         // Collect and deduplicate all IDs from all scripts
-        let mut all_ids: HashSet<u64> = HashSet::new();
-        for script_ref in &script_refs {
-            if let Some(instance) = self.dom.get_by_ref(*script_ref)
-                && let Some(Variant::String(source)) =
-                    instance.properties.get(&Ustr::from("Source"))
-            {
-                let cleaned_text: String = source
-                    .trim()
-                    .to_string()
-                    .chars()
-                    .filter(|c| !c.is_control())
-                    .collect();
-                // Iterate over all matches in the source
-                for cap in pattern.captures_iter(&cleaned_text) {
-                    if let Some(id_match) = cap.get(1)
-                        && let Ok(id) = id_match.as_str().parse::<u64>()
-                    {
-                        all_ids.insert(id);
-                    }
-                }
-            }
-        }
+        let all_ids: HashSet<u64> = references.iter().map(|r| r.asset_id).collect();
+
         // Convert to Vec and fetch assets
         let mut id_list: Vec<u64> = all_ids.into_iter().collect();
         id_list.sort();
-        println!("{:?}", id_list);
-        println!("Got all animations from scripts: {}", id_list.len());
+        debug!("{:?}", id_list);
+        info!("Got all animations from scripts: {}", id_list.len());
         self.fetch_animation_assets(id_list).await
     }
 
+    /// Finds every asset reference in script sources and classifies its URL style.
+    pub fn script_asset_references(&self) -> Vec<ScriptAssetReference> {
+        let mut references = Vec::new();
+
+        for script_ref in self.get_script_refs() {
+            if let Some(instance) = self.dom.get_by_ref(script_ref)
+                && let Some((_, source)) = props::source(instance)
+            {
+                for reference in source_asset_references(source) {
+                    references.push(ScriptAssetReference {
+                        script: script_ref,
+                        asset_id: reference.asset_id,
+                        span: reference.span,
+                        line: reference.line,
+                        style: reference.style,
+                    });
+                }
+            }
+        }
+
+        references
+    }
+
+    /// Returns the scripts whose Source couldn't be read (see props::source), so they are
+    /// neither scanned nor rewritten.
+    pub fn unreadable_scripts(&self) -> Vec<rbx_dom_weak::types::Ref> {
+        self.get_script_refs()
+            .into_iter()
+            .filter(|script_ref| {
+                self.dom
+                    .get_by_ref(*script_ref)
+                    .is_some_and(props::unreadable_source)
+            })
+            .collect()
+    }
+
+    /// Logs a warning listing the scripts whose Source couldn't be read.
+    pub fn warn_unreadable_scripts(&self) {
+        let unreadable = self.unreadable_scripts();
+        if unreadable.is_empty() {
+            return;
+        }
+
+        let paths: Vec<String> = unreadable
+            .into_iter()
+            .map(|script_ref| self.instance_path(script_ref))
+            .collect();
+        warn!(
+            "Skipped {} scripts whose Source couldn't be read: {}",
+            paths.len(),
+            paths.join(", ")
+        );
+    }
+
     /// Gets references to all script instances in the DOM.
     pub fn get_script_refs(&self) -> Vec<rbx_dom_weak::types::Ref> {
-        self.dom
-            .descendants()
+        self.scanned_instances()
             .filter(|instance| {
                 matches!(
                     instance.class.as_str(),
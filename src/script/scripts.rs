@@ -0,0 +1,170 @@
+use crate::studio::props;
+use crate::{Script, ScriptType, StudioParser};
+use rbx_dom_weak::types::Ref;
+use ustr::Ustr;
+
+impl StudioParser {
+    /// Reads every script in the scanned part of the place (Script, LocalScript and
+    /// ModuleScript) into a Script that can be edited and committed back.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = StudioParser::builder().file_path("MyPlace.rbxl").build()?;
+    /// for mut script in parser.scripts() {
+    ///     if script.source().contains("wait(") {
+    ///         script.set_source(script.source().replace("wait(", "task.wait("));
+    ///         script.commit(&mut parser)?;
+    ///     }
+    /// }
+    /// parser.save_to_rbxl("MyPlace.rbxl")?;
+    /// ```
+    pub fn scripts(&self) -> Vec<Script> {
+        self.get_script_refs()
+            .into_iter()
+            .filter_map(|referent| {
+                let instance = self.dom.get_by_ref(referent)?;
+                Some(Script {
+                    referent,
+                    name: instance.name.clone(),
+                    path: self.instance_path(referent),
+                    source: props::source(instance)
+                        .map(|(_, source)| source.to_string())
+                        .unwrap_or_default(),
+                    script_type: ScriptType::from_class_name(instance.class.as_str()),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Script {
+    /// The script's instance in the DOM it was read from.
+    pub fn referent(&self) -> Ref {
+        self.referent
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the script, keeping it under the same parent.
+    pub fn set_name<S: Into<String>>(&mut self, name: S) {
+        self.name = name.into();
+        self.path = match self.parent_path() {
+            "" => self.name.clone(),
+            parent => format!("{}.{}", parent, self.name),
+        };
+    }
+
+    /// Dotted path of the script, e.g. "ServerScriptService.Combat.Attack".
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Moves the script to another dotted path. The last part is its new name and the rest
+    /// has to name an existing instance when the script is committed.
+    pub fn set_path<S: Into<String>>(&mut self, path: S) {
+        self.path = path.into();
+        self.name = self.path.rsplit('.').next().unwrap_or_default().to_string();
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn set_source<S: Into<String>>(&mut self, source: S) {
+        self.source = source.into();
+    }
+
+    pub fn script_type(&self) -> &ScriptType {
+        &self.script_type
+    }
+
+    /// Changes the script's class, e.g. a Script to a ModuleScript.
+    pub fn set_script_type(&mut self, script_type: ScriptType) {
+        self.script_type = script_type;
+    }
+
+    /// Writes the script's source, name, class and location back to the DOM it was read from.
+    /// Returns whether anything changed.
+    ///
+    /// # Notes:
+    /// Fails without changing anything when the instance no longer exists or the new parent
+    /// path doesn't resolve.
+    pub fn commit(&self, parser: &mut StudioParser) -> anyhow::Result<bool> {
+        let instance = parser
+            .dom
+            .get_by_ref(self.referent)
+            .ok_or_else(|| anyhow::anyhow!("Script '{}' no longer exists", self.path))?;
+        let current_parent = instance.parent();
+
+        let new_parent = match self.parent_path() {
+            "" => parser.dom.root_ref(),
+            parent_path => parser
+                .find_by_path(parent_path)
+                .ok_or_else(|| anyhow::anyhow!("No instance found at '{}'", parent_path))?,
+        };
+        if is_within(parser, new_parent, self.referent) {
+            anyhow::bail!("Can't move script '{}' into itself", self.path);
+        }
+
+        let instance = parser
+            .dom
+            .get_by_ref_mut(self.referent)
+            .expect("looked up above");
+        let mut changed = false;
+
+        let source_key = props::find(instance, props::SOURCE)
+            .map(|(key, _)| key)
+            .unwrap_or_else(|| Ustr::from(props::SOURCE));
+        // scripts() reads an unreadable Source as "", which mustn't overwrite the real one
+        let keep_unreadable = props::unreadable_source(instance) && self.source.is_empty();
+        if !keep_unreadable
+            && props::source(instance).map(|(_, source)| source) != Some(self.source.as_str())
+        {
+            let value = props::source_value(instance, self.source.clone());
+            instance.properties.insert(source_key, value);
+            changed = true;
+        }
+        if instance.name != self.name {
+            instance.name = self.name.clone();
+            changed = true;
+        }
+        if instance.class.as_str() != self.script_type.as_str() {
+            instance.class = Ustr::from(self.script_type.as_str());
+            changed = true;
+        }
+
+        if new_parent != current_parent {
+            parser.dom.transfer_within(self.referent, new_parent);
+            changed = true;
+        }
+
+        Ok(changed)
+    }
+
+    /// The path of the instance the script sits under, or "" at the top of the DOM.
+    fn parent_path(&self) -> &str {
+        self.path
+            .rsplit_once('.')
+            .map(|(parent, _)| parent)
+            .unwrap_or_default()
+    }
+}
+
+/// Whether `referent` is `ancestor` or one of its descendants.
+fn is_within(parser: &StudioParser, referent: Ref, ancestor: Ref) -> bool {
+    let root = parser.dom.root_ref();
+    let mut current = referent;
+    while current != root {
+        if current == ancestor {
+            return true;
+        }
+        match parser.dom.get_by_ref(current) {
+            Some(instance) => current = instance.parent(),
+            None => return false,
+        }
+    }
+    false
+}
@@ -1,4 +1,5 @@
 use crate::ScriptType;
+use std::ops::Range;
 
 /// Used to Determine what script it is.
 impl ScriptType {
@@ -20,3 +21,105 @@ impl ScriptType {
         }
     }
 }
+
+/// The URL spelling a script uses to reference an asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum AssetUrlStyle {
+    /// Modern `rbxassetid://123` style.
+    #[value(name = "rbxassetid")]
+    RbxAssetId,
+    /// Deprecated `http://www.roblox.com/asset/?id=123` style.
+    #[value(name = "asset-url")]
+    AssetUrl,
+}
+
+impl AssetUrlStyle {
+    /// Classifies the prefix captured in front of an asset ID.
+    pub fn from_prefix(prefix: &str) -> Self {
+        if prefix.to_ascii_lowercase().starts_with("rbxassetid") {
+            AssetUrlStyle::RbxAssetId
+        } else {
+            AssetUrlStyle::AssetUrl
+        }
+    }
+
+    /// Formats an asset ID using this style.
+    pub fn format(&self, asset_id: &str) -> String {
+        match self {
+            AssetUrlStyle::RbxAssetId => format!("rbxassetid://{}", asset_id),
+            AssetUrlStyle::AssetUrl => format!("http://www.roblox.com/asset/?id={}", asset_id),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            AssetUrlStyle::RbxAssetId => "rbxassetid://",
+            AssetUrlStyle::AssetUrl => "asset/?id= (deprecated)",
+        }
+    }
+}
+
+/// An asset ID found in Lua source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceAssetRef {
+    pub asset_id: u64,
+    /// Byte range of the ID's digits in the source.
+    pub span: Range<usize>,
+    /// Byte range of the prefix and ID together. Same as `span` when the ID is concatenated
+    /// onto its prefix.
+    pub url_span: Range<usize>,
+    /// 1-based line the ID is on.
+    pub line: usize,
+    pub style: AssetUrlStyle,
+}
+
+/// An asset ID replaced in Lua source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceReplacement {
+    pub old_id: String,
+    pub new_id: String,
+    /// 1-based line the ID is on.
+    pub line: usize,
+}
+
+/// A single asset reference found inside a script's source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptAssetReference {
+    pub script: rbx_dom_weak::types::Ref,
+    pub asset_id: u64,
+    pub span: Range<usize>,
+    pub line: usize,
+    pub style: AssetUrlStyle,
+}
+
+/// An asset ID found in the remote source a script's LinkedSource points at. The place only
+/// holds the link, so these can be uploaded but never rewritten in the place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedSourceReference {
+    pub script: rbx_dom_weak::types::Ref,
+    /// The script asset the LinkedSource points at.
+    pub linked_source: u64,
+    pub asset_id: u64,
+    /// 1-based line of the ID in the linked source.
+    pub line: usize,
+}
+
+/// One match replaced by StudioParser::replace_in_scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptMatch {
+    /// The text the pattern matched.
+    pub old: String,
+    /// What it was replaced with, after expanding capture groups.
+    pub new: String,
+    /// 1-based line the match starts on.
+    pub line: usize,
+}
+
+/// Every replacement StudioParser::replace_in_scripts made in one script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptEdit {
+    pub script: rbx_dom_weak::types::Ref,
+    /// Dotted path of the script, e.g. "ServerScriptService.Combat".
+    pub path: String,
+    pub matches: Vec<ScriptMatch>,
+}
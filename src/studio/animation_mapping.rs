@@ -0,0 +1,254 @@
+use crate::StudioParser;
+use crate::asset_id;
+use crate::report::{Replacement, ReplacementReport};
+use crate::script::script_editor::apply_mapping_to_source;
+use crate::script::types::AssetUrlStyle;
+use crate::studio::attributes::{rewrite_attributes, rewrite_number};
+use crate::studio::props;
+use crate::studio::workplace_editor::ApplyScope;
+use rbx_dom_weak::Instance;
+use rbx_dom_weak::types::Ref;
+use rbx_types::Variant;
+use std::collections::HashMap;
+use ustr::Ustr;
+
+/// A property of one instance that the mapping changes.
+struct PropertyChange {
+    key: Ustr,
+    value: Variant,
+    replacements: Vec<Replacement>,
+}
+
+impl StudioParser {
+    /// Applies the old → new mapping to Animation instances, scripts, value objects
+    /// (StringValue/IntValue) and attributes in a single traversal of the DOM, and
+    /// removes every entry that was applied from the mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let report = parser.apply_animation_mapping(&mut mapping);
+    /// // Whatever is left wasn't referenced anywhere in the place
+    /// for old_id in mapping.keys() {
+    ///     println!("{} is not used by this place", old_id);
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// Entries are removed once the traversal is done, so an ID referenced from several
+    /// instances is replaced everywhere. Pass a clone to reuse the mapping for other files.
+    pub fn apply_animation_mapping(
+        &mut self,
+        animation_mapping: &mut HashMap<String, String>,
+    ) -> ReplacementReport {
+        let report = self.apply_in_one_pass(animation_mapping, None, ApplyScope::All);
+        for replacement in &report.replacements {
+            animation_mapping.remove(&replacement.old_id);
+        }
+        report
+    }
+
+    /// The traversal behind apply_mapping and apply_animation_mapping.
+    pub(crate) fn apply_in_one_pass(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+        apply_scope: ApplyScope,
+    ) -> ReplacementReport {
+        let referents: Vec<Ref> = self
+            .scanned_instances()
+            .map(|instance| instance.referent())
+            .collect();
+
+        self.last_edit.clear();
+        let mut report = ReplacementReport::new();
+        for referent in referents {
+            let Some(instance) = self.dom.get_by_ref(referent) else {
+                continue;
+            };
+            let mut changes =
+                property_changes(instance, animation_mapping, normalize_style, apply_scope);
+            if changes.is_empty() {
+                continue;
+            }
+
+            // Only look up the path for the few instances that change
+            let path = self.instance_path(referent);
+            let Some(instance) = self.dom.get_by_ref_mut(referent) else {
+                continue;
+            };
+            for change in &mut changes {
+                for replacement in &mut change.replacements {
+                    replacement.instance_path = Some(path.clone());
+                }
+            }
+            let mut instance_report = ReplacementReport::new();
+            for change in changes {
+                instance.properties.insert(change.key, change.value);
+                instance_report.replacements.extend(change.replacements);
+            }
+            self.record_edit(referent, &instance_report);
+            report.merge(instance_report);
+        }
+
+        report
+    }
+}
+
+/// Works out every property of `instance` the mapping changes, without modifying it.
+fn property_changes(
+    instance: &Instance,
+    animation_mapping: &HashMap<String, String>,
+    normalize_style: Option<AssetUrlStyle>,
+    apply_scope: ApplyScope,
+) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+
+    match instance.class.as_str() {
+        "Script" | "LocalScript" | "ModuleScript" if apply_scope.includes_scripts() => {
+            if let Some((key, source)) = props::source(instance) {
+                let (new_source, source_replacements) =
+                    apply_mapping_to_source(source, animation_mapping, normalize_style);
+                if !source_replacements.is_empty() {
+                    changes.push(PropertyChange {
+                        key,
+                        value: props::source_value(instance, new_source),
+                        replacements: source_replacements
+                            .into_iter()
+                            .map(|replacement| {
+                                replacement_of(
+                                    props::SOURCE.to_string(),
+                                    replacement.old_id,
+                                    replacement.new_id,
+                                    Some(replacement.line),
+                                )
+                            })
+                            .collect(),
+                    });
+                }
+            }
+        }
+        "Animation" if apply_scope.includes_instances() => {
+            if let Some((key, raw)) = props::animation_id(instance)
+                && let Some(old_id) = asset_id::parse_any(raw).map(|id| id.to_string())
+                && let Some(new_id) = animation_mapping.get(&old_id)
+            {
+                changes.push(PropertyChange {
+                    key,
+                    value: props::animation_id_value(instance, format!("rbxassetid://{}", new_id)),
+                    replacements: vec![replacement_of(
+                        props::ANIMATION_ID.to_string(),
+                        old_id,
+                        new_id.clone(),
+                        None,
+                    )],
+                });
+            }
+        }
+        "StringValue" if apply_scope.includes_instances() => {
+            if let Some((key, Variant::String(value))) = props::find(instance, props::VALUE)
+                && let Some((new_value, replacements)) =
+                    rewrite_string(value, animation_mapping, normalize_style)
+            {
+                changes.push(PropertyChange {
+                    key,
+                    value: Variant::String(new_value),
+                    replacements: replacements
+                        .into_iter()
+                        .map(|(old_id, new_id)| {
+                            replacement_of(props::VALUE.to_string(), old_id, new_id, None)
+                        })
+                        .collect(),
+                });
+            }
+        }
+        "IntValue" if apply_scope.includes_instances() => {
+            if let Some((key, Variant::Int64(value))) = props::find(instance, props::VALUE)
+                && let Some(new_id) = animation_mapping.get(&value.to_string())
+                && let Ok(new_value) = new_id.parse::<i64>()
+            {
+                changes.push(PropertyChange {
+                    key,
+                    value: Variant::Int64(new_value),
+                    replacements: vec![replacement_of(
+                        props::VALUE.to_string(),
+                        value.to_string(),
+                        new_id.clone(),
+                        None,
+                    )],
+                });
+            }
+        }
+        "NumberValue" if apply_scope.includes_instances() => {
+            if let Some((key, Variant::Float64(value))) = props::find(instance, props::VALUE)
+                && let Some((new_value, pairs)) = rewrite_number(*value, animation_mapping)
+            {
+                changes.push(PropertyChange {
+                    key,
+                    value: new_value,
+                    replacements: pairs
+                        .into_iter()
+                        .map(|(old_id, new_id)| {
+                            replacement_of(props::VALUE.to_string(), old_id, new_id, None)
+                        })
+                        .collect(),
+                });
+            }
+        }
+        _ => {}
+    }
+
+    if apply_scope.includes_instances()
+        && let Some((key, value, replacements)) =
+            rewrite_attributes(instance, animation_mapping, normalize_style)
+    {
+        changes.push(PropertyChange {
+            key,
+            value,
+            replacements,
+        });
+    }
+
+    changes
+}
+
+/// Rewrites a string property: a bare ID is replaced whole, anything else the way script
+/// sources are. Returns None if nothing changed.
+pub(crate) fn rewrite_string(
+    value: &str,
+    animation_mapping: &HashMap<String, String>,
+    normalize_style: Option<AssetUrlStyle>,
+) -> Option<(String, Vec<(String, String)>)> {
+    let trimmed = value.trim();
+    if !trimmed.is_empty() && trimmed.bytes().all(|byte| byte.is_ascii_digit()) {
+        let new_id = animation_mapping.get(trimmed)?;
+        return Some((new_id.clone(), vec![(trimmed.to_string(), new_id.clone())]));
+    }
+
+    let (new_value, replacements) =
+        apply_mapping_to_source(value, animation_mapping, normalize_style);
+    if replacements.is_empty() {
+        return None;
+    }
+    let replacements = replacements
+        .into_iter()
+        .map(|replacement| (replacement.old_id, replacement.new_id))
+        .collect();
+    Some((new_value, replacements))
+}
+
+pub(crate) fn replacement_of(
+    property: String,
+    old_id: String,
+    new_id: String,
+    line: Option<usize>,
+) -> Replacement {
+    Replacement {
+        file: None,
+        instance_path: None,
+        property,
+        old_id,
+        new_id,
+        line,
+    }
+}
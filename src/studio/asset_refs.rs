@@ -0,0 +1,82 @@
+use crate::StudioParser;
+use crate::script::types::ScriptAssetReference;
+use rbx_dom_weak::types::Ref;
+
+/// One asset reference found while scanning a place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetRef {
+    /// The AnimationId of an Animation instance.
+    Instance { instance: Ref, asset_id: u64 },
+    /// An asset ID in a script's source.
+    Script(ScriptAssetReference),
+    /// An asset ID in one of an instance's attributes.
+    Attribute {
+        instance: Ref,
+        name: String,
+        asset_id: u64,
+    },
+    /// An asset ID in another property of an instance, e.g. a StringValue's Value or whatever
+    /// a custom RefDetector reads.
+    Property {
+        instance: Ref,
+        property: String,
+        asset_id: u64,
+    },
+}
+
+impl AssetRef {
+    pub fn asset_id(&self) -> u64 {
+        match self {
+            AssetRef::Instance { asset_id, .. }
+            | AssetRef::Attribute { asset_id, .. }
+            | AssetRef::Property { asset_id, .. } => *asset_id,
+            AssetRef::Script(reference) => reference.asset_id,
+        }
+    }
+
+    /// The Animation instance, script or attributed instance the reference is in.
+    pub fn instance(&self) -> Ref {
+        match self {
+            AssetRef::Instance { instance, .. }
+            | AssetRef::Attribute { instance, .. }
+            | AssetRef::Property { instance, .. } => *instance,
+            AssetRef::Script(reference) => reference.script,
+        }
+    }
+}
+
+impl StudioParser {
+    /// Lazily yields every asset reference the registered RefDetectors find (by default in
+    /// Animation instances, script sources, value objects and attributes), in DOM order, as
+    /// the scan reaches it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // Fetch metadata 250 IDs at a time instead of waiting for the whole place
+    /// let mut seen = HashSet::new();
+    /// let mut batch = Vec::new();
+    /// for reference in parser.iter_asset_refs() {
+    ///     if seen.insert(reference.asset_id()) {
+    ///         batch.push(reference.asset_id());
+    ///     }
+    ///     if batch.len() == 250 {
+    ///         assets.extend(parser.fetch_animation_assets(std::mem::take(&mut batch)).await?);
+    ///     }
+    /// }
+    /// assets.extend(parser.fetch_animation_assets(batch).await?);
+    /// ```
+    ///
+    /// # Notes:
+    /// Nothing is collected up front, so memory stays flat on places with hundreds of
+    /// thousands of instances. IDs are not deduplicated, and script references aren't checked
+    /// to be animations.
+    pub fn iter_asset_refs(&self) -> impl Iterator<Item = AssetRef> + '_ {
+        self.scanned_instances().flat_map(move |instance| {
+            self.detectors
+                .iter()
+                .flat_map(|detector| detector.detect(instance))
+                .collect::<Vec<_>>()
+        })
+    }
+}
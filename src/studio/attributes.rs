@@ -0,0 +1,114 @@
+use crate::report::Replacement;
+use crate::script::script_parser::source_asset_references;
+use crate::script::types::AssetUrlStyle;
+use crate::studio::animation_mapping::{replacement_of, rewrite_string};
+use crate::studio::props;
+use rbx_dom_weak::Instance;
+use rbx_types::{Attributes, Variant};
+use std::collections::HashMap;
+use ustr::Ustr;
+
+/// Number attributes below this aren't treated as asset IDs, so speeds, counts and the like
+/// are never looked up or rewritten. Every animation uploaded in the last decade is above it.
+pub const MIN_NUMERIC_ASSET_ID: f64 = 100_000.0;
+
+/// Decodes an instance's Attributes and returns each asset ID found in them, with the name of
+/// the attribute, in attribute order.
+///
+/// # Notes:
+/// String attributes count when they are a bare ID or contain asset URLs. Number attributes
+/// (stored as doubles) count when they are whole numbers of at least MIN_NUMERIC_ASSET_ID.
+pub fn attribute_asset_ids(instance: &Instance) -> Vec<(String, u64)> {
+    let Some((_, Variant::Attributes(attributes))) = props::find(instance, props::ATTRIBUTES)
+    else {
+        return Vec::new();
+    };
+
+    let mut asset_ids = Vec::new();
+    for (name, value) in attributes.iter() {
+        match value {
+            Variant::String(value) => asset_ids.extend(
+                string_asset_ids(value)
+                    .into_iter()
+                    .map(|asset_id| (name.clone(), asset_id)),
+            ),
+            Variant::Float64(value) => {
+                if let Some(asset_id) = numeric_asset_id(*value) {
+                    asset_ids.push((name.clone(), asset_id));
+                }
+            }
+            _ => {}
+        }
+    }
+    asset_ids
+}
+
+/// The asset IDs in a string property or attribute: the whole value when it is a bare ID,
+/// otherwise every asset URL in it.
+pub(crate) fn string_asset_ids(value: &str) -> Vec<u64> {
+    if let Ok(asset_id) = value.trim().parse::<u64>() {
+        return vec![asset_id];
+    }
+    source_asset_references(value)
+        .into_iter()
+        .map(|reference| reference.asset_id)
+        .collect()
+}
+
+/// Rewrites the asset IDs in an instance's Attributes and encodes them back. Returns the key
+/// of the Attributes property, the new value and the replacements, or None if nothing changed.
+pub(crate) fn rewrite_attributes(
+    instance: &Instance,
+    animation_mapping: &HashMap<String, String>,
+    normalize_style: Option<AssetUrlStyle>,
+) -> Option<(Ustr, Variant, Vec<Replacement>)> {
+    let (key, Variant::Attributes(attributes)) = props::find(instance, props::ATTRIBUTES)? else {
+        return None;
+    };
+
+    let mut new_attributes = Attributes::new();
+    let mut replacements = Vec::new();
+    for (name, value) in attributes.iter() {
+        let property = format!("{}.{}", props::ATTRIBUTES, name);
+        let rewritten = match value {
+            Variant::String(value) => rewrite_string(value, animation_mapping, normalize_style)
+                .map(|(new_value, pairs)| (Variant::String(new_value), pairs)),
+            Variant::Float64(value) => rewrite_number(*value, animation_mapping),
+            _ => None,
+        };
+
+        match rewritten {
+            Some((new_value, pairs)) => {
+                replacements.extend(pairs.into_iter().map(|(old_id, new_id)| {
+                    replacement_of(property.clone(), old_id, new_id, None)
+                }));
+                new_attributes.insert(name.clone(), new_value);
+            }
+            None => {
+                new_attributes.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    if replacements.is_empty() {
+        return None;
+    }
+    Some((key, Variant::Attributes(new_attributes), replacements))
+}
+
+/// Replaces a number attribute holding a mapped asset ID, keeping it a number.
+pub(crate) fn rewrite_number(
+    value: f64,
+    animation_mapping: &HashMap<String, String>,
+) -> Option<(Variant, Vec<(String, String)>)> {
+    let old_id = numeric_asset_id(value)?.to_string();
+    let new_id = animation_mapping.get(&old_id)?;
+    let new_value = new_id.parse::<u64>().ok()? as f64;
+    Some((Variant::Float64(new_value), vec![(old_id, new_id.clone())]))
+}
+
+pub(crate) fn numeric_asset_id(value: f64) -> Option<u64> {
+    // Doubles hold integers exactly up to 2^53, far past any asset ID
+    (value >= MIN_NUMERIC_ASSET_ID && value.fract() == 0.0 && value < 9_007_199_254_740_992.0)
+        .then_some(value as u64)
+}
@@ -0,0 +1,104 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Copies a file to `<name>.bak-<unix timestamp>` next to it and returns the backup path.
+/// When that backup already exists (two saves in the same second), `-2`, `-3`... is added
+/// instead of overwriting it.
+pub fn backup_file<P: AsRef<Path>>(path: P) -> anyhow::Result<PathBuf> {
+    let path = path.as_ref();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Cannot back up '{}': no file name", path.display()))?;
+    let mut source = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to back up '{}': {}", path.display(), e))?;
+
+    for counter in 1.. {
+        let mut backup_name = file_name.to_os_string();
+        backup_name.push(format!(".bak-{}", timestamp));
+        if counter > 1 {
+            backup_name.push(format!("-{}", counter));
+        }
+        let backup_path = path.with_file_name(backup_name);
+
+        // create_new fails instead of truncating a backup made a moment ago
+        let mut backup = match File::options()
+            .write(true)
+            .create_new(true)
+            .open(&backup_path)
+        {
+            Ok(backup) => backup,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => anyhow::bail!(
+                "Failed to back up '{}' to '{}': {}",
+                path.display(),
+                backup_path.display(),
+                e
+            ),
+        };
+        io::copy(&mut source, &mut backup).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to back up '{}' to '{}': {}",
+                path.display(),
+                backup_path.display(),
+                e
+            )
+        })?;
+        return Ok(backup_path);
+    }
+    unreachable!("Counter never runs out")
+}
+
+/// Writes `contents` to a temporary file next to `path`, then renames it over it, so an
+/// interrupted write leaves the original untouched.
+pub fn write_atomically<P: AsRef<Path>>(path: P, contents: &[u8]) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let temp_path = temp_path_for(path);
+
+    if let Err(e) = fs::write(&temp_path, contents) {
+        let _ = fs::remove_file(&temp_path);
+        anyhow::bail!("Failed to write '{}': {}", temp_path.display(), e);
+    }
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        anyhow::anyhow!(
+            "Failed to move '{}' to '{}': {}",
+            temp_path.display(),
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Temporary sibling path used while saving, e.g. `.MyPlace.rbxl.tmp-1234`.
+pub(crate) fn temp_path_for(destination: &Path) -> PathBuf {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(destination.file_name().unwrap_or_default());
+    temp_name.push(format!(".tmp-{}", std::process::id()));
+    destination.with_file_name(temp_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_in_the_same_second_do_not_overwrite_each_other() {
+        let dir = std::env::temp_dir().join(format!("xixspoof-backup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let place = dir.join("Place.rbxl");
+
+        fs::write(&place, "first").unwrap();
+        let first = backup_file(&place).unwrap();
+        fs::write(&place, "second").unwrap();
+        let second = backup_file(&place).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use crate::StudioParser;
+use crate::report::ReplacementReport;
+use crate::script::types::AssetUrlStyle;
+use crate::studio::workplace_editor::ApplyScope;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Where and how often apply_mapping_with_checkpoints saves an intermediate copy of the place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoints {
+    path: PathBuf,
+    every: usize,
+    min_interval: Duration,
+}
+
+impl Checkpoints {
+    /// Saves to `path` after every `every` applied mapping entries.
+    pub fn new<P: AsRef<Path>>(path: P, every: usize) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            every: every.max(1),
+            min_interval: Duration::ZERO,
+        }
+    }
+
+    /// Checkpoints saved next to `output`, e.g. "MyPlace.checkpoint.rbxl" for "MyPlace.rbxl".
+    pub fn next_to<P: AsRef<Path>>(output: P, every: usize) -> Self {
+        let output = output.as_ref();
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = match output.extension() {
+            Some(extension) => format!("{}.checkpoint.{}", stem, extension.to_string_lossy()),
+            None => format!("{}.checkpoint", stem),
+        };
+        Self::new(output.with_file_name(file_name), every)
+    }
+
+    /// Skips a checkpoint if the previous one was saved less than `min_interval` ago, so saving
+    /// a giant place doesn't take longer than applying the mapping. Defaults to zero.
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StudioParser {
+    /// Same as apply_mapping, but applies the mapping in chunks of `checkpoints.every` entries
+    /// and saves a copy of the place to the checkpoint path after each chunk, yielding to the
+    /// runtime in between.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let checkpoints = Checkpoints::next_to("MyPlace.rbxl", 500)
+    ///     .min_interval(Duration::from_secs(60));
+    /// let report = parser
+    ///     .apply_mapping_with_checkpoints(&mapping, None, ApplyScope::All, &checkpoints)
+    ///     .await;
+    /// parser.save_to_rbxl("MyPlace.rbxl")?;
+    /// ```
+    ///
+    /// # Notes:
+    /// A crash near the end of a long session then loses at most one chunk. Entries are
+    /// applied in old ID order. A checkpoint that fails to save is logged and skipped; the
+    /// checkpoint file is never backed up and is left for the caller to remove.
+    pub async fn apply_mapping_with_checkpoints(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+        apply_scope: ApplyScope,
+        checkpoints: &Checkpoints,
+    ) -> ReplacementReport {
+        let mut entries: Vec<(&String, &String)> = animation_mapping.iter().collect();
+        entries.sort();
+
+        let mut report = ReplacementReport::new();
+        // Each chunk is an edit pass of its own, together they are the last one
+        let mut edited = indexmap::IndexMap::new();
+        let mut applied = 0;
+        let mut last_save = Instant::now();
+        let mut chunks = entries.chunks(checkpoints.every).peekable();
+
+        while let Some(chunk) = chunks.next() {
+            let chunk_mapping: HashMap<String, String> = chunk
+                .iter()
+                .map(|(old_id, new_id)| (old_id.to_string(), new_id.to_string()))
+                .collect();
+            report.merge(self.apply_mapping(&chunk_mapping, normalize_style, apply_scope));
+            for (referent, ids) in self.last_edit.drain(..) {
+                edited.entry(referent).or_insert_with(Vec::new).extend(ids);
+            }
+            applied += chunk.len();
+
+            // The last chunk is saved by the caller
+            if chunks.peek().is_some() && last_save.elapsed() >= checkpoints.min_interval {
+                match self.write_atomically(&checkpoints.path) {
+                    Ok(()) => info!(
+                        "Checkpoint after {} of {} entries saved to {}",
+                        applied,
+                        entries.len(),
+                        checkpoints.path.display()
+                    ),
+                    Err(e) => warn!("Failed to save checkpoint: {}", e),
+                }
+                last_save = Instant::now();
+            }
+            tokio::task::yield_now().await;
+        }
+
+        self.last_edit = edited;
+        report
+    }
+}
@@ -0,0 +1,339 @@
+use crate::AnimationUploader;
+use crate::StudioParser;
+use crate::animation::config::UploaderConfig;
+use crate::animation::model::AnimationAsset;
+use crate::report::ReplacementReport;
+use crate::script::types::AssetUrlStyle;
+use crate::studio::workplace_editor::ApplyScope;
+use roboat::RoboatError;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Scans and rewrites every model file (.rbxm/.rbxmx) in a directory using one shared mapping.
+/// Mainly used for the local Studio plugins folder, so plugins can be migrated alongside places.
+/// Also used for batch mode over a directory of places (see places_from_dir).
+pub struct ContentScanner {
+    pub files: Vec<(PathBuf, StudioParser)>,
+    dir: PathBuf,
+    roblosecurity: Option<String>,
+    uploader_config: UploaderConfig,
+}
+
+impl ContentScanner {
+    /// Loads every .rbxm/.rbxmx file directly inside the directory.
+    /// Files that fail to parse are skipped with a warning.
+    pub fn from_dir<P: AsRef<Path>>(dir: P, roblosecurity: Option<String>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read directory '{}': {}", dir.display(), e))?;
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if is_model_file(&path) {
+                paths.push(path);
+            }
+        }
+
+        Ok(Self::load(dir, paths, roblosecurity))
+    }
+
+    /// Recursively loads every .rbxl/.rbxlx place in the directory, for batch mode.
+    /// Hidden directories are skipped, and files that fail to parse are skipped with a warning.
+    pub fn places_from_dir<P: AsRef<Path>>(
+        dir: P,
+        roblosecurity: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+
+        while let Some(current) = pending.pop() {
+            let entries = fs::read_dir(&current).map_err(|e| {
+                anyhow::anyhow!("Failed to read directory '{}': {}", current.display(), e)
+            })?;
+
+            for entry in entries {
+                let path = entry?.path();
+                let is_hidden = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with('.'));
+
+                if path.is_dir() {
+                    if !is_hidden {
+                        pending.push(path);
+                    }
+                } else if is_place_file(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        Ok(Self::load(dir, paths, roblosecurity))
+    }
+
+    /// Loads the given places and models, e.g. from expand_paths, to migrate them together.
+    /// Files that fail to parse are skipped with a warning.
+    ///
+    /// # Notes:
+    /// dir() is the deepest directory containing all of them, so save_all_to mirrors their
+    /// layout below it.
+    pub fn from_paths(paths: Vec<PathBuf>, roblosecurity: Option<String>) -> Self {
+        // "./" keeps relative paths inside the common directory when it is the current one
+        let paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|path| {
+                if path.is_relative() {
+                    Path::new(".").join(path)
+                } else {
+                    path
+                }
+            })
+            .collect();
+        let dir = common_dir(&paths);
+        Self::load(&dir, paths, roblosecurity)
+    }
+
+    /// Parses the files on several threads, since big places take seconds each.
+    fn load(dir: &Path, paths: Vec<PathBuf>, roblosecurity: Option<String>) -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = paths.len().div_ceil(threads).max(1);
+
+        let mut files: Vec<(PathBuf, StudioParser)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut parsed = Vec::new();
+                        for path in chunk {
+                            match StudioParser::builder().file_path(path).build() {
+                                Ok(parser) => parsed.push((path.clone(), parser)),
+                                Err(e) => warn!("Skipping file {}: {}", path.display(), e),
+                            }
+                        }
+                        parsed
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Place loading thread panicked"))
+                .collect()
+        });
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Self {
+            files,
+            dir: dir.to_path_buf(),
+            roblosecurity,
+            uploader_config: UploaderConfig::default(),
+        }
+    }
+
+    /// Retries and timeouts used when fetching animation metadata.
+    pub fn with_uploader_config(mut self, uploader_config: UploaderConfig) -> Self {
+        self.uploader_config = uploader_config;
+        self
+    }
+
+    /// The directory the files were loaded from.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the local Roblox Studio plugins folder for this OS, if it can be located.
+    pub fn default_plugins_dir() -> Option<PathBuf> {
+        if cfg!(windows) {
+            std::env::var_os("LOCALAPPDATA")
+                .map(|dir| PathBuf::from(dir).join("Roblox").join("Plugins"))
+        } else {
+            std::env::var_os("HOME").map(|dir| {
+                PathBuf::from(dir)
+                    .join("Documents")
+                    .join("Roblox")
+                    .join("Plugins")
+            })
+        }
+    }
+
+    /// Returns every asset ID referenced by Animation instances or scripts across all files.
+    pub fn asset_ids(&self) -> Vec<u64> {
+        let mut ids: HashSet<u64> = HashSet::new();
+        for (_, parser) in &self.files {
+            ids.extend(parser.animation_instance_ids());
+            ids.extend(
+                parser
+                    .script_asset_references()
+                    .into_iter()
+                    .map(|reference| reference.asset_id),
+            );
+        }
+
+        let mut ids: Vec<u64> = ids.into_iter().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Fetches animation metadata for every asset ID found in the directory.
+    /// * Requires a cookie
+    pub async fn animations(&self) -> anyhow::Result<Vec<AnimationAsset>> {
+        let cookie = self
+            .roblosecurity
+            .clone()
+            .ok_or(RoboatError::InvalidRoblosecurity)?;
+
+        AnimationUploader::with_config(cookie, self.uploader_config.clone())
+            .map_err(RoboatError::ReqwestError)?
+            .fetch_animation_assets(self.asset_ids())
+            .await
+    }
+
+    /// Applies the old → new mapping to the Animation instances and scripts of every file.
+    /// Returns the replacements made, each marked with the file it was made in.
+    pub fn apply_mapping(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+        apply_scope: ApplyScope,
+    ) -> ReplacementReport {
+        let mut report = ReplacementReport::new();
+        for (path, parser) in &mut self.files {
+            let file_report = parser.apply_mapping(animation_mapping, normalize_style, apply_scope);
+            report.merge(file_report.with_file(&path.to_string_lossy()));
+        }
+        report
+    }
+
+    /// Saves every file back to the path it was loaded from.
+    pub fn save_all(&self) -> anyhow::Result<()> {
+        for (path, parser) in &self.files {
+            parser.save_to_rbxl(path)?;
+        }
+        Ok(())
+    }
+
+    /// Saves every file into `output_dir`, mirroring its path relative to the source directory.
+    /// Returns the paths written.
+    ///
+    /// # Notes:
+    /// Refuses to write anywhere inside the source directory; use save_all to overwrite the
+    /// source files on purpose.
+    pub fn save_all_to<P: AsRef<Path>>(&self, output_dir: P) -> anyhow::Result<Vec<PathBuf>> {
+        let output_dir = output_dir.as_ref();
+        check_output_dir(&self.dir, output_dir)?;
+
+        let mut written = Vec::new();
+        for (path, parser) in &self.files {
+            let relative = path.strip_prefix(&self.dir).map_err(|_| {
+                anyhow::anyhow!(
+                    "'{}' is not inside '{}'",
+                    path.display(),
+                    self.dir.display()
+                )
+            })?;
+            let destination = output_dir.join(relative);
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            parser.save_to_rbxl(&destination)?;
+            written.push(destination);
+        }
+        Ok(written)
+    }
+}
+
+/// Expands file arguments that may be glob patterns (e.g. "places/*.rbxl") into the matching
+/// paths, sorted and deduplicated. Arguments without glob characters are kept as they are.
+///
+/// # Notes:
+/// Shells usually expand globs already; this covers quoted patterns and shells that don't,
+/// like cmd.exe. A pattern matching nothing is an error, so a typo doesn't silently migrate
+/// fewer places.
+pub fn expand_paths<S: AsRef<str>>(patterns: &[S]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        if !pattern.contains(['*', '?', '[']) {
+            paths.push(PathBuf::from(pattern));
+            continue;
+        }
+
+        let matches = glob::glob(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+        let before = paths.len();
+        for path in matches {
+            paths.push(path?);
+        }
+        if paths.len() == before {
+            anyhow::bail!("No files match '{}'", pattern);
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Creates `output_dir` and makes sure it is outside `source_dir`, so batch mode can refuse a
+/// bad --output-dir before anything is uploaded. save_all_to runs the same check.
+pub fn check_output_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    source_dir: P,
+    output_dir: Q,
+) -> anyhow::Result<()> {
+    let (source_dir, output_dir) = (source_dir.as_ref(), output_dir.as_ref());
+    fs::create_dir_all(output_dir).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to create directory '{}': {}",
+            output_dir.display(),
+            e
+        )
+    })?;
+
+    let source_root = source_dir.canonicalize()?;
+    if output_dir.canonicalize()?.starts_with(&source_root) {
+        anyhow::bail!(
+            "Output directory '{}' is inside the source directory '{}'",
+            output_dir.display(),
+            source_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// The deepest directory every path is inside, which from_paths uses as dir().
+pub fn common_dir(paths: &[PathBuf]) -> PathBuf {
+    let mut dirs = paths
+        .iter()
+        .map(|path| path.parent().unwrap_or(Path::new(".")).to_path_buf());
+    let Some(mut common) = dirs.next() else {
+        return PathBuf::from(".");
+    };
+    for dir in dirs {
+        while !dir.starts_with(&common) && common.pop() {}
+    }
+
+    if common.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        common
+    }
+}
+
+/// Returns true for place files (.rbxl/.rbxlx).
+fn is_place_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rbxl") || ext.eq_ignore_ascii_case("rbxlx"))
+}
+
+/// Returns true for model files Studio loads as plugins.
+fn is_model_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rbxm") || ext.eq_ignore_ascii_case("rbxmx"))
+}
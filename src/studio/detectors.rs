@@ -0,0 +1,167 @@
+use crate::StudioParser;
+use crate::script::script_parser::source_asset_references;
+use crate::script::types::ScriptAssetReference;
+use crate::studio::asset_refs::AssetRef;
+use crate::studio::attributes::{
+    MIN_NUMERIC_ASSET_ID, attribute_asset_ids, numeric_asset_id, string_asset_ids,
+};
+use crate::studio::dom_parser::animation_id_of;
+use crate::studio::props;
+use rbx_dom_weak::Instance;
+use rbx_types::Variant;
+
+/// Finds asset references in one instance. Every scan (iter_asset_refs, animation_refs,
+/// all_animations, diffs) runs each detector registered on the StudioParser over every scanned
+/// instance.
+///
+/// # Examples
+///
+/// ```rust
+/// // A game that keeps animation IDs in an "Anim" NumberValue under each weapon
+/// struct WeaponAnimDetector;
+///
+/// impl RefDetector for WeaponAnimDetector {
+///     fn detect(&self, instance: &Instance) -> Vec<AssetRef> {
+///         match props::find(instance, "Value") {
+///             Some((_, Variant::Float64(value))) if instance.name == "Anim" => {
+///                 vec![AssetRef::Property {
+///                     instance: instance.referent(),
+///                     property: "Value".to_string(),
+///                     asset_id: *value as u64,
+///                 }]
+///             }
+///             _ => Vec::new(),
+///         }
+///     }
+/// }
+///
+/// parser.register_detector(WeaponAnimDetector);
+/// ```
+///
+/// # Notes:
+/// Detectors only find references. apply_mapping rewrites the properties the built-in
+/// detectors read; a property only a custom detector reads has to be written back by the
+/// caller.
+pub trait RefDetector: Send + Sync {
+    fn detect(&self, instance: &Instance) -> Vec<AssetRef>;
+}
+
+/// The AnimationId of Animation instances.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnimationIdDetector;
+
+impl RefDetector for AnimationIdDetector {
+    fn detect(&self, instance: &Instance) -> Vec<AssetRef> {
+        animation_id_of(instance)
+            .map(|asset_id| AssetRef::Instance {
+                instance: instance.referent(),
+                asset_id,
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Asset IDs in the Source of Script, LocalScript and ModuleScript instances.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScriptSourceDetector;
+
+impl RefDetector for ScriptSourceDetector {
+    fn detect(&self, instance: &Instance) -> Vec<AssetRef> {
+        if !matches!(
+            instance.class.as_str(),
+            "Script" | "LocalScript" | "ModuleScript"
+        ) {
+            return Vec::new();
+        }
+        let Some((_, source)) = props::source(instance) else {
+            return Vec::new();
+        };
+
+        source_asset_references(source)
+            .into_iter()
+            .map(|reference| {
+                AssetRef::Script(ScriptAssetReference {
+                    script: instance.referent(),
+                    asset_id: reference.asset_id,
+                    span: reference.span,
+                    line: reference.line,
+                    style: reference.style,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The Value of StringValue (a bare ID or asset URLs), IntValue and NumberValue objects. Numbers
+/// count when they are at least MIN_NUMERIC_ASSET_ID, like number attributes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValueDetector;
+
+impl RefDetector for ValueDetector {
+    fn detect(&self, instance: &Instance) -> Vec<AssetRef> {
+        let asset_ids = match (instance.class.as_str(), props::find(instance, props::VALUE)) {
+            ("StringValue", Some((_, Variant::String(value)))) => string_asset_ids(value),
+            ("IntValue", Some((_, Variant::Int64(value))))
+                if *value as f64 >= MIN_NUMERIC_ASSET_ID =>
+            {
+                vec![*value as u64]
+            }
+            ("NumberValue", Some((_, Variant::Float64(value)))) => {
+                numeric_asset_id(*value).into_iter().collect()
+            }
+            _ => return Vec::new(),
+        };
+
+        asset_ids
+            .into_iter()
+            .map(|asset_id| AssetRef::Property {
+                instance: instance.referent(),
+                property: props::VALUE.to_string(),
+                asset_id,
+            })
+            .collect()
+    }
+}
+
+/// Asset IDs in an instance's attributes (see attribute_asset_ids).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AttributeDetector;
+
+impl RefDetector for AttributeDetector {
+    fn detect(&self, instance: &Instance) -> Vec<AssetRef> {
+        attribute_asset_ids(instance)
+            .into_iter()
+            .map(|(name, asset_id)| AssetRef::Attribute {
+                instance: instance.referent(),
+                name,
+                asset_id,
+            })
+            .collect()
+    }
+}
+
+/// The detectors a StudioParser starts with: Animation instances, script sources, value
+/// objects and attributes.
+pub fn default_detectors() -> Vec<Box<dyn RefDetector>> {
+    vec![
+        Box::new(AnimationIdDetector),
+        Box::new(ScriptSourceDetector),
+        Box::new(ValueDetector),
+        Box::new(AttributeDetector),
+    ]
+}
+
+impl StudioParser {
+    /// Adds a detector that runs after the ones already registered, for IDs a game keeps
+    /// somewhere the built-in detectors don't look.
+    pub fn register_detector<D: RefDetector + 'static>(&mut self, detector: D) {
+        self.detectors.push(Box::new(detector));
+    }
+
+    /// Removes every detector, built-in ones included, e.g. to scan only with custom ones.
+    /// Add back the built-in ones with `register_detector(ValueDetector)` and so on.
+    pub fn clear_detectors(&mut self) {
+        self.detectors.clear();
+    }
+}
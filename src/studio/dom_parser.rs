@@ -1,43 +1,264 @@
 use crate::StudioParser;
-use rbx_binary::from_reader;
-use rbx_types::Variant;
-use regex::Regex;
-use roboat::assetdelivery::AssetBatchResponse;
+use crate::animation::config::UploaderConfig;
+use crate::animation::credentials::{cookie_from_env, cookie_from_file};
+use crate::animation::model::AnimationAsset;
+use crate::animation::roblox_assets::is_default_animation;
+use crate::asset_id;
+use crate::studio::asset_refs::AssetRef;
+use crate::studio::detectors::default_detectors;
+use crate::studio::props;
+use crate::{AnimationRef, AnimationUploader};
+use rbx_dom_weak::types::Ref;
+use rbx_dom_weak::{Instance, WeakDom};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
-use ustr::Ustr;
+use tracing::warn;
 
 impl StudioParser {
-    /// Finds Animation instances in the workspace and returns their metadata.
+    /// Finds Animation instances in Workspace and returns each animation once, with its
+    /// metadata and the instances that reference it.
+    /// * Requires a cookie
     ///
     /// # Examples
     ///
     /// ```rust
-    /// let parser = StudioParser::builder()
+    /// let mut parser = StudioParser::builder()
     ///     .file_path("MyPlace.rbxl")
     ///     .roblosecurity("cookie")
     ///     .build()?;
     /// let animations = parser.workspace_animations().await?;
+    /// // ... upload and build the mapping ...
+    /// // Rewrites just those instances, without searching the DOM again
+    /// let report = parser.apply_mapping_to_refs(&animations, &mapping, None, ApplyScope::Instances);
     /// ```
-    pub async fn workspace_animations(&self) -> anyhow::Result<Vec<AssetBatchResponse>> {
-        let re = Regex::new(r"\d+").unwrap();
+    pub async fn workspace_animations(&self) -> anyhow::Result<Vec<AnimationRef>> {
+        self.animations_in_service("Workspace").await
+    }
+
+    /// Finds Animation instances anywhere in the file (or in the scope, if one is set) and
+    /// returns their metadata.
+    pub async fn instance_animations(&self) -> anyhow::Result<Vec<AnimationAsset>> {
+        let asset_ids = self.animation_instance_ids();
+        self.fetch_animation_assets(asset_ids).await
+    }
+
+    /// Finds animations referenced by Animation instances and by scripts, and returns each
+    /// one once with every instance and script reference to it and its metadata.
+    /// * Requires a cookie
+    ///
+    /// # Notes:
+    /// All IDs are fetched in one batch, so an ID referenced from both an instance and a script
+    /// is only looked up once. Script references to other asset types are left out. Results
+    /// are sorted by asset ID.
+    /// * Scripts with a LinkedSource have their remote code downloaded and scanned too, with a
+    ///   warning that it can't be rewritten.
+    pub async fn all_animations(&self) -> anyhow::Result<Vec<AnimationRef>> {
+        self.warn_unreadable_scripts();
+        let mut references = self.animation_refs();
+        self.add_linked_source_refs(&mut references).await;
+        self.fetch_animation_refs(&mut references).await?;
+        references.retain(|reference| reference.asset.is_some());
+        Ok(references)
+    }
+
+    /// Returns every asset ID referenced by an Animation instance, a script, a value object or an
+    /// attribute (or found by a custom RefDetector),
+    /// with what references it, sorted by asset ID. Nothing is fetched, so script references aren't
+    /// checked to be animations.
+    pub fn animation_refs(&self) -> Vec<AnimationRef> {
+        let mut references: BTreeMap<u64, AnimationRef> = BTreeMap::new();
+
+        for asset_ref in self.iter_asset_refs() {
+            if !self.include_roblox_assets && is_default_animation(asset_ref.asset_id()) {
+                continue;
+            }
+            let reference = references
+                .entry(asset_ref.asset_id())
+                .or_insert_with(|| AnimationRef::new(asset_ref.asset_id()));
+            match asset_ref {
+                AssetRef::Instance { instance, .. } => reference.instances.push(instance),
+                AssetRef::Script(script) => reference.scripts.push(script),
+                AssetRef::Attribute { instance, name, .. } => {
+                    reference.attributes.push((instance, name))
+                }
+                AssetRef::Property {
+                    instance, property, ..
+                } => reference.properties.push((instance, property)),
+            }
+        }
+
+        references.into_values().collect()
+    }
+
+    /// Adds the asset IDs found in LinkedSource scripts to the references, keeping them sorted.
+    /// A failure to fetch the linked sources is only warned about.
+    async fn add_linked_source_refs(&self, references: &mut Vec<AnimationRef>) {
+        let linked = match self.linked_source_references().await {
+            Ok(linked) => linked,
+            Err(e) => {
+                warn!("Failed to fetch linked sources: {}", e);
+                return;
+            }
+        };
+
+        let mut by_id: BTreeMap<u64, AnimationRef> = references
+            .drain(..)
+            .map(|reference| (reference.asset_id, reference))
+            .collect();
+        let mut warned = HashSet::new();
+        for reference in linked {
+            if warned.insert(reference.script) {
+                warn!(
+                    "{} uses LinkedSource {}: its asset IDs are scanned but can't be rewritten in the place",
+                    self.instance_path(reference.script),
+                    reference.linked_source
+                );
+            }
+            by_id
+                .entry(reference.asset_id)
+                .or_insert_with(|| AnimationRef::new(reference.asset_id))
+                .linked_sources
+                .push(reference);
+        }
+        references.extend(by_id.into_values());
+    }
+
+    /// Fills in the metadata of every reference that is an animation.
+    /// * Requires a cookie
+    pub async fn fetch_animation_refs(
+        &self,
+        references: &mut [AnimationRef],
+    ) -> anyhow::Result<()> {
+        let asset_ids: Vec<u64> = references
+            .iter()
+            .map(|reference| reference.asset_id)
+            .collect();
+        let assets: HashMap<u64, AnimationAsset> = self
+            .fetch_animation_assets(asset_ids)
+            .await?
+            .into_iter()
+            .map(|asset| (asset.id, asset))
+            .collect();
+
+        for reference in references {
+            reference.asset = assets.get(&reference.asset_id).cloned();
+        }
+        Ok(())
+    }
+
+    /// Finds Animation instances under a top-level service (e.g. "ServerScriptService",
+    /// "StarterPlayer", "ReplicatedStorage") and returns each animation once, with its
+    /// metadata and the instances that reference it. Animations the batch API returns
+    /// nothing for are left out.
+    ///
+    /// # Notes:
+    /// The service is looked up from the top of the DOM, so any scope set with scoped is ignored.
+    /// * Requires a cookie
+    pub async fn animations_in_service(&self, service: &str) -> anyhow::Result<Vec<AnimationRef>> {
+        let mut references = self.animation_refs_in_service(service)?;
+        self.fetch_animation_refs(&mut references).await?;
+        references.retain(|reference| reference.asset.is_some());
+        Ok(references)
+    }
+
+    /// Returns the asset IDs referenced by Animation instances under a top-level service, each
+    /// with those instances, sorted by asset ID. Nothing is fetched.
+    pub fn animation_refs_in_service(&self, service: &str) -> anyhow::Result<Vec<AnimationRef>> {
+        let service_ref = self
+            .service(service)
+            .ok_or_else(|| anyhow::anyhow!("No {} service in this file", service))?;
+
+        let mut references: BTreeMap<u64, AnimationRef> = BTreeMap::new();
+        for instance in self.dom.descendants_of(service_ref) {
+            if let Some(asset_id) = animation_id_of(instance) {
+                references
+                    .entry(asset_id)
+                    .or_insert_with(|| AnimationRef::new(asset_id))
+                    .instances
+                    .push(instance.referent());
+            }
+        }
+
+        Ok(references.into_values().collect())
+    }
+
+    /// Returns the sorted asset IDs referenced by Animation instances under a top-level service,
+    /// without fetching metadata.
+    pub fn animation_ids_in_service(&self, service: &str) -> anyhow::Result<Vec<u64>> {
+        Ok(self
+            .animation_refs_in_service(service)?
+            .into_iter()
+            .map(|reference| reference.asset_id)
+            .collect())
+    }
+
+    /// Finds a top-level service by class name, falling back to its name.
+    pub fn service(&self, service: &str) -> Option<Ref> {
+        let root = self.dom.root();
+        let children = || {
+            root.children()
+                .iter()
+                .filter_map(|child| self.dom.get_by_ref(*child))
+        };
 
+        children()
+            .find(|child| child.class == service)
+            .or_else(|| children().find(|child| child.name == service))
+            .map(|child| child.referent())
+    }
+
+    /// Returns the sorted asset IDs referenced by Animation instances, without fetching metadata.
+    pub fn animation_instance_ids(&self) -> Vec<u64> {
         let mut asset_ids: Vec<u64> = self
-            .dom
-            .descendants()
-            .filter(|instance| instance.class == "Animation")
-            .filter_map(
-                |instance| match instance.properties.get(&Ustr::from("AnimationId")) {
-                    Some(Variant::ContentId(content_id)) => re
-                        .find(content_id.as_str())
-                        .and_then(|mat| mat.as_str().parse::<u64>().ok()),
-                    _ => None,
-                },
-            )
+            .scanned_instances()
+            .filter_map(animation_id_of)
             .collect();
 
+        // A renamed property would otherwise look like a place without animations
+        let unreadable = self
+            .scanned_instances()
+            .filter(|instance| instance.class == "Animation")
+            .filter(|instance| props::animation_id(instance).is_none())
+            .count();
+        if unreadable > 0 {
+            warn!(
+                "{} Animation instances have no readable {} property",
+                unreadable,
+                props::ANIMATION_ID
+            );
+        }
+
         asset_ids.sort();
-        self.fetch_animation_assets(asset_ids).await
+        asset_ids
+    }
+
+    /// Returns the dotted path of an instance as Studio's explorer shows it, without the
+    /// DataModel root, e.g. "Workspace.NPCs.Guard.AnimSaves.Idle".
+    pub fn instance_path(&self, referent: Ref) -> String {
+        let root = self.dom.root_ref();
+        let mut names = Vec::new();
+        let mut current = referent;
+
+        while current != root
+            && let Some(instance) = self.dom.get_by_ref(current)
+        {
+            names.push(instance.name.as_str());
+            current = instance.parent();
+        }
+
+        names.reverse();
+        names.join(".")
+    }
+
+    /// Returns the paths of the Animation instances that reference an asset ID.
+    pub fn animation_instance_paths(&self, asset_id: u64) -> Vec<String> {
+        self.scanned_instances()
+            .filter(|instance| animation_id_of(instance) == Some(asset_id))
+            .map(|instance| self.instance_path(instance.referent()))
+            .collect()
     }
 
     /// Creates a builder for fluent configuration with file path and authentication.
@@ -56,10 +277,29 @@ impl StudioParser {
 }
 
 /// Builder for creating StudioParser instances with optional authentication.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct StudioParserBuilder {
-    file_path: Option<String>,
+    source: Option<PlaceSource>,
     roblosecurity: Option<String>,
+    backup_on_save: bool,
+    uploader_config: UploaderConfig,
+    include_packages: bool,
+    include_roblox_assets: bool,
+    open_cloud_api_key: Option<String>,
+}
+
+impl Default for StudioParserBuilder {
+    fn default() -> Self {
+        Self {
+            source: None,
+            roblosecurity: None,
+            backup_on_save: true,
+            uploader_config: UploaderConfig::default(),
+            include_packages: false,
+            include_roblox_assets: false,
+            open_cloud_api_key: None,
+        }
+    }
 }
 
 impl StudioParserBuilder {
@@ -68,7 +308,30 @@ impl StudioParserBuilder {
     }
 
     pub fn file_path<P: AsRef<Path>>(mut self, path: P) -> Self {
-        self.file_path = Some(path.as_ref().to_string_lossy().to_string());
+        self.source = Some(PlaceSource::Path(
+            path.as_ref().to_string_lossy().to_string(),
+        ));
+        self
+    }
+
+    /// Sets the Open Cloud API key used by publish_to_place. It needs the universe-places
+    /// write permission for the universe being published to.
+    pub fn open_cloud_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.open_cloud_api_key = Some(api_key.into());
+        self
+    }
+
+    /// Parses the place from a reader instead of a file, e.g. an HTTP upload. Binary and XML
+    /// are told apart by the file's header. Replaces any file_path or from_bytes.
+    pub fn from_reader<R: Read + Send + 'static>(mut self, reader: R) -> Self {
+        self.source = Some(PlaceSource::Reader(Box::new(reader)));
+        self
+    }
+
+    /// Parses the place from the contents of a place file already in memory. Binary and XML
+    /// are told apart by the file's header. Replaces any file_path or from_reader.
+    pub fn from_bytes(mut self, data: Vec<u8>) -> Self {
+        self.source = Some(PlaceSource::Bytes(data));
         self
     }
 
@@ -79,24 +342,210 @@ impl StudioParserBuilder {
         self
     }
 
-    /// Builds the StudioParser. File path is required.
+    /// Sets the Roblosecurity cookie from the ROBLOSECURITY environment variable, if it is set.
+    /// A cookie set before is kept when it isn't.
+    pub fn roblosecurity_from_env(mut self) -> Self {
+        if let Some(cookie) = cookie_from_env() {
+            self.roblosecurity = Some(cookie);
+        }
+        self
+    }
+
+    /// Sets the Roblosecurity cookie from a file holding only the cookie.
+    pub fn roblosecurity_file<P: AsRef<Path>>(mut self, path: P) -> anyhow::Result<Self> {
+        self.roblosecurity = Some(cookie_from_file(path)?);
+        Ok(self)
+    }
+
+    /// Sets the Roblosecurity cookie from the OS keychain (see store_cookie_in_keyring).
+    /// Errors if no cookie is stored.
+    #[cfg(feature = "keyring")]
+    pub fn roblosecurity_from_keyring(mut self) -> anyhow::Result<Self> {
+        let cookie = crate::animation::credentials::cookie_from_keyring()?
+            .ok_or_else(|| anyhow::anyhow!("No cookie is stored in the keychain"))?;
+        self.roblosecurity = Some(cookie);
+        Ok(self)
+    }
+
+    /// Whether save_to_rbxl backs up an existing file before overwriting it. Defaults to true.
+    pub fn backup_on_save(mut self, backup_on_save: bool) -> Self {
+        self.backup_on_save = backup_on_save;
+        self
+    }
+
+    /// Retries and timeouts used when fetching animation metadata.
+    pub fn uploader_config(mut self, uploader_config: UploaderConfig) -> Self {
+        self.uploader_config = uploader_config;
+        self
+    }
+
+    /// Whether scanning and rewriting go inside packages, whose edits are reverted when the
+    /// package updates. Defaults to false.
+    pub fn include_packages(mut self, include_packages: bool) -> Self {
+        self.include_packages = include_packages;
+        self
+    }
+
+    /// Whether the default Animate script animations and Roblox-owned emotes are scanned.
+    /// Defaults to false: every game can play them, so they are never worth reuploading.
+    pub fn include_roblox_assets(mut self, include_roblox_assets: bool) -> Self {
+        self.include_roblox_assets = include_roblox_assets;
+        self
+    }
+
+    /// Downloads the latest saved version of a place from Roblox and parses that, so no local
+    /// copy is needed. Replaces any file_path, from_reader or from_bytes.
+    /// * Requires a cookie, set with roblosecurity before calling this
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let parser = StudioParser::builder()
+    ///     .roblosecurity("cookie")
+    ///     .from_place_id(1234567, 7654321)
+    ///     .await?
+    ///     .build()?;
+    /// ```
+    pub async fn from_place_id(self, universe_id: u64, place_id: u64) -> anyhow::Result<Self> {
+        self.download_place(universe_id, place_id, None).await
+    }
+
+    /// Same as from_place_id, but parses an older saved version of the place (see
+    /// AnimationUploader::list_place_versions).
+    /// * Requires a cookie, set with roblosecurity before calling this
+    pub async fn from_place_version(
+        self,
+        universe_id: u64,
+        place_id: u64,
+        version: u64,
+    ) -> anyhow::Result<Self> {
+        self.download_place(universe_id, place_id, Some(version))
+            .await
+    }
+
+    async fn download_place(
+        mut self,
+        universe_id: u64,
+        place_id: u64,
+        version: Option<u64>,
+    ) -> anyhow::Result<Self> {
+        let roblosecurity = self.roblosecurity.clone().ok_or_else(|| {
+            anyhow::anyhow!("Set roblosecurity before downloading place {}", place_id)
+        })?;
+        let uploader = AnimationUploader::with_config(roblosecurity, self.uploader_config.clone())?;
+
+        let data = uploader
+            .download_place(universe_id, place_id, version)
+            .await?;
+        self.source = Some(PlaceSource::Bytes(data));
+        Ok(self)
+    }
+
+    /// Builds the StudioParser. A file path, reader or bytes is required.
     pub fn build(self) -> Result<StudioParser, anyhow::Error> {
-        let file_path = self
-            .file_path
+        let source = self
+            .source
             .ok_or_else(|| anyhow::anyhow!("File path is required"))?;
 
-        let expanded_path = shellexpand::full(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to expand path '{}': {}", file_path, e))?;
+        let dom = match source {
+            PlaceSource::Path(file_path) => {
+                let expanded_path = shellexpand::full(&file_path)
+                    .map_err(|e| anyhow::anyhow!("Failed to expand path '{}': {}", file_path, e))?;
 
-        let file = File::open(expanded_path.as_ref())
-            .map_err(|e| anyhow::anyhow!("Failed to open file '{}': {}", expanded_path, e))?;
+                let file = File::open(expanded_path.as_ref()).map_err(|e| {
+                    anyhow::anyhow!("Failed to open file '{}': {}", expanded_path, e)
+                })?;
 
-        let dom =
-            from_reader(file).map_err(|e| anyhow::anyhow!("Failed to parse .rbxl DOM: {}", e))?;
+                let format = if is_xml_path(&expanded_path) {
+                    PlaceFormat::Xml
+                } else {
+                    PlaceFormat::Binary
+                };
+                read_dom(file, format)?
+            }
+            PlaceSource::Reader(mut reader) => {
+                let mut data = Vec::new();
+                reader
+                    .read_to_end(&mut data)
+                    .map_err(|e| anyhow::anyhow!("Failed to read place: {}", e))?;
+                read_dom(data.as_slice(), PlaceFormat::detect(&data))?
+            }
+            PlaceSource::Bytes(data) => read_dom(data.as_slice(), PlaceFormat::detect(&data))?,
+        };
 
         Ok(StudioParser {
             roblosecurity: self.roblosecurity,
             dom,
+            backup_on_save: self.backup_on_save,
+            scope: Vec::new(),
+            uploader_config: self.uploader_config,
+            include_packages: self.include_packages,
+            include_roblox_assets: self.include_roblox_assets,
+            open_cloud_api_key: self.open_cloud_api_key,
+            detectors: default_detectors(),
+            last_edit: Default::default(),
         })
     }
 }
+
+/// Where StudioParserBuilder reads the place from.
+enum PlaceSource {
+    Path(String),
+    Reader(Box<dyn Read + Send>),
+    Bytes(Vec<u8>),
+}
+
+impl fmt::Debug for PlaceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            PlaceSource::Reader(_) => f.write_str("Reader(..)"),
+            PlaceSource::Bytes(data) => write!(f, "Bytes({} bytes)", data.len()),
+        }
+    }
+}
+
+/// The two place/model file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceFormat {
+    /// .rbxl/.rbxm
+    Binary,
+    /// .rbxlx/.rbxmx
+    Xml,
+}
+
+impl PlaceFormat {
+    /// Tells the formats apart by the file header: binary files start with `<roblox!`.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(b"<roblox!") {
+            PlaceFormat::Binary
+        } else {
+            PlaceFormat::Xml
+        }
+    }
+}
+
+fn read_dom<R: Read>(reader: R, format: PlaceFormat) -> anyhow::Result<WeakDom> {
+    match format {
+        PlaceFormat::Xml => rbx_xml::from_reader_default(reader)
+            .map_err(|e| anyhow::anyhow!("Failed to parse XML DOM: {}", e)),
+        PlaceFormat::Binary => rbx_binary::from_reader(reader)
+            .map_err(|e| anyhow::anyhow!("Failed to parse .rbxl DOM: {}", e)),
+    }
+}
+
+/// Parses the asset ID out of an Animation instance's AnimationId, if it is one.
+pub(crate) fn animation_id_of(instance: &Instance) -> Option<u64> {
+    if instance.class != "Animation" {
+        return None;
+    }
+
+    let (_, animation_id) = props::animation_id(instance)?;
+    asset_id::parse_any(animation_id)
+}
+
+/// Returns true for the XML place/model formats (.rbxlx, .rbxmx).
+pub(crate) fn is_xml_path(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".rbxlx") || lower.ends_with(".rbxmx")
+}
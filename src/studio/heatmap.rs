@@ -0,0 +1,155 @@
+use crate::StudioParser;
+use crate::output::csv_field;
+use crate::studio::dom_parser::animation_id_of;
+use rbx_dom_weak::InstanceBuilder;
+use rbx_dom_weak::types::Ref;
+use rbx_types::Variant;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Name of the Folder the review markers are put in.
+pub const REVIEW_FOLDER_NAME: &str = "AnimationMigrationReview";
+
+/// An instance a migration will modify: an Animation, or a script that references animations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AffectedInstance {
+    pub referent: Ref,
+    /// Dotted path, e.g. "Workspace.NPCs.Guard.AnimSaves.Idle".
+    pub path: String,
+    pub class: String,
+    /// Path of the closest Model above the instance (usually the rig or NPC), if any.
+    pub model: Option<String>,
+    pub asset_ids: Vec<u64>,
+}
+
+impl StudioParser {
+    /// Lists every Animation instance and script a migration of this file will modify,
+    /// sorted by path.
+    pub fn affected_instances(&self) -> Vec<AffectedInstance> {
+        let mut affected: BTreeMap<Ref, Vec<u64>> = BTreeMap::new();
+
+        for instance in self.scanned_instances() {
+            if let Some(asset_id) = animation_id_of(instance) {
+                affected
+                    .entry(instance.referent())
+                    .or_default()
+                    .push(asset_id);
+            }
+        }
+        for reference in self.script_asset_references() {
+            affected
+                .entry(reference.script)
+                .or_default()
+                .push(reference.asset_id);
+        }
+
+        let mut affected: Vec<AffectedInstance> = affected
+            .into_iter()
+            .filter_map(|(referent, mut asset_ids)| {
+                let instance = self.dom.get_by_ref(referent)?;
+                asset_ids.sort();
+                asset_ids.dedup();
+
+                Some(AffectedInstance {
+                    referent,
+                    path: self.instance_path(referent),
+                    class: instance.class.to_string(),
+                    model: self.closest_model(referent),
+                    asset_ids,
+                })
+            })
+            .collect();
+
+        affected.sort_by(|a, b| a.path.cmp(&b.path));
+        affected
+    }
+
+    /// Writes the affected instances to review before migrating. Returns how many there are.
+    ///
+    /// # Notes:
+    /// A `.csv` path gets one row per instance. A `.rbxl`/`.rbxlx` path gets a copy of the place
+    /// with an AnimationMigrationReview Folder in ServerStorage holding an ObjectValue per
+    /// instance, so selecting a marker in Studio jumps to the instance. Markers can't point into
+    /// a place from a separate .rbxm, so the copy is the whole place.
+    pub fn save_heatmap<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<usize> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "csv" => self.save_heatmap_csv(path),
+            "rbxl" | "rbxlx" => self.save_review_place(path),
+            _ => anyhow::bail!(
+                "Unsupported heatmap file '{}', use .csv, .rbxl or .rbxlx",
+                path.display()
+            ),
+        }
+    }
+
+    /// Writes the affected instances as CSV: path, class, model, asset IDs.
+    pub fn save_heatmap_csv<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<usize> {
+        let path = path.as_ref();
+        let affected = self.affected_instances();
+
+        let mut contents = String::from("path,class,model,asset_ids\n");
+        for instance in &affected {
+            let asset_ids: Vec<String> = instance.asset_ids.iter().map(u64::to_string).collect();
+            contents.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&instance.path),
+                csv_field(&instance.class),
+                csv_field(instance.model.as_deref().unwrap_or_default()),
+                csv_field(&asset_ids.join(" "))
+            ));
+        }
+
+        fs::write(path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write '{}': {}", path.display(), e))?;
+        Ok(affected.len())
+    }
+
+    /// Saves a copy of the place with an ObjectValue marker for every affected instance.
+    /// The markers are removed again afterwards, so the DOM is left as it was.
+    pub fn save_review_place<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<usize> {
+        let affected = self.affected_instances();
+        let parent = self
+            .service("ServerStorage")
+            .unwrap_or_else(|| self.dom.root_ref());
+
+        let mut folder = InstanceBuilder::new("Folder").with_name(REVIEW_FOLDER_NAME);
+        for instance in &affected {
+            folder = folder.with_child(
+                InstanceBuilder::new("ObjectValue")
+                    .with_name(&instance.path)
+                    .with_property("Value", Variant::Ref(instance.referent)),
+            );
+        }
+        let folder = self.dom.insert(parent, folder);
+
+        let saved = self.save_to_rbxl(path);
+        self.dom.destroy(folder);
+        saved?;
+
+        Ok(affected.len())
+    }
+
+    fn closest_model(&self, referent: Ref) -> Option<String> {
+        let mut current = self
+            .dom
+            .get_by_ref(referent)
+            .and_then(|instance| self.dom.get_by_ref(instance.parent()));
+
+        while let Some(instance) = current {
+            if instance.class == "Model" {
+                return Some(self.instance_path(instance.referent()));
+            }
+            current = self.dom.get_by_ref(instance.parent());
+        }
+
+        None
+    }
+}
@@ -0,0 +1,93 @@
+use crate::StudioParser;
+use crate::studio::dom_parser::animation_id_of;
+use bytes::Bytes;
+use rbx_dom_weak::Instance;
+use rbx_dom_weak::types::Ref;
+use std::collections::HashMap;
+use tracing::info;
+
+impl StudioParser {
+    /// Finds the KeyframeSequence data embedded in the file for Animation instances and returns
+    /// it serialized as .rbxm, keyed by the asset ID the Animation references.
+    /// These can be published directly instead of downloading the asset from the CDN.
+    ///
+    /// # Notes:
+    /// A KeyframeSequence belongs to an Animation when it is a child of it. With `use_anim_saves`,
+    /// one with the same name in the AnimSaves folder of the Animation's nearest rig (the closest
+    /// ancestor with an AnimSaves folder, the layout the Animation Editor saves rigs with) is
+    /// used as well. Names are often reused across rigs, so that match is opt-in and logged.
+    pub fn embedded_keyframe_sequences(
+        &self,
+        use_anim_saves: bool,
+    ) -> anyhow::Result<HashMap<u64, Bytes>> {
+        let mut embedded = HashMap::new();
+
+        for instance in self.scanned_instances() {
+            let Some(asset_id) = animation_id_of(instance) else {
+                continue;
+            };
+            if embedded.contains_key(&asset_id) {
+                continue;
+            }
+            let Some(sequence) = self.keyframe_sequence_for(instance, use_anim_saves) else {
+                continue;
+            };
+
+            let mut data = Vec::new();
+            rbx_binary::to_writer(&mut data, &self.dom, &[sequence]).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to serialize the KeyframeSequence of {}: {}",
+                    self.instance_path(instance.referent()),
+                    e
+                )
+            })?;
+            embedded.insert(asset_id, Bytes::from(data));
+        }
+
+        Ok(embedded)
+    }
+
+    fn keyframe_sequence_for(&self, animation: &Instance, use_anim_saves: bool) -> Option<Ref> {
+        let child = self
+            .children_of(animation)
+            .find(|child| child.class == "KeyframeSequence");
+        if let Some(child) = child {
+            return Some(child.referent());
+        }
+        if !use_anim_saves {
+            return None;
+        }
+
+        // Only the nearest rig, so an Animation never picks up another rig's saves
+        let mut current = self.dom.get_by_ref(animation.parent());
+        while let Some(ancestor) = current {
+            let mut anim_saves = self
+                .children_of(ancestor)
+                .filter(|child| child.name == "AnimSaves")
+                .peekable();
+            if anim_saves.peek().is_some() {
+                let saved = anim_saves
+                    .flat_map(|anim_saves| self.children_of(anim_saves))
+                    .find(|saved| {
+                        saved.class == "KeyframeSequence" && saved.name == animation.name
+                    })?;
+                info!(
+                    "Using {} as the KeyframeSequence of {}",
+                    self.instance_path(saved.referent()),
+                    self.instance_path(animation.referent())
+                );
+                return Some(saved.referent());
+            }
+            current = self.dom.get_by_ref(ancestor.parent());
+        }
+
+        None
+    }
+
+    fn children_of<'a>(&'a self, instance: &'a Instance) -> impl Iterator<Item = &'a Instance> {
+        instance
+            .children()
+            .iter()
+            .filter_map(|child| self.dom.get_by_ref(*child))
+    }
+}
@@ -1,2 +1,22 @@
+pub mod animation_mapping;
+pub mod asset_refs;
+pub mod attributes;
+pub mod backup;
+pub mod checkpoint;
+pub mod content_scanner;
+pub mod detectors;
 pub mod dom_parser;
+pub mod heatmap;
+pub mod keyframes;
+pub mod packages;
+pub mod place_diff;
+pub mod props;
+pub mod publish;
+pub mod query;
+pub mod rescan;
+pub mod scan_budget;
+pub mod scope;
+pub mod selector;
+pub mod stats;
+pub mod usage;
 pub mod workplace_editor;
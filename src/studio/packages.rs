@@ -0,0 +1,102 @@
+use crate::StudioParser;
+use crate::script::script_parser::source_asset_references;
+use crate::studio::dom_parser::animation_id_of;
+use crate::studio::props;
+use rbx_dom_weak::types::Ref;
+use rbx_types::Variant;
+use std::collections::HashSet;
+
+/// A package in the file (an instance with a PackageLink child) and the animations inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageAnimations {
+    /// Dotted path of the package root.
+    pub path: String,
+    /// The PackageId of the PackageLink, e.g. "rbxassetid://123".
+    pub package_id: Option<String>,
+    /// Sorted, deduplicated asset IDs referenced by Animation instances and scripts inside it.
+    pub asset_ids: Vec<u64>,
+}
+
+impl StudioParser {
+    /// Returns the roots of every package (the parents of PackageLink instances).
+    pub fn package_roots(&self) -> Vec<Ref> {
+        self.dom
+            .descendants()
+            .filter(|instance| instance.class == "PackageLink")
+            .map(|instance| instance.parent())
+            .filter(|parent| parent.is_some())
+            .collect()
+    }
+
+    /// Every instance that belongs to a package: the package roots and their descendants.
+    pub fn package_instances(&self) -> HashSet<Ref> {
+        let mut owned = HashSet::new();
+        for root in self.package_roots() {
+            owned.insert(root);
+            owned.extend(
+                self.dom
+                    .descendants_of(root)
+                    .map(|instance| instance.referent()),
+            );
+        }
+        owned
+    }
+
+    /// Lists the packages in scope that contain animation references, sorted by path.
+    ///
+    /// # Notes:
+    /// Edits inside a package are reverted the next time the package updates, so scanning and
+    /// rewriting skip package contents unless `include_packages` is set. This lists what was
+    /// left out so it can be reported, whether or not it is set.
+    pub fn package_animations(&self) -> Vec<PackageAnimations> {
+        let scoped: HashSet<Ref> = self
+            .scope_instances()
+            .map(|instance| instance.referent())
+            .collect();
+
+        let mut packages: Vec<PackageAnimations> = self
+            .package_roots()
+            .into_iter()
+            .filter(|root| scoped.contains(root))
+            .filter_map(|root| {
+                let instance = self.dom.get_by_ref(root)?;
+                let package_id = instance
+                    .children()
+                    .iter()
+                    .filter_map(|child| self.dom.get_by_ref(*child))
+                    .find(|child| child.class == "PackageLink")
+                    .and_then(|link| match props::find(link, props::PACKAGE_ID) {
+                        Some((_, Variant::ContentId(package_id))) => {
+                            Some(package_id.as_str().to_string())
+                        }
+                        _ => None,
+                    });
+
+                let mut asset_ids = Vec::new();
+                for descendant in self.dom.descendants_of(root) {
+                    if let Some(asset_id) = animation_id_of(descendant) {
+                        asset_ids.push(asset_id);
+                    } else if let Some((_, source)) = props::source(descendant) {
+                        asset_ids.extend(
+                            source_asset_references(source)
+                                .into_iter()
+                                .map(|reference| reference.asset_id),
+                        );
+                    }
+                }
+                asset_ids.sort();
+                asset_ids.dedup();
+
+                Some(PackageAnimations {
+                    path: self.instance_path(root),
+                    package_id,
+                    asset_ids,
+                })
+            })
+            .filter(|package| !package.asset_ids.is_empty())
+            .collect();
+
+        packages.sort_by(|a, b| a.path.cmp(&b.path));
+        packages
+    }
+}
@@ -0,0 +1,140 @@
+use crate::StudioParser;
+use crate::studio::asset_refs::AssetRef;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Where an asset reference sits in a place, stable across two versions of it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AssetRefLocation {
+    pub instance_path: String,
+    /// "AnimationId" for Animation instances, "Source" for scripts, "Attributes.<name>" for
+    /// attributes.
+    pub property: String,
+    /// Which reference at this path and property it is, counting from 0. Tells apart several
+    /// references in one script and instances with the same path.
+    pub index: usize,
+}
+
+impl fmt::Display for AssetRefLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.instance_path, self.property)?;
+        if self.index > 0 {
+            write!(f, " #{}", self.index + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// How one asset reference differs between two versions of a place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetRefChange {
+    Added {
+        location: AssetRefLocation,
+        asset_id: u64,
+    },
+    Removed {
+        location: AssetRefLocation,
+        asset_id: u64,
+    },
+    Changed {
+        location: AssetRefLocation,
+        old_id: u64,
+        new_id: u64,
+    },
+}
+
+impl AssetRefChange {
+    pub fn location(&self) -> &AssetRefLocation {
+        match self {
+            AssetRefChange::Added { location, .. }
+            | AssetRefChange::Removed { location, .. }
+            | AssetRefChange::Changed { location, .. } => location,
+        }
+    }
+}
+
+/// The asset references added, removed and changed between two versions of a place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaceDiff {
+    /// Sorted by location.
+    pub changes: Vec<AssetRefChange>,
+}
+
+impl PlaceDiff {
+    /// Compares the asset references of two versions of a place, e.g. before and after a
+    /// migration.
+    ///
+    /// # Notes:
+    /// References are matched by instance path, property and position, so an edited script
+    /// that gained or lost a reference shows its later references as changed.
+    pub fn between(old: &StudioParser, new: &StudioParser) -> Self {
+        let old_refs = old.asset_ref_locations();
+        let mut new_refs = new.asset_ref_locations();
+
+        let mut changes = Vec::new();
+        for (location, old_id) in old_refs {
+            match new_refs.remove(&location) {
+                Some(new_id) if new_id == old_id => {}
+                Some(new_id) => changes.push(AssetRefChange::Changed {
+                    location,
+                    old_id,
+                    new_id,
+                }),
+                None => changes.push(AssetRefChange::Removed {
+                    location,
+                    asset_id: old_id,
+                }),
+            }
+        }
+        changes.extend(
+            new_refs
+                .into_iter()
+                .map(|(location, asset_id)| AssetRefChange::Added { location, asset_id }),
+        );
+        changes.sort_by(|a, b| a.location().cmp(b.location()));
+
+        Self { changes }
+    }
+
+    /// True when every difference is an asset ID swapped in place, which is all a migration
+    /// should do.
+    pub fn only_ids_changed(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| matches!(change, AssetRefChange::Changed { .. }))
+    }
+}
+
+impl StudioParser {
+    /// Every asset reference in the place, keyed by where it is.
+    fn asset_ref_locations(&self) -> BTreeMap<AssetRefLocation, u64> {
+        let mut seen: HashMap<(String, String), usize> = HashMap::new();
+        let mut locations = BTreeMap::new();
+
+        for reference in self.iter_asset_refs() {
+            let property = match &reference {
+                AssetRef::Instance { .. } => "AnimationId".to_string(),
+                AssetRef::Script(_) => "Source".to_string(),
+                AssetRef::Attribute { name, .. } => format!("Attributes.{}", name),
+                AssetRef::Property { property, .. } => property.clone(),
+            };
+            let instance_path = self.instance_path(reference.instance());
+            let index = seen
+                .entry((instance_path.clone(), property.clone()))
+                .or_default();
+
+            locations.insert(
+                AssetRefLocation {
+                    instance_path,
+                    property,
+                    index: *index,
+                },
+                reference.asset_id(),
+            );
+            *index += 1;
+        }
+        locations
+    }
+}
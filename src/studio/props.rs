@@ -0,0 +1,112 @@
+use crate::asset_id;
+use rbx_dom_weak::Instance;
+use rbx_types::{Content, SharedString, Variant};
+use ustr::Ustr;
+
+/// The property holding an Animation's asset URL.
+pub const ANIMATION_ID: &str = "AnimationId";
+
+/// The property holding a script's code.
+pub const SOURCE: &str = "Source";
+
+/// The property holding a value object's value (StringValue, IntValue, ...).
+pub const VALUE: &str = "Value";
+
+/// The property holding an instance's custom attributes.
+pub const ATTRIBUTES: &str = "Attributes";
+
+/// The property of a legacy script pointing at a script asset that holds its code.
+pub const LINKED_SOURCE: &str = "LinkedSource";
+
+/// The property of a PackageLink holding the package's asset URL.
+pub const PACKAGE_ID: &str = "PackageId";
+
+/// Looks up a property by name, falling back to a key that only differs in casing.
+/// Returns the key it was found under, so edits can be written back to the same property.
+///
+/// # Notes:
+/// rbx-dom versions have disagreed on how some property names are spelled and interned;
+/// going through here keeps a dependency upgrade from silently making every lookup miss.
+pub fn find<'a>(instance: &'a Instance, name: &str) -> Option<(Ustr, &'a Variant)> {
+    let key = Ustr::from(name);
+    if let Some(value) = instance.properties.get(&key) {
+        return Some((key, value));
+    }
+
+    instance
+        .properties
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(key, value)| (*key, value))
+}
+
+/// The AnimationId of an Animation instance, with the key it is stored under.
+///
+/// # Notes:
+/// AnimationId is a ContentId in current files, but older files and XML round-trips can
+/// store it as a Content or a plain String. All three are read.
+pub fn animation_id(instance: &Instance) -> Option<(Ustr, &str)> {
+    match find(instance, ANIMATION_ID)? {
+        (key, Variant::ContentId(content_id)) => Some((key, content_id.as_str())),
+        (key, Variant::Content(content)) => Some((key, content.as_uri()?)),
+        (key, Variant::String(value)) => Some((key, value.as_str())),
+        _ => None,
+    }
+}
+
+/// A new AnimationId for an Animation, stored the same way as its current one so the file
+/// round-trips as it was read.
+pub fn animation_id_value(instance: &Instance, url: String) -> Variant {
+    match find(instance, ANIMATION_ID) {
+        Some((_, Variant::Content(_))) => Variant::Content(Content::from_uri(url)),
+        Some((_, Variant::String(_))) => Variant::String(url),
+        _ => Variant::ContentId(url.into()),
+    }
+}
+
+/// The Source of a script instance, with the key it is stored under.
+///
+/// # Notes:
+/// Source is a ProtectedString, which rbx_binary and rbx_xml usually read as a String, but
+/// some files store it as a BinaryString or SharedString instead. Those are read too, as long
+/// as they are valid UTF-8. Use unreadable_source to find the scripts this returns None for.
+pub fn source(instance: &Instance) -> Option<(Ustr, &str)> {
+    match find(instance, SOURCE)? {
+        (key, Variant::String(source)) => Some((key, source.as_str())),
+        (key, Variant::BinaryString(source)) => {
+            Some((key, std::str::from_utf8(source.as_ref()).ok()?))
+        }
+        (key, Variant::SharedString(source)) => {
+            Some((key, std::str::from_utf8(source.data()).ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// A new Source for a script, stored the same way as its current one so the file round-trips
+/// as it was read.
+pub fn source_value(instance: &Instance, source: String) -> Variant {
+    match find(instance, SOURCE) {
+        Some((_, Variant::BinaryString(_))) => Variant::BinaryString(source.into_bytes().into()),
+        Some((_, Variant::SharedString(_))) => {
+            Variant::SharedString(SharedString::new(source.into_bytes()))
+        }
+        _ => Variant::String(source),
+    }
+}
+
+/// True when a script has a Source property that source can't read: a type other than a
+/// string, or bytes that aren't UTF-8.
+pub fn unreadable_source(instance: &Instance) -> bool {
+    find(instance, SOURCE).is_some() && source(instance).is_none()
+}
+
+/// The asset ID a script's LinkedSource points at, or None when the script embeds its Source.
+pub fn linked_source(instance: &Instance) -> Option<u64> {
+    match find(instance, LINKED_SOURCE)? {
+        (_, Variant::ContentId(content_id)) => asset_id::parse_any(content_id.as_str()),
+        (_, Variant::Content(content)) => asset_id::parse_any(content.as_uri()?),
+        (_, Variant::String(value)) => asset_id::parse_any(value),
+        _ => None,
+    }
+}
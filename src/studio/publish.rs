@@ -0,0 +1,123 @@
+use crate::StudioParser;
+use crate::studio::dom_parser::PlaceFormat;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::info;
+
+/// How publish_to_place stores the new place version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PlaceVersionType {
+    /// Goes live in the experience right away.
+    #[default]
+    Published,
+    /// Only saved, like File > Save to Roblox; publish it later from Studio or the Creator Hub.
+    Saved,
+}
+
+impl PlaceVersionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaceVersionType::Published => "Published",
+            PlaceVersionType::Saved => "Saved",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishedVersion {
+    version_number: u64,
+}
+
+impl StudioParser {
+    /// Uploads the DOM as a new version of a place through Open Cloud and returns the new
+    /// version number.
+    /// * Requires an Open Cloud API key (see StudioParserBuilder::open_cloud_api_key)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = StudioParser::builder()
+    ///     .roblosecurity("cookie")
+    ///     .open_cloud_api_key("api key")
+    ///     .from_place_id(1234567, 7654321)
+    ///     .await?
+    ///     .build()?;
+    /// parser.apply_mapping(&mapping, None, ApplyScope::All);
+    /// let version = parser
+    ///     .publish_to_place(1234567, 7654321, PlaceVersionType::Saved)
+    ///     .await?;
+    /// ```
+    ///
+    /// # Notes:
+    /// Open Cloud doesn't accept a .ROBLOSECURITY cookie for this, only an API key.
+    /// The whole DOM is published, ignoring any scope.
+    pub async fn publish_to_place(
+        &self,
+        universe_id: u64,
+        place_id: u64,
+        version_type: PlaceVersionType,
+    ) -> anyhow::Result<u64> {
+        let api_key = self.open_cloud_api_key.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("Publishing place {} needs an Open Cloud API key", place_id)
+        })?;
+
+        let mut data = Vec::new();
+        self.save_to_writer(&mut data, PlaceFormat::Binary)?;
+
+        let url = format!(
+            "https://apis.roblox.com/universes/v1/{}/places/{}/versions?versionType={}",
+            universe_id,
+            place_id,
+            version_type.as_str()
+        );
+        let client = self.uploader_config.client_builder().build()?;
+        let response = client
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("Content-Type", "application/octet-stream")
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to publish place {}: {}", place_id, e))?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => anyhow::bail!(
+                "The API key can't publish place {}: it needs the universe-places write permission for universe {}",
+                place_id,
+                universe_id
+            ),
+            StatusCode::NOT_FOUND => anyhow::bail!(
+                "Place {} doesn't exist in universe {}",
+                place_id,
+                universe_id
+            ),
+            status if !status.is_success() => {
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!(
+                    "Failed to publish place {}: HTTP {} {}",
+                    place_id,
+                    status,
+                    body
+                )
+            }
+            _ => {}
+        }
+
+        let body = response.text().await?;
+        let version: PublishedVersion = serde_json::from_str(&body).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse publish response of place {}: {}",
+                place_id,
+                e
+            )
+        })?;
+        info!(
+            "{} version {} of place {}",
+            version_type.as_str(),
+            version.version_number,
+            place_id
+        );
+        Ok(version.version_number)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::StudioParser;
+use crate::studio::props;
+use rbx_dom_weak::Instance;
+use rbx_types::Variant;
+use ustr::Ustr;
+
+impl StudioParser {
+    /// The instance at a dotted path from the top of the DOM, e.g.
+    /// "ReplicatedStorage.Animations.Wave". See find_by_path for its referent instead.
+    pub fn find_first_child(&self, path: &str) -> Option<&Instance> {
+        self.dom.get_by_ref(self.find_by_path(path)?)
+    }
+
+    /// Every instance of a class (e.g. "Sound", "RemoteEvent") in the place, or in the scope if
+    /// one is set, in DOM order. Package contents are included.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// for sound in parser.descendants_of_class("Sound") {
+    ///     println!("{}", parser.instance_path(sound.referent()));
+    /// }
+    /// ```
+    pub fn descendants_of_class(&self, class: &str) -> Vec<&Instance> {
+        self.scope_instances()
+            .filter(|instance| instance.class == class)
+            .collect()
+    }
+
+    /// A property of the instance at `path`, e.g. `get_property("Workspace.Music", "SoundId")`.
+    /// Property names are matched like every other lookup, falling back to a different casing.
+    pub fn get_property(&self, path: &str, name: &str) -> Option<&Variant> {
+        let instance = self.find_first_child(path)?;
+        props::find(instance, name).map(|(_, value)| value)
+    }
+
+    /// Sets a property of the instance at `path` and returns its previous value. An existing
+    /// property keeps the key it is stored under, so its casing doesn't change.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// parser.set_property(
+    ///     "Workspace.Music",
+    ///     "SoundId",
+    ///     Variant::ContentId("rbxassetid://1234567".into()),
+    /// )?;
+    /// parser.save_to_rbxl("MyPlace.rbxl")?;
+    /// ```
+    ///
+    /// # Notes:
+    /// The value is written as given, so it has to be the type the property expects (e.g.
+    /// ContentId for SoundId, String for a StringValue's Value), or Studio will drop it when
+    /// the file is opened.
+    pub fn set_property<V: Into<Variant>>(
+        &mut self,
+        path: &str,
+        name: &str,
+        value: V,
+    ) -> anyhow::Result<Option<Variant>> {
+        let referent = self
+            .find_by_path(path)
+            .ok_or_else(|| anyhow::anyhow!("No instance found at '{}'", path))?;
+        let instance = self
+            .dom
+            .get_by_ref_mut(referent)
+            .ok_or_else(|| anyhow::anyhow!("No instance found at '{}'", path))?;
+
+        let key = props::find(instance, name)
+            .map(|(key, _)| key)
+            .unwrap_or_else(|| Ustr::from(name));
+        Ok(instance.properties.insert(key, value.into()))
+    }
+}
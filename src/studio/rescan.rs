@@ -0,0 +1,124 @@
+use crate::StudioParser;
+use crate::report::ReplacementReport;
+use crate::studio::props;
+use rbx_dom_weak::types::Ref;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Something rescan_changed found wrong with an edited instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RescanIssue {
+    /// Dotted path of the instance, or its referent if it was removed.
+    pub instance_path: String,
+    pub problem: String,
+}
+
+impl fmt::Display for RescanIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.instance_path, self.problem)
+    }
+}
+
+/// The verdict of rescan_changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RescanReport {
+    /// Number of edited instances and scripts that were scanned again.
+    pub checked: usize,
+    pub issues: Vec<RescanIssue>,
+}
+
+impl RescanReport {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl StudioParser {
+    /// Scans only the instances and scripts the last edit pass (apply_mapping,
+    /// apply_animation_mapping, apply_mapping_to_refs...) changed, and checks each can still
+    /// be read and now references every new ID it was given instead of the old one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// parser.apply_animation_mapping(&mut mapping);
+    /// let rescan = parser.rescan_changed();
+    /// if !rescan.passed() {
+    ///     for issue in &rescan.issues {
+    ///         eprintln!("{}", issue);
+    ///     }
+    ///     anyhow::bail!("Not saving, {} edits didn't check out", rescan.issues.len());
+    /// }
+    /// parser.save_to_rbxl("MyPlace.rbxl")?;
+    /// ```
+    ///
+    /// # Notes:
+    /// A cheap check before saving: the rest of the DOM isn't scanned again. IDs are looked
+    /// for with the registered RefDetectors, the same way scans find them.
+    pub fn rescan_changed(&self) -> RescanReport {
+        let mut report = RescanReport::default();
+
+        for (referent, ids) in &self.last_edit {
+            report.checked += 1;
+            let Some(instance) = self.dom.get_by_ref(*referent) else {
+                report.issues.push(RescanIssue {
+                    instance_path: format!("{:?}", referent),
+                    problem: "the instance was removed".to_string(),
+                });
+                continue;
+            };
+            let path = self.instance_path(*referent);
+
+            let is_script = matches!(
+                instance.class.as_str(),
+                "Script" | "LocalScript" | "ModuleScript"
+            );
+            if is_script && props::source(instance).is_none() {
+                report.issues.push(RescanIssue {
+                    instance_path: path,
+                    problem: "its Source can no longer be read".to_string(),
+                });
+                continue;
+            }
+
+            let found: HashSet<String> = self
+                .detectors
+                .iter()
+                .flat_map(|detector| detector.detect(instance))
+                .map(|reference| reference.asset_id().to_string())
+                .collect();
+            let new_ids: HashSet<&String> = ids.iter().map(|(_, new_id)| new_id).collect();
+            for (old_id, new_id) in ids {
+                if !found.contains(new_id) {
+                    report.issues.push(RescanIssue {
+                        instance_path: path.clone(),
+                        problem: format!("new ID {} (for {}) isn't found", new_id, old_id),
+                    });
+                } else if found.contains(old_id) && !new_ids.contains(old_id) {
+                    report.issues.push(RescanIssue {
+                        instance_path: path.clone(),
+                        problem: format!("old ID {} is still referenced", old_id),
+                    });
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Records that `report`'s replacements were made in `referent` during the current edit
+    /// pass.
+    pub(crate) fn record_edit(&mut self, referent: Ref, report: &ReplacementReport) {
+        if report.replacements.is_empty() {
+            return;
+        }
+
+        let ids = self.last_edit.entry(referent).or_default();
+        for replacement in &report.replacements {
+            let pair = (replacement.old_id.clone(), replacement.new_id.clone());
+            if !ids.contains(&pair) {
+                ids.push(pair);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::StudioParser;
+use crate::script::script_parser::source_asset_references;
+use crate::studio::dom_parser::animation_id_of;
+use crate::studio::props;
+use std::collections::HashSet;
+use tokio::time::{Duration, Instant};
+
+/// Progress reported by scan_with_budget after every time slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub instances_scanned: usize,
+    pub asset_ids_found: usize,
+    pub finished: bool,
+}
+
+impl StudioParser {
+    /// Scans Animation instances and script sources for asset IDs, yielding to the runtime
+    /// whenever `budget` has been spent so hosts sharing the runtime (GUIs, servers) stay
+    /// responsive on giant DOMs.
+    ///
+    /// # Notes:
+    /// `on_progress` is called after every time slice and once more when the scan finishes.
+    /// Returns the sorted, deduplicated asset IDs without fetching any metadata.
+    pub async fn scan_with_budget<F>(&self, budget: Duration, mut on_progress: F) -> Vec<u64>
+    where
+        F: FnMut(ScanProgress),
+    {
+        let mut asset_ids: HashSet<u64> = HashSet::new();
+        let mut instances_scanned = 0;
+        let mut slice_start = Instant::now();
+
+        for instance in self.scanned_instances() {
+            instances_scanned += 1;
+
+            if let Some(asset_id) = animation_id_of(instance) {
+                asset_ids.insert(asset_id);
+            } else if matches!(
+                instance.class.as_str(),
+                "Script" | "LocalScript" | "ModuleScript"
+            ) && let Some((_, source)) = props::source(instance)
+            {
+                asset_ids.extend(
+                    source_asset_references(source)
+                        .into_iter()
+                        .map(|reference| reference.asset_id),
+                );
+            }
+
+            if slice_start.elapsed() >= budget {
+                on_progress(ScanProgress {
+                    instances_scanned,
+                    asset_ids_found: asset_ids.len(),
+                    finished: false,
+                });
+                tokio::task::yield_now().await;
+                slice_start = Instant::now();
+            }
+        }
+
+        on_progress(ScanProgress {
+            instances_scanned,
+            asset_ids_found: asset_ids.len(),
+            finished: true,
+        });
+
+        let mut asset_ids: Vec<u64> = asset_ids.into_iter().collect();
+        asset_ids.sort();
+        asset_ids
+    }
+}
@@ -0,0 +1,118 @@
+use crate::StudioParser;
+use crate::studio::selector::Selector;
+use rbx_dom_weak::Instance;
+use rbx_dom_weak::types::Ref;
+
+impl StudioParser {
+    /// Limits scanning and rewriting to the instance at `path` (e.g. "Workspace.NPCs") and
+    /// its descendants. Anything that isn't a plain path is read as a Selector, and every
+    /// instance it matches becomes part of the scope.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let parser = StudioParser::builder()
+    ///     .file_path("MyPlace.rbxl")
+    ///     .build()?
+    ///     .scoped("ReplicatedStorage.AnimationsFolder")?;
+    /// // Every NPC model in Workspace, at any depth
+    /// let parser = parser.scoped("Workspace//Model[Name=~\"^NPC\"]")?;
+    /// ```
+    pub fn scoped(mut self, path: &str) -> anyhow::Result<Self> {
+        self.set_scope(path)?;
+        Ok(self)
+    }
+
+    /// Same as scoped, for a parser that is already borrowed.
+    pub fn set_scope(&mut self, path: &str) -> anyhow::Result<()> {
+        if let Some(scope) = self.find_by_path(path) {
+            self.scope = vec![scope];
+            return Ok(());
+        }
+
+        let selector = Selector::parse(path)?;
+        let scope = selector.select_from(&self.dom, self.dom.root_ref());
+        if scope.is_empty() {
+            anyhow::bail!("No instance found at '{}'", path);
+        }
+        self.scope = scope;
+        Ok(())
+    }
+
+    /// Finds an instance by its dotted path from the top of the DOM, the same format
+    /// instance_path returns. Returns None if any part of the path is missing.
+    pub fn find_by_path(&self, path: &str) -> Option<Ref> {
+        let mut current = self.dom.root_ref();
+
+        for name in path.split('.').filter(|name| !name.is_empty()) {
+            let instance = self.dom.get_by_ref(current)?;
+            current = instance.children().iter().copied().find(|child| {
+                self.dom
+                    .get_by_ref(*child)
+                    .is_some_and(|child| child.name == name)
+            })?;
+        }
+
+        Some(current)
+    }
+
+    /// Every instance scanning and rewriting should look at: the scope's subtree if one is
+    /// set, otherwise the whole DOM, without package contents unless `include_packages` is set.
+    pub(crate) fn scanned_instances(&self) -> Box<dyn Iterator<Item = &Instance> + '_> {
+        if self.include_packages {
+            return self.scope_instances();
+        }
+
+        let packaged = self.package_instances();
+        if packaged.is_empty() {
+            return self.scope_instances();
+        }
+        Box::new(
+            self.scope_instances()
+                .filter(move |instance| !packaged.contains(&instance.referent())),
+        )
+    }
+
+    /// The scope's subtrees if one is set, otherwise the whole DOM. A subtree inside another
+    /// one is only visited once.
+    pub(crate) fn scope_instances(&self) -> Box<dyn Iterator<Item = &Instance> + '_> {
+        if self.scope.is_empty() {
+            return Box::new(self.dom.descendants());
+        }
+
+        let roots: Vec<Ref> = self
+            .scope
+            .iter()
+            .copied()
+            .filter(|root| {
+                !self
+                    .scope
+                    .iter()
+                    .any(|other| other != root && self.is_descendant_of(*root, *other))
+            })
+            .collect();
+        Box::new(
+            roots
+                .into_iter()
+                .flat_map(move |root| self.dom.descendants_of(root)),
+        )
+    }
+
+    /// True when `referent` is somewhere below `ancestor`.
+    fn is_descendant_of(&self, referent: Ref, ancestor: Ref) -> bool {
+        let mut current = self
+            .dom
+            .get_by_ref(referent)
+            .map(|instance| instance.parent());
+        while let Some(parent) = current.filter(|parent| parent.is_some()) {
+            if parent == ancestor {
+                return true;
+            }
+            current = self
+                .dom
+                .get_by_ref(parent)
+                .map(|instance| instance.parent());
+        }
+        false
+    }
+}
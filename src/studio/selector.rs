@@ -0,0 +1,490 @@
+use crate::StudioParser;
+use crate::studio::props;
+use rbx_dom_weak::Instance;
+use rbx_dom_weak::types::Ref;
+use rbx_types::Variant;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed instance selector, a small XPath-like language over the DOM.
+///
+/// # Examples
+///
+/// ```rust
+/// // Every Animation anywhere under Workspace whose AnimationId uses rbxassetid://
+/// let selector: Selector = r#"Workspace//Animation[AnimationId=~"rbxassetid"]"#.parse()?;
+/// // Every Script one level below each child of ServerScriptService
+/// let selector: Selector = "game.ServerScriptService.*.Script".parse()?;
+/// // Anything named "Wave" with an AnimationId, at any depth
+/// let selector: Selector = "//Wave[AnimationId]".parse()?;
+/// let matches = parser.select(&selector);
+/// ```
+///
+/// # Notes:
+/// * `.` and `/` step to the children of the current instances, `//` to all their
+///   descendants. A leading `game` is the top of the DOM and can be left out.
+/// * A step matches instances whose Name or ClassName equals it, `*` matches any, and a
+///   `"quoted name"` matches names with dots, slashes or spaces in them.
+/// * Filters in brackets check a property (Name and ClassName included): `[Prop]` exists,
+///   `[Prop="value"]` equals and `[Prop=~"regex"]` matches. Values can be left unquoted when
+///   they have no `]` in them.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    source: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    descendants: bool,
+    /// None for `*`.
+    name: Option<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Exists(String),
+    Equals(String, String),
+    Matches(String, Regex),
+}
+
+impl Selector {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let mut parser = SelectorParser {
+            source,
+            chars: source.chars().peekable(),
+        };
+        let steps = parser.steps()?;
+        if steps.is_empty() {
+            anyhow::bail!("Empty selector");
+        }
+        Ok(Self {
+            source: source.to_string(),
+            steps,
+        })
+    }
+
+    /// The referents of every instance the selector matches below `root`, in DOM order of
+    /// the first step that reached them, each once.
+    pub fn select_from(&self, dom: &rbx_dom_weak::WeakDom, root: Ref) -> Vec<Ref> {
+        let mut current = vec![root];
+
+        for step in &self.steps {
+            let mut seen = HashSet::new();
+            let mut next = Vec::new();
+            for referent in current {
+                let candidates: Vec<&Instance> = if step.descendants {
+                    dom.descendants_of(referent)
+                        .filter(|instance| instance.referent() != referent)
+                        .collect()
+                } else {
+                    dom.get_by_ref(referent)
+                        .into_iter()
+                        .flat_map(|instance| instance.children())
+                        .filter_map(|child| dom.get_by_ref(*child))
+                        .collect()
+                };
+                for instance in candidates {
+                    if step.matches(instance) && seen.insert(instance.referent()) {
+                        next.push(instance.referent());
+                    }
+                }
+            }
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Selector::parse(source)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Step {
+    fn matches(&self, instance: &Instance) -> bool {
+        let name_matches = self
+            .name
+            .as_ref()
+            .is_none_or(|name| instance.name == *name || instance.class == name.as_str());
+        name_matches && self.filters.iter().all(|filter| filter.matches(instance))
+    }
+}
+
+impl Filter {
+    fn matches(&self, instance: &Instance) -> bool {
+        match self {
+            Filter::Exists(property) => property_text(instance, property).is_some(),
+            Filter::Equals(property, value) => {
+                property_text(instance, property).is_some_and(|text| text == *value)
+            }
+            Filter::Matches(property, pattern) => {
+                property_text(instance, property).is_some_and(|text| pattern.is_match(&text))
+            }
+        }
+    }
+}
+
+/// A property as text for filters. Name and ClassName are the instance's own fields.
+fn property_text(instance: &Instance, property: &str) -> Option<String> {
+    match property {
+        "Name" => return Some(instance.name.clone()),
+        "ClassName" => return Some(instance.class.to_string()),
+        _ => {}
+    }
+
+    let text = match props::find(instance, property)?.1 {
+        Variant::String(value) => value.clone(),
+        Variant::ContentId(content_id) => content_id.as_str().to_string(),
+        Variant::Content(content) => content.as_uri()?.to_string(),
+        Variant::Int32(value) => value.to_string(),
+        Variant::Int64(value) => value.to_string(),
+        Variant::Float32(value) => value.to_string(),
+        Variant::Float64(value) => value.to_string(),
+        Variant::Bool(value) => value.to_string(),
+        _ => return None,
+    };
+    Some(text)
+}
+
+struct SelectorParser<'a> {
+    source: &'a str,
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl SelectorParser<'_> {
+    fn steps(&mut self) -> anyhow::Result<Vec<Step>> {
+        let mut steps = Vec::new();
+        let mut descendants = self.separator().unwrap_or(false);
+
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek().is_none() {
+                if !steps.is_empty() || descendants {
+                    anyhow::bail!("Selector '{}' ends with a separator", self.source);
+                }
+                break;
+            }
+
+            let name = self.name()?;
+            let filters = self.filters()?;
+            // `game` stands for the top of the DOM, which every selector starts from
+            let is_game = steps.is_empty()
+                && !descendants
+                && filters.is_empty()
+                && name.as_deref() == Some("game");
+            if !is_game {
+                steps.push(Step {
+                    descendants,
+                    name,
+                    filters,
+                });
+            }
+
+            self.skip_whitespace();
+            match self.separator() {
+                Some(next) => descendants = next,
+                None if self.chars.peek().is_none() => break,
+                None => anyhow::bail!(
+                    "Unexpected '{}' in selector '{}'",
+                    self.chars.peek().copied().unwrap_or_default(),
+                    self.source
+                ),
+            }
+        }
+
+        Ok(steps)
+    }
+
+    /// Some(true) for `//`, Some(false) for `.` or `/`, None when there is no separator.
+    fn separator(&mut self) -> Option<bool> {
+        match self.chars.peek() {
+            Some('.') => {
+                self.chars.next();
+                Some(false)
+            }
+            Some('/') => {
+                self.chars.next();
+                if let Some('/') = self.chars.peek() {
+                    self.chars.next();
+                    return Some(true);
+                }
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+
+    fn name(&mut self) -> anyhow::Result<Option<String>> {
+        match self.chars.peek() {
+            Some('*') => {
+                self.chars.next();
+                Ok(None)
+            }
+            Some('"') => Ok(Some(self.quoted()?)),
+            _ => {
+                let name = self.take_while(|c| !matches!(c, '.' | '/' | '[' | ']' | '"'));
+                let name = name.trim();
+                if name.is_empty() {
+                    anyhow::bail!("Missing instance name in selector '{}'", self.source);
+                }
+                Ok(Some(name.to_string()))
+            }
+        }
+    }
+
+    fn filters(&mut self) -> anyhow::Result<Vec<Filter>> {
+        let mut filters = Vec::new();
+        while let Some('[') = self.chars.peek() {
+            self.chars.next();
+            self.skip_whitespace();
+            let property = self
+                .take_while(|c| !matches!(c, '=' | ']'))
+                .trim()
+                .to_string();
+            if property.is_empty() {
+                anyhow::bail!("Missing property name in selector '{}'", self.source);
+            }
+
+            let filter = match self.chars.next() {
+                Some(']') => Filter::Exists(property),
+                Some('=') => {
+                    let is_regex = matches!(self.chars.peek(), Some('~'));
+                    if is_regex {
+                        self.chars.next();
+                    }
+                    let value = self.value()?;
+                    self.skip_whitespace();
+                    if !matches!(self.chars.next(), Some(']')) {
+                        anyhow::bail!("Missing ']' in selector '{}'", self.source);
+                    }
+                    if is_regex {
+                        let pattern = Regex::new(&value).map_err(|e| {
+                            anyhow::anyhow!("Invalid pattern '{}' in selector: {}", value, e)
+                        })?;
+                        Filter::Matches(property, pattern)
+                    } else {
+                        Filter::Equals(property, value)
+                    }
+                }
+                _ => anyhow::bail!("Missing ']' in selector '{}'", self.source),
+            };
+            filters.push(filter);
+        }
+        Ok(filters)
+    }
+
+    fn value(&mut self) -> anyhow::Result<String> {
+        self.skip_whitespace();
+        if let Some('"') = self.chars.peek() {
+            return self.quoted();
+        }
+        Ok(self.take_while(|c| c != ']').trim().to_string())
+    }
+
+    /// A double-quoted string, with `\"` and `\\` escapes.
+    fn quoted(&mut self) -> anyhow::Result<String> {
+        self.chars.next();
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                Some(c) => value.push(c),
+                None => break,
+            }
+        }
+        anyhow::bail!("Unterminated string in selector '{}'", self.source)
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !keep(*c) {
+                break;
+            }
+            taken.push(*c);
+            self.chars.next();
+        }
+        taken
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+}
+
+impl StudioParser {
+    /// Every instance a selector matches, searched from the top of the DOM.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let selector = "ReplicatedStorage//Animation[Name=~\"^Attack\"]".parse()?;
+    /// for animation in parser.select(&selector) {
+    ///     println!("{}", parser.instance_path(animation.referent()));
+    /// }
+    /// ```
+    pub fn select(&self, selector: &Selector) -> Vec<&Instance> {
+        selector
+            .select_from(&self.dom, self.dom.root_ref())
+            .into_iter()
+            .filter_map(|referent| self.dom.get_by_ref(referent))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rbx_dom_weak::{InstanceBuilder, WeakDom};
+    use rbx_types::ContentId;
+
+    fn animation(name: &str, id: &str) -> InstanceBuilder {
+        InstanceBuilder::new("Animation")
+            .with_name(name)
+            .with_property("AnimationId", ContentId::from(id))
+    }
+
+    /// DataModel > Workspace > NPC > (Walk, "Run.Fast"), and
+    /// DataModel > ReplicatedStorage > Anims > Idle, which has no AnimationId.
+    fn dom() -> WeakDom {
+        WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(
+                    InstanceBuilder::new("Workspace").with_child(
+                        InstanceBuilder::new("Model")
+                            .with_name("NPC")
+                            .with_child(animation("Walk", "rbxassetid://1"))
+                            .with_child(animation("Run.Fast", "http://www.roblox.com/asset/?id=2")),
+                    ),
+                )
+                .with_child(
+                    InstanceBuilder::new("ReplicatedStorage").with_child(
+                        InstanceBuilder::new("Folder")
+                            .with_name("Anims")
+                            .with_child(InstanceBuilder::new("Animation").with_name("Idle")),
+                    ),
+                ),
+        )
+    }
+
+    fn select(dom: &WeakDom, source: &str) -> Vec<String> {
+        Selector::parse(source)
+            .unwrap()
+            .select_from(dom, dom.root_ref())
+            .into_iter()
+            .map(|referent| dom.get_by_ref(referent).unwrap().name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn leading_game_is_the_top_of_the_dom() {
+        let with_game = Selector::parse("game.Workspace.NPC").unwrap();
+        let without = Selector::parse("Workspace.NPC").unwrap();
+
+        assert_eq!(with_game.steps.len(), 2);
+        assert_eq!(without.steps.len(), 2);
+        assert_eq!(with_game.steps[0].name.as_deref(), Some("Workspace"));
+
+        let dom = dom();
+        assert_eq!(select(&dom, "game.Workspace.NPC"), vec!["NPC"]);
+        assert_eq!(select(&dom, "game/Workspace/NPC"), vec!["NPC"]);
+    }
+
+    #[test]
+    fn double_slash_steps_to_descendants() {
+        let selector = Selector::parse("Workspace//Animation").unwrap();
+        assert!(!selector.steps[0].descendants);
+        assert!(selector.steps[1].descendants);
+
+        let dom = dom();
+        assert_eq!(
+            select(&dom, "Workspace//Animation"),
+            vec!["Walk", "Run.Fast"]
+        );
+        assert_eq!(select(&dom, "Workspace.Animation"), Vec::<String>::new());
+        assert_eq!(select(&dom, "//Animation").len(), 3);
+    }
+
+    #[test]
+    fn quoted_names_keep_separators() {
+        let selector = Selector::parse(r#"//"Run.Fast""#).unwrap();
+        assert_eq!(selector.steps.len(), 1);
+        assert_eq!(selector.steps[0].name.as_deref(), Some("Run.Fast"));
+
+        let dom = dom();
+        assert_eq!(
+            select(&dom, r#"Workspace.NPC."Run.Fast""#),
+            vec!["Run.Fast"]
+        );
+    }
+
+    #[test]
+    fn filters_on_properties() {
+        let selector =
+            Selector::parse(r#"//*[AnimationId][Name="Walk"][AnimationId=~"^rbxassetid"]"#)
+                .unwrap();
+        let filters = &selector.steps[0].filters;
+        assert!(selector.steps[0].name.is_none());
+        assert!(matches!(&filters[0], Filter::Exists(property) if property == "AnimationId"));
+        assert!(
+            matches!(&filters[1], Filter::Equals(property, value) if property == "Name" && value == "Walk")
+        );
+        assert!(matches!(&filters[2], Filter::Matches(property, _) if property == "AnimationId"));
+
+        let dom = dom();
+        assert_eq!(
+            select(&dom, "//Animation[AnimationId]"),
+            vec!["Walk", "Run.Fast"]
+        );
+        assert_eq!(select(&dom, r#"//Animation[Name="Idle"]"#), vec!["Idle"]);
+        assert_eq!(select(&dom, "//*[ClassName=Model]"), vec!["NPC"]);
+        assert_eq!(
+            select(&dom, r#"//Animation[AnimationId=~"asset/\?id="]"#),
+            vec!["Run.Fast"]
+        );
+    }
+
+    #[test]
+    fn rejects_a_trailing_separator() {
+        for source in ["Workspace.", "Workspace/", "Workspace//", "//"] {
+            let error = Selector::parse(source).unwrap_err().to_string();
+            assert!(
+                error.contains("ends with a separator"),
+                "{}: {}",
+                source,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_an_empty_selector() {
+        for source in ["", "  ", "game"] {
+            let error = Selector::parse(source).unwrap_err().to_string();
+            assert_eq!(error, "Empty selector", "{}", source);
+        }
+    }
+}
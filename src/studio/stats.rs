@@ -0,0 +1,180 @@
+use crate::StudioParser;
+use crate::asset_id;
+use crate::script::script_parser::source_asset_references;
+use crate::studio::props;
+use rbx_dom_weak::Instance;
+use rbx_types::Variant;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The properties of a Sound holding its asset.
+const SOUND_PROPERTIES: [&str; 1] = ["SoundId"];
+
+/// Properties holding an image asset: Decal/Texture, ImageLabel/ImageButton, MeshPart,
+/// SurfaceAppearance, ParticleEmitter, Beam, Trail and Sky.
+const IMAGE_PROPERTIES: [&str; 13] = [
+    "Texture",
+    "TextureID",
+    "Image",
+    "ColorMap",
+    "NormalMap",
+    "MetalnessMap",
+    "RoughnessMap",
+    "SkyboxBk",
+    "SkyboxDn",
+    "SkyboxFt",
+    "SkyboxLf",
+    "SkyboxRt",
+    "SkyboxUp",
+];
+
+/// Counts describing a place, from StudioParser::stats.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaceStats {
+    pub instances: usize,
+    /// Instance count per class.
+    pub classes: BTreeMap<String, usize>,
+    /// Script, LocalScript and ModuleScript instances.
+    pub scripts: usize,
+    /// Total size of their sources, in bytes.
+    pub script_bytes: u64,
+    /// AnimationIds of Animation instances.
+    pub animation_refs: usize,
+    /// SoundIds of Sound instances.
+    pub sound_refs: usize,
+    /// Image properties (textures, decals, image labels, skyboxes, ...).
+    pub image_refs: usize,
+    /// Asset IDs in script sources, of any asset type.
+    pub script_refs: usize,
+    /// Size of the file, when the stats were read with with_file.
+    pub file_bytes: Option<u64>,
+    /// Bytes per chunk type (INST, PROP, PRNT, SSTR, ...) of a binary file, compressed size.
+    /// Empty for XML files.
+    pub chunks: BTreeMap<String, u64>,
+}
+
+impl PlaceStats {
+    /// Adds the file size and, for binary files, its breakdown by chunk type.
+    pub fn with_file(mut self, data: &[u8]) -> Self {
+        self.file_bytes = Some(data.len() as u64);
+        self.chunks = binary_chunk_sizes(data);
+        self
+    }
+
+    /// The classes with the most instances, most first.
+    pub fn top_classes(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut classes: Vec<(&str, usize)> = self
+            .classes
+            .iter()
+            .map(|(class, instances)| (class.as_str(), *instances))
+            .collect();
+        classes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        classes.truncate(count);
+        classes
+    }
+}
+
+impl StudioParser {
+    /// Counts the instances, scripts and asset references in the place (or the scope, if one
+    /// is set), packages included. Nothing is fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let data = std::fs::read("MyPlace.rbxl")?;
+    /// let parser = StudioParser::builder().from_bytes(data.clone()).build()?;
+    /// let stats = parser.stats().with_file(&data);
+    /// println!("{} scripts, {} animations", stats.scripts, stats.animation_refs);
+    /// ```
+    pub fn stats(&self) -> PlaceStats {
+        let mut stats = PlaceStats::default();
+
+        for instance in self.scope_instances() {
+            stats.instances += 1;
+            *stats.classes.entry(instance.class.to_string()).or_default() += 1;
+
+            match instance.class.as_str() {
+                "Script" | "LocalScript" | "ModuleScript" => {
+                    stats.scripts += 1;
+                    if let Some((_, source)) = props::source(instance) {
+                        stats.script_bytes += source.len() as u64;
+                        stats.script_refs += source_asset_references(source).len();
+                    }
+                }
+                "Animation"
+                    if props::animation_id(instance).is_some_and(|(_, animation_id)| {
+                        asset_id::parse_any(animation_id).is_some()
+                    }) =>
+                {
+                    stats.animation_refs += 1;
+                }
+                _ => {}
+            }
+            stats.sound_refs += count_asset_properties(instance, &SOUND_PROPERTIES);
+            stats.image_refs += count_asset_properties(instance, &IMAGE_PROPERTIES);
+        }
+
+        stats
+    }
+}
+
+/// How many of the properties hold an asset ID.
+fn count_asset_properties(instance: &Instance, properties: &[&str]) -> usize {
+    properties
+        .iter()
+        .filter(|property| {
+            let value = match props::find(instance, property) {
+                Some((_, Variant::ContentId(content_id))) => Some(content_id.as_str()),
+                Some((_, Variant::Content(content))) => content.as_uri(),
+                _ => None,
+            };
+            value.and_then(asset_id::parse_any).is_some()
+        })
+        .count()
+}
+
+/// Sizes of the chunks of a binary place or model, summed per chunk name.
+///
+/// # Notes:
+/// The header is 32 bytes. Each chunk then has a 4 byte name, compressed and uncompressed
+/// lengths and 4 reserved bytes, followed by its data, which is stored uncompressed when the
+/// compressed length is 0.
+fn binary_chunk_sizes(data: &[u8]) -> BTreeMap<String, u64> {
+    const HEADER_LEN: usize = 32;
+    const CHUNK_HEADER_LEN: usize = 16;
+
+    let mut chunks = BTreeMap::new();
+    if !data.starts_with(b"<roblox!") {
+        return chunks;
+    }
+
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    let mut offset = HEADER_LEN;
+    while offset + CHUNK_HEADER_LEN <= data.len() {
+        let name = String::from_utf8_lossy(&data[offset..offset + 4])
+            .trim_end_matches('\0')
+            .to_string();
+        let (Some(compressed), Some(uncompressed)) = (read_u32(offset + 4), read_u32(offset + 8))
+        else {
+            break;
+        };
+        let len = if compressed == 0 {
+            uncompressed
+        } else {
+            compressed
+        } as usize;
+
+        *chunks.entry(name.clone()).or_default() += (CHUNK_HEADER_LEN + len) as u64;
+        offset += CHUNK_HEADER_LEN + len;
+        if name == "END" {
+            break;
+        }
+    }
+
+    chunks
+}
@@ -0,0 +1,69 @@
+use crate::report::ModelUsage;
+use crate::{AnimationRef, StudioParser};
+use rbx_dom_weak::types::Ref;
+
+impl StudioParser {
+    /// The outermost Model an instance is in (itself included), e.g. the NPC a script inside
+    /// "Workspace.NPCs.Zombie.Humanoid" belongs to. Instances outside of any model belong to
+    /// their service, e.g. "ReplicatedStorage".
+    pub fn owning_model(&self, referent: Ref) -> Ref {
+        let root = self.dom.root_ref();
+        let mut owner = referent;
+        let mut model = None;
+        let mut current = referent;
+
+        while current != root
+            && let Some(instance) = self.dom.get_by_ref(current)
+        {
+            if instance.class == "Model" {
+                model = Some(current);
+            }
+            owner = current;
+            current = instance.parent();
+        }
+
+        model.unwrap_or(owner)
+    }
+
+    /// Groups the references of all_animations by the model (see owning_model) of each
+    /// Animation instance, script, attribute or property that uses them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let references = parser.all_animations().await?;
+    /// for usage in parser.model_usage(&references) {
+    ///     // "Workspace.NPCs.Zombie uses 9 animations, 2 broken"
+    ///     println!("{}", usage);
+    /// }
+    /// ```
+    ///
+    /// # Notes:
+    /// An animation is broken when asset delivery has no download location for it, which is
+    /// what a deleted, moderated or private animation looks like. References that weren't
+    /// fetched count as broken too.
+    pub fn model_usage(&self, references: &[AnimationRef]) -> Vec<ModelUsage> {
+        let uses = references.iter().flat_map(|reference| {
+            let broken = reference
+                .asset
+                .as_ref()
+                .is_none_or(|asset| asset.location().is_none());
+            reference
+                .instances
+                .iter()
+                .copied()
+                .chain(reference.scripts.iter().map(|script| script.script))
+                .chain(reference.attributes.iter().map(|(instance, _)| *instance))
+                .chain(reference.properties.iter().map(|(instance, _)| *instance))
+                .chain(reference.linked_sources.iter().map(|linked| linked.script))
+                .map(move |instance| {
+                    (
+                        self.instance_path(self.owning_model(instance)),
+                        reference.asset_id,
+                        broken,
+                    )
+                })
+        });
+        ModelUsage::aggregate(uses)
+    }
+}
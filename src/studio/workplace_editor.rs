@@ -1,19 +1,67 @@
-use crate::StudioParser;
-use rbx_binary::to_writer;
-use rbx_types::Variant;
-use std::collections::HashMap;
-use std::fs::File;
+use crate::asset_id;
+use crate::report::{Replacement, ReplacementReport};
+use crate::script::types::AssetUrlStyle;
+use crate::studio::backup::{backup_file, temp_path_for};
+use crate::studio::dom_parser::{PlaceFormat, is_xml_path};
+use crate::studio::props;
+use crate::{AnimationRef, StudioParser};
+use rbx_dom_weak::types::Ref;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::Path;
-use ustr::Ustr;
+use tracing::{info, warn};
+
+/// Which changes apply_mapping writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ApplyScope {
+    /// Only Animation instances (e.g. when scripts are managed through a Rojo repository).
+    Instances,
+    /// Only script sources.
+    Scripts,
+    /// Both instances and scripts.
+    #[default]
+    All,
+}
+
+impl ApplyScope {
+    pub fn includes_instances(&self) -> bool {
+        matches!(self, ApplyScope::Instances | ApplyScope::All)
+    }
+
+    pub fn includes_scripts(&self) -> bool {
+        matches!(self, ApplyScope::Scripts | ApplyScope::All)
+    }
+}
 
 impl StudioParser {
+    /// Applies the old → new mapping to scripts and/or Animation instances, value objects and
+    /// attributes, depending on the apply scope, in one traversal of the DOM.
+    pub fn apply_mapping(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+        apply_scope: ApplyScope,
+    ) -> ReplacementReport {
+        if apply_scope.includes_scripts() {
+            self.warn_unreadable_scripts();
+        }
+        self.apply_in_one_pass(animation_mapping, normalize_style, apply_scope)
+    }
+
     /// Gets all animation instances in the file, scans their animationId then replaces them if a
     /// new one is provided.
     ///
-    pub fn update_game_animations(&mut self, animation_mapping: &HashMap<String, String>) {
+    /// # Notes:
+    /// This searches the whole DOM for Animation instances. With the references from
+    /// workspace_animations or animations_in_service, apply_mapping_to_refs with
+    /// ApplyScope::Instances rewrites only those instances.
+    pub fn update_game_animations(
+        &mut self,
+        animation_mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
         let animation_instances_referent: Vec<_> = self
-            .dom
-            .descendants()
+            .scanned_instances()
             .filter(|instance| {
                 let instance_class_str = instance.class.as_str();
                 let is_animation = matches!(instance_class_str, "Animation");
@@ -26,28 +74,136 @@ impl StudioParser {
         //     animation_instances_referent.len()
         // );
 
-        let animation_id_key = Ustr::from("AnimationId");
+        self.last_edit.clear();
+        let mut report = ReplacementReport::new();
         for animation_ref in animation_instances_referent {
-            if let Some(instance) = self.dom.get_by_ref_mut(animation_ref) {
-                if let Some(Variant::ContentId(content_id)) =
-                    instance.properties.get(&animation_id_key)
-                {
-                    let raw = content_id.as_str();
-                    let trimmed_id = raw.strip_prefix("rbxassetid://").unwrap_or(raw);
-                    if let Some(new_id) = animation_mapping.get(trimmed_id) {
-                        // Replace the AnimationId with the new one
-                        let rbxasset = format!("rbxassetid://{}", new_id);
-                        instance.properties.insert(
-                            animation_id_key,
-                            Variant::ContentId(rbxasset.clone().into()),
-                        );
-                    }
-                }
+            let instance_report = self.update_animation_instance(animation_ref, animation_mapping);
+            self.record_edit(animation_ref, &instance_report);
+            report.merge(instance_report);
+        }
+
+        report
+    }
+
+    /// Applies the mapping to only what the given references point at, without traversing the
+    /// DOM again. The references come from animation_refs or all_animations on this parser.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let references = parser.all_animations().await?;
+    /// // ... upload and build the mapping ...
+    /// let report = parser.apply_mapping_to_refs(&references, &mapping, None, ApplyScope::All);
+    /// ```
+    ///
+    /// # Notes:
+    /// A script is rewritten once even when several references point into it, and with its
+    /// current source, so earlier edits don't invalidate the recorded byte ranges.
+    pub fn apply_mapping_to_refs(
+        &mut self,
+        references: &[AnimationRef],
+        animation_mapping: &HashMap<String, String>,
+        normalize_style: Option<AssetUrlStyle>,
+        apply_scope: ApplyScope,
+    ) -> ReplacementReport {
+        // IDs whose metadata says they aren't animations are never rewritten, even when the
+        // mapping has them
+        let other_assets: HashSet<String> = references
+            .iter()
+            .filter(|reference| {
+                reference
+                    .asset
+                    .as_ref()
+                    .is_some_and(|asset| !asset.is_animation())
+            })
+            .map(|reference| reference.asset_id.to_string())
+            .collect();
+        let animation_mapping: HashMap<String, String> = animation_mapping
+            .iter()
+            .filter(|(old_id, _)| !other_assets.contains(*old_id))
+            .map(|(old_id, new_id)| (old_id.clone(), new_id.clone()))
+            .collect();
+        let animation_mapping = &animation_mapping;
+
+        let mapped: Vec<&AnimationRef> = references
+            .iter()
+            .filter(|reference| animation_mapping.contains_key(&reference.asset_id.to_string()))
+            .collect();
+
+        self.last_edit.clear();
+        let mut report = ReplacementReport::new();
+        if apply_scope.includes_scripts() {
+            for linked in mapped
+                .iter()
+                .flat_map(|reference| reference.linked_sources.iter())
+            {
+                warn!(
+                    "Can't rewrite {} in {}: the script uses LinkedSource {}, update that script asset instead",
+                    linked.asset_id,
+                    self.instance_path(linked.script),
+                    linked.linked_source
+                );
+            }
+
+            let mut seen_scripts = HashSet::new();
+            let scripts: Vec<Ref> = mapped
+                .iter()
+                .flat_map(|reference| reference.scripts.iter().map(|script| script.script))
+                .filter(|script_ref| seen_scripts.insert(*script_ref))
+                .collect();
+            for script_ref in scripts {
+                let script_report =
+                    self.update_script(script_ref, animation_mapping, normalize_style);
+                self.record_edit(script_ref, &script_report);
+                report.merge(script_report);
+            }
+        }
+        if apply_scope.includes_instances() {
+            for instance_ref in mapped.iter().flat_map(|reference| &reference.instances) {
+                let instance_report =
+                    self.update_animation_instance(*instance_ref, animation_mapping);
+                self.record_edit(*instance_ref, &instance_report);
+                report.merge(instance_report);
             }
         }
+        report
+    }
+
+    /// Replaces the AnimationId of one Animation instance if the mapping has a new ID for it.
+    fn update_animation_instance(
+        &mut self,
+        animation_ref: Ref,
+        animation_mapping: &HashMap<String, String>,
+    ) -> ReplacementReport {
+        let mut report = ReplacementReport::new();
+        let path = self.instance_path(animation_ref);
+        if let Some(instance) = self.dom.get_by_ref_mut(animation_ref)
+            && let Some((animation_id_key, raw)) = props::animation_id(instance)
+            && let Some(old_id) = asset_id::parse_any(raw).map(|id| id.to_string())
+            && let Some(new_id) = animation_mapping.get(&old_id)
+        {
+            // Replace the AnimationId with the new one
+            let rbxasset = format!("rbxassetid://{}", new_id);
+            report.replacements.push(Replacement {
+                file: None,
+                instance_path: Some(path),
+                property: props::ANIMATION_ID.to_string(),
+                old_id,
+                new_id: new_id.clone(),
+                line: None,
+            });
+            let value = props::animation_id_value(instance, rbxasset);
+            instance.properties.insert(animation_id_key, value);
+        }
+
+        report
     }
 
-    /// Saves the DOM to a .rbxl file.
+    /// Saves the DOM to a .rbxl file. Paths ending in .rbxlx or .rbxmx are written as XML.
+    /// If the file already exists and `backup_on_save` is set, it is first copied to
+    /// `<file>.bak-<timestamp>`.
+    /// The DOM is written to a temporary file in the same directory and renamed over the
+    /// destination once complete, so an interrupted save leaves the original untouched.
     ///
     /// # Examples
     ///
@@ -59,12 +215,71 @@ impl StudioParser {
     /// ```
     pub fn save_to_rbxl<P: AsRef<Path>>(&self, file_path: P) -> Result<(), anyhow::Error> {
         let expanded_path = shellexpand::full(file_path.as_ref().to_str().unwrap())?;
-        let file = File::create(expanded_path.as_ref())?;
 
+        if self.backup_on_save && Path::new(expanded_path.as_ref()).exists() {
+            let backup_path = backup_file(expanded_path.as_ref())?;
+            info!("Backed up original file to {}", backup_path.display());
+        }
+
+        self.write_atomically(Path::new(expanded_path.as_ref()))
+    }
+
+    /// Writes the DOM to a temporary file next to `destination`, then renames it over it.
+    pub(crate) fn write_atomically(&self, destination: &Path) -> Result<(), anyhow::Error> {
+        // Write next to the destination first so a crash mid-write can't truncate the place
+        let temp_path = temp_path_for(destination);
+
+        if let Err(e) = self.write_dom(&temp_path, is_xml_path(&destination.to_string_lossy())) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        fs::rename(&temp_path, destination).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            anyhow::anyhow!(
+                "Failed to move '{}' to '{}': {}",
+                temp_path.display(),
+                destination.display(),
+                e
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Writes the DOM to a file and flushes it to disk.
+    fn write_dom(&self, path: &Path, as_xml: bool) -> Result<(), anyhow::Error> {
+        let file = File::create(path)?;
+        let format = if as_xml {
+            PlaceFormat::Xml
+        } else {
+            PlaceFormat::Binary
+        };
+        self.save_to_writer(&file, format)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Writes the DOM to any writer in the given format, e.g. an HTTP response or a buffer.
+    /// No backup is made.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut data = Vec::new();
+    /// parser.save_to_writer(&mut data, PlaceFormat::Binary)?;
+    /// ```
+    pub fn save_to_writer<W: Write>(
+        &self,
+        writer: W,
+        format: PlaceFormat,
+    ) -> Result<(), anyhow::Error> {
         // Get the children of the root instead of the root
         let root_children = self.dom.get_by_ref(self.dom.root_ref()).unwrap().children();
 
-        to_writer(file, &self.dom, root_children)?;
+        match format {
+            PlaceFormat::Xml => rbx_xml::to_writer_default(writer, &self.dom, root_children)?,
+            PlaceFormat::Binary => rbx_binary::to_writer(writer, &self.dom, root_children)?,
+        }
         Ok(())
     }
 }
@@ -0,0 +1,114 @@
+#![cfg(feature = "mock-server")]
+
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const GROUP: u64 = 5;
+
+/// A fresh directory for one test's files, which the CLI also runs in so its blocklist and
+/// caches stay out of the repo.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xixspoof-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_json(path: &PathBuf, value: Value) {
+    fs::write(path, serde_json::to_string_pretty(&value).unwrap()).unwrap();
+}
+
+fn scan(asset_ids: &[u64]) -> Value {
+    let animations: Vec<Value> = asset_ids
+        .iter()
+        .map(|asset_id| {
+            json!({
+                "id": asset_id,
+                "locations": [],
+                "errors": [],
+                "name": null,
+                "creator": null,
+            })
+        })
+        .collect();
+    json!({ "schema_version": 2, "source": "Place.rbxl", "animations": animations })
+}
+
+fn upload(dir: &PathBuf, fixture: Value, asset_ids: &[u64]) -> Output {
+    write_json(&dir.join("fixture.json"), fixture);
+    write_json(&dir.join("scan.json"), scan(asset_ids));
+
+    Command::new(env!("CARGO_BIN_EXE_animation-replace-roblox"))
+        .current_dir(dir)
+        .env_remove("ROBLOSECURITY")
+        .args([
+            "--mock-api",
+            "fixture.json",
+            "upload",
+            "--cookie",
+            "unused",
+            "--scan",
+            "scan.json",
+            "--mapping",
+            "mapping.json",
+            "--group",
+            &GROUP.to_string(),
+        ])
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn uploads_a_scan_through_rate_limits() {
+    let dir = test_dir("upload");
+    let fixture = json!({
+        "user_id": 1,
+        "username": "Owner",
+        "assets": {
+            // "walk" and "run"
+            "100": { "creator": { "User": 2 }, "data": "d2Fsaw==" },
+            "101": { "creator": { "User": 2 }, "data": "cnVu" },
+            // Nothing to download
+            "102": { "creator": { "User": 2 } },
+        },
+        "rate_limits": { "asset_metadata_batch": 1, "upload_animation": 2 },
+    });
+
+    let output = upload(&dir, fixture, &[100, 101, 102]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // 102 can't be downloaded, so the upload is partial
+    assert_eq!(output.status.code(), Some(4), "{}", stderr);
+    let mapping: Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("mapping.json")).unwrap()).unwrap();
+    let mappings = mapping["mappings"].as_object().unwrap();
+    assert_eq!(mappings.len(), 2, "{}", mapping);
+    assert!(mappings.contains_key("100"));
+    assert!(mappings.contains_key("101"));
+    assert_ne!(mappings["100"], mappings["101"]);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn refuses_a_group_without_the_upload_permission() {
+    let dir = test_dir("permission");
+    let fixture = json!({
+        "assets": { "100": { "creator": { "User": 2 }, "data": "d2Fsaw==" } },
+        "groups_without_permission": [GROUP],
+    });
+
+    let output = upload(&dir, fixture, &[100]);
+
+    assert_eq!(
+        output.status.code(),
+        Some(3),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!dir.join("mapping.json").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,199 @@
+use animation_replace_roblox::animation::api::{MockApiAsset, MockPlace};
+use animation_replace_roblox::animation::failure::FailureKind;
+use animation_replace_roblox::animation::place::PlaceVersion;
+use animation_replace_roblox::modules::upload::MODEL_ASSET_TYPE_ID;
+use animation_replace_roblox::{
+    AnimationAsset, AnimationUploader, AssetCreator, MockRobloxApi, UploadOptions, UploadTarget,
+};
+use bytes::Bytes;
+use std::sync::Arc;
+
+const OWNER: u64 = 1;
+const GROUP: u64 = 5;
+
+fn uploader(api: Arc<MockRobloxApi>) -> Arc<AnimationUploader> {
+    Arc::new(
+        AnimationUploader::builder()
+            .roblosecurity("unused")
+            .api(api)
+            .build()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn reuploads_and_verifies_through_the_api() {
+    let api = Arc::new(
+        MockRobloxApi::new()
+            .with_user(OWNER, "Owner")
+            .with_animation(100, AssetCreator::User(2), &b"walk"[..])
+            .with_animation(101, AssetCreator::User(2), &b"run"[..]),
+    );
+    let uploader = uploader(api.clone());
+
+    let animations = uploader
+        .fetch_animation_assets(vec![100, 101])
+        .await
+        .unwrap();
+    assert_eq!(animations.len(), 2);
+
+    let options = UploadOptions::new()
+        .target(UploadTarget::Group(GROUP))
+        .verify(true);
+    let mapping = uploader
+        .reupload_animations(animations, &options)
+        .await
+        .unwrap();
+
+    assert_eq!(mapping.len(), 2);
+    let uploads = api.uploads();
+    assert_eq!(uploads.len(), 2);
+    for upload in &uploads {
+        assert_eq!(upload.group_id, Some(GROUP));
+        let old_id = mapping
+            .iter()
+            .find(|(_, new_id)| **new_id == upload.asset_id.to_string())
+            .map(|(old_id, _)| old_id.as_str())
+            .unwrap();
+        let expected: &[u8] = if old_id == "100" { b"walk" } else { b"run" };
+        assert_eq!(upload.data.as_ref(), expected);
+    }
+}
+
+#[tokio::test]
+async fn refuses_groups_without_the_upload_permission() {
+    let api = Arc::new(
+        MockRobloxApi::new()
+            .with_animation(100, AssetCreator::User(2), &b"walk"[..])
+            .without_group_permission(GROUP),
+    );
+    let uploader = uploader(api.clone());
+
+    let animations = uploader.fetch_animation_assets(vec![100]).await.unwrap();
+    let options = UploadOptions::new().target(UploadTarget::Group(GROUP));
+    let error = uploader
+        .reupload_animations(animations, &options)
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("Create and edit group items"));
+    assert!(api.uploads().is_empty());
+}
+
+#[tokio::test]
+async fn reports_animations_that_cannot_be_downloaded() {
+    let api = Arc::new(
+        MockRobloxApi::new()
+            .with_animation(100, AssetCreator::User(2), &b"walk"[..])
+            .with_asset(102, AssetCreator::User(2), None),
+    );
+    let uploader = uploader(api.clone());
+
+    let options = UploadOptions::new().target(UploadTarget::CurrentUser);
+    let mapping = uploader
+        .clone()
+        .reupload_animations(
+            vec![AnimationAsset::new(100), AnimationAsset::new(102)],
+            &options,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(mapping.len(), 1);
+    assert!(mapping.contains_key("100"));
+    let failed = uploader.failed_uploads();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].asset_id, 102);
+}
+
+#[tokio::test]
+async fn looks_up_places_and_owners_through_the_api() {
+    let version = |version_number| PlaceVersion {
+        version_number,
+        created: "2024-01-01T00:00:00Z".to_string(),
+        is_published: false,
+    };
+    let api = Arc::new(
+        MockRobloxApi::new()
+            .with_user(OWNER, "Owner")
+            .with_creator_name(AssetCreator::Group(GROUP), "Studio")
+            .with_place(
+                10,
+                MockPlace {
+                    universe_id: 20,
+                    versions: vec![
+                        (version(2), Bytes::from_static(b"latest")),
+                        (version(1), Bytes::from_static(b"first")),
+                    ],
+                },
+            ),
+    );
+    let uploader = uploader(api);
+
+    assert_eq!(uploader.place_universe_id(10).await.unwrap(), 20);
+    assert!(uploader.place_universe_id(11).await.is_err());
+    let versions = uploader.list_place_versions(10).await.unwrap();
+    assert_eq!(versions, vec![version(2), version(1)]);
+    assert_eq!(
+        uploader.download_place(20, 10, None).await.unwrap(),
+        b"latest"
+    );
+    assert_eq!(
+        uploader.download_place(20, 10, Some(1)).await.unwrap(),
+        b"first"
+    );
+    let error = uploader.download_place(20, 10, Some(3)).await.unwrap_err();
+    assert_eq!(error.to_string(), "Place 10 has no version 3");
+    assert!(uploader.download_place(21, 10, None).await.is_err());
+
+    assert_eq!(
+        uploader
+            .creator_name(AssetCreator::User(OWNER))
+            .await
+            .unwrap(),
+        "Owner"
+    );
+    assert_eq!(
+        uploader
+            .creator_name(AssetCreator::Group(GROUP))
+            .await
+            .unwrap(),
+        "Studio"
+    );
+    assert!(uploader.creator_name(AssetCreator::User(3)).await.is_err());
+}
+
+#[tokio::test]
+async fn reuploads_modules_through_the_api() {
+    let module = |location: Option<&str>, data: Option<&'static [u8]>| MockApiAsset {
+        creator: AssetCreator::User(2),
+        name: Some("MainModule".to_string()),
+        location: location.map(str::to_string),
+        asset_type_id: MODEL_ASSET_TYPE_ID,
+        data: data.map(Bytes::from_static),
+    };
+    let api = Arc::new(
+        MockRobloxApi::new()
+            .with_asset_details(
+                200,
+                module(Some("https://mock.invalid/200"), Some(b"module")),
+            )
+            .with_asset_details(201, module(Some("https://mock.invalid/201"), None)),
+    );
+    let uploader = uploader(api.clone());
+
+    let mapping = uploader
+        .reupload_modules(vec![200, 201], UploadTarget::Group(GROUP))
+        .await
+        .unwrap();
+
+    let uploads = api.model_uploads();
+    assert_eq!(uploads.len(), 1);
+    assert_eq!(uploads[0].group_id, Some(GROUP));
+    assert_eq!(uploads[0].data.as_ref(), b"module");
+    assert_eq!(mapping["200"], uploads[0].asset_id.to_string());
+    let failed = uploader.failed_uploads();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].asset_id, 201);
+    assert_eq!(failed[0].kind, FailureKind::Deleted);
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "record-replay")]
+
+use animation_replace_roblox::animation::record_replay::RecordReplayApi;
+use animation_replace_roblox::{
+    AnimationUploader, AssetCreator, MockRobloxApi, RobloxApi, UploadOptions, UploadTarget,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const GROUP: u64 = 5;
+
+async fn reupload(api: Arc<dyn RobloxApi>) -> anyhow::Result<HashMap<String, String>> {
+    let uploader = Arc::new(
+        AnimationUploader::builder()
+            .roblosecurity("unused")
+            .api(api)
+            .build()?,
+    );
+    let animations = uploader.fetch_animation_assets(vec![100, 101]).await?;
+    let options = UploadOptions::new()
+        .target(UploadTarget::Group(GROUP))
+        .verify(true);
+    uploader.reupload_animations(animations, &options).await
+}
+
+#[tokio::test]
+async fn replays_a_recorded_upload_offline() {
+    let mock = Arc::new(
+        MockRobloxApi::new()
+            .with_animation(100, AssetCreator::User(2), &b"walk"[..])
+            .with_animation(101, AssetCreator::User(2), &b"run"[..]),
+    );
+    let recorder = Arc::new(RecordReplayApi::record(mock.clone()));
+    let recorded = reupload(recorder.clone()).await.unwrap();
+    assert_eq!(recorded.len(), 2);
+
+    let path = std::env::temp_dir().join(format!("xixspoof-fixtures-{}.json", std::process::id()));
+    recorder.save(&path).unwrap();
+    let replay = Arc::new(RecordReplayApi::replay(&path).unwrap());
+    let _ = std::fs::remove_file(&path);
+
+    // Nothing reaches the mock anymore, yet the run ends the same
+    let replayed = reupload(replay).await.unwrap();
+    assert_eq!(replayed, recorded);
+    assert_eq!(mock.uploads().len(), 2);
+}
+
+#[tokio::test]
+async fn fails_calls_that_were_never_recorded() {
+    let replay = Arc::new(RecordReplayApi::from_fixtures(Default::default()));
+
+    let error = replay.user_id("unused").await.unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("No recorded response for user_id")
+    );
+}